@@ -0,0 +1,83 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.0;
+
+import {CrossChainTest} from "./CrossChainTest.sol";
+
+/// @title Storage Layout Test
+/// @dev Pins the storage slots the ZK coprocessor proves `getProofData`
+///      amounts from (`crates/malda_utils/src/storage.rs`). The slot keys are
+///      the vectors of `test_amount_slots_match_solidity_layout`; writing them
+///      must change what `getProofData` returns.
+/// @custom:command forge test --match-contract StorageLayoutTest
+contract StorageLayoutTest is CrossChainTest {
+    address constant USER =
+        address(bytes20(hex"1111111111111111111111111111111111111111"));
+
+    uint256 constant GATEWAY_ACC_AMOUNT_IN_SLOT = 5;
+    uint256 constant GATEWAY_ACC_AMOUNT_OUT_SLOT = 6;
+    uint256 constant HOST_ACC_SLOT = 22;
+
+    function setUp() external {
+        setupCrossChainTest();
+    }
+
+    function test_GatewayAmountSlots() external {
+        bytes32 slotIn =
+            keccak256(abi.encode(USER, GATEWAY_ACC_AMOUNT_IN_SLOT));
+        bytes32 slotOut =
+            keccak256(abi.encode(USER, GATEWAY_ACC_AMOUNT_OUT_SLOT));
+        assertEq(
+            slotIn,
+            bytes32(
+                0xe211e23e74ee2556989cb624831cd15e4324ec0f5e5d9a1c3ec21f309f497c8c
+            )
+        );
+        assertEq(
+            slotOut,
+            bytes32(
+                0x70f16d33119391b8b067ddbccc95d800dffc462fb735b11fb31d20dbd5ec1617
+            )
+        );
+
+        vm.store(address(gateway), slotIn, bytes32(uint256(111)));
+        vm.store(address(gateway), slotOut, bytes32(uint256(222)));
+
+        (uint256 amountIn, uint256 amountOut) =
+            gateway.getProofData(USER, LINEA_CHAIN_ID);
+        assertEq(amountIn, 111);
+        assertEq(amountOut, 222);
+    }
+
+    function test_HostAmountSlots() external {
+        // Slot of the `Accumulated` struct of `acc[BASE_CHAIN_ID]`
+        uint256 accumulated =
+            uint256(keccak256(abi.encode(BASE_CHAIN_ID, HOST_ACC_SLOT)));
+        bytes32 slotIn = keccak256(abi.encode(USER, accumulated));
+        bytes32 slotOut = keccak256(abi.encode(USER, accumulated + 1));
+        assertEq(
+            slotIn,
+            bytes32(
+                0x3073b4343a7b281a56c29f5aa0eb0e049a991e6ded877b821ebbcb5e37ffc1ab
+            )
+        );
+        assertEq(
+            slotOut,
+            bytes32(
+                0xabf7ddeccbe0247701e47ac776f6b57cab0858086edf7c9e30dccd2381aa5a2f
+            )
+        );
+
+        vm.store(address(market), slotIn, bytes32(uint256(111)));
+        vm.store(address(market), slotOut, bytes32(uint256(222)));
+
+        (uint256 amountIn, uint256 amountOut) =
+            market.getProofData(USER, BASE_CHAIN_ID);
+        assertEq(amountIn, 111);
+        assertEq(amountOut, 222);
+
+        // Other target chains are stored apart
+        (amountIn, amountOut) = market.getProofData(USER, LINEA_CHAIN_ID);
+        assertEq(amountIn, 0);
+        assertEq(amountOut, 0);
+    }
+}
@@ -0,0 +1,42 @@
+#![no_main]
+
+use alloy_primitives::{Bytes, Signature, U256};
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use ssz::{Decode, Encode};
+
+use malda_utils::types::{ExecutionPayload, SequencerCommitment};
+
+#[derive(Arbitrary, Debug)]
+enum FuzzInput {
+    // Snappy-compressed gossip message, as received by the relayer.
+    Compressed(Vec<u8>),
+    // Decompressed commitment data, including data shorter than the parent
+    // beacon block root.
+    Data(Vec<u8>),
+}
+
+fuzz_target!(|input: FuzzInput| {
+    let commitment = match input {
+        FuzzInput::Compressed(bytes) => {
+            match SequencerCommitment::new(&bytes) {
+                Ok(commitment) => commitment,
+                Err(_) => return,
+            }
+        }
+        FuzzInput::Data(data) => SequencerCommitment {
+            data: Bytes::from(data),
+            signature: Signature::new(U256::from(1), U256::from(1), false),
+        },
+    };
+
+    // Must never panic, whatever the sequencer sent.
+    let Ok(payload) = ExecutionPayload::try_from(&commitment) else {
+        return;
+    };
+
+    // Any accepted payload re-encodes to a payload that decodes to itself.
+    let reencoded = ExecutionPayload::from_ssz_bytes(&payload.as_ssz_bytes())
+        .expect("re-encoded payload failed to decode");
+    assert_eq!(reencoded, payload, "SSZ re-encoding changed the payload");
+});
@@ -0,0 +1,94 @@
+#![no_main]
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+
+use alloy_primitives::{Address, Bytes, U256, hex};
+use alloy_sol_types::{SolType, SolValue, sol_data};
+use malda_utils::types::{SolidityDataType, TakeLastXBytes, abi};
+
+#[derive(Arbitrary, Debug)]
+enum Item {
+    String(String),
+    Address([u8; 20]),
+    Bytes(Vec<u8>),
+    Bool(bool),
+    Number([u8; 32]),
+    // Width in bytes of a `uintN`, wrapped to 1..=32.
+    NumberWithShift([u8; 32], u8),
+}
+
+impl Item {
+    /// Width in bits of a `NumberWithShift`.
+    fn bits(width: u8) -> usize {
+        (width as usize % 32 + 1) * 8
+    }
+
+    fn as_solidity(&self) -> SolidityDataType<'_> {
+        match self {
+            Item::String(s) => SolidityDataType::String(s),
+            Item::Address(a) => SolidityDataType::Address(Address::from(*a)),
+            Item::Bytes(b) => SolidityDataType::Bytes(b),
+            Item::Bool(b) => SolidityDataType::Bool(*b),
+            Item::Number(n) => {
+                SolidityDataType::Number(U256::from_be_bytes(*n))
+            }
+            Item::NumberWithShift(n, width) => {
+                SolidityDataType::NumberWithShift(
+                    U256::from_be_bytes(*n),
+                    TakeLastXBytes(Self::bits(*width)),
+                )
+            }
+        }
+    }
+
+    /// Packed encoding of the equivalent Solidity value.
+    fn sol_packed(&self) -> Vec<u8> {
+        match self {
+            Item::String(s) => s.abi_encode_packed(),
+            Item::Address(a) => Address::from(*a).abi_encode_packed(),
+            Item::Bytes(b) => Bytes::copy_from_slice(b).abi_encode_packed(),
+            Item::Bool(b) => b.abi_encode_packed(),
+            Item::Number(n) => U256::from_be_bytes(*n).abi_encode_packed(),
+            Item::NumberWithShift(n, width) => {
+                uint_packed(U256::from_be_bytes(*n), Self::bits(*width))
+            }
+        }
+    }
+}
+
+/// Truncates `value` to `bits` like the Solidity cast `uintN(value)`.
+fn truncate<T: TryFrom<U256>>(value: U256, bits: usize) -> T {
+    let masked = value & (U256::MAX >> (256 - bits));
+    T::try_from(masked)
+        .ok()
+        .expect("masked value fits its width")
+}
+
+/// Packs `value` as `sol_data::Uint<bits>`.
+fn uint_packed(value: U256, bits: usize) -> Vec<u8> {
+    macro_rules! dispatch {
+        ($($b:literal),*) => {
+            match bits {
+                $($b => sol_data::Uint::<$b>::abi_encode_packed(
+                    &truncate(value, $b),
+                ),)*
+                _ => unreachable!("unsupported width {bits}"),
+            }
+        };
+    }
+    dispatch!(
+        8, 16, 24, 32, 40, 48, 56, 64, 72, 80, 88, 96, 104, 112, 120, 128, 136,
+        144, 152, 160, 168, 176, 184, 192, 200, 208, 216, 224, 232, 240, 248,
+        256
+    )
+}
+
+fuzz_target!(|items: Vec<Item>| {
+    let input: Vec<_> = items.iter().map(Item::as_solidity).collect();
+    let (encoded, hexed) = abi::encode_packed(&input);
+
+    let expected: Vec<u8> = items.iter().flat_map(Item::sol_packed).collect();
+    assert_eq!(encoded, expected, "packed encoding differs for {items:?}");
+    assert_eq!(hexed, hex::encode(&expected));
+});
@@ -0,0 +1,29 @@
+#![no_main]
+
+use alloy_primitives::{Bytes, Signature, U256};
+use libfuzzer_sys::fuzz_target;
+use ssz::{Decode, Encode};
+
+use malda_utils::types::{ExecutionPayload, SequencerCommitment};
+
+fuzz_target!(|payload: ExecutionPayload| {
+    let encoded = payload.as_ssz_bytes();
+    let decoded = ExecutionPayload::from_ssz_bytes(&encoded)
+        .expect("encoded payload failed to decode");
+    assert_eq!(decoded, payload, "SSZ round-trip changed the payload");
+
+    // The same payload must survive the commitment conversion, behind the
+    // 32 byte parent beacon block root.
+    let mut data = vec![0u8; 32];
+    data.extend_from_slice(&encoded);
+    let commitment = SequencerCommitment {
+        data: Bytes::from(data),
+        signature: Signature::new(U256::from(1), U256::from(1), false),
+    };
+    let converted = ExecutionPayload::try_from(&commitment)
+        .expect("commitment conversion failed");
+    assert_eq!(
+        converted, payload,
+        "commitment conversion changed the payload"
+    );
+});
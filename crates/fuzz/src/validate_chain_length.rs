@@ -0,0 +1,175 @@
+#![no_main]
+
+use std::panic::{self, AssertUnwindSafe};
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+
+use alloy_consensus::Header;
+use alloy_primitives::{B256, Bytes};
+use malda_utils::{constants::*, validators::validate_chain_length};
+use risc0_steel::serde::RlpHeader;
+
+/// Chains and their reorg protection depths, restated for the oracle.
+const CHAINS: [(u64, u64); 8] = [
+    (ETHEREUM_CHAIN_ID, REORG_PROTECTION_DEPTH_ETHEREUM),
+    (OPTIMISM_CHAIN_ID, REORG_PROTECTION_DEPTH_OPTIMISM),
+    (BASE_CHAIN_ID, REORG_PROTECTION_DEPTH_BASE),
+    (LINEA_CHAIN_ID, REORG_PROTECTION_DEPTH_LINEA),
+    (
+        ETHEREUM_SEPOLIA_CHAIN_ID,
+        REORG_PROTECTION_DEPTH_ETHEREUM_SEPOLIA,
+    ),
+    (
+        OPTIMISM_SEPOLIA_CHAIN_ID,
+        REORG_PROTECTION_DEPTH_OPTIMISM_SEPOLIA,
+    ),
+    (BASE_SEPOLIA_CHAIN_ID, REORG_PROTECTION_DEPTH_BASE_SEPOLIA),
+    (LINEA_SEPOLIA_CHAIN_ID, REORG_PROTECTION_DEPTH_LINEA_SEPOLIA),
+];
+
+/// Edit applied to a canonical chain. Indices wrap around the chain length.
+#[derive(Arbitrary, Debug)]
+enum Mutation {
+    // Replace the chain from `at` on with a sibling branch of `len` blocks.
+    Fork { at: u8, len: u8, salt: u8 },
+    Duplicate(u8),
+    Swap(u8, u8),
+    Remove(u8),
+    // Point a block's parent hash at another block of the chain.
+    Relink { at: u8, parent: u8 },
+    SetParentHash { at: u8, parent_hash: [u8; 32] },
+}
+
+/// Hash the caller expects the linking blocks to end in.
+#[derive(Arbitrary, Debug)]
+enum CurrentHash {
+    Tip,
+    Historical,
+    Block(u8),
+    Arbitrary([u8; 32]),
+}
+
+#[derive(Arbitrary, Debug)]
+struct FuzzInput {
+    chain: u8,
+    historical_hash: [u8; 32],
+    length: u8,
+    mutations: Vec<Mutation>,
+    current_hash: CurrentHash,
+}
+
+fn header(parent_hash: B256, number: u64, salt: u8) -> RlpHeader<Header> {
+    RlpHeader::new(Header {
+        parent_hash,
+        number,
+        // Distinguishes sibling blocks of the same height
+        extra_data: Bytes::from(vec![salt]),
+        ..Default::default()
+    })
+}
+
+/// Appends `len` blocks on top of `parent_hash`.
+fn extend(
+    chain: &mut Vec<RlpHeader<Header>>,
+    mut parent_hash: B256,
+    len: usize,
+    salt: u8,
+) {
+    for _ in 0..len {
+        let block = header(parent_hash, chain.len() as u64 + 1, salt);
+        parent_hash = block.hash_slow();
+        chain.push(block);
+    }
+}
+
+/// Reference implementation: the blocks must form a chain from
+/// `historical_hash` to `current_hash` that is at least `depth` blocks long.
+fn is_valid_chain(
+    depth: u64,
+    historical_hash: B256,
+    blocks: &[RlpHeader<Header>],
+    current_hash: B256,
+) -> bool {
+    let hashes: Vec<B256> = blocks.iter().map(|b| b.hash_slow()).collect();
+    let parents = std::iter::once(historical_hash).chain(hashes.clone());
+    blocks.len() as u64 >= depth
+        && blocks.iter().zip(parents).all(|(b, p)| b.parent_hash == p)
+        && hashes.last().copied().unwrap_or(historical_hash) == current_hash
+}
+
+fuzz_target!(|input: FuzzInput| {
+    let (chain_id, depth) = CHAINS[input.chain as usize % CHAINS.len()];
+    let historical_hash = B256::from(input.historical_hash);
+
+    let mut blocks = Vec::new();
+    extend(&mut blocks, historical_hash, input.length as usize % 16, 0);
+
+    for mutation in input.mutations.into_iter().take(8) {
+        if blocks.is_empty() {
+            break;
+        }
+        let index = |i: u8| i as usize % blocks.len();
+        match mutation {
+            Mutation::Fork { at, len, salt } => {
+                let at = index(at);
+                blocks.truncate(at);
+                let parent_hash =
+                    blocks.last().map_or(historical_hash, |b| b.hash_slow());
+                extend(&mut blocks, parent_hash, len as usize % 8, salt);
+            }
+            Mutation::Duplicate(i) => {
+                let i = index(i);
+                blocks.insert(i, blocks[i].clone());
+            }
+            Mutation::Swap(i, j) => {
+                let (i, j) = (index(i), index(j));
+                blocks.swap(i, j);
+            }
+            Mutation::Remove(i) => {
+                blocks.remove(index(i));
+            }
+            Mutation::Relink { at, parent } => {
+                let (at, parent) = (index(at), index(parent));
+                let mut inner = blocks[at].inner().clone();
+                inner.parent_hash = blocks[parent].hash_slow();
+                blocks[at] = RlpHeader::new(inner);
+            }
+            Mutation::SetParentHash { at, parent_hash } => {
+                let at = index(at);
+                let mut inner = blocks[at].inner().clone();
+                inner.parent_hash = B256::from(parent_hash);
+                blocks[at] = RlpHeader::new(inner);
+            }
+        }
+    }
+
+    let current_hash = match input.current_hash {
+        CurrentHash::Tip => {
+            blocks.last().map_or(historical_hash, |b| b.hash_slow())
+        }
+        CurrentHash::Historical => historical_hash,
+        CurrentHash::Block(i) if !blocks.is_empty() => {
+            blocks[i as usize % blocks.len()].hash_slow()
+        }
+        CurrentHash::Block(_) => historical_hash,
+        CurrentHash::Arbitrary(hash) => B256::from(hash),
+    };
+
+    let expected =
+        is_valid_chain(depth, historical_hash, &blocks, current_hash);
+
+    // Rejections are panics; silence them while probing the validator.
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    let accepted = panic::catch_unwind(AssertUnwindSafe(|| {
+        validate_chain_length(chain_id, historical_hash, &blocks, current_hash)
+    }))
+    .is_ok();
+    panic::set_hook(hook);
+
+    assert_eq!(
+        accepted, expected,
+        "validator and reference disagree on chain {chain_id}"
+    );
+});
@@ -0,0 +1,74 @@
+// Copyright (c) 2025 Merge Layers Inc.
+//
+// This source code is licensed under the Business Source License 1.1
+// (the "License"); you may not use this file except in compliance with the
+// License. You may obtain a copy of the License at
+//
+//     https://github.com/malda-protocol/malda-zk-coprocessor/blob/main/LICENSE-BSL
+//
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+//
+//! Aggregates get_proof_data receipts proven per chain.
+//!
+//! Reads the journals of the per-chain receipts, verifies every journal with
+//! `env::verify` against the get_proof_data image ID compiled in for the
+//! network profile (see `malda_utils::constants::GET_PROOF_DATA_ID_MAINNET`),
+//! and commits the concatenated entries and finality policy hashes, that
+//! image ID and the concatenated events: `(bytes[] output, bytes32[]
+//! finality_policy_hashes, bytes32 image_id, bytes[] events)`.
+//! The receipts themselves are supplied by the host as assumptions.
+
+use alloy_primitives::{B256, Bytes};
+use alloy_sol_types::SolValue;
+use malda_utils::constants::*;
+use risc0_zkvm::{guest::env, sha::Digest};
+
+/// Image ID of the get_proof_data guest whose receipts this build accepts,
+/// selected by the `mainnet` (default), `sepolia` or `devnet` feature.
+#[cfg(all(
+    feature = "mainnet",
+    not(any(feature = "sepolia", feature = "devnet"))
+))]
+const GET_PROOF_DATA_ID: [u32; 8] = GET_PROOF_DATA_ID_MAINNET;
+#[cfg(all(
+    feature = "sepolia",
+    not(any(feature = "mainnet", feature = "devnet"))
+))]
+const GET_PROOF_DATA_ID: [u32; 8] = GET_PROOF_DATA_ID_SEPOLIA;
+#[cfg(all(
+    feature = "devnet",
+    not(any(feature = "mainnet", feature = "sepolia"))
+))]
+const GET_PROOF_DATA_ID: [u32; 8] = GET_PROOF_DATA_ID_DEVNET;
+
+fn main() {
+    let image_id = Digest::from(GET_PROOF_DATA_ID);
+    let journals: Vec<Vec<u8>> = env::read();
+    assert!(!journals.is_empty(), "No chain journals to aggregate");
+
+    let mut output: Vec<Bytes> = Vec::new();
+    let mut finality_policy_hashes: Vec<B256> = Vec::new();
+    let mut events: Vec<Bytes> = Vec::new();
+    for journal in journals {
+        env::verify(image_id, &journal).expect("Failed to verify chain proof");
+        let (entries, hashes, chain_events) =
+            <(Vec<Bytes>, Vec<B256>, Vec<Bytes>)>::abi_decode_params(&journal)
+                .expect("Invalid chain journal");
+        output.extend(entries);
+        finality_policy_hashes.extend(hashes);
+        events.extend(chain_events);
+    }
+    // The image ID is appended after the entries and policy hashes, and the
+    // events after it, so earlier decoders keep working.
+    env::commit_slice(
+        &(
+            output,
+            finality_policy_hashes,
+            B256::from_slice(image_id.as_bytes()),
+            events,
+        )
+            .abi_encode_params(),
+    );
+}
@@ -12,24 +12,102 @@
 //
 
 use alloy_consensus::Header;
-use alloy_primitives::{Address, Bytes};
+use alloy_primitives::{Address, B256, Bytes};
 use alloy_sol_types::SolValue;
-use malda_utils::constants::{
-    BASE_CHAIN_ID, BASE_SEPOLIA_CHAIN_ID, ETHEREUM_CHAIN_ID,
-    ETHEREUM_SEPOLIA_CHAIN_ID, LINEA_CHAIN_ID, LINEA_SEPOLIA_CHAIN_ID,
-    OPTIMISM_CHAIN_ID, OPTIMISM_SEPOLIA_CHAIN_ID,
-};
 use malda_utils::{
-    types::SequencerCommitment, validators::validate_get_proof_data_call,
+    receipts::EventInput,
+    storage::StorageProofInput,
+    types::{FinalityPolicy, L1Anchor, NetworkProfile, SequencerCommitment},
+    validators::{
+        ValidatedL1Anchor, validate_get_proof_data_call, validate_l1_anchor,
+    },
 };
+#[cfg(feature = "profile")]
+use malda_utils::{types::PROFILE_MARKER, validators::set_stage_hook};
 use risc0_op_steel::optimism::OpEvmInput;
 use risc0_steel::{ethereum::EthEvmInput, serde::RlpHeader};
 use risc0_zkvm::guest::env;
 
+/// Chains this build accepts, selected by the `mainnet` (default), `sepolia`
+/// or `devnet` feature.
+#[cfg(all(
+    feature = "mainnet",
+    not(any(feature = "sepolia", feature = "devnet"))
+))]
+const PROFILE: NetworkProfile = NetworkProfile::Mainnet;
+#[cfg(all(
+    feature = "sepolia",
+    not(any(feature = "mainnet", feature = "devnet"))
+))]
+const PROFILE: NetworkProfile = NetworkProfile::Sepolia;
+#[cfg(all(
+    feature = "devnet",
+    not(any(feature = "mainnet", feature = "sepolia"))
+))]
+const PROFILE: NetworkProfile = NetworkProfile::Devnet;
+
+#[cfg(not(any(
+    all(
+        feature = "mainnet",
+        not(any(feature = "sepolia", feature = "devnet"))
+    ),
+    all(
+        feature = "sepolia",
+        not(any(feature = "mainnet", feature = "devnet"))
+    ),
+    all(
+        feature = "devnet",
+        not(any(feature = "mainnet", feature = "sepolia"))
+    ),
+)))]
+compile_error!(
+    "enable exactly one of the `mainnet`, `sepolia` and `devnet` features \
+     (use --no-default-features for sepolia and devnet)"
+);
+
+/// Writes a profiling marker for `event` with the current cycle count.
+#[cfg(feature = "profile")]
+fn mark(event: std::fmt::Arguments) {
+    let cycles = env::cycle_count();
+    eprintln!("{PROFILE_MARKER} {event} {cycles}");
+}
+
 fn main() {
+    #[cfg(feature = "profile")]
+    set_stage_hook(|stage| mark(format_args!("stage {}", stage.name())));
+
+    // Validate the Ethereum blocks shared by the chains of the batch once,
+    // before the chains refer to them.
+    let anchors: Vec<L1Anchor> = env::read();
+    let mut l1_anchors: Vec<ValidatedL1Anchor> = Vec::new();
+    for anchor in anchors {
+        #[cfg(feature = "profile")]
+        mark(format_args!("anchor"));
+        let anchor_chain_id = anchor.chain_id;
+        if !PROFILE.allows(anchor_chain_id) {
+            panic!(
+                "Chain ID {anchor_chain_id} is not supported by the {} guest",
+                PROFILE.name()
+            );
+        }
+        if l1_anchors
+            .iter()
+            .any(|validated| validated.chain_id == anchor_chain_id)
+        {
+            panic!("Duplicate L1 anchor for chain ID {anchor_chain_id}");
+        }
+        l1_anchors.push(validate_l1_anchor(anchor));
+        #[cfg(feature = "profile")]
+        mark(format_args!("end anchor {anchor_chain_id}"));
+    }
+
     let mut output: Vec<Bytes> = Vec::new();
+    let mut finality_policy_hashes: Vec<B256> = Vec::new();
+    let mut events: Vec<Bytes> = Vec::new();
     let length: u64 = env::read();
     for _i in 0..length {
+        #[cfg(feature = "profile")]
+        mark(format_args!("chain"));
         // Read the input data for this application.
         let env_input: Option<EthEvmInput> = env::read();
         let chain_id: u64 = env::read();
@@ -37,32 +115,20 @@ fn main() {
         let asset: Vec<Address> = env::read();
         let target_chain_ids: Vec<u64> = env::read();
         let sequencer_commitment: Option<SequencerCommitment> = env::read();
-        let env_op_input: Option<EthEvmInput> = env::read();
         let linking_blocks: Vec<RlpHeader<Header>> = env::read();
         let env_eth_input: Option<EthEvmInput> = env::read();
         let op_evm_input: Option<OpEvmInput> = env::read();
-        let sequencer_commitment_opstack_2: Option<SequencerCommitment> =
-            env::read();
-        let env_op_input_2: Option<EthEvmInput> = env::read();
+        let finality_policy: FinalityPolicy = env::read();
+        let event_input: Option<EventInput> = env::read();
+        let storage_proof_input: Option<StorageProofInput> = env::read();
 
-        // This makes the guest program only compatible with mainnet chains,
-        // remove for testnet and enable the below testnet code
-        if chain_id != LINEA_CHAIN_ID
-            && chain_id != BASE_CHAIN_ID
-            && chain_id != ETHEREUM_CHAIN_ID
-            && chain_id != OPTIMISM_CHAIN_ID
-        {
-            panic!("Chain ID is not Linea, Base, Ethereum or Optimism");
+        if !PROFILE.allows(chain_id) {
+            panic!(
+                "Chain ID {chain_id} is not supported by the {} guest",
+                PROFILE.name()
+            );
         }
 
-        // This makes the guest program only compatible with testnet chains,
-        // remove for mainnet and enable the above mainnet code
-        // if chain_id != LINEA_SEPOLIA_CHAIN_ID && chain_id !=
-        // BASE_SEPOLIA_CHAIN_ID && chain_id != ETHEREUM_SEPOLIA_CHAIN_ID &&
-        // chain_id != OPTIMISM_SEPOLIA_CHAIN_ID {     panic!("Chain ID
-        // is not Linea Sepolia, Base Sepolia, Ethereum Sepolia or Optimism
-        // Sepolia"); }
-
         validate_get_proof_data_call(
             chain_id,
             account,
@@ -70,14 +136,23 @@ fn main() {
             target_chain_ids,
             env_input,
             sequencer_commitment,
-            env_op_input,
             &linking_blocks,
             &mut output,
-            &env_eth_input,
+            env_eth_input,
             op_evm_input,
-            sequencer_commitment_opstack_2,
-            env_op_input_2,
+            &l1_anchors,
+            &finality_policy,
+            event_input,
+            &mut events,
+            storage_proof_input,
         );
+        finality_policy_hashes.push(finality_policy.hash(chain_id));
+        #[cfg(feature = "profile")]
+        mark(format_args!("end {chain_id}"));
     }
-    env::commit_slice(&output.abi_encode());
+    // The policy hashes and events are appended after the entries so that
+    // verifiers decoding the journal as `bytes[]` keep working unchanged.
+    env::commit_slice(
+        &(output, finality_policy_hashes, events).abi_encode_params(),
+    );
 }
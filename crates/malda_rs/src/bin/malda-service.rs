@@ -0,0 +1,59 @@
+// Copyright (c) 2025 Merge Layers Inc.
+//
+// This source code is licensed under the Business Source License 1.1
+// (the "License"); you may not use this file except in compliance with the
+// License. You may obtain a copy of the License at
+//
+//     https://github.com/malda-protocol/malda-zk-coprocessor/blob/main/LICENSE-BSL
+//
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+//
+//! Proof request service.
+//!
+//! Loads `MaldaConfig` from the environment (see `config`), then serves the
+//! job API of `malda_rs::service` on `service.listen_addr` and proves the
+//! queued jobs with the configured data source, commitment source and
+//! prover.
+
+use malda_rs::{
+    commitments::commitment_source_from_config,
+    config::MaldaConfig,
+    data_source::data_source_from_config,
+    prover::prover_from_config,
+    service::{ProofService, ProvingRunner},
+};
+
+use anyhow::Result;
+use tracing_subscriber::EnvFilter;
+
+use std::sync::Arc;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::from_default_env())
+        .init();
+
+    let config = Arc::new(MaldaConfig::from_env()?);
+    let data_source = data_source_from_config(config.clone()).await?;
+    let commitments =
+        commitment_source_from_config(&config, data_source.clone()).await?;
+    let prover = prover_from_config(&config)?;
+    let runner = Arc::new(ProvingRunner::new(
+        config.clone(),
+        data_source,
+        commitments,
+        prover.clone(),
+    ));
+
+    let mut service = ProofService::start(&config.service, runner).await?;
+    tracing::info!(
+        "Proof service listening on {} ({} prover, database {})",
+        service.addr(),
+        prover.name(),
+        config.service.database.display()
+    );
+    service.wait().await
+}
@@ -0,0 +1,473 @@
+// Copyright (c) 2025 Merge Layers Inc.
+//
+// This source code is licensed under the Business Source License 1.1
+// (the "License"); you may not use this file except in compliance with the
+// License. You may obtain a copy of the License at
+//
+//     https://github.com/malda-protocol/malda-zk-coprocessor/blob/main/LICENSE-BSL
+//
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+//
+//! Command-line tool for reproducing proofs.
+//!
+//! Loads `MaldaConfig` from the environment (see `config`) and provides one
+//! subcommand per step of the proving pipeline, so a failing proof can be
+//! reproduced step by step:
+//!
+//! - `input`: builds the guest input for a request and saves it to a file.
+//! - `execute`: executes a saved input and prints cycle stats and journal.
+//! - `prove`: proves a saved input with the configured or a chosen prover.
+//! - `journal`: decodes a journal and prints it as JSON.
+//! - `inspect`: prints a summary of a saved input as JSON.
+//! - `profile`: profiles the cycles of saved inputs per chain and stage, and
+//!   compares them with a baseline.
+//!
+//! Requests use the JSON format of the `malda-service` job API, see
+//! `service::JobSpec`. Inputs are saved as `snapshot::InputSnapshot` files,
+//! so the snapshots written to `snapshot_dir` by a failing service can be
+//! executed, proven and inspected directly.
+
+use malda_rs::{
+    commitments::commitment_source_from_config,
+    config::{MaldaConfig, ProverKind},
+    data_source::data_source_from_config,
+    journal::{decode_aggregated_journal, decode_journal},
+    profile::{Baseline, GuestProfile, profile_input},
+    programs::GuestProgram,
+    prover::{prover_for_program, prover_from_config},
+    receipts::EventInput,
+    service::{JobResult, JobSpec},
+    snapshot::InputSnapshot,
+    storage::StorageProofInput,
+    types::{FinalityPolicy, L1Anchor, SequencerCommitment},
+    viewcalls::get_proof_data_input,
+};
+
+use alloy::primitives::{Address, B256, BlockNumber, hex};
+use alloy_consensus::Header;
+use anyhow::{Context, Result, bail, ensure};
+use clap::{Parser, Subcommand};
+use risc0_op_steel::optimism::OpEvmInput;
+use risc0_steel::{ethereum::EthEvmInput, serde::RlpHeader};
+use serde::{Deserialize, Serialize};
+use tracing_subscriber::EnvFilter;
+
+use std::{collections::BTreeMap, path::PathBuf, sync::Arc};
+
+/// Input of one chain, in the order the guest reads it.
+type ChainInput = (
+    Option<EthEvmInput>,
+    u64,
+    Vec<Address>,
+    Vec<Address>,
+    Vec<u64>,
+    Option<SequencerCommitment>,
+    Vec<RlpHeader<Header>>,
+    Option<EthEvmInput>,
+    Option<OpEvmInput>,
+    FinalityPolicy,
+    Option<EventInput>,
+    Option<StorageProofInput>,
+);
+
+/// Build, execute, prove and inspect Malda proof data inputs.
+#[derive(Parser)]
+#[command(name = "malda")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Build the guest input for a request and save it.
+    Input {
+        /// JSON request in the `malda-service` job format.
+        #[arg(long)]
+        request: PathBuf,
+        /// File the input snapshot is written to.
+        #[arg(long)]
+        out: PathBuf,
+    },
+    /// Execute a saved input and print cycle stats and the journal.
+    Execute {
+        /// Input snapshot, e.g. written by `malda input`.
+        #[arg(long)]
+        input: PathBuf,
+    },
+    /// Prove a saved input.
+    Prove {
+        /// Input snapshot, e.g. written by `malda input`.
+        #[arg(long)]
+        input: PathBuf,
+        /// Prover backend, overriding the configured one (local, bonsai,
+        /// boundless, execute or dev).
+        #[arg(long)]
+        prover: Option<String>,
+        /// Guest ELF to prove instead of the embedded one, e.g. the previous
+        /// version during an upgrade.
+        #[arg(long)]
+        elf: Option<PathBuf>,
+        /// File the proof is written to as JSON, printed if not set.
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// Decode a journal and print it as JSON.
+    Journal {
+        /// Hex-encoded journal.
+        #[arg(required_unless_present = "file")]
+        hex: Option<String>,
+        /// File holding the raw journal bytes.
+        #[arg(long, conflicts_with = "hex")]
+        file: Option<PathBuf>,
+        /// Decode a journal of the aggregate_proof_data guest.
+        #[arg(long)]
+        aggregated: bool,
+    },
+    /// Print a summary of a saved input as JSON.
+    Inspect {
+        /// Input snapshot, e.g. written by `malda input`.
+        #[arg(long)]
+        input: PathBuf,
+    },
+    /// Profile the cycles of saved inputs per chain and stage.
+    Profile {
+        /// Input snapshots, or directories of them. Each input is profiled
+        /// under its file stem.
+        #[arg(required = true)]
+        inputs: Vec<PathBuf>,
+        /// Guest ELF to execute, e.g. built with `just build-guest-profile`.
+        /// Defaults to the guest each input was built for, which only
+        /// reports totals.
+        #[arg(long)]
+        elf: Option<PathBuf>,
+        /// Baseline the profiles are compared with.
+        #[arg(long)]
+        baseline: Option<PathBuf>,
+        /// Write the profiles to the baseline instead of comparing.
+        #[arg(long, requires = "baseline")]
+        update_baseline: bool,
+        /// Allowed cycle increase over the baseline, in percent.
+        #[arg(long, default_value_t = 2.0)]
+        tolerance: f64,
+    },
+}
+
+/// Summary of an input, printed by `malda inspect`.
+#[derive(Serialize)]
+struct InputSummary {
+    /// Ethereum chain IDs of the L1 anchors.
+    l1_anchors: Vec<u64>,
+    chains: Vec<ChainSummary>,
+}
+
+/// Summary of one chain's input, printed by `malda inspect`.
+#[derive(Serialize)]
+struct ChainSummary {
+    chain_id: u64,
+    users: Vec<Address>,
+    markets: Vec<Address>,
+    target_chain_ids: Vec<u64>,
+    finality_policy: FinalityPolicy,
+    has_env_input: bool,
+    has_sequencer_commitment: bool,
+    has_env_eth_input: bool,
+    has_op_evm_input: bool,
+    has_storage_proofs: bool,
+    linking_blocks: Vec<(BlockNumber, B256)>,
+    /// Receipts proven per block, by block number.
+    event_receipts: Vec<(BlockNumber, usize)>,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::from_default_env())
+        .with_writer(std::io::stderr)
+        .init();
+
+    match Cli::parse().command {
+        Command::Input { request, out } => build_input(request, out).await,
+        Command::Execute { input } => execute(input).await,
+        Command::Prove {
+            input,
+            prover,
+            elf,
+            out,
+        } => prove(input, prover, elf, out).await,
+        Command::Journal {
+            hex,
+            file,
+            aggregated,
+        } => {
+            let journal = match (hex, file) {
+                (Some(journal), _) => hex::decode(journal.trim())
+                    .context("journal is not valid hex")?,
+                (None, Some(file)) => read(&file)?,
+                (None, None) => unreachable!("enforced by clap"),
+            };
+            if aggregated {
+                print_json(&decode_aggregated_journal(&journal)?)
+            } else {
+                print_json(&decode_journal(&journal)?)
+            }
+        }
+        Command::Inspect { input } => inspect(input),
+        Command::Profile {
+            inputs,
+            elf,
+            baseline,
+            update_baseline,
+            tolerance,
+        } => profile(inputs, elf, baseline, update_baseline, tolerance),
+    }
+}
+
+/// Builds the input of the request in `request` and writes it to `out`.
+async fn build_input(request: PathBuf, out: PathBuf) -> Result<()> {
+    let spec: JobSpec = serde_json::from_slice(&read(&request)?)
+        .with_context(|| format!("invalid request {}", request.display()))?;
+    let request = spec.to_request()?;
+
+    let config = Arc::new(MaldaConfig::from_env()?);
+    let data_source = data_source_from_config(config.clone()).await?;
+    let commitments =
+        commitment_source_from_config(&config, data_source.clone()).await?;
+    let input =
+        get_proof_data_input(&config, data_source, commitments, &request)
+            .await?;
+
+    let input_len = input.len();
+    InputSnapshot::new(&config, &request, input)?.save(&out)?;
+    eprintln!("Wrote {input_len} bytes of input to {}", out.display());
+    Ok(())
+}
+
+/// Executes the input in `input` and prints the session stats and journal.
+async fn execute(input: PathBuf) -> Result<()> {
+    let elf_dir = std::env::var_os("MALDA_GUEST_ELF_DIR").map(PathBuf::from);
+    let result = InputSnapshot::load(&input)?
+        .execute(elf_dir.as_deref())
+        .await?;
+    if let Some(stats) = &result.stats {
+        eprintln!("segments:        {}", stats.segments);
+        eprintln!("total cycles:    {}", stats.total_cycles);
+        eprintln!("user cycles:     {}", stats.user_cycles);
+        eprintln!("paging cycles:   {}", stats.paging_cycles);
+        eprintln!("reserved cycles: {}", stats.reserved_cycles);
+    }
+    print_json(&decode_journal(&result.journal)?)
+}
+
+/// Proves the input in `input` and writes the proof to `out` or stdout.
+async fn prove(
+    input: PathBuf,
+    prover: Option<String>,
+    elf: Option<PathBuf>,
+    out: Option<PathBuf>,
+) -> Result<()> {
+    let mut config = MaldaConfig::from_env()?;
+    if let Some(prover) = prover {
+        config.prover =
+            serde_json::from_value::<ProverKind>(prover.clone().into())
+                .with_context(|| format!("unknown prover: {prover}"))?;
+        config.validate()?;
+    }
+    let prover = match elf {
+        Some(elf) => prover_for_program(
+            &config,
+            GuestProgram::load(elf.display().to_string(), &elf)?,
+        )?,
+        None => prover_from_config(&config)?,
+    };
+
+    let result = InputSnapshot::load(&input)?.prove(&*prover).await?;
+    eprintln!(
+        "Proved {} with the {} prover",
+        prover.program().name(),
+        prover.name()
+    );
+    let proof = JobResult {
+        journal: result.journal,
+        seal: result.seal,
+        image_id: B256::from_slice(result.image_id.as_bytes()),
+    };
+
+    match out {
+        Some(out) => std::fs::write(&out, serde_json::to_vec_pretty(&proof)?)
+            .with_context(|| format!("failed to write {}", out.display())),
+        None => print_json(&proof),
+    }
+}
+
+/// Prints the L1 anchors and a summary of every chain's input in `input`.
+fn inspect(input: PathBuf) -> Result<()> {
+    let bytes = InputSnapshot::load(&input)?.input;
+    ensure!(
+        bytes.len() % 4 == 0,
+        "input length {} is not a multiple of 4",
+        bytes.len()
+    );
+    let words: Vec<u32> = bytemuck::pod_collect_to_vec(&bytes);
+    let mut de = risc0_zkvm::serde::Deserializer::new(words.as_slice());
+
+    let l1_anchors = Vec::<L1Anchor>::deserialize(&mut de)
+        .context("failed to read L1 anchors")?;
+    let chain_count =
+        u64::deserialize(&mut de).context("failed to read chain count")?;
+    let chains = (0..chain_count)
+        .map(|i| {
+            let chain = ChainInput::deserialize(&mut de)
+                .with_context(|| format!("failed to read chain input {i}"))?;
+            Ok(summarize(chain))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    print_json(&InputSummary {
+        l1_anchors: l1_anchors.iter().map(|anchor| anchor.chain_id).collect(),
+        chains,
+    })
+}
+
+/// Profiles the inputs in `inputs` and compares them with `baseline`.
+fn profile(
+    inputs: Vec<PathBuf>,
+    elf: Option<PathBuf>,
+    baseline: Option<PathBuf>,
+    update_baseline: bool,
+    tolerance: f64,
+) -> Result<()> {
+    let elf_dir = std::env::var_os("MALDA_GUEST_ELF_DIR").map(PathBuf::from);
+    let program = elf
+        .map(|elf| GuestProgram::load(elf.display().to_string(), &elf))
+        .transpose()?;
+
+    let mut paths = Vec::new();
+    for input in inputs {
+        if input.is_dir() {
+            let mut entries = std::fs::read_dir(&input)
+                .with_context(|| format!("failed to read {}", input.display()))?
+                .map(|entry| Ok(entry?.path()))
+                .collect::<Result<Vec<_>>>()?;
+            entries.retain(|path| {
+                path.extension().is_some_and(|ext| ext == "json")
+            });
+            entries.sort();
+            paths.extend(entries);
+        } else {
+            paths.push(input);
+        }
+    }
+
+    let mut profiles = BTreeMap::new();
+    for path in paths {
+        let name = path
+            .file_stem()
+            .with_context(|| format!("no file name in {}", path.display()))?
+            .to_string_lossy()
+            .into_owned();
+        let snapshot = InputSnapshot::load(&path)?;
+        let profile = match &program {
+            Some(program) => profile_input(program, &snapshot.input),
+            None => profile_input(
+                &snapshot.program(elf_dir.as_deref())?,
+                &snapshot.input,
+            ),
+        }
+        .with_context(|| format!("failed to profile {}", path.display()))?;
+        print_profile(&name, &profile);
+        profiles.insert(name, profile);
+    }
+
+    match baseline {
+        Some(path) if update_baseline => {
+            Baseline { profiles }.save(&path)?;
+            eprintln!("Wrote baseline {}", path.display());
+        }
+        Some(path) => {
+            let regressions =
+                Baseline::load(&path)?.regressions(&profiles, tolerance);
+            print_json(&profiles)?;
+            if !regressions.is_empty() {
+                for regression in &regressions {
+                    eprintln!("regression: {regression}");
+                }
+                bail!(
+                    "{} cost(s) exceed {} by more than {tolerance}%",
+                    regressions.len(),
+                    path.display()
+                );
+            }
+        }
+        None => print_json(&profiles)?,
+    }
+    Ok(())
+}
+
+fn print_profile(name: &str, profile: &GuestProfile) {
+    eprintln!(
+        "{name}: {} user cycles, {} total cycles, {} segments",
+        profile.user_cycles, profile.total_cycles, profile.segments
+    );
+    for anchor in &profile.l1_anchors {
+        eprintln!("  anchor {}: {} cycles", anchor.chain_id, anchor.cycles);
+    }
+    for chain in &profile.chains {
+        eprintln!("  chain {}: {} cycles", chain.chain_id, chain.cycles);
+        for (stage, cycles) in &chain.stages {
+            eprintln!("    {:<22}{cycles}", stage.name());
+        }
+        eprintln!("    {:<22}{}", "other", chain.other_cycles());
+    }
+}
+
+fn summarize(chain: ChainInput) -> ChainSummary {
+    let (
+        env_input,
+        chain_id,
+        users,
+        markets,
+        target_chain_ids,
+        sequencer_commitment,
+        linking_blocks,
+        env_eth_input,
+        op_evm_input,
+        finality_policy,
+        event_input,
+        storage_proof_input,
+    ) = chain;
+
+    ChainSummary {
+        chain_id,
+        users,
+        markets,
+        target_chain_ids,
+        finality_policy,
+        has_env_input: env_input.is_some(),
+        has_sequencer_commitment: sequencer_commitment.is_some(),
+        has_env_eth_input: env_eth_input.is_some(),
+        has_op_evm_input: op_evm_input.is_some(),
+        has_storage_proofs: storage_proof_input.is_some(),
+        linking_blocks: linking_blocks
+            .iter()
+            .map(|header| (header.number, header.hash_slow()))
+            .collect(),
+        event_receipts: event_input
+            .iter()
+            .flat_map(|input| &input.blocks)
+            .map(|block| (block.block_number, block.receipts.len()))
+            .collect(),
+    }
+}
+
+fn read(path: &PathBuf) -> Result<Vec<u8>> {
+    std::fs::read(path)
+        .with_context(|| format!("failed to read {}", path.display()))
+}
+
+fn print_json(value: &impl Serialize) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
@@ -0,0 +1,895 @@
+// Copyright (c) 2025 Merge Layers Inc.
+//
+// This source code is licensed under the Business Source License 1.1
+// (the "License"); you may not use this file except in compliance with the
+// License. You may obtain a copy of the License at
+//
+//     https://github.com/malda-protocol/malda-zk-coprocessor/blob/main/LICENSE-BSL
+//
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+//
+//! Sources of OpStack sequencer commitments.
+//!
+//! A sequencer commitment is the signed execution payload the sequencer of
+//! an OpStack chain gossips for every unsafe block. The guest verifies its
+//! signature, so a bad commitment used to surface only as a failed proof. A
+//! `CommitmentSource` returns the latest commitment of a chain, and
+//! `viewcalls::get_current_sequencer_commitment` passes every commitment
+//! through `check_commitment` before it enters the input: it must be signed
+//! by the chain's sequencer, hold an execution payload and, for live
+//! sources, be at most `max_age` old. Every error names the source that
+//! returned the commitment.
+//!
+//! Three sources are provided:
+//!
+//! - `HttpCommitmentSource`: fetches the latest commitment from the
+//!   configured `sequencer_url` endpoints (a Helios-style OpStack consensus
+//!   server), through a `ChainDataSource` so record and replay cover it.
+//! - `FileCommitmentSource`: reads `<chain_id>.json` fixtures from a
+//!   directory. Fixtures are never checked for freshness.
+//! - `GossipCommitmentSource`: keeps the newest commitment per chain out of
+//!   the op-node `blocks/v3` gossip messages. It subscribes to the gossip
+//!   topics through the configured op-node peers over libp2p, and can also
+//!   accept raw messages a relay posts to `POST /gossip/{chain_id}`.
+//!
+//! ```toml
+//! [commitments]
+//! source = "gossip"
+//! gossip_peers = ["/ip4/203.0.113.1/tcp/9222/p2p/16Uiu2..."]
+//! max_age_secs = 60
+//! ```
+
+use crate::config::{CommitmentSourceKind, DataSourceMode, MaldaConfig};
+use crate::constants::*;
+use crate::data_source::{ChainDataSource, endpoint_id};
+use crate::error::BoxError;
+use crate::types::{ExecutionPayload, SequencerCommitment};
+use crate::viewcalls::get_chain_params;
+
+use alloy::primitives::Address;
+use anyhow::{Context, Result, bail};
+use axum::{
+    Router,
+    body::Bytes,
+    extract::{Path, State},
+    http::StatusCode,
+    routing::post,
+};
+use futures::{StreamExt, future::BoxFuture};
+use libp2p::{
+    Multiaddr, PeerId, Swarm, SwarmBuilder,
+    gossipsub::{
+        self, IdentTopic, MessageAcceptance, MessageAuthenticity, MessageId,
+        TopicHash, ValidationMode,
+    },
+    multiaddr::Protocol,
+    noise,
+    swarm::SwarmEvent,
+    tcp, yamux,
+};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use tokio::{net::TcpListener, task::JoinHandle};
+use tracing::{debug, warn};
+
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    path::PathBuf,
+    sync::{Arc, RwLock},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Timeout of a sequencer endpoint request. The endpoints serve a cached
+/// payload, so a slow answer means a broken endpoint.
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Chains whose `blocks/v3` topics the gossip source subscribes to.
+const GOSSIP_CHAINS: [u64; 4] = [
+    OPTIMISM_CHAIN_ID,
+    BASE_CHAIN_ID,
+    OPTIMISM_SEPOLIA_CHAIN_ID,
+    BASE_SEPOLIA_CHAIN_ID,
+];
+
+/// Maximum size of a decompressed gossip message, as enforced by op-node.
+const MAX_GOSSIP_SIZE: usize = 10 * (1 << 20);
+
+/// How often disconnected gossip peers are dialed again.
+const GOSSIP_REDIAL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Error returned by a `CommitmentSource` or `check_commitment`.
+#[derive(Debug, Error)]
+pub enum CommitmentError {
+    /// The chain has no sequencer commitments.
+    #[error("chain {0} has no sequencer commitments")]
+    UnsupportedChain(u64),
+
+    /// The source did not return a commitment.
+    #[error("{origin}: no commitment for chain {chain_id}: {message}")]
+    Fetch {
+        origin: String,
+        chain_id: u64,
+        message: String,
+    },
+
+    /// The commitment is not a signed execution payload.
+    #[error("{origin}: malformed commitment for chain {chain_id}: {message}")]
+    Malformed {
+        origin: String,
+        chain_id: u64,
+        message: String,
+    },
+
+    /// The commitment is not signed by the chain's sequencer.
+    #[error(
+        "{origin}: commitment for chain {chain_id} is not signed by \
+         sequencer {sequencer}: {message}"
+    )]
+    Signature {
+        origin: String,
+        chain_id: u64,
+        sequencer: Address,
+        message: String,
+    },
+
+    /// The commitment is older than the source's maximum age.
+    #[error(
+        "{origin}: commitment for block {block} of chain {chain_id} is \
+         {age}s old, at most {max_age}s allowed"
+    )]
+    Stale {
+        origin: String,
+        chain_id: u64,
+        block: u64,
+        age: u64,
+        max_age: u64,
+    },
+}
+
+/// A source of sequencer commitments.
+pub trait CommitmentSource: Send + Sync {
+    /// Describes where the commitment of a chain comes from, e.g. the
+    /// endpoint or file. Used in errors and logs.
+    fn origin(&self, chain_id: u64, fallback: bool) -> String;
+
+    /// Returns the maximum age of the commitments of this source, or `None`
+    /// if they are recorded and must not be checked for freshness.
+    fn max_age(&self) -> Option<Duration>;
+
+    /// Returns the latest commitment of an OpStack chain, unchecked.
+    ///
+    /// # Arguments
+    /// * `chain_id` - Chain ID (Optimism, Base, or their Sepolia variants).
+    /// * `fallback` - Whether to use the fallback endpoint, if the source
+    ///   has one.
+    fn latest(
+        &self,
+        chain_id: u64,
+        fallback: bool,
+    ) -> BoxFuture<'_, Result<SequencerCommitment, CommitmentError>>;
+}
+
+/// Returns the address whose signature the guest accepts on the
+/// commitments of `chain_id`.
+pub fn sequencer_signer(chain_id: u64) -> Option<Address> {
+    match chain_id {
+        OPTIMISM_CHAIN_ID => Some(OPTIMISM_SEQUENCER),
+        BASE_CHAIN_ID => Some(BASE_SEQUENCER),
+        OPTIMISM_SEPOLIA_CHAIN_ID => Some(OPTIMISM_SEPOLIA_SEQUENCER),
+        BASE_SEPOLIA_CHAIN_ID => Some(BASE_SEPOLIA_SEQUENCER),
+        _ => None,
+    }
+}
+
+/// Checks a commitment the way the guest will, plus its freshness.
+///
+/// # Arguments
+/// * `origin` - Where the commitment comes from, see
+///   `CommitmentSource::origin`.
+/// * `chain_id` - The chain the commitment is for.
+/// * `commitment` - The commitment to check.
+/// * `max_age` - Maximum age of the payload, `None` to skip the check.
+///
+/// # Returns
+/// * `Result<u64, CommitmentError>` - The block number of the payload.
+///
+/// # Errors
+/// Returns an error if the chain has no sequencer, the commitment is not
+/// signed by it, does not hold an execution payload or is too old.
+pub fn check_commitment(
+    origin: &str,
+    chain_id: u64,
+    commitment: &SequencerCommitment,
+    max_age: Option<Duration>,
+) -> Result<u64, CommitmentError> {
+    let sequencer = sequencer_signer(chain_id)
+        .ok_or(CommitmentError::UnsupportedChain(chain_id))?;
+    commitment.verify(sequencer, chain_id).map_err(|e| {
+        CommitmentError::Signature {
+            origin: origin.to_string(),
+            chain_id,
+            sequencer,
+            message: e.to_string(),
+        }
+    })?;
+
+    let payload = ExecutionPayload::try_from(commitment).map_err(|e| {
+        CommitmentError::Malformed {
+            origin: origin.to_string(),
+            chain_id,
+            message: e.to_string(),
+        }
+    })?;
+
+    if let Some(max_age) = max_age {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+        let age = now.saturating_sub(payload.timestamp);
+        if age > max_age.as_secs() {
+            return Err(CommitmentError::Stale {
+                origin: origin.to_string(),
+                chain_id,
+                block: payload.block_number,
+                age,
+                max_age: max_age.as_secs(),
+            });
+        }
+    }
+
+    Ok(payload.block_number)
+}
+
+/// Fetches commitments from the sequencer endpoints of a data source.
+pub struct HttpCommitmentSource {
+    data_source: Arc<dyn ChainDataSource>,
+    client: reqwest::Client,
+    max_age: Option<Duration>,
+}
+
+impl HttpCommitmentSource {
+    /// Creates a source fetching from the `sequencer_request_url` endpoints
+    /// of `data_source`.
+    ///
+    /// # Arguments
+    /// * `data_source` - Source of the sequencer endpoints.
+    /// * `max_age` - Maximum commitment age, `None` when `data_source`
+    ///   replays recorded responses.
+    pub fn new(
+        data_source: Arc<dyn ChainDataSource>,
+        max_age: Option<Duration>,
+    ) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(HTTP_TIMEOUT)
+            .build()
+            .expect("static HTTP client settings are valid");
+        Self {
+            data_source,
+            client,
+            max_age,
+        }
+    }
+}
+
+impl CommitmentSource for HttpCommitmentSource {
+    fn origin(&self, chain_id: u64, fallback: bool) -> String {
+        // Never the URL itself, which may embed an API key
+        match get_chain_params(chain_id) {
+            Ok((chain_name, testnet)) => format!(
+                "http:{}",
+                endpoint_id("sequencer", chain_name, fallback, testnet)
+            ),
+            Err(_) => "http".to_string(),
+        }
+    }
+
+    fn max_age(&self) -> Option<Duration> {
+        self.max_age
+    }
+
+    fn latest(
+        &self,
+        chain_id: u64,
+        fallback: bool,
+    ) -> BoxFuture<'_, Result<SequencerCommitment, CommitmentError>> {
+        Box::pin(async move {
+            sequencer_signer(chain_id)
+                .ok_or(CommitmentError::UnsupportedChain(chain_id))?;
+            let (chain_name, testnet) = get_chain_params(chain_id)
+                .map_err(|_| CommitmentError::UnsupportedChain(chain_id))?;
+            let url = self
+                .data_source
+                .sequencer_request_url(chain_name, fallback, testnet)
+                .map_err(|e| CommitmentError::Fetch {
+                    origin: self.origin(chain_id, fallback),
+                    chain_id,
+                    message: e.to_string(),
+                })?;
+
+            let response = self
+                .client
+                .get(url)
+                .send()
+                .await
+                .and_then(|res| res.error_for_status())
+                .map_err(|e| CommitmentError::Fetch {
+                    origin: self.origin(chain_id, fallback),
+                    chain_id,
+                    // Without the URL, which may embed an API key
+                    message: e.without_url().to_string(),
+                })?;
+            response.json::<SequencerCommitment>().await.map_err(|e| {
+                CommitmentError::Malformed {
+                    origin: self.origin(chain_id, fallback),
+                    chain_id,
+                    message: e.without_url().to_string(),
+                }
+            })
+        })
+    }
+}
+
+/// Reads commitments from `<chain_id>.json` files, in the JSON format of the
+/// sequencer endpoints.
+#[derive(Debug, Clone)]
+pub struct FileCommitmentSource {
+    dir: PathBuf,
+}
+
+impl FileCommitmentSource {
+    /// Creates a source reading the commitment files in `dir`.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn path(&self, chain_id: u64) -> PathBuf {
+        self.dir.join(format!("{chain_id}.json"))
+    }
+}
+
+impl CommitmentSource for FileCommitmentSource {
+    fn origin(&self, chain_id: u64, _fallback: bool) -> String {
+        format!("file:{}", self.path(chain_id).display())
+    }
+
+    fn max_age(&self) -> Option<Duration> {
+        None
+    }
+
+    fn latest(
+        &self,
+        chain_id: u64,
+        fallback: bool,
+    ) -> BoxFuture<'_, Result<SequencerCommitment, CommitmentError>> {
+        Box::pin(async move {
+            let contents =
+                tokio::fs::read(self.path(chain_id)).await.map_err(|e| {
+                    CommitmentError::Fetch {
+                        origin: self.origin(chain_id, fallback),
+                        chain_id,
+                        message: e.to_string(),
+                    }
+                })?;
+            serde_json::from_slice(&contents).map_err(|e| {
+                CommitmentError::Malformed {
+                    origin: self.origin(chain_id, fallback),
+                    chain_id,
+                    message: e.to_string(),
+                }
+            })
+        })
+    }
+}
+
+/// Keeps the newest commitment per chain out of op-node gossip messages.
+///
+/// A gossip message is the snappy-compressed signature and payload op-node
+/// publishes on `/optimism/<chain_id>/2/blocks`. Messages arrive through:
+///
+/// - `subscribe`: a libp2p gossipsub subscription to the `blocks/v3` topics
+///   of the OpStack chains, through the given op-node peers. Messages are
+///   only forwarded to other peers once they pass the checks below.
+/// - `listen`: an HTTP endpoint a relay posts raw messages to.
+/// - `receive`: messages passed in directly.
+///
+/// Messages are checked when they arrive, so an invalid message never
+/// replaces a valid commitment.
+pub struct GossipCommitmentSource {
+    commitments: Arc<RwLock<HashMap<u64, (u64, SequencerCommitment)>>>,
+    max_age: Duration,
+    relay: Option<(SocketAddr, JoinHandle<()>)>,
+    subscription: Option<JoinHandle<()>>,
+}
+
+impl GossipCommitmentSource {
+    /// Creates a source fed through `receive` only.
+    pub fn new(max_age: Duration) -> Self {
+        Self {
+            commitments: Default::default(),
+            max_age,
+            relay: None,
+            subscription: None,
+        }
+    }
+
+    /// Creates a source and starts the relay endpoint on `addr`.
+    ///
+    /// The endpoint accepts `POST /gossip/{chain_id}` with a raw gossip
+    /// message as body, and answers `400 Bad Request` with the reason if the
+    /// message is rejected.
+    ///
+    /// # Errors
+    /// Returns an error if `addr` cannot be bound.
+    pub async fn listen(addr: SocketAddr, max_age: Duration) -> Result<Self> {
+        let mut source = Self::new(max_age);
+        let listener = TcpListener::bind(addr)
+            .await
+            .with_context(|| format!("failed to bind gossip relay {addr}"))?;
+        let addr = listener.local_addr()?;
+        let app = Router::new()
+            .route("/gossip/{chain_id}", post(handle_gossip))
+            .with_state(source.commitments.clone());
+        let server = tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, app).await {
+                tracing::error!("Gossip relay endpoint failed: {e}");
+            }
+        });
+        source.relay = Some((addr, server));
+        Ok(source)
+    }
+
+    /// Subscribes to the `blocks/v3` topics of the OpStack chains through
+    /// `peers`, the multiaddrs of op-node peers including their peer ID.
+    ///
+    /// The peers are dialed in the background and dialed again whenever
+    /// they disconnect.
+    ///
+    /// # Errors
+    /// Returns an error if a peer address has no peer ID, the source is
+    /// already subscribed, or the libp2p swarm cannot be built.
+    pub fn subscribe(&mut self, peers: &[String]) -> Result<()> {
+        if self.subscription.is_some() {
+            bail!("gossip source is already subscribed");
+        }
+        let peers = peers
+            .iter()
+            .map(|peer| parse_gossip_peer(peer))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut swarm = SwarmBuilder::with_new_identity()
+            .with_tokio()
+            .with_tcp(
+                tcp::Config::default(),
+                noise::Config::new,
+                yamux::Config::default,
+            )?
+            .with_dns()?
+            .with_behaviour(|_| gossip_behaviour())?
+            .with_swarm_config(|config| {
+                config.with_idle_connection_timeout(Duration::from_secs(60))
+            })
+            .build();
+        let mut topics = HashMap::new();
+        for chain_id in GOSSIP_CHAINS {
+            let topic = blocks_v3_topic(chain_id);
+            swarm.behaviour_mut().subscribe(&topic)?;
+            topics.insert(topic.hash(), chain_id);
+        }
+
+        let commitments = self.commitments.clone();
+        self.subscription = Some(tokio::spawn(async move {
+            let mut redial = tokio::time::interval(GOSSIP_REDIAL_INTERVAL);
+            loop {
+                tokio::select! {
+                    _ = redial.tick() => {
+                        for (peer_id, addr) in &peers {
+                            if swarm.is_connected(peer_id) {
+                                continue;
+                            }
+                            if let Err(e) = swarm.dial(addr.clone()) {
+                                warn!("Failed to dial gossip peer {addr}: {e}");
+                            }
+                        }
+                    }
+                    event = swarm.select_next_some() => {
+                        handle_swarm_event(
+                            &mut swarm,
+                            &topics,
+                            &commitments,
+                            event,
+                        );
+                    }
+                }
+            }
+        }));
+        Ok(())
+    }
+
+    /// Returns the address of the relay endpoint, if started.
+    pub fn addr(&self) -> Option<SocketAddr> {
+        self.relay.as_ref().map(|(addr, _)| *addr)
+    }
+
+    /// Decodes and checks a raw gossip message of `chain_id`, and keeps it
+    /// if it is newer than the current commitment of the chain.
+    ///
+    /// # Returns
+    /// * `Result<u64, CommitmentError>` - The block number of the message.
+    ///
+    /// # Errors
+    /// Returns an error if the message is malformed or not signed by the
+    /// chain's sequencer.
+    pub fn receive(
+        &self,
+        chain_id: u64,
+        message: &[u8],
+    ) -> Result<u64, CommitmentError> {
+        receive_gossip(&self.commitments, chain_id, message)
+    }
+}
+
+impl Drop for GossipCommitmentSource {
+    fn drop(&mut self) {
+        if let Some((_, server)) = &self.relay {
+            server.abort();
+        }
+        if let Some(subscription) = &self.subscription {
+            subscription.abort();
+        }
+    }
+}
+
+impl CommitmentSource for GossipCommitmentSource {
+    fn origin(&self, _chain_id: u64, _fallback: bool) -> String {
+        "gossip".to_string()
+    }
+
+    fn max_age(&self) -> Option<Duration> {
+        Some(self.max_age)
+    }
+
+    fn latest(
+        &self,
+        chain_id: u64,
+        fallback: bool,
+    ) -> BoxFuture<'_, Result<SequencerCommitment, CommitmentError>> {
+        Box::pin(async move {
+            sequencer_signer(chain_id)
+                .ok_or(CommitmentError::UnsupportedChain(chain_id))?;
+            self.commitments
+                .read()
+                .unwrap()
+                .get(&chain_id)
+                .map(|(_, commitment)| commitment.clone())
+                .ok_or_else(|| CommitmentError::Fetch {
+                    origin: self.origin(chain_id, fallback),
+                    chain_id,
+                    message: "no message received yet".to_string(),
+                })
+        })
+    }
+}
+
+fn receive_gossip(
+    commitments: &RwLock<HashMap<u64, (u64, SequencerCommitment)>>,
+    chain_id: u64,
+    message: &[u8],
+) -> Result<u64, CommitmentError> {
+    let commitment = SequencerCommitment::new(message).map_err(|e| {
+        CommitmentError::Malformed {
+            origin: "gossip".to_string(),
+            chain_id,
+            message: e.to_string(),
+        }
+    })?;
+    let block = check_commitment("gossip", chain_id, &commitment, None)?;
+
+    let mut commitments = commitments.write().unwrap();
+    if commitments
+        .get(&chain_id)
+        .is_none_or(|(latest, _)| block > *latest)
+    {
+        debug!("Gossip commitment for block {block} of chain {chain_id}");
+        commitments.insert(chain_id, (block, commitment));
+    }
+    Ok(block)
+}
+
+/// Parses the multiaddr of an op-node gossip peer.
+///
+/// # Returns
+/// * `Result<(PeerId, Multiaddr)>` - The peer ID and the address.
+///
+/// # Errors
+/// Returns an error if `peer` is not a multiaddr ending in a peer ID.
+pub(crate) fn parse_gossip_peer(peer: &str) -> Result<(PeerId, Multiaddr)> {
+    let addr: Multiaddr = peer
+        .parse()
+        .with_context(|| format!("invalid gossip peer {peer}"))?;
+    let peer_id = addr
+        .iter()
+        .find_map(|protocol| match protocol {
+            Protocol::P2p(peer_id) => Some(peer_id),
+            _ => None,
+        })
+        .with_context(|| format!("gossip peer {peer} has no peer ID"))?;
+    Ok((peer_id, addr))
+}
+
+/// Returns the gossip topic op-node publishes `blocks/v3` messages of
+/// `chain_id` on.
+fn blocks_v3_topic(chain_id: u64) -> IdentTopic {
+    IdentTopic::new(format!("/optimism/{chain_id}/2/blocks"))
+}
+
+/// Builds a gossipsub behaviour compatible with op-node: messages carry no
+/// author or signature, are identified like op-node identifies them, and
+/// are only forwarded once validated.
+fn gossip_behaviour() -> Result<gossipsub::Behaviour, BoxError> {
+    let config = gossipsub::ConfigBuilder::default()
+        .validation_mode(ValidationMode::Anonymous)
+        .validate_messages()
+        .message_id_fn(gossip_message_id)
+        .max_transmit_size(MAX_GOSSIP_SIZE)
+        .build()?;
+    Ok(gossipsub::Behaviour::new(
+        MessageAuthenticity::Anonymous,
+        config,
+    )?)
+}
+
+/// Returns the ID op-node gives a gossip message: the first 20 bytes of
+/// `sha256(domain ++ len(topic) ++ topic ++ data)`, where `data` is the
+/// decompressed message and `domain` is `1` if the message is valid snappy,
+/// or the raw message and `0` otherwise.
+fn gossip_message_id(message: &gossipsub::Message) -> MessageId {
+    let decompressed = snap::raw::decompress_len(&message.data)
+        .ok()
+        .filter(|len| *len <= MAX_GOSSIP_SIZE)
+        .and_then(|_| {
+            snap::raw::Decoder::new().decompress_vec(&message.data).ok()
+        });
+    let (domain, data) = match &decompressed {
+        Some(data) => ([1, 0, 0, 0], data.as_slice()),
+        None => ([0, 0, 0, 0], message.data.as_slice()),
+    };
+    let topic = message.topic.as_str().as_bytes();
+
+    let mut hasher = Sha256::new();
+    hasher.update(domain);
+    hasher.update((topic.len() as u64).to_le_bytes());
+    hasher.update(topic);
+    hasher.update(data);
+    MessageId::from(hasher.finalize()[..20].to_vec())
+}
+
+/// Checks the messages of the subscribed topics and reports the result to
+/// gossipsub, which only forwards accepted messages.
+fn handle_swarm_event(
+    swarm: &mut Swarm<gossipsub::Behaviour>,
+    topics: &HashMap<TopicHash, u64>,
+    commitments: &RwLock<HashMap<u64, (u64, SequencerCommitment)>>,
+    event: SwarmEvent<gossipsub::Event>,
+) {
+    match event {
+        SwarmEvent::Behaviour(gossipsub::Event::Message {
+            propagation_source,
+            message_id,
+            message,
+        }) => {
+            let acceptance = match topics.get(&message.topic) {
+                Some(&chain_id) => {
+                    match receive_gossip(commitments, chain_id, &message.data) {
+                        Ok(_) => MessageAcceptance::Accept,
+                        Err(e) => {
+                            debug!(
+                                "Rejected gossip message from \
+                                 {propagation_source}: {e}"
+                            );
+                            MessageAcceptance::Reject
+                        }
+                    }
+                }
+                None => MessageAcceptance::Ignore,
+            };
+            let _ = swarm.behaviour_mut().report_message_validation_result(
+                &message_id,
+                &propagation_source,
+                acceptance,
+            );
+        }
+        SwarmEvent::ConnectionEstablished { peer_id, .. } => {
+            debug!("Connected to gossip peer {peer_id}");
+        }
+        SwarmEvent::ConnectionClosed { peer_id, cause, .. } => {
+            debug!("Disconnected from gossip peer {peer_id}: {cause:?}");
+        }
+        SwarmEvent::OutgoingConnectionError { peer_id, error, .. } => {
+            warn!("Failed to connect to gossip peer {peer_id:?}: {error}");
+        }
+        _ => {}
+    }
+}
+
+async fn handle_gossip(
+    State(commitments): State<
+        Arc<RwLock<HashMap<u64, (u64, SequencerCommitment)>>>,
+    >,
+    Path(chain_id): Path<u64>,
+    body: Bytes,
+) -> Result<StatusCode, (StatusCode, String)> {
+    receive_gossip(&commitments, chain_id, &body)
+        .map(|_| StatusCode::NO_CONTENT)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))
+}
+
+/// Builds the commitment source selected by `config.commitments`.
+///
+/// The `http` source checks freshness unless the data source replays
+/// recorded responses.
+///
+/// # Errors
+/// Returns an error if the `file` source has no directory, or the `gossip`
+/// relay endpoint or subscription cannot be started.
+pub async fn commitment_source_from_config(
+    config: &MaldaConfig,
+    data_source: Arc<dyn ChainDataSource>,
+) -> Result<Arc<dyn CommitmentSource>> {
+    let max_age = Duration::from_secs(config.commitments.max_age_secs);
+
+    Ok(match config.commitments.source {
+        CommitmentSourceKind::Http => Arc::new(HttpCommitmentSource::new(
+            data_source,
+            (config.data_source.mode != DataSourceMode::Replay)
+                .then_some(max_age),
+        )),
+        CommitmentSourceKind::File => Arc::new(FileCommitmentSource::new(
+            config
+                .commitments
+                .dir
+                .clone()
+                .context("commitments.dir must be set")?,
+        )),
+        CommitmentSourceKind::Gossip => {
+            let mut source = match &config.commitments.gossip_listen_addr {
+                Some(addr) => {
+                    let addr = addr
+                        .parse()
+                        .context("invalid commitments.gossip_listen_addr")?;
+                    GossipCommitmentSource::listen(addr, max_age).await?
+                }
+                None => GossipCommitmentSource::new(max_age),
+            };
+            if !config.commitments.gossip_peers.is_empty() {
+                source.subscribe(&config.commitments.gossip_peers)?;
+            }
+            Arc::new(source)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloy::primitives::{Bytes, Signature, U256};
+
+    fn unsigned_commitment() -> SequencerCommitment {
+        SequencerCommitment {
+            data: Bytes::from(vec![0u8; 64]),
+            signature: Signature::new(U256::from(1), U256::from(1), false),
+        }
+    }
+
+    #[test]
+    fn test_check_rejects_commitment_not_signed_by_sequencer() {
+        let err = check_commitment(
+            "http:sequencer_base",
+            BASE_CHAIN_ID,
+            &unsigned_commitment(),
+            None,
+        )
+        .unwrap_err();
+
+        assert!(matches!(err, CommitmentError::Signature { .. }), "{err}");
+        assert!(err.to_string().starts_with("http:sequencer_base: "));
+
+        assert!(matches!(
+            check_commitment(
+                "test",
+                LINEA_CHAIN_ID,
+                &unsigned_commitment(),
+                None
+            ),
+            Err(CommitmentError::UnsupportedChain(LINEA_CHAIN_ID))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_file_source_errors_name_the_file() {
+        let dir = std::env::temp_dir()
+            .join(format!("malda-commitments-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join(format!("{OPTIMISM_CHAIN_ID}.json")),
+            serde_json::to_vec(&unsigned_commitment()).unwrap(),
+        )
+        .unwrap();
+        let source = FileCommitmentSource::new(&dir);
+
+        let commitment = source.latest(OPTIMISM_CHAIN_ID, false).await;
+        let missing = source.latest(BASE_CHAIN_ID, false).await.unwrap_err();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let origin = source.origin(OPTIMISM_CHAIN_ID, false);
+        let err = check_commitment(
+            &origin,
+            OPTIMISM_CHAIN_ID,
+            &commitment.unwrap(),
+            source.max_age(),
+        )
+        .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains(&format!("{OPTIMISM_CHAIN_ID}.json"))
+        );
+        assert!(
+            matches!(missing, CommitmentError::Fetch { .. }),
+            "{missing}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_gossip_source_rejects_invalid_messages() {
+        let source = GossipCommitmentSource::new(Duration::from_secs(60));
+
+        let err = source.receive(BASE_CHAIN_ID, b"not a message").unwrap_err();
+        assert!(matches!(err, CommitmentError::Malformed { .. }), "{err}");
+
+        let err = source.latest(BASE_CHAIN_ID, false).await.unwrap_err();
+        assert!(matches!(err, CommitmentError::Fetch { .. }), "{err}");
+    }
+
+    #[test]
+    fn test_gossip_message_id_matches_op_node() {
+        let message = |data: &[u8]| gossipsub::Message {
+            source: None,
+            data: data.to_vec(),
+            sequence_number: None,
+            topic: blocks_v3_topic(BASE_CHAIN_ID).hash(),
+        };
+
+        // Snappy literal "hello", hashed decompressed in the valid domain
+        assert_eq!(
+            gossip_message_id(&message(b"\x05\x10hello")),
+            MessageId::from(
+                hex::decode("d3c38450f34790e6d8fcd7b9cab8f996512cb586")
+                    .unwrap()
+            )
+        );
+        assert_eq!(
+            gossip_message_id(&message(b"\xff")),
+            MessageId::from(
+                hex::decode("3de6f96205902b23b87c31ec9a3932e5b83fa53a")
+                    .unwrap()
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_gossip_peer_requires_peer_id() {
+        let peer_id = PeerId::random();
+        let (parsed, _) = parse_gossip_peer(&format!(
+            "/ip4/127.0.0.1/tcp/9222/p2p/{peer_id}"
+        ))
+        .unwrap();
+        assert_eq!(parsed, peer_id);
+
+        assert!(parse_gossip_peer("/ip4/127.0.0.1/tcp/9222").is_err());
+        assert!(parse_gossip_peer("127.0.0.1:9222").is_err());
+    }
+}
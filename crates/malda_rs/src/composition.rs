@@ -0,0 +1,404 @@
+// Copyright (c) 2025 Merge Layers Inc.
+//
+// This source code is licensed under the Business Source License 1.1
+// (the "License"); you may not use this file except in compliance with the
+// License. You may obtain a copy of the License at
+//
+//     https://github.com/malda-protocol/malda-zk-coprocessor/blob/main/LICENSE-BSL
+//
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+//
+//! Proving the chains of a request separately and aggregating the receipts.
+//!
+//! In composition mode every chain of a request is proven on its own by the
+//! get_proof_data guest (see `viewcalls::get_chain_proof_data_inputs`),
+//! possibly in parallel on different provers. The aggregate_proof_data guest
+//! then verifies the per-chain receipts with `env::verify` against the
+//! get_proof_data image ID and concatenates their journals, see
+//! `journal::decode_aggregated_journal`.
+//!
+//! `ChainProofs` keeps the receipts of the chains already proven, so a chain
+//! that fails or times out is retried on its own instead of re-proving the
+//! whole request:
+//!
+//! ```ignore
+//! let mut proofs = ChainProofs::new(inputs);
+//! for _ in 0..attempts {
+//!     proofs.prove_pending(&chain_provers).await?;
+//!     if proofs.is_complete() {
+//!         break;
+//!     }
+//! }
+//! let result = proofs.aggregate(&*aggregator).await?;
+//! ```
+//!
+//! Receipts are verified by the aggregation guest as assumptions, which must
+//! be succinct (or fake, in dev mode). `chain_prover_from_config` builds a
+//! backend producing such receipts.
+
+use crate::commitments::CommitmentSource;
+use crate::config::{MaldaConfig, ProverKind};
+use crate::data_source::ChainDataSource;
+use crate::programs::GuestProgram;
+use crate::prover::{
+    LocalProver, ProofResult, ProverBackend, prover_for_program,
+};
+use crate::request::ProofDataRequest;
+use crate::viewcalls::{
+    ChainProofInput, ensure_program_supports, get_chain_proof_data_inputs,
+};
+
+use anyhow::{Context, Result, bail, ensure};
+use futures::future::join_all;
+use risc0_zkvm::ProverOpts;
+use tracing::{info, warn};
+
+use std::sync::Arc;
+
+/// The per-chain proofs of a request.
+pub struct ChainProofs {
+    chains: Vec<(ChainProofInput, Option<Result<ProofResult>>)>,
+    attempts: usize,
+}
+
+impl ChainProofs {
+    /// Creates the proofs of `inputs`, none of them proven yet.
+    pub fn new(inputs: Vec<ChainProofInput>) -> Self {
+        Self {
+            chains: inputs.into_iter().map(|input| (input, None)).collect(),
+            attempts: 0,
+        }
+    }
+
+    /// Proves every chain that has no receipt yet, in parallel.
+    ///
+    /// The pending chains are spread over `provers`, starting with another
+    /// prover on every call, so a chain that failed is retried on the next
+    /// prover. Failures are recorded per chain, see `failures`.
+    ///
+    /// # Errors
+    /// Returns an error if `provers` is empty or the provers run guests with
+    /// different image IDs.
+    pub async fn prove_pending(
+        &mut self,
+        provers: &[Arc<dyn ProverBackend>],
+    ) -> Result<()> {
+        let image_id = provers
+            .first()
+            .context("no chain prover")?
+            .program()
+            .image_id();
+        ensure!(
+            provers
+                .iter()
+                .all(|prover| prover.program().image_id() == image_id),
+            "the chain provers run guests with different image IDs"
+        );
+
+        let pending: Vec<usize> = (0..self.chains.len())
+            .filter(|&i| !matches!(self.chains[i].1, Some(Ok(_))))
+            .collect();
+        let futures = pending.iter().enumerate().map(|(n, &i)| {
+            let prover = provers[(n + self.attempts) % provers.len()].clone();
+            let ChainProofInput { chain_id, input } = self.chains[i].0.clone();
+            async move {
+                let start_time = std::time::Instant::now();
+                let result = prover.prove(input).await.and_then(|result| {
+                    ensure!(
+                        result.receipt.is_some(),
+                        "the {} prover returned no receipt",
+                        prover.name()
+                    );
+                    Ok(result)
+                });
+                match &result {
+                    Ok(_) => info!(
+                        "Chain {chain_id} {} prover time: {:?}",
+                        prover.name(),
+                        start_time.elapsed()
+                    ),
+                    Err(e) => warn!(
+                        "Chain {chain_id} failed on the {} prover: {e:#}",
+                        prover.name()
+                    ),
+                }
+                result
+            }
+        });
+        let results = join_all(futures).await;
+
+        for (i, result) in pending.into_iter().zip(results) {
+            self.chains[i].1 = Some(result);
+        }
+        self.attempts += 1;
+        Ok(())
+    }
+
+    /// Returns whether every chain has a receipt.
+    pub fn is_complete(&self) -> bool {
+        self.chains
+            .iter()
+            .all(|(_, result)| matches!(result, Some(Ok(_))))
+    }
+
+    /// Returns the chains whose last attempt failed, with the error.
+    pub fn failures(&self) -> Vec<(u64, &anyhow::Error)> {
+        self.chains
+            .iter()
+            .filter_map(|(input, result)| match result {
+                Some(Err(e)) => Some((input.chain_id, e)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Proves the aggregate_proof_data guest on the per-chain receipts.
+    ///
+    /// # Errors
+    /// Returns an error if a chain has no receipt, the receipts were
+    /// produced by guests with different image IDs, or `aggregator` fails.
+    pub async fn aggregate(
+        &self,
+        aggregator: &dyn ProverBackend,
+    ) -> Result<ProofResult> {
+        let mut image_id = None;
+        let mut journals = Vec::new();
+        let mut receipts = Vec::new();
+        for (input, result) in &self.chains {
+            let proof = match result {
+                Some(Ok(proof)) => proof,
+                Some(Err(e)) => bail!("chain {} failed: {e:#}", input.chain_id),
+                None => bail!("chain {} is not proven", input.chain_id),
+            };
+            ensure!(
+                image_id.is_none_or(|image_id| image_id == proof.image_id),
+                "the chain receipts have different image IDs"
+            );
+            image_id = Some(proof.image_id);
+            journals.push(proof.journal.to_vec());
+            receipts.push(
+                proof
+                    .receipt
+                    .clone()
+                    .context("chain proof has no receipt")?,
+            );
+        }
+        // The aggregator verifies the receipts against its compiled-in image
+        // ID, this only reports mixed guests before proving
+        image_id.context("no chains to aggregate")?;
+
+        let input: Vec<u8> = bytemuck::pod_collect_to_vec(
+            &risc0_zkvm::serde::to_vec(&journals)
+                .context("Failed to serialize aggregation input")?,
+        );
+        aggregator.prove_with_assumptions(input, receipts).await
+    }
+}
+
+/// Proves the chains of a request separately and aggregates the receipts.
+///
+/// Each chain is attempted up to `attempts` times; only the chains that
+/// failed are proven again.
+///
+/// # Arguments
+/// * `config` - Host configuration.
+/// * `data_source` - Source of the RPC and sequencer endpoints.
+/// * `commitments` - Source of the sequencer commitments.
+/// * `chain_provers` - Backends proving the get_proof_data guest per chain.
+/// * `aggregator` - Backend proving the aggregate_proof_data guest.
+/// * `request` - The validated proof data request.
+/// * `attempts` - Maximum number of attempts per chain.
+///
+/// # Returns
+/// * `Result<ProofResult>` - The aggregated journal, seal and image ID.
+///
+/// # Errors
+/// Returns an error if:
+/// - A chain is not supported by the chain provers' guest.
+/// - Any chain's input fails to build.
+/// - A chain still fails after `attempts` attempts.
+/// - The aggregation fails.
+pub async fn prove_composed(
+    config: &MaldaConfig,
+    data_source: Arc<dyn ChainDataSource>,
+    commitments: Arc<dyn CommitmentSource>,
+    chain_provers: &[Arc<dyn ProverBackend>],
+    aggregator: &dyn ProverBackend,
+    request: &ProofDataRequest,
+    attempts: usize,
+) -> Result<ProofResult> {
+    for prover in chain_provers {
+        ensure_program_supports(prover.program(), request)?;
+    }
+
+    let start_time = std::time::Instant::now();
+    let inputs =
+        get_chain_proof_data_inputs(config, data_source, commitments, request)
+            .await?;
+    info!("Input creation time: {:?}", start_time.elapsed());
+
+    let mut proofs = ChainProofs::new(inputs);
+    for _ in 0..attempts {
+        proofs.prove_pending(chain_provers).await?;
+        if proofs.is_complete() {
+            break;
+        }
+    }
+
+    let start_time = std::time::Instant::now();
+    let result = proofs.aggregate(aggregator).await?;
+    info!(
+        "{} aggregation time: {:?}",
+        aggregator.name(),
+        start_time.elapsed()
+    );
+    Ok(result)
+}
+
+/// Builds the backend selected by `config.prover` for proving single chains
+/// with the get_proof_data guest of the configured network profile.
+///
+/// The local prover produces succinct receipts, which the aggregation guest
+/// can verify.
+///
+/// # Errors
+/// Returns an error if the guest ELF cannot be loaded, or the selected
+/// backend cannot produce receipts the aggregation guest can verify: only
+/// `local` and `dev` can.
+pub fn chain_prover_from_config(
+    config: &MaldaConfig,
+) -> Result<Arc<dyn ProverBackend>> {
+    let program = GuestProgram::for_config(config)?;
+    match config.prover {
+        ProverKind::Local => Ok(Arc::new(LocalProver::with_opts(
+            program,
+            ProverOpts::succinct(),
+        ))),
+        ProverKind::Dev => prover_for_program(config, program),
+        ProverKind::Bonsai => bail!(
+            "the bonsai prover returns Groth16 receipts, which cannot be \
+             aggregated"
+        ),
+        ProverKind::Boundless => {
+            bail!("the boundless prover returns no receipts to aggregate")
+        }
+        ProverKind::Execute => {
+            bail!("the execute prover returns no receipts to aggregate")
+        }
+    }
+}
+
+/// Builds the backend selected by `config.prover` for the
+/// aggregate_proof_data guest of `config.guest_profile()`, loaded from
+/// `guest.elf_dir`.
+///
+/// # Errors
+/// Returns an error if the ELF cannot be loaded or the selected backend's
+/// settings are missing.
+pub fn aggregator_from_config(
+    config: &MaldaConfig,
+) -> Result<Arc<dyn ProverBackend>> {
+    let program = GuestProgram::aggregate_proof_data(
+        config.guest_profile(),
+        config.guest.elf_dir.as_deref(),
+    )?;
+    prover_for_program(config, program)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloy::primitives::Bytes;
+    use futures::future::BoxFuture;
+    use risc0_zkvm::{FakeReceipt, InnerReceipt, Receipt, ReceiptClaim};
+    use std::sync::Mutex;
+
+    /// Backend that fails on inputs listed in `fail` and records the
+    /// inputs it proved.
+    struct MockProver {
+        name: &'static str,
+        program: GuestProgram,
+        fail: Vec<Vec<u8>>,
+        proved: Mutex<Vec<Vec<u8>>>,
+    }
+
+    impl MockProver {
+        fn new(name: &'static str, fail: Vec<Vec<u8>>) -> Arc<Self> {
+            Arc::new(Self {
+                name,
+                program: GuestProgram::get_proof_data().unwrap(),
+                fail,
+                proved: Mutex::new(Vec::new()),
+            })
+        }
+    }
+
+    impl ProverBackend for MockProver {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        fn program(&self) -> &GuestProgram {
+            &self.program
+        }
+
+        fn prove(&self, input: Vec<u8>) -> BoxFuture<'_, Result<ProofResult>> {
+            Box::pin(async move {
+                ensure!(!self.fail.contains(&input), "prover unavailable");
+                self.proved.lock().unwrap().push(input.clone());
+                let image_id = self.program.image_id();
+                let claim = ReceiptClaim::ok(image_id, input.clone());
+                Ok(ProofResult {
+                    journal: Bytes::from(input.clone()),
+                    seal: Bytes::new(),
+                    image_id,
+                    stats: None,
+                    receipt: Some(Receipt::new(
+                        InnerReceipt::Fake(FakeReceipt::new(claim)),
+                        input,
+                    )),
+                })
+            })
+        }
+    }
+
+    fn input(chain_id: u64) -> ChainProofInput {
+        ChainProofInput {
+            chain_id,
+            input: vec![chain_id as u8],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_chain_is_retried_alone_on_next_prover() {
+        let flaky = MockProver::new("flaky", vec![vec![10]]);
+        let stable = MockProver::new("stable", Vec::new());
+        let provers: Vec<Arc<dyn ProverBackend>> =
+            vec![flaky.clone() as Arc<dyn ProverBackend>, stable.clone()];
+        let mut proofs = ChainProofs::new(vec![input(10), input(1)]);
+
+        proofs.prove_pending(&provers).await.unwrap();
+        assert!(!proofs.is_complete());
+        let failed: Vec<u64> =
+            proofs.failures().iter().map(|(id, _)| *id).collect();
+        assert_eq!(failed, vec![10]);
+
+        proofs.prove_pending(&provers).await.unwrap();
+        assert!(proofs.is_complete());
+        assert!(flaky.proved.lock().unwrap().is_empty());
+        assert_eq!(*stable.proved.lock().unwrap(), vec![vec![1], vec![10]]);
+    }
+
+    #[tokio::test]
+    async fn test_aggregate_requires_every_chain() {
+        let prover = MockProver::new("stable", Vec::new());
+        let proofs = ChainProofs::new(vec![input(1)]);
+
+        let err = proofs.aggregate(&*prover).await.unwrap_err();
+
+        assert!(err.to_string().contains("chain 1 is not proven"));
+    }
+}
@@ -0,0 +1,526 @@
+// Copyright (c) 2025 Merge Layers Inc.
+//
+// This source code is licensed under the Business Source License 1.1
+// (the "License"); you may not use this file except in compliance with the
+// License. You may obtain a copy of the License at
+//
+//     https://github.com/malda-protocol/malda-zk-coprocessor/blob/main/LICENSE-BSL
+//
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+//
+//! Chain data source that cross-checks several RPC endpoints per chain.
+//!
+//! `CrossCheckDataSource` serves every chain through a local proxy. The proxy
+//! sends each JSON-RPC request to all RPC endpoints of the chain and only
+//! answers if the endpoints agree:
+//!
+//! - Block responses are compared by block hash, every other response by its
+//!   full result, which covers account and storage proofs.
+//! - Block tags such as `latest` are resolved to the lowest block number
+//!   reported by the endpoints, so endpoints a few blocks apart still agree.
+//!   Endpoints further apart than `max_lag_blocks` are reported as lagging.
+//! - Endpoints that fail are skipped as long as `quorum` answers remain.
+//!
+//! A disagreement, a lagging endpoint or a missed quorum fails the request,
+//! and therefore the input builder, before any proving starts. Endpoints are
+//! identified by their config key (`rpc_url`, `rpc_url_fallback`,
+//! `rpc_urls_extra[i]`) in errors and logs, never by URL.
+//!
+//! Sequencer commitments are signed, and checked on the host by
+//! `commitments::check_commitment`, so sequencer requests go to the
+//! configured endpoint directly.
+
+use crate::config::{CrossCheckConfig, MaldaConfig};
+use crate::data_source::{ChainDataSource, endpoint_id};
+use crate::error::HostError;
+
+use anyhow::{Context, Result, bail};
+use axum::{
+    Router,
+    body::Bytes,
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::post,
+};
+use futures::future::join_all;
+use serde_json::{Value, json};
+use tokio::{net::TcpListener, task::JoinHandle};
+
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+/// Timeout of a single upstream request. A slow endpoint is treated like a
+/// failing one.
+const UPSTREAM_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Methods whose result is a block, compared by block hash.
+const BLOCK_METHODS: [&str; 2] = ["eth_getBlockByNumber", "eth_getBlockByHash"];
+
+/// Data source that sends every RPC request to all endpoints of a chain and
+/// rejects responses they disagree on.
+///
+/// Lookups of endpoints the config does not hold fail with
+/// `HostError::Config`, like `RpcDataSource`.
+pub struct CrossCheckDataSource {
+    config: Arc<MaldaConfig>,
+    proxy: CrossCheckProxy,
+}
+
+impl CrossCheckDataSource {
+    /// Starts a cross-checking proxy for the endpoints of `config`.
+    ///
+    /// # Errors
+    /// Returns an error if the proxy cannot be started.
+    pub async fn start(config: Arc<MaldaConfig>) -> Result<Self> {
+        let proxy =
+            CrossCheckProxy::start(config.data_source.cross_check.clone())
+                .await?;
+        Ok(Self { config, proxy })
+    }
+}
+
+impl ChainDataSource for CrossCheckDataSource {
+    /// Returns the proxy URL of a chain. `fallback` is ignored, as every
+    /// request already goes to the fallback endpoint as well.
+    fn rpc_url(
+        &self,
+        chain_name: &str,
+        _fallback: bool,
+        testnet: bool,
+    ) -> Result<String, HostError> {
+        let upstreams = self
+            .config
+            .rpc_urls(chain_name, testnet)
+            .map_err(|e| HostError::Config(format!("{e:#}")))?
+            .into_iter()
+            .map(str::to_string)
+            .collect();
+        Ok(self.proxy.register(
+            &endpoint_id("rpc", chain_name, false, testnet),
+            upstreams,
+        ))
+    }
+
+    fn sequencer_request_url(
+        &self,
+        chain_name: &str,
+        fallback: bool,
+        testnet: bool,
+    ) -> Result<String, HostError> {
+        self.config
+            .sequencer_request_url(chain_name, fallback, testnet)
+            .map(str::to_string)
+            .map_err(|e| HostError::Config(format!("{e:#}")))
+    }
+
+    /// Returns the configured beacon API URL. Beacon API requests are not
+    /// cross-checked; the beacon roots they return are verified against the
+    /// cross-checked execution headers in the guest.
+    fn beacon_api_url(&self, testnet: bool) -> Option<String> {
+        self.config.beacon_api_url(testnet).ok().map(str::to_string)
+    }
+}
+
+/// Local HTTP proxy that fans JSON-RPC requests out to several endpoints.
+struct CrossCheckProxy {
+    addr: SocketAddr,
+    state: Arc<ProxyState>,
+    server: JoinHandle<()>,
+}
+
+struct ProxyState {
+    settings: CrossCheckConfig,
+    upstreams: RwLock<HashMap<String, Arc<Vec<String>>>>,
+    client: reqwest::Client,
+}
+
+impl CrossCheckProxy {
+    async fn start(settings: CrossCheckConfig) -> Result<Self> {
+        let state = Arc::new(ProxyState {
+            settings,
+            upstreams: RwLock::new(HashMap::new()),
+            client: reqwest::Client::builder()
+                .timeout(UPSTREAM_TIMEOUT)
+                .build()?,
+        });
+        let app = Router::new()
+            .route("/{endpoint}", post(handle_request))
+            .with_state(state.clone());
+
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .context("failed to bind cross-check proxy")?;
+        let addr = listener.local_addr()?;
+        let server = tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, app).await {
+                tracing::error!("cross-check proxy stopped: {e}");
+            }
+        });
+
+        Ok(Self {
+            addr,
+            state,
+            server,
+        })
+    }
+
+    fn register(&self, endpoint: &str, upstreams: Vec<String>) -> String {
+        self.state
+            .upstreams
+            .write()
+            .expect("cross-check proxy lock poisoned")
+            .insert(endpoint.to_string(), Arc::new(upstreams));
+        format!("http://{}/{endpoint}", self.addr)
+    }
+}
+
+impl Drop for CrossCheckProxy {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+async fn handle_request(
+    State(state): State<Arc<ProxyState>>,
+    Path(endpoint): Path<String>,
+    body: Bytes,
+) -> Response {
+    match state.serve_json_rpc(&endpoint, &body).await {
+        Ok(body) => {
+            ([("content-type", "application/json")], body).into_response()
+        }
+        Err(e) => {
+            tracing::error!("cross-check failed on {endpoint}: {e:#}");
+            (StatusCode::BAD_GATEWAY, format!("{e:#}")).into_response()
+        }
+    }
+}
+
+/// Answer of one endpoint to a JSON-RPC call.
+enum Answer {
+    /// The call succeeded with this result.
+    Result(Value),
+    /// The endpoint answered with a JSON-RPC error object.
+    Error(Value),
+}
+
+impl ProxyState {
+    fn upstreams(&self, endpoint: &str) -> Result<Arc<Vec<String>>> {
+        self.upstreams
+            .read()
+            .expect("cross-check proxy lock poisoned")
+            .get(endpoint)
+            .cloned()
+            .with_context(|| format!("no upstreams registered for {endpoint}"))
+    }
+
+    /// Serves a single or batched JSON-RPC request.
+    async fn serve_json_rpc(
+        &self,
+        endpoint: &str,
+        body: &[u8],
+    ) -> Result<Vec<u8>> {
+        let request: Value = serde_json::from_slice(body)
+            .context("request is not valid JSON")?;
+        let response = match request {
+            Value::Array(calls) => {
+                let mut responses = Vec::with_capacity(calls.len());
+                for call in calls {
+                    responses.push(self.serve_call(endpoint, call).await?);
+                }
+                Value::Array(responses)
+            }
+            call => self.serve_call(endpoint, call).await?,
+        };
+        Ok(serde_json::to_vec(&response)?)
+    }
+
+    async fn serve_call(
+        &self,
+        endpoint: &str,
+        mut call: Value,
+    ) -> Result<Value> {
+        let upstreams = self.upstreams(endpoint)?;
+        let id = call.get("id").cloned().unwrap_or(Value::Null);
+        let method = call
+            .get("method")
+            .and_then(Value::as_str)
+            .context("call has no method")?
+            .to_string();
+
+        if method == "eth_blockNumber" {
+            let number = self.pin_block(endpoint, &upstreams, "latest").await?;
+            return Ok(json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "result": format!("{number:#x}"),
+            }));
+        }
+        if let Some(tag) = block_tag(&call) {
+            let number = self.pin_block(endpoint, &upstreams, &tag).await?;
+            call["params"][0] = json!(format!("{number:#x}"));
+        }
+
+        let answers = self.query_all(endpoint, &upstreams, &call).await?;
+        let (key, payload) = match agreed_answer(&method, &answers)? {
+            Answer::Result(result) => ("result", result),
+            Answer::Error(error) => ("error", error),
+        };
+        Ok(json!({ "jsonrpc": "2.0", "id": id, key: payload }))
+    }
+
+    /// Resolves a block tag to the lowest block number reported by the
+    /// endpoints.
+    async fn pin_block(
+        &self,
+        endpoint: &str,
+        upstreams: &[String],
+        tag: &str,
+    ) -> Result<u64> {
+        let call = json!({
+            "jsonrpc": "2.0",
+            "id": 0,
+            "method": "eth_getBlockByNumber",
+            "params": [tag, false],
+        });
+        let answers = self.query_all(endpoint, upstreams, &call).await?;
+        let numbers = answers
+            .iter()
+            .map(|(i, answer)| match answer {
+                Answer::Result(block) => block
+                    .get("number")
+                    .and_then(Value::as_str)
+                    .and_then(|n| parse_quantity(n))
+                    .map(|n| (*i, n))
+                    .with_context(|| {
+                        format!("{} returned no {tag} block", provider_name(*i))
+                    }),
+                Answer::Error(error) => bail!(
+                    "{} failed to resolve {tag}: {error}",
+                    provider_name(*i)
+                ),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        lowest_block(&numbers, self.settings.max_lag_blocks)
+            .with_context(|| format!("failed to resolve {tag} on {endpoint}"))
+    }
+
+    /// Sends `call` to every upstream and returns the answers of the
+    /// endpoints that responded, tagged with the endpoint index.
+    ///
+    /// Transport failures and JSON-RPC errors are skipped. If fewer than
+    /// `quorum` results remain, the JSON-RPC errors are returned instead, so
+    /// a request every endpoint rejects is answered with that rejection.
+    async fn query_all(
+        &self,
+        endpoint: &str,
+        upstreams: &[String],
+        call: &Value,
+    ) -> Result<Vec<(usize, Answer)>> {
+        let responses =
+            join_all(upstreams.iter().map(|url| self.query(url, call))).await;
+
+        let mut results = Vec::new();
+        let mut errors = Vec::new();
+        for (i, response) in responses.into_iter().enumerate() {
+            match response {
+                Ok(Answer::Result(result)) => {
+                    results.push((i, Answer::Result(result)))
+                }
+                Ok(Answer::Error(error)) => {
+                    tracing::warn!(
+                        "{} of {endpoint} returned an error: {error}",
+                        provider_name(i)
+                    );
+                    errors.push((i, Answer::Error(error)));
+                }
+                Err(e) => tracing::warn!(
+                    "{} of {endpoint} failed: {e:#}",
+                    provider_name(i)
+                ),
+            }
+        }
+
+        let quorum = self.settings.quorum;
+        if results.len() >= quorum {
+            Ok(results)
+        } else if errors.len() >= quorum {
+            Ok(errors)
+        } else {
+            bail!(
+                "only {} of {} endpoints of {endpoint} answered, quorum is \
+                 {quorum}",
+                results.len(),
+                upstreams.len()
+            )
+        }
+    }
+
+    async fn query(&self, url: &str, call: &Value) -> Result<Answer> {
+        let mut response: Value = self
+            .client
+            .post(url)
+            .json(call)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        if let Some(error) = response.get_mut("error") {
+            return Ok(Answer::Error(error.take()));
+        }
+        response
+            .get_mut("result")
+            .map(|result| Answer::Result(result.take()))
+            .context("response has neither result nor error")
+    }
+}
+
+/// Returns the block tag of a tagged `eth_getBlockByNumber` call, e.g.
+/// `latest`. Calls for an explicit block number return `None`.
+fn block_tag(call: &Value) -> Option<String> {
+    if call.get("method")?.as_str()? != "eth_getBlockByNumber" {
+        return None;
+    }
+    let block = call.get("params")?.get(0)?.as_str()?;
+    (!block.starts_with("0x")).then(|| block.to_string())
+}
+
+/// Returns the answer all endpoints agree on.
+///
+/// Blocks are compared by hash, as endpoints may differ in fields that are
+/// not covered by it. Every other result, including account and storage
+/// proofs, must match exactly. JSON-RPC errors are worded differently by
+/// different clients, so any error is accepted if every endpoint failed.
+///
+/// # Errors
+/// Returns an error naming two endpoints that disagree.
+fn agreed_answer(method: &str, answers: &[(usize, Answer)]) -> Result<Answer> {
+    let compared = |answer: &Answer| match answer {
+        Answer::Result(result) if BLOCK_METHODS.contains(&method) => {
+            result.get("hash").cloned()
+        }
+        Answer::Result(result) => Some(result.clone()),
+        Answer::Error(_) => None,
+    };
+
+    let (first_index, first) = answers.first().context("no answers")?;
+    let expected = compared(first);
+    for (i, answer) in &answers[1..] {
+        if compared(answer) != expected {
+            bail!(
+                "{} and {} disagree on {method}",
+                provider_name(*first_index),
+                provider_name(*i)
+            );
+        }
+    }
+
+    Ok(match first {
+        Answer::Result(result) => Answer::Result(result.clone()),
+        Answer::Error(error) => Answer::Error(error.clone()),
+    })
+}
+
+/// Returns the lowest of the block numbers reported by the endpoints.
+///
+/// # Errors
+/// Returns an error naming the lagging endpoint if the numbers are more than
+/// `max_lag_blocks` apart.
+fn lowest_block(numbers: &[(usize, u64)], max_lag_blocks: u64) -> Result<u64> {
+    let (lowest_index, lowest) = *numbers
+        .iter()
+        .min_by_key(|(_, number)| *number)
+        .context("no block numbers")?;
+    let highest = numbers.iter().map(|(_, number)| *number).max().unwrap_or(0);
+    if highest - lowest > max_lag_blocks {
+        bail!(
+            "{} lags {} blocks behind, at most {max_lag_blocks} allowed",
+            provider_name(lowest_index),
+            highest - lowest
+        );
+    }
+    Ok(lowest)
+}
+
+/// Returns the config key of the endpoint at `index` in
+/// `MaldaConfig::rpc_urls`.
+fn provider_name(index: usize) -> String {
+    match index {
+        0 => "rpc_url".to_string(),
+        1 => "rpc_url_fallback".to_string(),
+        i => format!("rpc_urls_extra[{}]", i - 2),
+    }
+}
+
+fn parse_quantity(quantity: &str) -> Option<u64> {
+    u64::from_str_radix(quantity.strip_prefix("0x")?, 16).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(hash: &str, size: &str) -> Answer {
+        Answer::Result(json!({ "hash": hash, "size": size }))
+    }
+
+    #[test]
+    fn test_blocks_are_compared_by_hash() {
+        let answers = [(0, block("0xaa", "0x1")), (1, block("0xaa", "0x2"))];
+        assert!(agreed_answer("eth_getBlockByNumber", &answers).is_ok());
+        // Any other method compares the full result
+        assert!(agreed_answer("eth_getProof", &answers).is_err());
+
+        let answers = [
+            (0, block("0xaa", "0x1")),
+            (1, block("0xaa", "0x1")),
+            (3, block("0xbb", "0x1")),
+        ];
+        let err = agreed_answer("eth_getBlockByNumber", &answers)
+            .err()
+            .unwrap()
+            .to_string();
+        assert_eq!(
+            err,
+            "rpc_url and rpc_urls_extra[1] disagree on eth_getBlockByNumber"
+        );
+    }
+
+    #[test]
+    fn test_lagging_endpoint_is_rejected() {
+        assert_eq!(
+            lowest_block(&[(0, 100), (1, 98), (2, 101)], 3).unwrap(),
+            98
+        );
+
+        let err = lowest_block(&[(0, 100), (1, 80)], 16).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "rpc_url_fallback lags 20 blocks behind, at most 16 allowed"
+        );
+    }
+
+    #[test]
+    fn test_only_tagged_block_requests_are_pinned() {
+        let call = |method: &str, block: &str| {
+            json!({
+                "method": method,
+                "params": [block, false],
+            })
+        };
+        assert_eq!(
+            block_tag(&call("eth_getBlockByNumber", "latest")).as_deref(),
+            Some("latest")
+        );
+        assert_eq!(block_tag(&call("eth_getBlockByNumber", "0x10")), None);
+        assert_eq!(block_tag(&call("eth_getBlockByHash", "latest")), None);
+    }
+}
@@ -0,0 +1,781 @@
+// Copyright (c) 2025 Merge Layers Inc.
+//
+// This source code is licensed under the Business Source License 1.1
+// (the "License"); you may not use this file except in compliance with the
+// License. You may obtain a copy of the License at
+//
+//     https://github.com/malda-protocol/malda-zk-coprocessor/blob/main/LICENSE-BSL
+//
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+//
+//! Chain data sources used by the host to reach RPC and sequencer endpoints.
+//!
+//! Every host builder in `viewcalls` resolves its endpoints through a
+//! `ChainDataSource` instead of reading URLs directly. Three implementations
+//! are provided here, and `cross_check::CrossCheckDataSource` adds a fourth:
+//!
+//! - `RpcDataSource`: talks to the configured endpoints directly.
+//! - `RecordingDataSource`: forwards every request to the configured
+//!   endpoints through a local proxy and writes each response to a fixture
+//!   directory.
+//! - `ReplayDataSource`: serves previously recorded fixtures from a local
+//!   proxy without touching the network.
+//!
+//! The proxies work at the HTTP level, so they cover both the JSON-RPC
+//! traffic issued by Steel and the plain `GET` requests used to fetch
+//! sequencer commitments. JSON-RPC requests are keyed by their method and
+//! parameters, ignoring the request ID, so replays are independent of the
+//! order in which the host issues requests. The beacon API, which historical
+//! blocks need, is served by a proxy of its own per network, since its
+//! clients request many paths below the endpoint URL; its `GET` requests are
+//! keyed by path and query.
+
+use crate::config::{DataSourceMode, MaldaConfig};
+use crate::cross_check::CrossCheckDataSource;
+use crate::error::HostError;
+
+use alloy::primitives::keccak256;
+use anyhow::{Context, Result, bail};
+use axum::{
+    Router,
+    body::Bytes,
+    extract::{Path, State},
+    http::{Method, StatusCode, Uri},
+    response::{IntoResponse, Response},
+    routing::any,
+};
+use serde_json::Value;
+use tokio::{net::TcpListener, task::JoinHandle};
+use url::Url;
+
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    path::PathBuf,
+    sync::{Arc, RwLock},
+};
+
+/// Source of the endpoints the host uses to fetch chain data.
+///
+/// Implementations return URLs rather than clients so that they can be used
+/// with every Steel builder, including the ones that only accept a URL.
+pub trait ChainDataSource: Send + Sync {
+    /// Returns the RPC URL for a chain.
+    ///
+    /// # Arguments
+    /// * `chain_name` - The chain name (e.g., "LINEA", "ETHEREUM", "BASE",
+    ///   "OPTIMISM")
+    /// * `fallback` - Whether to use the fallback endpoint
+    /// * `testnet` - Whether to use the testnet (Sepolia) endpoint
+    ///
+    /// # Errors
+    /// Returns `HostError::Config` if the endpoint is not configured.
+    fn rpc_url(
+        &self,
+        chain_name: &str,
+        fallback: bool,
+        testnet: bool,
+    ) -> Result<String, HostError>;
+
+    /// Returns the sequencer commitment URL for an L2 chain.
+    ///
+    /// # Arguments
+    /// * `chain_name` - The L2 chain name (e.g., "OPTIMISM", "BASE")
+    /// * `fallback` - Whether to use the fallback endpoint
+    /// * `testnet` - Whether to use the testnet (Sepolia) endpoint
+    ///
+    /// # Errors
+    /// Returns `HostError::Config` if the endpoint is not configured.
+    fn sequencer_request_url(
+        &self,
+        chain_name: &str,
+        fallback: bool,
+        testnet: bool,
+    ) -> Result<String, HostError>;
+
+    /// Returns the Ethereum beacon API URL used to query historical blocks,
+    /// or `None` if the source cannot serve beacon API requests.
+    ///
+    /// # Arguments
+    /// * `testnet` - Whether to use the testnet (Sepolia) endpoint
+    fn beacon_api_url(&self, _testnet: bool) -> Option<String> {
+        None
+    }
+}
+
+/// Data source that talks to the endpoints of a `MaldaConfig` directly.
+///
+/// Lookups of endpoints the config does not hold fail with
+/// `HostError::Config`.
+#[derive(Debug, Clone)]
+pub struct RpcDataSource {
+    config: Arc<MaldaConfig>,
+}
+
+impl RpcDataSource {
+    /// Creates a data source serving the endpoints of `config`.
+    pub fn new(config: Arc<MaldaConfig>) -> Self {
+        Self { config }
+    }
+}
+
+impl ChainDataSource for RpcDataSource {
+    fn rpc_url(
+        &self,
+        chain_name: &str,
+        fallback: bool,
+        testnet: bool,
+    ) -> Result<String, HostError> {
+        self.config
+            .rpc_url(chain_name, fallback, testnet)
+            .map(str::to_string)
+            .map_err(|e| HostError::Config(format!("{e:#}")))
+    }
+
+    fn sequencer_request_url(
+        &self,
+        chain_name: &str,
+        fallback: bool,
+        testnet: bool,
+    ) -> Result<String, HostError> {
+        self.config
+            .sequencer_request_url(chain_name, fallback, testnet)
+            .map(str::to_string)
+            .map_err(|e| HostError::Config(format!("{e:#}")))
+    }
+
+    fn beacon_api_url(&self, testnet: bool) -> Option<String> {
+        self.config.beacon_api_url(testnet).ok().map(str::to_string)
+    }
+}
+
+/// Data source that records every response served by another data source.
+///
+/// Requests are forwarded through a local proxy to the endpoints returned by
+/// the wrapped source, and each response is written to the fixture
+/// directory so it can later be served by `ReplayDataSource`.
+pub struct RecordingDataSource {
+    inner: Arc<dyn ChainDataSource>,
+    proxy: FixtureProxy,
+    beacon: BeaconProxies,
+}
+
+impl RecordingDataSource {
+    /// Starts a recording proxy in front of `inner`.
+    ///
+    /// # Arguments
+    /// * `inner` - The data source whose responses are recorded.
+    /// * `fixture_dir` - Directory the fixtures are written to.
+    ///
+    /// # Errors
+    /// Returns an error if the fixture directory cannot be created or the
+    /// proxy cannot be started.
+    pub async fn start(
+        inner: Arc<dyn ChainDataSource>,
+        fixture_dir: impl Into<PathBuf>,
+    ) -> Result<Self> {
+        let fixture_dir = fixture_dir.into();
+        std::fs::create_dir_all(&fixture_dir).with_context(|| {
+            format!("failed to create fixture dir {}", fixture_dir.display())
+        })?;
+        let proxy =
+            FixtureProxy::start(fixture_dir.clone(), true, None).await?;
+        let beacon = BeaconProxies::start(fixture_dir, true).await?;
+        Ok(Self {
+            inner,
+            proxy,
+            beacon,
+        })
+    }
+}
+
+impl ChainDataSource for RecordingDataSource {
+    fn rpc_url(
+        &self,
+        chain_name: &str,
+        fallback: bool,
+        testnet: bool,
+    ) -> Result<String, HostError> {
+        let upstream = self.inner.rpc_url(chain_name, fallback, testnet)?;
+        Ok(self.proxy.register(
+            &endpoint_id("rpc", chain_name, fallback, testnet),
+            upstream,
+        ))
+    }
+
+    fn sequencer_request_url(
+        &self,
+        chain_name: &str,
+        fallback: bool,
+        testnet: bool,
+    ) -> Result<String, HostError> {
+        let upstream = self
+            .inner
+            .sequencer_request_url(chain_name, fallback, testnet)?;
+        Ok(self.proxy.register(
+            &endpoint_id("sequencer", chain_name, fallback, testnet),
+            upstream,
+        ))
+    }
+
+    fn beacon_api_url(&self, testnet: bool) -> Option<String> {
+        let upstream = self.inner.beacon_api_url(testnet)?;
+        let proxy = self.beacon.get(testnet);
+        Some(proxy.register(&beacon_endpoint_id(testnet), upstream))
+    }
+}
+
+/// Data source that serves fixtures written by `RecordingDataSource`.
+///
+/// Requests without a matching fixture are answered with an error, so a
+/// replay never falls back to the network.
+pub struct ReplayDataSource {
+    proxy: FixtureProxy,
+    beacon: BeaconProxies,
+}
+
+impl ReplayDataSource {
+    /// Starts a replay proxy serving the fixtures in `fixture_dir`.
+    ///
+    /// # Errors
+    /// Returns an error if the fixture directory does not exist or the proxy
+    /// cannot be started.
+    pub async fn start(fixture_dir: impl Into<PathBuf>) -> Result<Self> {
+        let fixture_dir = fixture_dir.into();
+        if !fixture_dir.is_dir() {
+            bail!("fixture dir {} does not exist", fixture_dir.display());
+        }
+        let proxy =
+            FixtureProxy::start(fixture_dir.clone(), false, None).await?;
+        let beacon = BeaconProxies::start(fixture_dir, false).await?;
+        Ok(Self { proxy, beacon })
+    }
+}
+
+impl ChainDataSource for ReplayDataSource {
+    fn rpc_url(
+        &self,
+        chain_name: &str,
+        fallback: bool,
+        testnet: bool,
+    ) -> Result<String, HostError> {
+        Ok(self
+            .proxy
+            .url(&endpoint_id("rpc", chain_name, fallback, testnet)))
+    }
+
+    fn sequencer_request_url(
+        &self,
+        chain_name: &str,
+        fallback: bool,
+        testnet: bool,
+    ) -> Result<String, HostError> {
+        Ok(self.proxy.url(&endpoint_id(
+            "sequencer",
+            chain_name,
+            fallback,
+            testnet,
+        )))
+    }
+
+    fn beacon_api_url(&self, testnet: bool) -> Option<String> {
+        Some(self.beacon.get(testnet).url(&beacon_endpoint_id(testnet)))
+    }
+}
+
+/// Builds the data source selected by `config.data_source`.
+///
+/// # Errors
+/// Returns an error if the fixture directory is missing in `record` or
+/// `replay` mode, or a proxy cannot be started.
+pub async fn data_source_from_config(
+    config: Arc<MaldaConfig>,
+) -> Result<Arc<dyn ChainDataSource>> {
+    let fixture_dir = || {
+        config
+            .data_source
+            .fixture_dir
+            .clone()
+            .context("data_source.fixture_dir must be set")
+    };
+
+    Ok(match config.data_source.mode {
+        DataSourceMode::Live => Arc::new(RpcDataSource::new(config)),
+        DataSourceMode::Record => {
+            let fixture_dir = fixture_dir()?;
+            Arc::new(
+                RecordingDataSource::start(
+                    Arc::new(RpcDataSource::new(config)),
+                    fixture_dir,
+                )
+                .await?,
+            )
+        }
+        DataSourceMode::Replay => {
+            Arc::new(ReplayDataSource::start(fixture_dir()?).await?)
+        }
+        DataSourceMode::CrossCheck => {
+            Arc::new(CrossCheckDataSource::start(config).await?)
+        }
+    })
+}
+
+/// Returns the fixture subdirectory name for an endpoint.
+///
+/// The name is derived from the endpoint kind and chain, never from the URL,
+/// so API keys embedded in RPC URLs don't end up in fixtures.
+pub(crate) fn endpoint_id(
+    kind: &str,
+    chain_name: &str,
+    fallback: bool,
+    testnet: bool,
+) -> String {
+    let testnet_suffix = if testnet { "_sepolia" } else { "" };
+    let fallback_suffix = if fallback { "_fallback" } else { "" };
+    format!(
+        "{kind}_{}{testnet_suffix}{fallback_suffix}",
+        chain_name.to_lowercase()
+    )
+}
+
+/// Returns the fixture subdirectory name of the Ethereum beacon API.
+fn beacon_endpoint_id(testnet: bool) -> String {
+    endpoint_id("beacon", "ETHEREUM", false, testnet)
+}
+
+/// Beacon API proxies of mainnet and Sepolia.
+struct BeaconProxies {
+    mainnet: FixtureProxy,
+    sepolia: FixtureProxy,
+}
+
+impl BeaconProxies {
+    async fn start(fixture_dir: PathBuf, record: bool) -> Result<Self> {
+        Ok(Self {
+            mainnet: FixtureProxy::start(
+                fixture_dir.clone(),
+                record,
+                Some(beacon_endpoint_id(false)),
+            )
+            .await?,
+            sepolia: FixtureProxy::start(
+                fixture_dir,
+                record,
+                Some(beacon_endpoint_id(true)),
+            )
+            .await?,
+        })
+    }
+
+    fn get(&self, testnet: bool) -> &FixtureProxy {
+        if testnet {
+            &self.sepolia
+        } else {
+            &self.mainnet
+        }
+    }
+}
+
+/// Local HTTP proxy that records or replays fixtures.
+///
+/// A proxy either serves several single-path endpoints at `/{endpoint}`, or
+/// one REST endpoint, e.g. the beacon API, at its root.
+struct FixtureProxy {
+    addr: SocketAddr,
+    state: Arc<ProxyState>,
+    server: JoinHandle<()>,
+}
+
+struct ProxyState {
+    fixture_dir: PathBuf,
+    record: bool,
+    /// The endpoint served at the root, if any.
+    root_endpoint: Option<String>,
+    upstreams: RwLock<HashMap<String, String>>,
+    client: reqwest::Client,
+}
+
+impl FixtureProxy {
+    async fn start(
+        fixture_dir: PathBuf,
+        record: bool,
+        root_endpoint: Option<String>,
+    ) -> Result<Self> {
+        let rooted = root_endpoint.is_some();
+        let state = Arc::new(ProxyState {
+            fixture_dir,
+            record,
+            root_endpoint,
+            upstreams: RwLock::new(HashMap::new()),
+            client: reqwest::Client::new(),
+        });
+        let app = if rooted {
+            Router::new().fallback(handle_rooted_request)
+        } else {
+            Router::new().route("/{endpoint}", any(handle_request))
+        }
+        .with_state(state.clone());
+
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .context("failed to bind fixture proxy")?;
+        let addr = listener.local_addr()?;
+        let server = tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, app).await {
+                tracing::error!("fixture proxy stopped: {e}");
+            }
+        });
+
+        Ok(Self {
+            addr,
+            state,
+            server,
+        })
+    }
+
+    fn url(&self, endpoint: &str) -> String {
+        if self.state.root_endpoint.is_some() {
+            format!("http://{}", self.addr)
+        } else {
+            format!("http://{}/{endpoint}", self.addr)
+        }
+    }
+
+    fn register(&self, endpoint: &str, upstream: String) -> String {
+        self.state
+            .upstreams
+            .write()
+            .expect("fixture proxy lock poisoned")
+            .insert(endpoint.to_string(), upstream);
+        self.url(endpoint)
+    }
+}
+
+impl Drop for FixtureProxy {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+async fn handle_request(
+    State(state): State<Arc<ProxyState>>,
+    Path(endpoint): Path<String>,
+    method: Method,
+    body: Bytes,
+) -> Response {
+    let result = if method == Method::GET {
+        state.serve_get(&endpoint).await
+    } else {
+        state.serve_json_rpc(&endpoint, &body).await
+    };
+    fixture_response(&endpoint, result)
+}
+
+async fn handle_rooted_request(
+    State(state): State<Arc<ProxyState>>,
+    method: Method,
+    uri: Uri,
+) -> Response {
+    let endpoint = state
+        .root_endpoint
+        .clone()
+        .expect("rooted proxies have a root endpoint");
+    let result = if method == Method::GET {
+        let path = uri.path_and_query().map_or("/", |path| path.as_str());
+        state.serve_rest_get(&endpoint, path).await
+    } else {
+        Err(anyhow::anyhow!("only GET is supported on {endpoint}"))
+    };
+    fixture_response(&endpoint, result)
+}
+
+fn fixture_response(endpoint: &str, result: Result<Vec<u8>>) -> Response {
+    match result {
+        Ok(body) => {
+            ([("content-type", "application/json")], body).into_response()
+        }
+        Err(e) => {
+            tracing::error!("fixture proxy error on {endpoint}: {e:#}");
+            (StatusCode::BAD_GATEWAY, format!("{e:#}")).into_response()
+        }
+    }
+}
+
+impl ProxyState {
+    fn upstream(&self, endpoint: &str) -> Result<String> {
+        self.upstreams
+            .read()
+            .expect("fixture proxy lock poisoned")
+            .get(endpoint)
+            .cloned()
+            .with_context(|| format!("no upstream registered for {endpoint}"))
+    }
+
+    fn fixture_path(&self, endpoint: &str, key: &str) -> PathBuf {
+        self.fixture_dir.join(endpoint).join(format!("{key}.json"))
+    }
+
+    fn read_fixture(&self, endpoint: &str, key: &str) -> Result<Vec<u8>> {
+        let path = self.fixture_path(endpoint, key);
+        std::fs::read(&path)
+            .with_context(|| format!("no fixture at {}", path.display()))
+    }
+
+    fn write_fixture(
+        &self,
+        endpoint: &str,
+        key: &str,
+        body: &[u8],
+    ) -> Result<()> {
+        let path = self.fixture_path(endpoint, key);
+        std::fs::create_dir_all(path.parent().expect("fixture has a parent"))?;
+        std::fs::write(&path, body)
+            .with_context(|| format!("failed to write {}", path.display()))
+    }
+
+    /// Serves a plain `GET`, e.g. a sequencer commitment request.
+    async fn serve_get(&self, endpoint: &str) -> Result<Vec<u8>> {
+        if !self.record {
+            return self.read_fixture(endpoint, "get");
+        }
+        let body = self
+            .client
+            .get(self.upstream(endpoint)?)
+            .send()
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?
+            .to_vec();
+        self.write_fixture(endpoint, "get", &body)?;
+        Ok(body)
+    }
+
+    /// Serves a `GET` of `path` below a REST endpoint, e.g. a beacon API
+    /// request, keyed by the path and query.
+    async fn serve_rest_get(
+        &self,
+        endpoint: &str,
+        path: &str,
+    ) -> Result<Vec<u8>> {
+        let key = format!("get_{}", hex::encode(keccak256(path)));
+        if !self.record {
+            return self.read_fixture(endpoint, &key);
+        }
+        // Relative to the upstream URL, which may hold an API key in its path
+        let mut upstream = Url::parse(&self.upstream(endpoint)?)?;
+        if !upstream.path().ends_with('/') {
+            upstream.set_path(&format!("{}/", upstream.path()));
+        }
+        let body = self
+            .client
+            .get(upstream.join(path.trim_start_matches('/'))?)
+            .send()
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?
+            .to_vec();
+        self.write_fixture(endpoint, &key, &body)?;
+        Ok(body)
+    }
+
+    /// Serves a single or batched JSON-RPC request.
+    async fn serve_json_rpc(
+        &self,
+        endpoint: &str,
+        body: &[u8],
+    ) -> Result<Vec<u8>> {
+        let request: Value = serde_json::from_slice(body)
+            .context("request is not valid JSON")?;
+        let response = match request {
+            Value::Array(calls) => {
+                let mut responses = Vec::with_capacity(calls.len());
+                for call in calls {
+                    responses.push(self.serve_call(endpoint, call).await?);
+                }
+                Value::Array(responses)
+            }
+            call => self.serve_call(endpoint, call).await?,
+        };
+        Ok(serde_json::to_vec(&response)?)
+    }
+
+    async fn serve_call(&self, endpoint: &str, call: Value) -> Result<Value> {
+        let id = call.get("id").cloned().unwrap_or(Value::Null);
+        let key = call_key(&call)?;
+
+        let mut response: Value = if self.record {
+            let response: Value = self
+                .client
+                .post(self.upstream(endpoint)?)
+                .json(&call)
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await?;
+            let mut fixture = response.clone();
+            if let Some(fixture) = fixture.as_object_mut() {
+                fixture.remove("id");
+            }
+            self.write_fixture(endpoint, &key, &serde_json::to_vec(&fixture)?)?;
+            response
+        } else {
+            serde_json::from_slice(&self.read_fixture(endpoint, &key)?)?
+        };
+
+        if let Some(response) = response.as_object_mut() {
+            response.insert("id".to_string(), id);
+        }
+        Ok(response)
+    }
+}
+
+/// Returns the fixture key for a JSON-RPC call: the hash of the compact JSON
+/// of the call without its request ID and with sorted object keys, so keys
+/// don't depend on the field order of the client or on `serde_json`'s
+/// `preserve_order` feature.
+fn call_key(call: &Value) -> Result<String> {
+    let mut call = call.clone();
+    if let Some(call) = call.as_object_mut() {
+        call.remove("id");
+    }
+    Ok(hex::encode(keccak256(serde_json::to_vec(&sorted_keys(
+        &call,
+    ))?)))
+}
+
+/// Returns `value` with the keys of every object in sorted order.
+fn sorted_keys(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key.clone(), sorted_keys(value)))
+                    .collect(),
+            )
+        }
+        Value::Array(values) => {
+            Value::Array(values.iter().map(sorted_keys).collect())
+        }
+        value => value.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_call_key_ignores_request_id() {
+        let a = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "eth_blockNumber",
+            "params": []
+        });
+        let b = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 42,
+            "method": "eth_blockNumber",
+            "params": []
+        });
+        let c = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "eth_chainId",
+            "params": []
+        });
+
+        assert_eq!(call_key(&a).unwrap(), call_key(&b).unwrap());
+        assert_ne!(call_key(&a).unwrap(), call_key(&c).unwrap());
+        // keccak256 of {"jsonrpc":"2.0","method":"eth_blockNumber","params":[]}
+        assert_eq!(
+            call_key(&a).unwrap(),
+            "0777dacd3af2dfcf01fb8bda06f600ac654e7091d55b7f5d1253b7a3e114baf0"
+        );
+    }
+
+    #[test]
+    fn test_endpoint_id_does_not_leak_url() {
+        assert_eq!(
+            endpoint_id("rpc", "ETHEREUM", true, true),
+            "rpc_ethereum_sepolia_fallback"
+        );
+        assert_eq!(
+            endpoint_id("sequencer", "OPTIMISM", false, false),
+            "sequencer_optimism"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_beacon_api_requests_are_replayed() {
+        // Mock beacon API echoing the requested path and query
+        let app = Router::new().fallback(|uri: Uri| async move {
+            format!("{{\"uri\":\"{uri}\"}}")
+        });
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/key/", listener.local_addr().unwrap());
+        let beacon = tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        let mut config = MaldaConfig::default();
+        config.mainnet.ethereum.beacon_api_url = Some(url);
+        let fixture_dir = std::env::temp_dir()
+            .join(format!("malda-beacon-fixtures-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&fixture_dir);
+        let get = |base: String, path: &str| {
+            let url = format!("{base}{path}");
+            async move { reqwest::get(url).await.unwrap() }
+        };
+        let path = "/eth/v1/beacon/headers/123?finalized=true";
+
+        let recording = RecordingDataSource::start(
+            Arc::new(RpcDataSource::new(Arc::new(config))),
+            &fixture_dir,
+        )
+        .await
+        .unwrap();
+        assert!(recording.beacon_api_url(true).is_none());
+        let recorded = get(recording.beacon_api_url(false).unwrap(), path)
+            .await
+            .text()
+            .await
+            .unwrap();
+        assert_eq!(
+            recorded,
+            r#"{"uri":"/key/eth/v1/beacon/headers/123?finalized=true"}"#
+        );
+        beacon.abort();
+        drop(recording);
+
+        let replay = ReplayDataSource::start(&fixture_dir).await.unwrap();
+        let base = replay.beacon_api_url(false).unwrap();
+        let replayed = get(base.clone(), path).await.text().await.unwrap();
+        assert_eq!(replayed, recorded);
+        let missing = get(base, "/eth/v1/beacon/headers/124").await;
+        assert_eq!(missing.status(), StatusCode::BAD_GATEWAY);
+        std::fs::remove_dir_all(&fixture_dir).unwrap();
+    }
+
+    #[test]
+    fn test_missing_endpoint_is_a_config_error() {
+        let source = RpcDataSource::new(Arc::new(MaldaConfig::default()));
+
+        assert!(matches!(
+            source.rpc_url("ETHEREUM", false, false),
+            Err(HostError::Config(_))
+        ));
+        assert!(matches!(
+            source.sequencer_request_url("OPTIMISM", true, true),
+            Err(HostError::Config(_))
+        ));
+    }
+}
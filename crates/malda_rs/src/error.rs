@@ -0,0 +1,122 @@
+// Copyright (c) 2025 Merge Layers Inc.
+//
+// This source code is licensed under the Business Source License 1.1
+// (the "License"); you may not use this file except in compliance with the
+// License. You may obtain a copy of the License at
+//
+//     https://github.com/malda-protocol/malda-zk-coprocessor/blob/main/LICENSE-BSL
+//
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+//
+//! Errors returned by the host input builders.
+//!
+//! Every builder in `viewcalls` returns `Result<_, HostError>`. A builder
+//! either produces a complete guest input or an error; it never returns an
+//! input with missing chains or fields.
+
+use thiserror::Error;
+
+/// Boxed error source, e.g. an `anyhow::Error` returned by Steel.
+pub type BoxError = Box<dyn std::error::Error + Send + Sync + 'static>;
+
+/// Error returned by the host input builders.
+#[derive(Debug, Error)]
+pub enum HostError {
+    /// An RPC request, or a Steel preflight backed by RPC requests, failed.
+    #[error("{context}: {source}")]
+    Rpc {
+        context: String,
+        #[source]
+        source: BoxError,
+    },
+
+    /// Fetching or converting a sequencer commitment failed.
+    #[error("{context}: {source}")]
+    Sequencer {
+        context: String,
+        #[source]
+        source: BoxError,
+    },
+
+    /// Data returned by an endpoint could not be decoded.
+    #[error("{context}: {source}")]
+    Decode {
+        context: String,
+        #[source]
+        source: BoxError,
+    },
+
+    /// The guest input could not be serialized.
+    #[error("{context}: {source}")]
+    Encode {
+        context: String,
+        #[source]
+        source: BoxError,
+    },
+
+    /// The configuration does not provide what the request needs.
+    #[error("invalid configuration: {0}")]
+    Config(String),
+
+    /// The request is malformed or not supported, e.g. mismatched argument
+    /// lengths or L1 inclusion for a chain without L1 inclusion support.
+    #[error("{0}")]
+    InvalidRequest(String),
+
+    /// On-chain state failed a host-side check, e.g. a dispute game that is
+    /// not resolved in favor of the defender.
+    #[error("{0}")]
+    Validation(String),
+
+    /// A spawned builder task panicked or was cancelled.
+    #[error("builder task failed: {0}")]
+    Join(#[from] tokio::task::JoinError),
+}
+
+impl HostError {
+    /// Creates an `Rpc` error.
+    pub fn rpc(
+        context: impl Into<String>,
+        source: impl Into<BoxError>,
+    ) -> Self {
+        Self::Rpc {
+            context: context.into(),
+            source: source.into(),
+        }
+    }
+
+    /// Creates a `Sequencer` error.
+    pub fn sequencer(
+        context: impl Into<String>,
+        source: impl Into<BoxError>,
+    ) -> Self {
+        Self::Sequencer {
+            context: context.into(),
+            source: source.into(),
+        }
+    }
+
+    /// Creates a `Decode` error.
+    pub fn decode(
+        context: impl Into<String>,
+        source: impl Into<BoxError>,
+    ) -> Self {
+        Self::Decode {
+            context: context.into(),
+            source: source.into(),
+        }
+    }
+
+    /// Creates an `Encode` error.
+    pub fn encode(
+        context: impl Into<String>,
+        source: impl Into<BoxError>,
+    ) -> Self {
+        Self::Encode {
+            context: context.into(),
+            source: source.into(),
+        }
+    }
+}
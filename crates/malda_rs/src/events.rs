@@ -0,0 +1,204 @@
+// Copyright (c) 2025 Merge Layers Inc.
+//
+// This source code is licensed under the Business Source License 1.1
+// (the "License"); you may not use this file except in compliance with the
+// License. You may obtain a copy of the License at
+//
+//     https://github.com/malda-protocol/malda-zk-coprocessor/blob/main/LICENSE-BSL
+//
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+//
+//! Host builders for event proofs.
+//!
+//! Resolves the `EventQuery`s of a chain to the supported logs they select,
+//! fetches all receipts of the blocks holding them with
+//! `debug_getRawReceipts`, and builds the `receipts::EventInput` the guest
+//! validates against the view call block. The RPC endpoint of the chain must
+//! serve the `debug` namespace.
+
+use crate::error::HostError;
+use crate::receipts::{
+    BlockReceiptProofs, EventInput, ReceiptProof, build_receipt_proofs,
+    is_supported_event,
+};
+use crate::request::EventQuery;
+use crate::viewcalls::parse_rpc_url;
+
+use alloy::{
+    eips::BlockNumberOrTag,
+    primitives::Bytes,
+    providers::{Provider, ProviderBuilder},
+};
+use alloy_consensus::Header;
+use futures::future::join_all;
+use risc0_steel::serde::RlpHeader;
+
+use std::collections::BTreeMap;
+
+/// Builds the event proofs of a chain.
+///
+/// # Arguments
+/// * `rpc_url` - RPC URL of the chain, serving `debug_getRawReceipts`.
+/// * `view_call_block` - Number of the view call block, the newest block
+///   events can be proven in.
+/// * `events` - The chain's event queries.
+///
+/// # Returns
+/// * `Result<Option<EventInput>, HostError>` - The event input, or `None`
+///   if `events` is empty.
+///
+/// # Errors
+/// Returns an error if:
+/// - A transaction is not found, or is newer than `view_call_block`.
+/// - A query selects no supported event.
+/// - The receipts returned by the RPC do not match the block's receipts
+///   root.
+/// - RPC calls fail.
+pub async fn get_event_input(
+    rpc_url: &str,
+    view_call_block: u64,
+    events: &[EventQuery],
+) -> Result<Option<EventInput>, HostError> {
+    if events.is_empty() {
+        return Ok(None);
+    }
+    let provider = ProviderBuilder::new().connect_http(parse_rpc_url(rpc_url)?);
+
+    // Selected log indices by block and transaction index
+    let mut selected: BTreeMap<u64, BTreeMap<u64, Vec<u64>>> = BTreeMap::new();
+    for event in events {
+        let receipt = provider
+            .get_transaction_receipt(event.tx_hash)
+            .await
+            .map_err(|e| HostError::rpc("Failed to fetch receipt", e))?
+            .ok_or_else(|| {
+                HostError::InvalidRequest(format!(
+                    "transaction {} not found",
+                    event.tx_hash
+                ))
+            })?;
+        let (Some(block_number), Some(tx_index)) =
+            (receipt.block_number, receipt.transaction_index)
+        else {
+            return Err(HostError::InvalidRequest(format!(
+                "transaction {} is pending",
+                event.tx_hash
+            )));
+        };
+        if block_number > view_call_block {
+            return Err(HostError::InvalidRequest(format!(
+                "transaction {} in block {block_number} is newer than the \
+                 view call block {view_call_block}",
+                event.tx_hash
+            )));
+        }
+
+        let log_indices: Vec<u64> = receipt
+            .inner
+            .logs()
+            .iter()
+            .enumerate()
+            .filter(|(_, log)| {
+                log.address() == event.emitter && is_supported_event(&log.inner)
+            })
+            .map(|(i, _)| i as u64)
+            .collect();
+        if log_indices.is_empty() {
+            return Err(HostError::InvalidRequest(format!(
+                "no supported event of {} in transaction {}",
+                event.emitter, event.tx_hash
+            )));
+        }
+        let selected_logs = selected
+            .entry(block_number)
+            .or_default()
+            .entry(tx_index)
+            .or_default();
+        selected_logs.extend(log_indices);
+        selected_logs.sort_unstable();
+        selected_logs.dedup();
+    }
+
+    let mut blocks = Vec::with_capacity(selected.len());
+    for (block_number, transactions) in selected {
+        blocks.push(
+            get_block_receipt_proofs(&provider, block_number, transactions)
+                .await?,
+        );
+    }
+
+    // Link the oldest block to the view call block
+    let oldest = blocks[0].block_number;
+    let futures: Vec<_> = (oldest..view_call_block)
+        .map(|block_number| get_header(&provider, block_number))
+        .collect();
+    let ancestors = join_all(futures)
+        .await
+        .into_iter()
+        .map(|header| header.map(RlpHeader::new))
+        .collect::<Result<_, _>>()?;
+
+    Ok(Some(EventInput { ancestors, blocks }))
+}
+
+/// Builds the receipt proofs of the selected transactions of one block.
+async fn get_block_receipt_proofs(
+    provider: &impl Provider,
+    block_number: u64,
+    transactions: BTreeMap<u64, Vec<u64>>,
+) -> Result<BlockReceiptProofs, HostError> {
+    let receipts: Vec<Bytes> = provider
+        .raw_request(
+            "debug_getRawReceipts".into(),
+            (BlockNumberOrTag::Number(block_number),),
+        )
+        .await
+        .map_err(|e| HostError::rpc("Failed to fetch raw receipts", e))?;
+    let header = get_header(provider, block_number).await?;
+
+    let tx_indices: Vec<u64> = transactions.keys().copied().collect();
+    if let Some(index) =
+        tx_indices.iter().find(|i| **i as usize >= receipts.len())
+    {
+        return Err(HostError::Validation(format!(
+            "block {block_number} has no receipt {index}"
+        )));
+    }
+    let (receipts_root, proofs) = build_receipt_proofs(&receipts, &tx_indices);
+    if receipts_root != header.receipts_root {
+        return Err(HostError::Validation(format!(
+            "receipts of block {block_number} do not match its receipts root"
+        )));
+    }
+
+    Ok(BlockReceiptProofs {
+        block_number,
+        receipts: transactions
+            .into_iter()
+            .zip(proofs)
+            .map(|((tx_index, log_indices), proof)| ReceiptProof {
+                tx_index,
+                receipt: receipts[tx_index as usize].clone(),
+                proof,
+                log_indices,
+            })
+            .collect(),
+    })
+}
+
+/// Fetches the header of a block.
+async fn get_header(
+    provider: &impl Provider,
+    block_number: u64,
+) -> Result<Header, HostError> {
+    provider
+        .get_block_by_number(BlockNumberOrTag::Number(block_number))
+        .await
+        .map_err(|e| HostError::rpc("Failed to fetch block", e))?
+        .map(|block| block.header.inner)
+        .ok_or_else(|| {
+            HostError::InvalidRequest(format!("block {block_number} not found"))
+        })
+}
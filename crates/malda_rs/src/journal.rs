@@ -0,0 +1,351 @@
+// Copyright (c) 2025 Merge Layers Inc.
+//
+// This source code is licensed under the Business Source License 1.1
+// (the "License"); you may not use this file except in compliance with the
+// License. You may obtain a copy of the License at
+//
+//     https://github.com/malda-protocol/malda-zk-coprocessor/blob/main/LICENSE-BSL
+//
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+//
+//! Decoding of the get_proof_data journal.
+//!
+//! The guest commits `(bytes[] output, bytes32[] finality_policy_hashes,
+//! bytes[] events)`, ABI-encoded as parameters. Each output entry is the
+//! packed encoding of one query result, as written by
+//! `validators::validate_get_proof_data_call`:
+//!
+//! | Field             | Type    | Bytes |
+//! |-------------------|---------|-------|
+//! | `user`            | address | 20    |
+//! | `market`          | address | 20    |
+//! | `amount_in`       | uint256 | 32    |
+//! | `amount_out`      | uint256 | 32    |
+//! | `chain_id`        | uint32  | 4     |
+//! | `target_chain_id` | uint32  | 4     |
+//! | `l1_inclusion`    | bool    | 1     |
+//!
+//! Each event is an ABI-encoded `receipts::ProvenEvent`.
+//!
+//! The aggregate_proof_data guest (see `composition`) commits the same
+//! fields, concatenated over the per-chain journals, with the image ID of
+//! the get_proof_data guest it verified them against before the events.
+
+use crate::error::HostError;
+use crate::receipts::ProvenEvent;
+
+use alloy::primitives::{Address, B256, Bytes, U256};
+use alloy_sol_types::SolValue;
+use serde::Serialize;
+
+/// Length of a packed journal entry in bytes.
+pub const JOURNAL_ENTRY_LEN: usize = 20 + 20 + 32 + 32 + 4 + 4 + 1;
+
+/// A decoded get_proof_data journal.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Journal {
+    /// One entry per query, in the order of the request's chains and queries.
+    pub entries: Vec<JournalEntry>,
+    /// Hash of each chain's enforced finality policy and chain ID, see
+    /// `FinalityPolicy::hash`, in the order of the request's chains.
+    pub finality_policy_hashes: Vec<B256>,
+    /// Proven events, in the order of the request's chains and blocks.
+    pub events: Vec<JournalEvent>,
+}
+
+/// A decoded aggregate_proof_data journal.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AggregatedJournal {
+    /// The concatenated entries and finality policy hashes of the chains.
+    pub journal: Journal,
+    /// Image ID of the guest the per-chain receipts were verified against.
+    pub image_id: B256,
+}
+
+/// Result of one proof data query.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct JournalEntry {
+    pub user: Address,
+    pub market: Address,
+    pub amount_in: U256,
+    pub amount_out: U256,
+    pub chain_id: u32,
+    pub target_chain_id: u32,
+    pub l1_inclusion: bool,
+}
+
+/// A log proven to be emitted in a verified block.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct JournalEvent {
+    pub chain_id: u32,
+    pub block_number: u64,
+    pub block_hash: B256,
+    pub tx_index: u32,
+    /// Index of the log within its receipt.
+    pub log_index: u32,
+    /// Contract that emitted the log, always one of the markets queried on
+    /// the chain.
+    pub emitter: Address,
+    pub topics: Vec<B256>,
+    pub data: Bytes,
+}
+
+/// Decodes a journal committed by the get_proof_data guest.
+///
+/// # Arguments
+/// * `journal` - The raw journal bytes.
+///
+/// # Returns
+/// * `Result<Journal, HostError>` - The decoded entries, finality policy
+///   hashes and events.
+///
+/// # Errors
+/// Returns a `Decode` error if the journal is not ABI-encoded as
+/// `(bytes[], bytes32[], bytes[])`, an entry does not have the packed layout
+/// or an event is not an encoded `ProvenEvent`.
+pub fn decode_journal(journal: &[u8]) -> Result<Journal, HostError> {
+    let (output, finality_policy_hashes, events) =
+        <(Vec<Bytes>, Vec<B256>, Vec<Bytes>)>::abi_decode_params(journal)
+            .map_err(|e| HostError::decode("Failed to decode journal", e))?;
+    Journal::new(output, finality_policy_hashes, events)
+}
+
+/// Decodes a journal committed by the aggregate_proof_data guest.
+///
+/// # Arguments
+/// * `journal` - The raw journal bytes.
+///
+/// # Returns
+/// * `Result<AggregatedJournal, HostError>` - The decoded entries, finality
+///   policy hashes, events and per-chain image ID.
+///
+/// # Errors
+/// Returns a `Decode` error if the journal is not ABI-encoded as
+/// `(bytes[], bytes32[], bytes32, bytes[])`, an entry does not have the
+/// packed layout or an event is not an encoded `ProvenEvent`.
+pub fn decode_aggregated_journal(
+    journal: &[u8],
+) -> Result<AggregatedJournal, HostError> {
+    let (output, finality_policy_hashes, image_id, events) =
+        <(Vec<Bytes>, Vec<B256>, B256, Vec<Bytes>)>::abi_decode_params(journal)
+            .map_err(|e| {
+                HostError::decode("Failed to decode aggregated journal", e)
+            })?;
+    Ok(AggregatedJournal {
+        journal: Journal::new(output, finality_policy_hashes, events)?,
+        image_id,
+    })
+}
+
+impl Journal {
+    /// Decodes the packed `output` entries and the `events`.
+    fn new(
+        output: Vec<Bytes>,
+        finality_policy_hashes: Vec<B256>,
+        events: Vec<Bytes>,
+    ) -> Result<Self, HostError> {
+        let entries = output
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| {
+                JournalEntry::decode(entry).map_err(|e| {
+                    HostError::decode(format!("Invalid journal entry {i}"), e)
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        let events = events
+            .iter()
+            .enumerate()
+            .map(|(i, event)| {
+                ProvenEvent::abi_decode(event)
+                    .map(JournalEvent::from)
+                    .map_err(|e| {
+                        HostError::decode(
+                            format!("Invalid journal event {i}"),
+                            e,
+                        )
+                    })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            entries,
+            finality_policy_hashes,
+            events,
+        })
+    }
+}
+
+impl From<ProvenEvent> for JournalEvent {
+    fn from(event: ProvenEvent) -> Self {
+        Self {
+            chain_id: event.chainId,
+            block_number: event.blockNumber,
+            block_hash: event.blockHash,
+            tx_index: event.txIndex,
+            log_index: event.logIndex,
+            emitter: event.emitter,
+            topics: event.topics,
+            data: event.data,
+        }
+    }
+}
+
+impl JournalEntry {
+    /// Decodes one packed journal entry.
+    ///
+    /// # Errors
+    /// Returns an error if `bytes` is not `JOURNAL_ENTRY_LEN` bytes long or
+    /// the L1 inclusion flag is neither 0 nor 1.
+    pub fn decode(bytes: &[u8]) -> Result<Self, String> {
+        if bytes.len() != JOURNAL_ENTRY_LEN {
+            return Err(format!(
+                "expected {JOURNAL_ENTRY_LEN} bytes, got {}",
+                bytes.len()
+            ));
+        }
+        let (user, rest) = bytes.split_at(20);
+        let (market, rest) = rest.split_at(20);
+        let (amount_in, rest) = rest.split_at(32);
+        let (amount_out, rest) = rest.split_at(32);
+        let (chain_id, rest) = rest.split_at(4);
+        let (target_chain_id, rest) = rest.split_at(4);
+        let l1_inclusion = match rest[0] {
+            0 => false,
+            1 => true,
+            flag => return Err(format!("invalid l1_inclusion flag {flag}")),
+        };
+
+        Ok(Self {
+            user: Address::from_slice(user),
+            market: Address::from_slice(market),
+            amount_in: U256::from_be_slice(amount_in),
+            amount_out: U256::from_be_slice(amount_out),
+            chain_id: u32::from_be_bytes(chain_id.try_into().unwrap()),
+            target_chain_id: u32::from_be_bytes(
+                target_chain_id.try_into().unwrap(),
+            ),
+            l1_inclusion,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{SolidityDataType, TakeLastXBytes, abi};
+
+    fn packed_entry(entry: &JournalEntry) -> Bytes {
+        let (bytes, _) = abi::encode_packed(&[
+            SolidityDataType::Address(entry.user),
+            SolidityDataType::Address(entry.market),
+            SolidityDataType::Number(entry.amount_in),
+            SolidityDataType::Number(entry.amount_out),
+            SolidityDataType::NumberWithShift(
+                U256::from(entry.chain_id),
+                TakeLastXBytes(32),
+            ),
+            SolidityDataType::NumberWithShift(
+                U256::from(entry.target_chain_id),
+                TakeLastXBytes(32),
+            ),
+            SolidityDataType::Bool(entry.l1_inclusion),
+        ]);
+        bytes.into()
+    }
+
+    #[test]
+    fn test_decode_journal_round_trips_guest_encoding() {
+        let entries = vec![
+            JournalEntry {
+                user: Address::repeat_byte(0x11),
+                market: Address::repeat_byte(0x22),
+                amount_in: U256::from(1_000u64),
+                amount_out: U256::MAX,
+                chain_id: 59144,
+                target_chain_id: 8453,
+                l1_inclusion: true,
+            },
+            JournalEntry {
+                user: Address::repeat_byte(0x33),
+                market: Address::repeat_byte(0x44),
+                amount_in: U256::ZERO,
+                amount_out: U256::from(7u64),
+                chain_id: 10,
+                target_chain_id: 1,
+                l1_inclusion: false,
+            },
+        ];
+        let hashes = vec![B256::repeat_byte(0xaa)];
+        let event = ProvenEvent {
+            chainId: 59144,
+            blockNumber: 100,
+            blockHash: B256::repeat_byte(0xbb),
+            txIndex: 3,
+            logIndex: 1,
+            emitter: Address::repeat_byte(0x22),
+            topics: vec![B256::repeat_byte(0xcc)],
+            data: Bytes::from(vec![1, 2, 3]),
+        };
+        let output: Vec<Bytes> = entries.iter().map(packed_entry).collect();
+        let events = vec![Bytes::from(event.abi_encode())];
+        let journal = (output, hashes.clone(), events).abi_encode_params();
+
+        let decoded = decode_journal(&journal).unwrap();
+
+        assert_eq!(decoded.entries, entries);
+        assert_eq!(decoded.finality_policy_hashes, hashes);
+        assert_eq!(decoded.events, vec![JournalEvent::from(event)]);
+    }
+
+    #[test]
+    fn test_decode_journal_rejects_malformed_entries() {
+        let short = vec![Bytes::from(vec![0u8; JOURNAL_ENTRY_LEN - 1])];
+        let journal = (short, Vec::<B256>::new(), Vec::<Bytes>::new())
+            .abi_encode_params();
+        assert!(matches!(
+            decode_journal(&journal),
+            Err(HostError::Decode { .. })
+        ));
+
+        let event = vec![Bytes::from(vec![0u8; 31])];
+        let journal = (Vec::<Bytes>::new(), Vec::<B256>::new(), event)
+            .abi_encode_params();
+        assert!(matches!(
+            decode_journal(&journal),
+            Err(HostError::Decode { .. })
+        ));
+
+        assert!(decode_journal(&[0u8; 7]).is_err());
+    }
+
+    #[test]
+    fn test_decode_aggregated_journal() {
+        let entry = JournalEntry {
+            user: Address::repeat_byte(0x11),
+            market: Address::repeat_byte(0x22),
+            amount_in: U256::from(5u64),
+            amount_out: U256::ZERO,
+            chain_id: 10,
+            target_chain_id: 59144,
+            l1_inclusion: false,
+        };
+        let hashes = vec![B256::repeat_byte(0xaa), B256::repeat_byte(0xbb)];
+        let image_id = B256::repeat_byte(0xcc);
+        let journal = (
+            vec![packed_entry(&entry)],
+            hashes.clone(),
+            image_id,
+            Vec::<Bytes>::new(),
+        )
+            .abi_encode_params();
+
+        let decoded = decode_aggregated_journal(&journal).unwrap();
+
+        assert_eq!(decoded.journal.entries, vec![entry]);
+        assert_eq!(decoded.journal.finality_policy_hashes, hashes);
+        assert_eq!(decoded.image_id, image_id);
+    }
+}
@@ -14,8 +14,42 @@
 //! Code for host/client and zkVM guest program including constants,
 //! view calls, cryptographic operations, type definitions, and validation logic.
 
+pub mod commitments;
+
+pub mod composition;
+
+pub mod config;
+
 pub mod constants;
 
+pub mod cross_check;
+
+pub mod data_source;
+
+pub mod error;
+
+pub mod events;
+
+pub mod journal;
+
+pub mod profile;
+
+pub mod programs;
+
+pub mod prover;
+
+pub mod request;
+
+pub mod request_cache;
+
+pub mod service;
+
+pub mod snapshot;
+
+pub mod storage_proofs;
+
+pub mod verify;
+
 pub mod viewcalls;
 
 #[path = "../../malda_utils/src/cryptography.rs"]
@@ -27,4 +61,10 @@ pub mod types;
 #[path = "../../malda_utils/src/validators.rs"]
 pub mod validators;
 
+#[path = "../../malda_utils/src/receipts.rs"]
+pub mod receipts;
+
+#[path = "../../malda_utils/src/storage.rs"]
+pub mod storage;
+
 pub mod elfs_ids;
@@ -0,0 +1,406 @@
+// Copyright (c) 2025 Merge Layers Inc.
+//
+// This source code is licensed under the Business Source License 1.1
+// (the "License"); you may not use this file except in compliance with the
+// License. You may obtain a copy of the License at
+//
+//     https://github.com/malda-protocol/malda-zk-coprocessor/blob/main/LICENSE-BSL
+//
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+//
+//! Cycle profiling of the get_proof_data guest.
+//!
+//! Proving cost grows with the cycles the guest executes. `profile_input`
+//! executes a serialized input and breaks its cycles down by L1 anchor, chain
+//! and `ProfileStage`, from the markers the guest writes to stderr when it is
+//! built with its `profile` feature (`just build-guest-profile`). Other
+//! builds write no markers, so only the session totals are reported.
+//!
+//! Profiles of recorded inputs (see `snapshot`) are kept in a `Baseline`.
+//! `Baseline::regressions` lists every total, chain and stage that costs
+//! more than the baseline allows, so a benchmark run (`just bench`) fails
+//! when a change makes the guest more expensive.
+
+use crate::programs::GuestProgram;
+use crate::prover::session_stats;
+use crate::types::{PROFILE_MARKER, ProfileStage};
+
+use anyhow::{Context, Result, bail};
+use risc0_zkvm::{ExecutorEnv, default_executor};
+use serde::{Deserialize, Serialize};
+
+use std::{collections::BTreeMap, fmt, path::Path};
+
+/// Cycles of one execution of the guest.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GuestProfile {
+    /// Number of segments.
+    pub segments: usize,
+    /// Cycles executed by the guest.
+    pub user_cycles: u64,
+    /// Cycles proven, i.e. the sum of the padded segment sizes.
+    pub total_cycles: u64,
+    /// Breakdown per L1 anchor, in input order, see `types::L1Anchor`. Empty
+    /// unless the guest was built with the `profile` feature.
+    #[serde(default)]
+    pub l1_anchors: Vec<ChainProfile>,
+    /// Breakdown per chain, in input order. Empty unless the guest was
+    /// built with the `profile` feature.
+    pub chains: Vec<ChainProfile>,
+}
+
+/// Cycles the guest spent on one chain or L1 anchor of the input.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainProfile {
+    pub chain_id: u64,
+    /// Cycles from reading the chain's input to committing its journal
+    /// entries.
+    pub cycles: u64,
+    /// Cycles per stage. A stage that runs more than once, e.g. the
+    /// environment decoding of a chain and of its L1, is summed.
+    pub stages: BTreeMap<ProfileStage, u64>,
+}
+
+impl ChainProfile {
+    /// Returns the cycles not attributed to any stage, e.g. reading the
+    /// input and committing the journal.
+    pub fn other_cycles(&self) -> u64 {
+        self.cycles.saturating_sub(self.stages.values().sum())
+    }
+}
+
+/// Executes `input` with `program` and returns its profile.
+///
+/// The guest's stdout is discarded, and stderr lines other than profiling
+/// markers are forwarded to stderr.
+///
+/// # Errors
+/// Returns an error if the execution fails or the guest writes malformed
+/// markers.
+pub fn profile_input(
+    program: &GuestProgram,
+    input: &[u8],
+) -> Result<GuestProfile> {
+    let mut stderr = Vec::new();
+    let env = ExecutorEnv::builder()
+        .write_slice(input)
+        .stdout(std::io::sink())
+        .stderr(&mut stderr)
+        .build()
+        .context("Failed to build executor env")?;
+    let session = default_executor()
+        .execute(env, program.elf())
+        .context("Failed to execute ZKVM")?;
+    let stats = session_stats(&session);
+
+    let stderr = String::from_utf8_lossy(&stderr);
+    for line in stderr.lines() {
+        if !line.starts_with(PROFILE_MARKER) {
+            eprintln!("{line}");
+        }
+    }
+
+    Ok(GuestProfile {
+        segments: stats.segments,
+        user_cycles: stats.user_cycles,
+        total_cycles: stats.total_cycles,
+        ..parse_markers(&stderr)?
+    })
+}
+
+/// Parses the profiling markers in the guest's stderr into the anchor and
+/// chain profiles of a `GuestProfile`, leaving its totals at zero.
+///
+/// A stage is charged the cycles since the previous marker of its anchor or
+/// chain. Lines that are not markers are ignored.
+///
+/// # Errors
+/// Returns an error if a marker is malformed or out of order.
+pub fn parse_markers(stderr: &str) -> Result<GuestProfile> {
+    let mut profile = GuestProfile::default();
+    // Whether an anchor is being read, its start cycle count, last marked
+    // cycle count and stages.
+    let mut current: Option<(bool, u64, u64, BTreeMap<ProfileStage, u64>)> =
+        None;
+
+    for line in stderr.lines() {
+        let Some(marker) = line
+            .strip_prefix(PROFILE_MARKER)
+            .and_then(|rest| rest.strip_prefix(' '))
+        else {
+            continue;
+        };
+        let fields: Vec<&str> = marker.split_whitespace().collect();
+        let Some((cycles, event)) = fields.split_last() else {
+            bail!("empty profiling marker");
+        };
+        let cycles: u64 = cycles
+            .parse()
+            .with_context(|| format!("invalid cycle count in {line:?}"))?;
+
+        match event {
+            ["anchor"] => {
+                current = Some((true, cycles, cycles, BTreeMap::new()))
+            }
+            ["chain"] => {
+                current = Some((false, cycles, cycles, BTreeMap::new()))
+            }
+            ["stage", name] => {
+                let Some((_, _, last, stages)) = current.as_mut() else {
+                    bail!("stage marker outside of a chain: {line:?}");
+                };
+                let stage = ProfileStage::from_name(name)
+                    .with_context(|| format!("unknown stage {name:?}"))?;
+                *stages.entry(stage).or_default() +=
+                    cycles.saturating_sub(*last);
+                *last = cycles;
+            }
+            ["end", ids @ ..] => {
+                let (is_anchor, chain_id) = match ids {
+                    ["anchor", chain_id] => (true, chain_id),
+                    [chain_id] => (false, chain_id),
+                    _ => bail!("invalid profiling marker: {line:?}"),
+                };
+                let Some((open_anchor, start, _, stages)) = current.take()
+                else {
+                    bail!("end marker outside of a chain: {line:?}");
+                };
+                if open_anchor != is_anchor {
+                    bail!("end marker does not match its start: {line:?}");
+                }
+                let section = ChainProfile {
+                    chain_id: chain_id.parse().with_context(|| {
+                        format!("invalid chain ID {line:?}")
+                    })?,
+                    cycles: cycles.saturating_sub(start),
+                    stages,
+                };
+                if is_anchor {
+                    profile.l1_anchors.push(section);
+                } else {
+                    profile.chains.push(section);
+                }
+            }
+            _ => bail!("invalid profiling marker: {line:?}"),
+        }
+    }
+
+    if current.is_some() {
+        bail!("profiling markers end inside a chain");
+    }
+    Ok(profile)
+}
+
+/// Profiles of named benchmark inputs, saved as a JSON object.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Baseline {
+    pub profiles: BTreeMap<String, GuestProfile>,
+}
+
+/// A cost that exceeds its baseline.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Regression {
+    /// What got more expensive, e.g. `base: chain 8453 signatures`.
+    pub metric: String,
+    pub baseline: u64,
+    pub current: u64,
+}
+
+impl fmt::Display for Regression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let increase =
+            (self.current as f64 / self.baseline as f64 - 1.0) * 100.0;
+        write!(
+            f,
+            "{}: {} -> {} cycles (+{increase:.1}%)",
+            self.metric, self.baseline, self.current
+        )
+    }
+}
+
+impl Baseline {
+    /// Reads a baseline from `path`.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read or parsed.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        serde_json::from_slice(&contents)
+            .with_context(|| format!("invalid baseline {}", path.display()))
+    }
+
+    /// Writes the baseline to `path`.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be written.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let contents = serde_json::to_vec_pretty(self)?;
+        std::fs::write(path, contents)
+            .with_context(|| format!("failed to write {}", path.display()))
+    }
+
+    /// Compares `profiles` with the baseline.
+    ///
+    /// Only benchmarks, chains and stages present in both are compared, so
+    /// adding a benchmark is not a regression. Chains are matched by
+    /// position, as an input may hold the same chain more than once.
+    ///
+    /// # Arguments
+    /// * `profiles` - Current profiles, by benchmark name.
+    /// * `tolerance` - Allowed increase over the baseline, in percent.
+    ///
+    /// # Returns
+    /// Every cost that exceeds its baseline by more than `tolerance`.
+    pub fn regressions(
+        &self,
+        profiles: &BTreeMap<String, GuestProfile>,
+        tolerance: f64,
+    ) -> Vec<Regression> {
+        let mut regressions = Vec::new();
+        let mut check = |metric: String, baseline: u64, current: u64| {
+            if current as f64 > baseline as f64 * (1.0 + tolerance / 100.0) {
+                regressions.push(Regression {
+                    metric,
+                    baseline,
+                    current,
+                });
+            }
+        };
+
+        for (name, current) in profiles {
+            let Some(baseline) = self.profiles.get(name) else {
+                continue;
+            };
+            check(
+                format!("{name}: user cycles"),
+                baseline.user_cycles,
+                current.user_cycles,
+            );
+            check(
+                format!("{name}: total cycles"),
+                baseline.total_cycles,
+                current.total_cycles,
+            );
+            let sections = [
+                ("anchor", &baseline.l1_anchors, &current.l1_anchors),
+                ("chain", &baseline.chains, &current.chains),
+            ];
+            for (kind, base_chains, chains) in sections {
+                for (base_chain, chain) in base_chains.iter().zip(chains) {
+                    if base_chain.chain_id != chain.chain_id {
+                        continue;
+                    }
+                    let chain_name =
+                        format!("{name}: {kind} {}", chain.chain_id);
+                    check(chain_name.clone(), base_chain.cycles, chain.cycles);
+                    for (stage, cycles) in &chain.stages {
+                        if let Some(base_cycles) = base_chain.stages.get(stage)
+                        {
+                            check(
+                                format!("{chain_name} {}", stage.name()),
+                                *base_cycles,
+                                *cycles,
+                            );
+                        }
+                    }
+                }
+            }
+        }
+        regressions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_markers_charges_stages_since_previous_marker() {
+        let stderr = "\
+            malda-profile anchor 10\n\
+            malda-profile stage env_decode 40\n\
+            malda-profile stage signatures 90\n\
+            malda-profile end anchor 1 100\n\
+            malda-profile chain 100\n\
+            some guest output\n\
+            malda-profile stage env_decode 400\n\
+            malda-profile stage signatures 1000\n\
+            malda-profile stage env_decode 1100\n\
+            malda-profile stage multicall 1500\n\
+            malda-profile end 8453 1600\n\
+            malda-profile chain 1600\n\
+            malda-profile stage env_decode 1700\n\
+            malda-profile end 1 1750\n";
+
+        let profile = parse_markers(stderr).unwrap();
+
+        let anchors = &profile.l1_anchors;
+        assert_eq!(anchors.len(), 1);
+        assert_eq!(anchors[0].chain_id, 1);
+        assert_eq!(anchors[0].cycles, 90);
+        assert_eq!(anchors[0].stages[&ProfileStage::Signatures], 50);
+        let chains = &profile.chains;
+        assert_eq!(chains.len(), 2);
+        assert_eq!(chains[0].chain_id, 8453);
+        assert_eq!(chains[0].cycles, 1500);
+        assert_eq!(chains[0].stages[&ProfileStage::EnvDecode], 400);
+        assert_eq!(chains[0].stages[&ProfileStage::Signatures], 600);
+        assert_eq!(chains[0].stages[&ProfileStage::Multicall], 400);
+        assert_eq!(chains[0].other_cycles(), 100);
+        assert_eq!(chains[1].chain_id, 1);
+        assert_eq!(chains[1].cycles, 150);
+
+        assert!(parse_markers("malda-profile stage env_decode 10\n").is_err());
+        assert!(parse_markers("malda-profile chain 10\n").is_err());
+        let mismatched =
+            "malda-profile chain 10\nmalda-profile end anchor 1 20\n";
+        assert!(parse_markers(mismatched).is_err());
+    }
+
+    #[test]
+    fn test_regressions_respect_tolerance() {
+        let profile = |user_cycles, signatures| GuestProfile {
+            segments: 1,
+            user_cycles,
+            total_cycles: 1 << 20,
+            l1_anchors: Vec::new(),
+            chains: vec![ChainProfile {
+                chain_id: 8453,
+                cycles: user_cycles,
+                stages: BTreeMap::from([(
+                    ProfileStage::Signatures,
+                    signatures,
+                )]),
+            }],
+        };
+        let baseline = Baseline {
+            profiles: BTreeMap::from([(
+                "base".to_string(),
+                profile(1000, 500),
+            )]),
+        };
+
+        let within = BTreeMap::from([("base".to_string(), profile(1010, 505))]);
+        assert!(baseline.regressions(&within, 2.0).is_empty());
+
+        let worse = BTreeMap::from([
+            ("base".to_string(), profile(1010, 600)),
+            ("new".to_string(), profile(5000, 5000)),
+        ]);
+        let regressions = baseline.regressions(&worse, 2.0);
+        assert_eq!(
+            regressions,
+            vec![Regression {
+                metric: "base: chain 8453 signatures".to_string(),
+                baseline: 500,
+                current: 600,
+            }]
+        );
+    }
+}
@@ -0,0 +1,530 @@
+// Copyright (c) 2025 Merge Layers Inc.
+//
+// This source code is licensed under the Business Source License 1.1
+// (the "License"); you may not use this file except in compliance with the
+// License. You may obtain a copy of the License at
+//
+//     https://github.com/malda-protocol/malda-zk-coprocessor/blob/main/LICENSE-BSL
+//
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+//
+//! Guest programs and their image IDs.
+//!
+//! `elfs_ids` pairs the checked-in guest ELF with a declared image ID. A
+//! `GuestProgram` computes the image ID of its ELF instead of trusting the
+//! declared one, so a stale ELF or ID is caught before anything is proven,
+//! and `GuestProgram::check_onchain` compares it with the image ID the
+//! deployed `ZkVerifier` expects.
+//!
+//! The guest is built per `NetworkProfile`. The mainnet ELF is embedded, the
+//! ELFs of the other profiles are loaded from `guest.elf_dir`, and
+//! `GuestProgram::for_config` picks the one matching the configuration.
+//!
+//! The aggregate_proof_data guest verifies per-chain get_proof_data
+//! receipts, see `composition`. It only accepts receipts of the
+//! get_proof_data image of its profile, compiled in from
+//! `constants::GET_PROOF_DATA_ID_<PROFILE>`, and is loaded from
+//! `guest.elf_dir` for every profile.
+//!
+//! Several versions of the guest can be held side by side in a
+//! `GuestRegistry`, e.g. the old and new image during an upgrade. Every
+//! `ProverBackend` runs one program, and `GuestRegistry::identify` tells
+//! which registered program a receipt belongs to.
+//!
+//! ```ignore
+//! let mut registry = GuestRegistry::embedded()?;
+//! let v1 = GuestProgram::load("get-proof-data-v1", "v1.bin")?
+//!     .with_profile(NetworkProfile::Mainnet);
+//! registry.register(v1)?;
+//!
+//! for program in registry.programs() {
+//!     let prover = prover_for_program(&config, program.clone())?;
+//!     // prove with every version in parallel ...
+//! }
+//! ```
+
+use crate::config::MaldaConfig;
+use crate::elfs_ids::{GET_PROOF_DATA_ELF, GET_PROOF_DATA_ID};
+use crate::types::NetworkProfile;
+
+use alloy::{
+    primitives::{Address, B256},
+    providers::ProviderBuilder,
+    sol,
+};
+use risc0_zkvm::{Receipt, compute_image_id, sha::Digest};
+use thiserror::Error;
+use url::Url;
+
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, OnceLock},
+};
+
+/// Name of the embedded get_proof_data guest.
+pub const GET_PROOF_DATA_NAME: &str = "get-proof-data";
+
+/// Name of the aggregate_proof_data guest.
+pub const AGGREGATE_PROOF_DATA_NAME: &str = "aggregate-proof-data";
+
+sol! {
+    /// Image ID getter of `ZkVerifier.sol`.
+    #[sol(rpc)]
+    interface IZkVerifier {
+        function imageId() external view returns (bytes32);
+    }
+}
+
+/// Error returned when loading, registering or checking a guest program.
+#[derive(Debug, Clone, Error)]
+pub enum ProgramError {
+    /// The ELF could not be read or its image ID could not be computed.
+    #[error("guest program {name}: {reason}")]
+    InvalidElf { name: String, reason: String },
+
+    /// The image ID computed from the ELF differs from the declared one.
+    #[error(
+        "guest program {name}: ELF has image ID {computed}, but {declared} is \
+         declared"
+    )]
+    DeclaredIdMismatch {
+        name: String,
+        declared: Digest,
+        computed: Digest,
+    },
+
+    /// The on-chain verifier expects another image ID.
+    #[error(
+        "guest program {name} has image ID {image_id}, but the verifier \
+         expects {onchain}"
+    )]
+    OnchainIdMismatch {
+        name: String,
+        image_id: B256,
+        onchain: B256,
+    },
+
+    /// The image ID of the on-chain verifier could not be read.
+    #[error("failed to read the verifier image ID: {0}")]
+    Onchain(String),
+
+    /// A program with the same name or image ID is already registered.
+    #[error("guest program {0} is already registered")]
+    Duplicate(String),
+}
+
+/// A guest ELF together with its computed image ID.
+#[derive(Debug, Clone)]
+pub struct GuestProgram {
+    name: String,
+    elf: Arc<[u8]>,
+    image_id: Digest,
+    profile: Option<NetworkProfile>,
+}
+
+impl GuestProgram {
+    /// Creates a program from an ELF, computing its image ID.
+    ///
+    /// # Errors
+    /// Returns `ProgramError::InvalidElf` if the image ID cannot be computed.
+    pub fn new(
+        name: impl Into<String>,
+        elf: impl Into<Arc<[u8]>>,
+    ) -> Result<Self, ProgramError> {
+        let name = name.into();
+        let elf = elf.into();
+        let image_id =
+            compute_image_id(&elf).map_err(|e| ProgramError::InvalidElf {
+                name: name.clone(),
+                reason: format!("failed to compute image ID: {e}"),
+            })?;
+        Ok(Self {
+            name,
+            elf,
+            image_id,
+            profile: None,
+        })
+    }
+
+    /// Creates a program from an ELF and checks its image ID against the
+    /// declared one.
+    ///
+    /// # Errors
+    /// Returns an error if the image ID cannot be computed or differs from
+    /// `declared_id`.
+    pub fn with_declared_id(
+        name: impl Into<String>,
+        elf: impl Into<Arc<[u8]>>,
+        declared_id: impl Into<Digest>,
+    ) -> Result<Self, ProgramError> {
+        let program = Self::new(name, elf)?;
+        let declared = declared_id.into();
+        if program.image_id != declared {
+            return Err(ProgramError::DeclaredIdMismatch {
+                name: program.name,
+                declared,
+                computed: program.image_id,
+            });
+        }
+        Ok(program)
+    }
+
+    /// Loads a program from an ELF file.
+    ///
+    /// # Errors
+    /// Returns `ProgramError::InvalidElf` if the file cannot be read or its
+    /// image ID cannot be computed.
+    pub fn load(
+        name: impl Into<String>,
+        path: impl AsRef<Path>,
+    ) -> Result<Self, ProgramError> {
+        let name = name.into();
+        let path = path.as_ref();
+        let elf =
+            std::fs::read(path).map_err(|e| ProgramError::InvalidElf {
+                name: name.clone(),
+                reason: format!("failed to read {}: {e}", path.display()),
+            })?;
+        Self::new(name, elf)
+    }
+
+    /// Returns the embedded get_proof_data guest.
+    ///
+    /// The image ID is computed once per process and checked against
+    /// `GET_PROOF_DATA_ID`.
+    ///
+    /// # Errors
+    /// Returns an error if the embedded ELF does not match the declared
+    /// image ID.
+    pub fn get_proof_data() -> Result<Self, ProgramError> {
+        static EMBEDDED: OnceLock<Result<GuestProgram, ProgramError>> =
+            OnceLock::new();
+        EMBEDDED
+            .get_or_init(|| {
+                Self::with_declared_id(
+                    GET_PROOF_DATA_NAME,
+                    GET_PROOF_DATA_ELF,
+                    GET_PROOF_DATA_ID,
+                )
+                .map(|program| program.with_profile(NetworkProfile::Mainnet))
+            })
+            .clone()
+    }
+
+    /// Returns the get_proof_data guest built for `profile`.
+    ///
+    /// The mainnet guest is the embedded one. The others are loaded from
+    /// `get-proof-data-<profile>.bin` in `elf_dir`, or in the `bin` directory
+    /// of this crate if not set.
+    ///
+    /// # Errors
+    /// Returns an error if the ELF cannot be loaded or, for the embedded
+    /// guest, does not match the declared image ID.
+    pub fn for_profile(
+        profile: NetworkProfile,
+        elf_dir: Option<&Path>,
+    ) -> Result<Self, ProgramError> {
+        if profile == NetworkProfile::Mainnet {
+            return Self::get_proof_data();
+        }
+        let name = format!("{GET_PROOF_DATA_NAME}-{}", profile.name());
+        let path = elf_path(elf_dir, &name);
+        Ok(Self::load(name, path)?.with_profile(profile))
+    }
+
+    /// Returns the aggregate_proof_data guest built for `profile`, loaded
+    /// from `aggregate-proof-data.bin` (mainnet) or
+    /// `aggregate-proof-data-<profile>.bin` in `elf_dir`, or in the `bin`
+    /// directory of this crate if not set.
+    ///
+    /// # Errors
+    /// Returns `ProgramError::InvalidElf` if the ELF cannot be loaded.
+    pub fn aggregate_proof_data(
+        profile: NetworkProfile,
+        elf_dir: Option<&Path>,
+    ) -> Result<Self, ProgramError> {
+        let name = if profile == NetworkProfile::Mainnet {
+            AGGREGATE_PROOF_DATA_NAME.to_string()
+        } else {
+            format!("{AGGREGATE_PROOF_DATA_NAME}-{}", profile.name())
+        };
+        let path = elf_path(elf_dir, &name);
+        Ok(Self::load(name, path)?.with_profile(profile))
+    }
+
+    /// Returns the get_proof_data guest for `config.guest_profile()`.
+    ///
+    /// # Errors
+    /// See `for_profile`.
+    pub fn for_config(config: &MaldaConfig) -> Result<Self, ProgramError> {
+        Self::for_profile(
+            config.guest_profile(),
+            config.guest.elf_dir.as_deref(),
+        )
+    }
+
+    /// Marks the program as built for `profile`, so requests for chains
+    /// outside of it are rejected before proving.
+    pub fn with_profile(mut self, profile: NetworkProfile) -> Self {
+        self.profile = Some(profile);
+        self
+    }
+
+    /// Returns the program name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the ELF.
+    pub fn elf(&self) -> &[u8] {
+        &self.elf
+    }
+
+    /// Returns the network profile the program was built for, if known.
+    pub fn profile(&self) -> Option<NetworkProfile> {
+        self.profile
+    }
+
+    /// Returns the image ID computed from the ELF.
+    pub fn image_id(&self) -> Digest {
+        self.image_id
+    }
+
+    /// Returns the image ID in the `bytes32` form used on-chain.
+    pub fn image_id_b256(&self) -> B256 {
+        B256::from_slice(self.image_id.as_bytes())
+    }
+
+    /// Checks the image ID against the one a `ZkVerifier` expects.
+    ///
+    /// # Arguments
+    /// * `rpc_url` - RPC endpoint of the chain the verifier is deployed on.
+    /// * `verifier` - Address of the `ZkVerifier` contract.
+    ///
+    /// # Errors
+    /// Returns an error if the verifier cannot be queried or expects another
+    /// image ID.
+    pub async fn check_onchain(
+        &self,
+        rpc_url: Url,
+        verifier: Address,
+    ) -> Result<(), ProgramError> {
+        let provider = ProviderBuilder::new().connect_http(rpc_url);
+        let onchain = IZkVerifier::new(verifier, provider)
+            .imageId()
+            .call()
+            .await
+            .map_err(|e| ProgramError::Onchain(e.to_string()))?;
+        if onchain != self.image_id_b256() {
+            return Err(ProgramError::OnchainIdMismatch {
+                name: self.name.clone(),
+                image_id: self.image_id_b256(),
+                onchain,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Returns the path of the ELF of the guest `name` in `elf_dir`, or in the
+/// `bin` directory of this crate if not set.
+fn elf_path(elf_dir: Option<&Path>, name: &str) -> PathBuf {
+    elf_dir
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| {
+            PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/bin"))
+        })
+        .join(format!("{name}.bin"))
+}
+
+/// A set of guest programs with unique names and image IDs.
+#[derive(Debug, Clone, Default)]
+pub struct GuestRegistry {
+    programs: Vec<GuestProgram>,
+}
+
+impl GuestRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a registry holding the embedded get_proof_data guest.
+    ///
+    /// # Errors
+    /// Returns an error if the embedded ELF does not match the declared
+    /// image ID.
+    pub fn embedded() -> Result<Self, ProgramError> {
+        let mut registry = Self::new();
+        registry.register(GuestProgram::get_proof_data()?)?;
+        Ok(registry)
+    }
+
+    /// Adds a program.
+    ///
+    /// # Errors
+    /// Returns `ProgramError::Duplicate` if a program with the same name or
+    /// image ID is already registered.
+    pub fn register(
+        &mut self,
+        program: GuestProgram,
+    ) -> Result<(), ProgramError> {
+        if let Some(existing) = self.programs.iter().find(|existing| {
+            existing.name == program.name
+                || existing.image_id == program.image_id
+        }) {
+            return Err(ProgramError::Duplicate(existing.name.clone()));
+        }
+        self.programs.push(program);
+        Ok(())
+    }
+
+    /// Returns the registered programs, in registration order.
+    pub fn programs(&self) -> &[GuestProgram] {
+        &self.programs
+    }
+
+    /// Returns the program registered under `name`.
+    pub fn get(&self, name: &str) -> Option<&GuestProgram> {
+        self.programs.iter().find(|program| program.name == name)
+    }
+
+    /// Returns the program with the given image ID.
+    pub fn by_image_id(
+        &self,
+        image_id: impl Into<Digest>,
+    ) -> Option<&GuestProgram> {
+        let image_id = image_id.into();
+        self.programs
+            .iter()
+            .find(|program| program.image_id == image_id)
+    }
+
+    /// Returns the program a receipt was produced by, i.e. the first one
+    /// the receipt verifies against.
+    pub fn identify(&self, receipt: &Receipt) -> Option<&GuestProgram> {
+        self.programs
+            .iter()
+            .find(|program| receipt.verify(program.image_id).is_ok())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::constants::GET_PROOF_DATA_ID_MAINNET;
+
+    use alloy::hex;
+    use sha2::{Digest as _, Sha256};
+
+    use std::{fs, path::Path};
+
+    /// Returns the digest `just guest-sources-digest` prints for the guest
+    /// sources in the tree.
+    fn guest_sources_digest() -> String {
+        fn collect(root: &Path, path: &str, files: &mut Vec<String>) {
+            let full = root.join(path);
+            if full.is_file() {
+                files.push(path.to_string());
+                return;
+            }
+            for entry in fs::read_dir(full).unwrap() {
+                let name = entry.unwrap().file_name();
+                collect(
+                    root,
+                    &format!("{path}/{}", name.to_str().unwrap()),
+                    files,
+                );
+            }
+        }
+
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("../..");
+        let mut files = Vec::new();
+        for path in [
+            "crates/guest/Cargo.toml",
+            "crates/guest/src",
+            "crates/malda_utils/Cargo.toml",
+            "crates/malda_utils/src",
+        ] {
+            collect(&root, path, &mut files);
+        }
+        files.sort();
+        let listing: String = files
+            .iter()
+            .map(|file| {
+                let contents = fs::read(root.join(file)).unwrap();
+                format!("{}  {file}\n", hex::encode(Sha256::digest(contents)))
+            })
+            .collect();
+        hex::encode(Sha256::digest(listing))
+    }
+
+    #[test]
+    fn test_embedded_elf_matches_declared_image_id() {
+        let program = GuestProgram::get_proof_data().unwrap();
+
+        assert_eq!(program.image_id(), Digest::from(GET_PROOF_DATA_ID));
+        assert_eq!(program.name(), GET_PROOF_DATA_NAME);
+        assert_eq!(program.profile(), Some(NetworkProfile::Mainnet));
+    }
+
+    #[test]
+    fn test_embedded_elf_is_built_from_guest_sources() {
+        let recorded = include_str!("../bin/get-proof-data.sources");
+
+        assert_eq!(
+            recorded.trim(),
+            guest_sources_digest(),
+            "the guest sources changed since the embedded ELF was built, \
+             rebuild it with `just build-guest` and update GET_PROOF_DATA_ID"
+        );
+    }
+
+    #[test]
+    fn test_aggregator_accepts_embedded_image_id() {
+        assert_eq!(GET_PROOF_DATA_ID_MAINNET, GET_PROOF_DATA_ID);
+    }
+
+    #[test]
+    fn test_wrong_declared_image_id_is_rejected() {
+        let mut declared = GET_PROOF_DATA_ID;
+        declared[0] ^= 1;
+
+        let result = GuestProgram::with_declared_id(
+            "stale",
+            GET_PROOF_DATA_ELF,
+            declared,
+        );
+
+        assert!(matches!(
+            result,
+            Err(ProgramError::DeclaredIdMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_registry_rejects_duplicates() {
+        let mut registry = GuestRegistry::embedded().unwrap();
+        let program = GuestProgram::get_proof_data().unwrap();
+
+        let same_image = GuestProgram {
+            name: "renamed".to_string(),
+            ..program.clone()
+        };
+        assert!(matches!(
+            registry.register(same_image),
+            Err(ProgramError::Duplicate(_))
+        ));
+
+        assert_eq!(
+            registry
+                .by_image_id(GET_PROOF_DATA_ID)
+                .map(GuestProgram::name),
+            Some(GET_PROOF_DATA_NAME)
+        );
+        assert!(registry.get("renamed").is_none());
+    }
+}
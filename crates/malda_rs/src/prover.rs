@@ -0,0 +1,793 @@
+// Copyright (c) 2025 Merge Layers Inc.
+//
+// This source code is licensed under the Business Source License 1.1
+// (the "License"); you may not use this file except in compliance with the
+// License. You may obtain a copy of the License at
+//
+//     https://github.com/malda-protocol/malda-zk-coprocessor/blob/main/LICENSE-BSL
+//
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+//
+//! Prover backends for the get_proof_data guest.
+//!
+//! Input building is the same for every prover: `viewcalls` serializes the
+//! guest input once and hands it to a `ProverBackend`, which runs its
+//! `GuestProgram` on it and returns a `ProofResult`. The backend is selected
+//! by `MaldaConfig::prover`, so switching provers does not change the call
+//! site:
+//!
+//! - `LocalProver`: proves with the local RISC Zero prover.
+//! - `BonsaiProver`: proves remotely with the Bonsai SDK.
+//! - `BoundlessProver`: submits a proof request to the Boundless market.
+//! - `ExecuteOnly`: executes the guest without proving, e.g. to check inputs
+//!   or measure cycles.
+//! - `DevModeProver`: executes the guest and returns a fake receipt, for
+//!   tests that need a receipt without paying for a proof.
+//!
+//! All backends but Boundless can also run a guest that verifies the
+//! receipts of other guests, see `ProverBackend::prove_with_assumptions`.
+
+use crate::config::{BonsaiConfig, BoundlessConfig, MaldaConfig, ProverKind};
+use crate::programs::GuestProgram;
+use crate::verify::{VerifyError, encode_seal};
+
+use alloy::{primitives::Bytes, signers::local::PrivateKeySigner};
+use anyhow::{Context, Result, bail};
+use bonsai_sdk::blocking::Client;
+use boundless_market::{
+    Client as BoundlessClient, storage::storage_provider_from_env,
+};
+use futures::future::BoxFuture;
+use risc0_zkvm::{
+    ExecutorEnv, FakeReceipt, InnerReceipt, ProverOpts, Receipt, SessionInfo,
+    default_executor, default_prover, sha::Digest,
+};
+use tracing::info;
+use url::Url;
+
+use std::{str::FromStr, sync::Arc, time::Duration};
+
+/// Timeout duration for transaction confirmation.
+///
+/// The maximum time to wait for a transaction to be confirmed on the
+/// blockchain. This is used for operations that require transaction
+/// confirmation, such as submitting proof requests to the Boundless market.
+pub const TX_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Statistics for a Malda ZK proof session.
+///
+/// This struct contains detailed information about the computational resources
+/// used during proof generation. It is useful for auditing, performance
+/// analysis, and understanding the cost of ZK proof generation.
+///
+/// Fields:
+/// - `segments`: Number of proof segments generated by the ZKVM session.
+/// - `total_cycles`: Total computational cycles used (all segments).
+/// - `user_cycles`: User-specific computational cycles (cycles spent on user
+///   logic).
+/// - `paging_cycles`: Cycles used for memory paging operations (if any).
+/// - `reserved_cycles`: Reserved cycles for system operations (if any).
+#[derive(Debug, Clone)]
+pub struct MaldaSessionStats {
+    pub segments: usize,
+    pub total_cycles: u64,
+    pub user_cycles: u64,
+    pub paging_cycles: u64,
+    pub reserved_cycles: u64,
+}
+
+/// Result of a prover backend.
+///
+/// Fields:
+/// - `journal`: The journal committed by the guest.
+/// - `seal`: The seal as expected by the verifier router, prefixed with the
+///   verifier selector (see `verify::encode_seal`). Empty for receipts the
+///   router cannot verify (`ExecuteOnly`, or `LocalProver` with non-Groth16
+///   options).
+/// - `image_id`: Image ID of the guest program that was run.
+/// - `stats`: Session statistics, if the backend reports them.
+/// - `receipt`: The receipt, if the backend produces one. Boundless only
+///   returns the journal and seal.
+#[derive(Debug, Clone)]
+pub struct ProofResult {
+    pub journal: Bytes,
+    pub seal: Bytes,
+    pub image_id: Digest,
+    pub stats: Option<MaldaSessionStats>,
+    pub receipt: Option<Receipt>,
+}
+
+/// A backend that runs the get_proof_data guest on a serialized input.
+pub trait ProverBackend: Send + Sync {
+    /// Returns the backend name, used in logs.
+    fn name(&self) -> &'static str;
+
+    /// Returns the guest program the backend runs.
+    fn program(&self) -> &GuestProgram;
+
+    /// Runs the guest on `input`, the serialized input built by
+    /// `viewcalls`.
+    ///
+    /// # Errors
+    /// Returns an error if execution or proving fails.
+    fn prove(&self, input: Vec<u8>) -> BoxFuture<'_, Result<ProofResult>>;
+
+    /// Runs the guest on `input` with `assumptions`, the receipts the guest
+    /// verifies with `env::verify` (see `composition`).
+    ///
+    /// # Errors
+    /// Returns an error if the backend does not support composition, or
+    /// execution or proving fails.
+    fn prove_with_assumptions(
+        &self,
+        input: Vec<u8>,
+        assumptions: Vec<Receipt>,
+    ) -> BoxFuture<'_, Result<ProofResult>> {
+        if assumptions.is_empty() {
+            return self.prove(input);
+        }
+        let name = self.name();
+        Box::pin(async move {
+            bail!("the {name} prover does not support composition")
+        })
+    }
+}
+
+/// Proves with the local RISC Zero prover.
+#[derive(Debug, Clone)]
+pub struct LocalProver {
+    program: GuestProgram,
+    opts: ProverOpts,
+}
+
+impl LocalProver {
+    /// Creates a local prover producing Groth16 proofs of `program`.
+    pub fn new(program: GuestProgram) -> Self {
+        Self::with_opts(program, ProverOpts::groth16())
+    }
+
+    /// Creates a local prover with custom prover options.
+    pub fn with_opts(program: GuestProgram, opts: ProverOpts) -> Self {
+        Self { program, opts }
+    }
+}
+
+impl ProverBackend for LocalProver {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    fn program(&self) -> &GuestProgram {
+        &self.program
+    }
+
+    fn prove(&self, input: Vec<u8>) -> BoxFuture<'_, Result<ProofResult>> {
+        self.prove_with_assumptions(input, Vec::new())
+    }
+
+    fn prove_with_assumptions(
+        &self,
+        input: Vec<u8>,
+        assumptions: Vec<Receipt>,
+    ) -> BoxFuture<'_, Result<ProofResult>> {
+        let program = self.program.clone();
+        let opts = self.opts.clone();
+        Box::pin(async move {
+            // Run the proof generation in a blocking task to avoid blocking
+            // the async runtime
+            tokio::task::spawn_blocking(move || {
+                let start_time = std::time::Instant::now();
+                let prove_info = default_prover().prove_with_opts(
+                    executor_env(&input, assumptions)?,
+                    program.elf(),
+                    &opts,
+                )?;
+                info!("Local proof time: {:?}", start_time.elapsed());
+
+                let stats = prove_info.stats;
+                Ok(ProofResult::from_receipt(
+                    prove_info.receipt,
+                    program.image_id(),
+                    Some(MaldaSessionStats {
+                        segments: stats.segments,
+                        total_cycles: stats.total_cycles,
+                        user_cycles: stats.user_cycles,
+                        paging_cycles: stats.paging_cycles,
+                        reserved_cycles: stats.reserved_cycles,
+                    }),
+                )?)
+            })
+            .await?
+        })
+    }
+}
+
+/// Proves remotely with the Bonsai SDK.
+#[derive(Debug, Clone)]
+pub struct BonsaiProver {
+    config: BonsaiConfig,
+    program: GuestProgram,
+}
+
+impl BonsaiProver {
+    /// Creates a Bonsai prover of `program` using the given API endpoint and
+    /// key. The ELF is uploaded if Bonsai does not know its image ID yet.
+    pub fn new(config: BonsaiConfig, program: GuestProgram) -> Self {
+        Self { config, program }
+    }
+}
+
+impl ProverBackend for BonsaiProver {
+    fn name(&self) -> &'static str {
+        "bonsai"
+    }
+
+    fn program(&self) -> &GuestProgram {
+        &self.program
+    }
+
+    fn prove(&self, input: Vec<u8>) -> BoxFuture<'_, Result<ProofResult>> {
+        self.prove_with_assumptions(input, Vec::new())
+    }
+
+    fn prove_with_assumptions(
+        &self,
+        input: Vec<u8>,
+        assumptions: Vec<Receipt>,
+    ) -> BoxFuture<'_, Result<ProofResult>> {
+        let config = self.config.clone();
+        let program = self.program.clone();
+        Box::pin(async move {
+            // The Bonsai SDK client is blocking
+            tokio::task::spawn_blocking(move || {
+                run_bonsai(input, assumptions, &config, &program)
+            })
+            .await?
+        })
+    }
+}
+
+/// Submits proof requests to the Boundless market for decentralized proving.
+///
+/// The request is submitted offchain (gasless) or onchain, depending on
+/// `BoundlessConfig::onchain`. The backend waits for the request to be
+/// fulfilled.
+///
+/// # Environment Variables
+/// The Boundless storage provider is still configured from the environment,
+/// see `boundless_market::storage::storage_provider_from_env`.
+#[derive(Debug, Clone)]
+pub struct BoundlessProver {
+    config: BoundlessConfig,
+    program: GuestProgram,
+}
+
+impl BoundlessProver {
+    /// Creates a Boundless prover of `program` using the given market
+    /// settings. If `config.program_url` is set, it must host the ELF of
+    /// `program`.
+    pub fn new(config: BoundlessConfig, program: GuestProgram) -> Self {
+        Self { config, program }
+    }
+}
+
+impl ProverBackend for BoundlessProver {
+    fn name(&self) -> &'static str {
+        "boundless"
+    }
+
+    fn program(&self) -> &GuestProgram {
+        &self.program
+    }
+
+    fn prove(&self, input: Vec<u8>) -> BoxFuture<'_, Result<ProofResult>> {
+        Box::pin(async move {
+            let (journal, seal) =
+                run_boundless(input, &self.config, &self.program).await?;
+            Ok(ProofResult {
+                journal,
+                seal,
+                image_id: self.program.image_id(),
+                stats: None,
+                receipt: None,
+            })
+        })
+    }
+}
+
+/// Executes the guest without proving.
+#[derive(Debug, Clone)]
+pub struct ExecuteOnly {
+    program: GuestProgram,
+}
+
+impl ExecuteOnly {
+    /// Creates a backend executing `program`.
+    pub fn new(program: GuestProgram) -> Self {
+        Self { program }
+    }
+}
+
+impl ProverBackend for ExecuteOnly {
+    fn name(&self) -> &'static str {
+        "execute"
+    }
+
+    fn program(&self) -> &GuestProgram {
+        &self.program
+    }
+
+    fn prove(&self, input: Vec<u8>) -> BoxFuture<'_, Result<ProofResult>> {
+        self.prove_with_assumptions(input, Vec::new())
+    }
+
+    fn prove_with_assumptions(
+        &self,
+        input: Vec<u8>,
+        assumptions: Vec<Receipt>,
+    ) -> BoxFuture<'_, Result<ProofResult>> {
+        let program = self.program.clone();
+        Box::pin(async move {
+            tokio::task::spawn_blocking(move || {
+                let session = execute(&program, &input, assumptions)?;
+                Ok(ProofResult {
+                    journal: session.journal.bytes.clone().into(),
+                    seal: Bytes::new(),
+                    image_id: program.image_id(),
+                    stats: Some(session_stats(&session)),
+                    receipt: None,
+                })
+            })
+            .await?
+        })
+    }
+}
+
+/// Executes the guest and wraps the result in a fake receipt.
+///
+/// Fake receipts are only accepted by verifiers running in dev mode
+/// (`RISC0_DEV_MODE`), so this backend must not be used in production.
+#[derive(Debug, Clone)]
+pub struct DevModeProver {
+    program: GuestProgram,
+}
+
+impl DevModeProver {
+    /// Creates a backend executing `program` and faking its receipts.
+    pub fn new(program: GuestProgram) -> Self {
+        Self { program }
+    }
+}
+
+impl ProverBackend for DevModeProver {
+    fn name(&self) -> &'static str {
+        "dev"
+    }
+
+    fn program(&self) -> &GuestProgram {
+        &self.program
+    }
+
+    fn prove(&self, input: Vec<u8>) -> BoxFuture<'_, Result<ProofResult>> {
+        self.prove_with_assumptions(input, Vec::new())
+    }
+
+    fn prove_with_assumptions(
+        &self,
+        input: Vec<u8>,
+        assumptions: Vec<Receipt>,
+    ) -> BoxFuture<'_, Result<ProofResult>> {
+        let program = self.program.clone();
+        Box::pin(async move {
+            tokio::task::spawn_blocking(move || {
+                let session = execute(&program, &input, assumptions)?;
+                let claim = session
+                    .receipt_claim
+                    .clone()
+                    .context("execution produced no receipt claim")?;
+                let receipt = Receipt::new(
+                    InnerReceipt::Fake(FakeReceipt::new(claim)),
+                    session.journal.bytes.clone(),
+                );
+                Ok(ProofResult::from_receipt(
+                    receipt,
+                    program.image_id(),
+                    Some(session_stats(&session)),
+                )?)
+            })
+            .await?
+        })
+    }
+}
+
+/// Builds the prover backend selected by `config.prover` for the
+/// get_proof_data guest of the configured network profile (see
+/// `GuestProgram::for_config`).
+///
+/// # Errors
+/// Returns an error if:
+/// - The guest ELF cannot be loaded, or the embedded ELF does not match
+///   `GET_PROOF_DATA_ID`.
+/// - The selected backend is `bonsai` and `bonsai.image_id` is not the image
+///   ID of the guest.
+/// - The selected backend is `bonsai` or `boundless` and its settings are
+///   missing.
+pub fn prover_from_config(
+    config: &MaldaConfig,
+) -> Result<Arc<dyn ProverBackend>> {
+    let program = GuestProgram::for_config(config)?;
+    if let (ProverKind::Bonsai, Some(bonsai)) = (config.prover, &config.bonsai)
+    {
+        let image_id = parse_image_id(&bonsai.image_id)?;
+        if image_id != program.image_id() {
+            bail!(
+                "bonsai.image_id {image_id} is not the image ID of the {} \
+                 guest ({})",
+                program.name(),
+                program.image_id()
+            );
+        }
+    }
+    prover_for_program(config, program)
+}
+
+/// Builds the prover backend selected by `config.prover` for `program`,
+/// e.g. another guest version loaded into a `GuestRegistry`.
+///
+/// # Errors
+/// Returns an error if the selected backend is `bonsai` or `boundless` and
+/// its settings are missing.
+pub fn prover_for_program(
+    config: &MaldaConfig,
+    program: GuestProgram,
+) -> Result<Arc<dyn ProverBackend>> {
+    Ok(match config.prover {
+        ProverKind::Local => Arc::new(LocalProver::new(program)),
+        ProverKind::Bonsai => Arc::new(BonsaiProver::new(
+            config.bonsai.clone().context("bonsai is not configured")?,
+            program,
+        )),
+        ProverKind::Boundless => Arc::new(BoundlessProver::new(
+            config
+                .boundless
+                .clone()
+                .context("boundless is not configured")?,
+            program,
+        )),
+        ProverKind::Execute => Arc::new(ExecuteOnly::new(program)),
+        ProverKind::Dev => Arc::new(DevModeProver::new(program)),
+    })
+}
+
+impl ProofResult {
+    /// Creates the result of a backend that produced `receipt`.
+    ///
+    /// # Errors
+    /// Returns an error if the seal of a receipt the router can verify
+    /// cannot be encoded. Only receipts without an on-chain seal get an
+    /// empty one.
+    fn from_receipt(
+        receipt: Receipt,
+        image_id: Digest,
+        stats: Option<MaldaSessionStats>,
+    ) -> Result<Self, VerifyError> {
+        let seal = encode_seal(&receipt)?.unwrap_or_default();
+        Ok(Self {
+            journal: receipt.journal.bytes.clone().into(),
+            seal,
+            image_id,
+            stats,
+            receipt: Some(receipt),
+        })
+    }
+}
+
+fn executor_env(
+    input: &[u8],
+    assumptions: Vec<Receipt>,
+) -> Result<ExecutorEnv<'static>> {
+    let mut builder = ExecutorEnv::builder();
+    builder.write_slice(input);
+    for assumption in assumptions {
+        builder.add_assumption(assumption);
+    }
+    builder.build().context("Failed to build executor env")
+}
+
+fn execute(
+    program: &GuestProgram,
+    input: &[u8],
+    assumptions: Vec<Receipt>,
+) -> Result<SessionInfo> {
+    default_executor()
+        .execute(executor_env(input, assumptions)?, program.elf())
+        .context("Failed to execute ZKVM")
+}
+
+fn parse_image_id(image_id: &str) -> Result<Digest> {
+    Digest::try_from(hex::decode(image_id)?.as_slice())
+        .with_context(|| format!("{image_id} is not a valid image ID"))
+}
+
+pub(crate) fn session_stats(session: &SessionInfo) -> MaldaSessionStats {
+    MaldaSessionStats {
+        segments: session.segments.len(),
+        total_cycles: session
+            .segments
+            .iter()
+            .map(|segment| 1u64 << segment.po2)
+            .sum(),
+        user_cycles: session
+            .segments
+            .iter()
+            .map(|segment| u64::from(segment.cycles))
+            .sum(),
+        paging_cycles: 0, // Paging cycles not tracked in this context
+        reserved_cycles: 0, // Reserved cycles not tracked in this context
+    }
+}
+
+/// Runs a Bonsai ZK proof session with the provided input data.
+///
+/// This function handles the complete Bonsai SDK workflow:
+/// 1. Uploads the guest ELF, unless Bonsai already has its image ID, the
+///    input data and the assumption receipts to Bonsai
+/// 2. Creates a proof session
+/// 3. Polls for session completion (STARK phase)
+/// 4. Creates a SNARK session
+/// 5. Polls for SNARK completion
+/// 6. Downloads and deserializes the proof receipt
+///
+/// The function logs timing information for both STARK and SNARK phases.
+///
+/// # Arguments
+/// * `input_data` - The serialized input data for the ZKVM session.
+/// * `assumptions` - Receipts the guest verifies with `env::verify`.
+/// * `bonsai` - Bonsai API endpoint and key.
+/// * `program` - The guest program to prove.
+///
+/// # Returns
+/// * `Result<ProofResult, anyhow::Error>` - Proof and statistics if
+///   successful, or an error.
+///
+/// # Errors
+/// Returns an error if:
+/// - The Bonsai client fails to initialize.
+/// - The image, input or receipt upload, session creation, or polling fails.
+/// - The SNARK proof or receipt download fails.
+/// - The receipt cannot be deserialized.
+/// - Session status indicates failure.
+fn run_bonsai(
+    input_data: Vec<u8>,
+    assumptions: Vec<Receipt>,
+    bonsai: &BonsaiConfig,
+    program: &GuestProgram,
+) -> Result<ProofResult, anyhow::Error> {
+    // Initialize the Bonsai client from the config (uses RISC Zero version for
+    // compatibility)
+    let client = Client::from_parts(
+        bonsai.api_url.clone(),
+        bonsai.api_key.clone(),
+        risc0_zkvm::VERSION,
+    )?;
+
+    // The image ID the proof session runs against. Uploading is a no-op if
+    // Bonsai already knows the image.
+    let image_id = program.image_id();
+    let image_id_hex = hex::encode(image_id.as_bytes());
+    client.upload_img(&image_id_hex, program.elf().to_vec())?;
+
+    // Upload the input data to Bonsai and get an input ID
+    let input_id = client.upload_input(input_data)?;
+
+    // Upload the receipts the guest verifies and pass them as assumptions
+    let assumptions = assumptions
+        .iter()
+        .map(|receipt| Ok(client.upload_receipt(bincode::serialize(receipt)?)?))
+        .collect::<Result<Vec<String>>>()?;
+    let execute_only = false;
+
+    // Create a new proof session on Bonsai
+    let session = client.create_session(
+        image_id_hex,
+        input_id,
+        assumptions,
+        execute_only,
+    )?;
+
+    let polling_interval = Duration::from_millis(500);
+
+    // --- STARK phase: Wait for the session to complete and collect stats ---
+    let stark_time = std::time::Instant::now();
+    let succinct_stats = loop {
+        let res = session.status(&client)?;
+        if res.status == "RUNNING" {
+            // Session is still running, wait and poll again
+            std::thread::sleep(polling_interval);
+            continue;
+        }
+        if res.status == "SUCCEEDED" {
+            // Session succeeded, extract stats
+            let stats = res
+                .stats
+                .context("Missing stats object on Bonsai status res")?;
+            tracing::debug!(
+                "Bonsai usage: cycles: {} total_cycles: {}",
+                stats.cycles,
+                stats.total_cycles
+            );
+
+            break MaldaSessionStats {
+                segments: stats.segments,
+                total_cycles: stats.total_cycles,
+                user_cycles: stats.cycles,
+                paging_cycles: 0, // Paging cycles not tracked in this context
+                reserved_cycles: 0, /* Reserved cycles not tracked in this
+                                   * context */
+            };
+        } else {
+            // Session failed or exited unexpectedly
+            return Err(anyhow::Error::msg(format!(
+                "Bonsai prover workflow [{}] exited: {} err: {}",
+                session.uuid,
+                res.status,
+                res.error_msg
+                    .unwrap_or("Bonsai workflow missing error_msg".into())
+            )));
+        }
+    };
+    let stark_time = stark_time.elapsed();
+
+    // --- SNARK phase: Create a SNARK session and wait for completion ---
+    let snark_session = client.create_snark(session.uuid.clone())?;
+
+    let start = std::time::Instant::now();
+    let snark_receipt_url = loop {
+        let res = snark_session.status(&client)?;
+        match res.status.as_str() {
+            "RUNNING" => {
+                // SNARK session is still running, wait and poll again
+                std::thread::sleep(polling_interval);
+                continue;
+            }
+            "SUCCEEDED" => {
+                // SNARK session succeeded, get the output URL
+                break res.output.ok_or_else(|| {
+                    anyhow::Error::msg(format!(
+                        "Bonsai prover workflow [{}] reported success, but provided no receipt",
+                        snark_session.uuid
+                    ))
+                })?;
+            }
+            _ => {
+                // SNARK session failed or exited unexpectedly
+                return Err(anyhow::Error::msg(format!(
+                    "Bonsai prover workflow [{}] exited: {} err: {}",
+                    snark_session.uuid,
+                    res.status,
+                    res.error_msg
+                        .unwrap_or("Bonsai workflow missing error_msg".into())
+                )));
+            }
+        }
+    };
+
+    let snark_time = start.elapsed();
+    info!(
+        "Bonsai session {}: STARK time {:?}, SNARK time {:?}",
+        session.uuid, stark_time, snark_time
+    );
+
+    // Download the Groth16 receipt (proof) from Bonsai and deserialize it
+    let receipt_buf = client.download(&snark_receipt_url)?;
+    let groth16_receipt: Receipt = bincode::deserialize(&receipt_buf)?;
+
+    Ok(ProofResult::from_receipt(
+        groth16_receipt,
+        image_id,
+        Some(succinct_stats),
+    )?)
+}
+
+/// Submits a proof request to the Boundless market and waits for it to be
+/// fulfilled.
+///
+/// The function supports both onchain and offchain submission modes:
+/// - **Offchain**: Uses `client.submit_offchain()` for gasless submission
+/// - **Onchain**: Uses `client.submit_onchain()` for on-chain submission with
+///   gas costs
+///
+/// # Arguments
+/// * `input_bytes` - The serialized input data for the guest.
+/// * `boundless` - Boundless market settings.
+/// * `program` - The guest program to prove, uploaded unless
+///   `boundless.program_url` is set.
+///
+/// # Returns
+/// * `Result<(Bytes, Bytes)>` - Tuple of (journal, seal) if successful, or an
+///   error.
+///
+/// # Errors
+/// Returns an error if:
+/// - The Boundless client fails to initialize.
+/// - The request submission or fulfillment fails.
+async fn run_boundless(
+    input_bytes: Vec<u8>,
+    boundless: &BoundlessConfig,
+    program: &GuestProgram,
+) -> Result<(Bytes, Bytes)> {
+    // Only initialize tracing if it hasn't been set up already
+    if tracing_subscriber::util::SubscriberInitExt::try_init(
+        tracing_subscriber::fmt()
+            .with_env_filter(tracing_subscriber::EnvFilter::from_default_env()),
+    )
+    .is_err()
+    {
+        // Tracing is already initialized, which is fine
+        tracing::debug!("Tracing subscriber already initialized");
+    }
+
+    // Load environment variables from .env if present, for the storage
+    // provider
+    match dotenvy::dotenv() {
+        Ok(path) => {
+            tracing::debug!("Loaded environment variables from {:?}", path)
+        }
+        Err(e) if e.not_found() => tracing::debug!("No .env file found"),
+        Err(e) => bail!("failed to load .env file: {}", e),
+    }
+
+    let rpc_url = Url::parse(&boundless.rpc_url)?;
+    let private_key = PrivateKeySigner::from_str(&boundless.private_key)?;
+
+    // Create a Boundless client from the provided parameters.
+    let client = BoundlessClient::builder()
+        .with_storage_provider(Some(storage_provider_from_env()?))
+        .with_rpc_url(rpc_url)
+        .with_private_key(private_key)
+        .build()
+        .await
+        .context("failed to build boundless client")?;
+
+    // Build the request - use program URL if available to avoid re-upload
+    let request = if let Some(program_url) = &boundless.program_url {
+        tracing::info!("Using pre-uploaded program from URL: {}", program_url);
+        let parsed_url = Url::parse(program_url)
+            .context("Failed to parse boundless.program_url")?;
+        client
+            .new_request()
+            .with_program_url(parsed_url)?
+            .with_stdin(input_bytes)
+            .with_groth16_proof()
+    } else {
+        tracing::info!("No program URL configured, uploading program directly");
+        client
+            .new_request()
+            .with_program(program.elf().to_vec())
+            .with_stdin(input_bytes)
+            .with_groth16_proof()
+    };
+
+    // Submit the request to the Boundless market (onchain or offchain)
+    let (request_id, expires_at) = if boundless.onchain {
+        client.submit_onchain(request).await?
+    } else {
+        client.submit_offchain(request).await?
+    };
+
+    // Wait for the request to be fulfilled. The market will return the journal
+    // and seal.
+    tracing::info!("Waiting for request {:x} to be fulfilled", request_id);
+    let (journal, seal) = client
+        .wait_for_request_fulfillment(
+            request_id,
+            Duration::from_secs(5), // check every 5 seconds
+            expires_at,
+        )
+        .await?;
+    tracing::info!("Request {:x} fulfilled", request_id);
+
+    Ok((journal, seal))
+}
@@ -0,0 +1,661 @@
+// Copyright (c) 2025 Merge Layers Inc.
+//
+// This source code is licensed under the Business Source License 1.1
+// (the "License"); you may not use this file except in compliance with the
+// License. You may obtain a copy of the License at
+//
+//     https://github.com/malda-protocol/malda-zk-coprocessor/blob/main/LICENSE-BSL
+//
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+//
+//! Typed proof data requests.
+//!
+//! A `ProofDataRequest` groups the `(user, market, target_chain_id)` queries
+//! of every chain together with the chain's finality policy, the
+//! `InclusionMode` and the `RpcSelection`. It can only be created with
+//! `ProofDataRequest::builder()`, which validates the whole request at build
+//! time, so the viewcalls entry points never see misaligned or unsupported
+//! arguments.
+//!
+//! ```ignore
+//! let request = ProofDataRequest::builder()
+//!     .query(LINEA_CHAIN_ID, ProofDataQuery::new(user, market, BASE_CHAIN_ID))
+//!     .query(BASE_CHAIN_ID, ProofDataQuery::new(user, market, LINEA_CHAIN_ID))
+//!     .finality_policy(BASE_CHAIN_ID, FinalityPolicy::Finalized)
+//!     .inclusion(InclusionMode::L1)
+//!     .build()?;
+//! ```
+//!
+//! Ethereum chains can additionally be queried at a historical block with
+//! `ProofDataRequestBuilder::historical_block`. The state of that block is
+//! linked to the validated recent block through the EIP-4788 beacon roots
+//! instead of the fixed `REORG_PROTECTION_DEPTH` window, see
+//! `docs/architecture.md`.
+//!
+//! Chains can also prove gateway and host events with
+//! `ProofDataRequestBuilder::event`, see `receipts`, and read the queried
+//! amounts from storage proofs instead of a multicall with
+//! `ProofDataRequestBuilder::storage_proofs`, see `storage`.
+
+use crate::constants::*;
+use crate::error::HostError;
+use crate::types::FinalityPolicy;
+
+use alloy::primitives::{Address, B256};
+use serde::{Deserialize, Serialize};
+
+/// A single `getProofData(user, target_chain_id)` query on a market.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ProofDataQuery {
+    pub user: Address,
+    pub market: Address,
+    pub target_chain_id: u64,
+}
+
+impl ProofDataQuery {
+    /// Creates a query for `user` on `market` towards `target_chain_id`.
+    pub fn new(user: Address, market: Address, target_chain_id: u64) -> Self {
+        Self {
+            user,
+            market,
+            target_chain_id,
+        }
+    }
+}
+
+/// Selects the supported events `emitter` emitted in transaction `tx_hash`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct EventQuery {
+    pub tx_hash: B256,
+    pub emitter: Address,
+}
+
+impl EventQuery {
+    /// Creates a query for the events of `emitter` in `tx_hash`.
+    pub fn new(tx_hash: B256, emitter: Address) -> Self {
+        Self { tx_hash, emitter }
+    }
+}
+
+/// How the queried state is anchored.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum InclusionMode {
+    /// Anchor L2 state to the sequencer commitment (the default).
+    #[default]
+    Sequencer,
+    /// Additionally prove that the queried L2 state is included on L1. Only
+    /// supported for Optimism, Base, Linea and their Sepolia variants.
+    L1,
+}
+
+impl InclusionMode {
+    /// Returns whether L1 inclusion is requested.
+    pub fn is_l1(self) -> bool {
+        self == InclusionMode::L1
+    }
+}
+
+/// Which configured RPC endpoints are used.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum RpcSelection {
+    /// Use the primary RPC and sequencer URLs (the default).
+    #[default]
+    Primary,
+    /// Use the fallback RPC and sequencer URLs.
+    Fallback,
+}
+
+impl RpcSelection {
+    /// Returns whether the fallback endpoints are selected.
+    pub fn is_fallback(self) -> bool {
+        self == RpcSelection::Fallback
+    }
+}
+
+/// The queries of a single chain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChainRequest {
+    chain_id: u64,
+    finality_policy: FinalityPolicy,
+    historical_block: Option<u64>,
+    queries: Vec<ProofDataQuery>,
+    events: Vec<EventQuery>,
+    storage_proofs: bool,
+}
+
+impl ChainRequest {
+    /// Returns the chain the queries are executed on.
+    pub fn chain_id(&self) -> u64 {
+        self.chain_id
+    }
+
+    /// Returns the confirmation requirement for the queried block.
+    pub fn finality_policy(&self) -> FinalityPolicy {
+        self.finality_policy
+    }
+
+    /// Returns the block the queries are executed at, if the chain is queried
+    /// at a historical block instead of near the tip.
+    pub fn historical_block(&self) -> Option<u64> {
+        self.historical_block
+    }
+
+    /// Returns the queries, in the order they were added.
+    pub fn queries(&self) -> &[ProofDataQuery] {
+        &self.queries
+    }
+
+    /// Returns the event queries, in the order they were added.
+    pub fn events(&self) -> &[EventQuery] {
+        &self.events
+    }
+
+    /// Returns whether the amounts are read from storage proofs instead of
+    /// the multicall.
+    pub fn storage_proofs(&self) -> bool {
+        self.storage_proofs
+    }
+
+    /// Returns the users, markets and target chain IDs of the queries as the
+    /// parallel vectors the guest input expects.
+    pub fn columns(&self) -> (Vec<Address>, Vec<Address>, Vec<u64>) {
+        let users = self.queries.iter().map(|query| query.user).collect();
+        let markets = self.queries.iter().map(|query| query.market).collect();
+        let target_chain_ids = self
+            .queries
+            .iter()
+            .map(|query| query.target_chain_id)
+            .collect();
+        (users, markets, target_chain_ids)
+    }
+}
+
+/// A validated proof data request over one or more chains.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProofDataRequest {
+    chains: Vec<ChainRequest>,
+    inclusion: InclusionMode,
+    rpc: RpcSelection,
+}
+
+impl ProofDataRequest {
+    /// Returns a builder for a new request.
+    pub fn builder() -> ProofDataRequestBuilder {
+        ProofDataRequestBuilder::default()
+    }
+
+    /// Returns the chains of the request, in the order the guest reads them.
+    pub fn chains(&self) -> &[ChainRequest] {
+        &self.chains
+    }
+
+    /// Returns the chain IDs of the request, in order.
+    pub fn chain_ids(&self) -> Vec<u64> {
+        self.chains.iter().map(ChainRequest::chain_id).collect()
+    }
+
+    /// Returns how the queried state is anchored.
+    pub fn inclusion(&self) -> InclusionMode {
+        self.inclusion
+    }
+
+    /// Returns which RPC endpoints are used.
+    pub fn rpc(&self) -> RpcSelection {
+        self.rpc
+    }
+}
+
+/// Builder for `ProofDataRequest`.
+///
+/// Chains appear in the request in the order of their first query. The
+/// builder methods never fail; every check is done by `build`.
+#[derive(Debug, Clone, Default)]
+pub struct ProofDataRequestBuilder {
+    chains: Vec<(u64, Vec<ProofDataQuery>)>,
+    finality_policies: Vec<(u64, FinalityPolicy)>,
+    historical_blocks: Vec<(u64, u64)>,
+    events: Vec<(u64, EventQuery)>,
+    storage_proofs: Vec<u64>,
+    inclusion: InclusionMode,
+    rpc: RpcSelection,
+}
+
+impl ProofDataRequestBuilder {
+    /// Adds a query on `chain_id`.
+    pub fn query(mut self, chain_id: u64, query: ProofDataQuery) -> Self {
+        match self.chains.iter_mut().find(|(id, _)| *id == chain_id) {
+            Some((_, queries)) => queries.push(query),
+            None => self.chains.push((chain_id, vec![query])),
+        }
+        self
+    }
+
+    /// Adds several queries on `chain_id`.
+    pub fn queries(
+        self,
+        chain_id: u64,
+        queries: impl IntoIterator<Item = ProofDataQuery>,
+    ) -> Self {
+        queries
+            .into_iter()
+            .fold(self, |builder, query| builder.query(chain_id, query))
+    }
+
+    /// Sets the finality policy of `chain_id`. Chains without a policy use
+    /// `FinalityPolicy::default_for_chain`.
+    pub fn finality_policy(
+        mut self,
+        chain_id: u64,
+        finality_policy: FinalityPolicy,
+    ) -> Self {
+        self.finality_policies.retain(|(id, _)| *id != chain_id);
+        self.finality_policies.push((chain_id, finality_policy));
+        self
+    }
+
+    /// Queries `chain_id` at `block` instead of near the tip. Only supported
+    /// for Ethereum and Ethereum Sepolia.
+    pub fn historical_block(mut self, chain_id: u64, block: u64) -> Self {
+        self.historical_blocks.retain(|(id, _)| *id != chain_id);
+        self.historical_blocks.push((chain_id, block));
+        self
+    }
+
+    /// Adds an event query on `chain_id`.
+    pub fn event(mut self, chain_id: u64, event: EventQuery) -> Self {
+        self.events.push((chain_id, event));
+        self
+    }
+
+    /// Reads the amounts of `chain_id` from storage proofs of the market
+    /// mappings instead of executing the multicall. Not supported for
+    /// OpStack chains with L1 inclusion.
+    pub fn storage_proofs(mut self, chain_id: u64) -> Self {
+        if !self.storage_proofs.contains(&chain_id) {
+            self.storage_proofs.push(chain_id);
+        }
+        self
+    }
+
+    /// Sets how the queried state is anchored.
+    pub fn inclusion(mut self, inclusion: InclusionMode) -> Self {
+        self.inclusion = inclusion;
+        self
+    }
+
+    /// Sets which RPC endpoints are used.
+    pub fn rpc(mut self, rpc: RpcSelection) -> Self {
+        self.rpc = rpc;
+        self
+    }
+
+    /// Validates and builds the request.
+    ///
+    /// # Errors
+    /// Returns `HostError::InvalidRequest` if:
+    /// - The request has no queries.
+    /// - A chain is not supported.
+    /// - A chain holds the same query twice.
+    /// - A finality policy is set for a chain without queries.
+    /// - A historical block is set for a chain without queries, for a chain
+    ///   other than Ethereum or Ethereum Sepolia, or is the genesis block.
+    /// - An event query is set for a chain without queries, is set twice, is
+    ///   set for an OpStack chain with L1 inclusion, or its emitter is not a
+    ///   market queried on the chain.
+    /// - Storage proofs are requested for a chain without queries, or for an
+    ///   OpStack chain with L1 inclusion.
+    /// - L1 inclusion is requested for a chain without L1 inclusion support.
+    pub fn build(self) -> Result<ProofDataRequest, HostError> {
+        if self.chains.is_empty() {
+            return Err(HostError::InvalidRequest(
+                "proof data request has no queries".to_string(),
+            ));
+        }
+
+        if let Some((chain_id, _)) = self
+            .finality_policies
+            .iter()
+            .find(|(id, _)| !self.chains.iter().any(|(c, _)| c == id))
+        {
+            return Err(HostError::InvalidRequest(format!(
+                "finality policy set for chain {chain_id} without queries"
+            )));
+        }
+
+        for (chain_id, block) in &self.historical_blocks {
+            if !self.chains.iter().any(|(c, _)| c == chain_id) {
+                return Err(HostError::InvalidRequest(format!(
+                    "historical block set for chain {chain_id} without queries"
+                )));
+            }
+            if !is_ethereum_chain(*chain_id) {
+                return Err(HostError::InvalidRequest(format!(
+                    "historical blocks only supported for Ethereum and \
+                     Ethereum Sepolia, not chain {chain_id}"
+                )));
+            }
+            if *block == 0 {
+                return Err(HostError::InvalidRequest(
+                    "historical block must not be the genesis block"
+                        .to_string(),
+                ));
+            }
+        }
+
+        for (i, (chain_id, event)) in self.events.iter().enumerate() {
+            if !self.chains.iter().any(|(c, _)| c == chain_id) {
+                return Err(HostError::InvalidRequest(format!(
+                    "event query set for chain {chain_id} without queries"
+                )));
+            }
+            if !self.chains.iter().any(|(c, queries)| {
+                c == chain_id
+                    && queries.iter().any(|query| query.market == event.emitter)
+            }) {
+                return Err(HostError::InvalidRequest(format!(
+                    "event emitter {} is not a market queried on chain \
+                     {chain_id}",
+                    event.emitter
+                )));
+            }
+            if self.events[..i].contains(&(*chain_id, *event)) {
+                return Err(HostError::InvalidRequest(format!(
+                    "duplicate event query on chain {chain_id}: {event:?}"
+                )));
+            }
+            if self.inclusion.is_l1() && is_opstack_chain(*chain_id) {
+                return Err(HostError::InvalidRequest(format!(
+                    "event proofs not supported for OpStack chain {chain_id} \
+                     with L1 inclusion"
+                )));
+            }
+        }
+
+        for chain_id in &self.storage_proofs {
+            if !self.chains.iter().any(|(c, _)| c == chain_id) {
+                return Err(HostError::InvalidRequest(format!(
+                    "storage proofs set for chain {chain_id} without queries"
+                )));
+            }
+            if self.inclusion.is_l1() && is_opstack_chain(*chain_id) {
+                return Err(HostError::InvalidRequest(format!(
+                    "storage proofs not supported for OpStack chain \
+                     {chain_id} with L1 inclusion"
+                )));
+            }
+        }
+
+        let mut chains = Vec::with_capacity(self.chains.len());
+        for (chain_id, queries) in self.chains {
+            if !is_supported_chain(chain_id) {
+                return Err(HostError::InvalidRequest(format!(
+                    "Invalid chain ID: {chain_id}"
+                )));
+            }
+            if self.inclusion.is_l1() && is_ethereum_chain(chain_id) {
+                return Err(HostError::InvalidRequest(
+                    "L1 Inclusion only supported for Optimism, Base, Linea \
+                     and their Sepolia variants"
+                        .to_string(),
+                ));
+            }
+            for (i, query) in queries.iter().enumerate() {
+                if queries[..i].contains(query) {
+                    return Err(HostError::InvalidRequest(format!(
+                        "duplicate query on chain {chain_id}: {query:?}"
+                    )));
+                }
+            }
+
+            let finality_policy = self
+                .finality_policies
+                .iter()
+                .find(|(id, _)| *id == chain_id)
+                .map(|(_, policy)| *policy)
+                .unwrap_or_else(|| FinalityPolicy::default_for_chain(chain_id));
+            let historical_block = self
+                .historical_blocks
+                .iter()
+                .find(|(id, _)| *id == chain_id)
+                .map(|(_, block)| *block);
+            let events = self
+                .events
+                .iter()
+                .filter(|(id, _)| *id == chain_id)
+                .map(|(_, event)| *event)
+                .collect();
+            chains.push(ChainRequest {
+                chain_id,
+                finality_policy,
+                historical_block,
+                queries,
+                events,
+                storage_proofs: self.storage_proofs.contains(&chain_id),
+            });
+        }
+
+        Ok(ProofDataRequest {
+            chains,
+            inclusion: self.inclusion,
+            rpc: self.rpc,
+        })
+    }
+}
+
+fn is_supported_chain(chain_id: u64) -> bool {
+    matches!(
+        chain_id,
+        ETHEREUM_CHAIN_ID
+            | OPTIMISM_CHAIN_ID
+            | BASE_CHAIN_ID
+            | LINEA_CHAIN_ID
+            | ETHEREUM_SEPOLIA_CHAIN_ID
+            | OPTIMISM_SEPOLIA_CHAIN_ID
+            | BASE_SEPOLIA_CHAIN_ID
+            | LINEA_SEPOLIA_CHAIN_ID
+    )
+}
+
+fn is_ethereum_chain(chain_id: u64) -> bool {
+    matches!(chain_id, ETHEREUM_CHAIN_ID | ETHEREUM_SEPOLIA_CHAIN_ID)
+}
+
+fn is_opstack_chain(chain_id: u64) -> bool {
+    matches!(
+        chain_id,
+        OPTIMISM_CHAIN_ID
+            | BASE_CHAIN_ID
+            | OPTIMISM_SEPOLIA_CHAIN_ID
+            | BASE_SEPOLIA_CHAIN_ID
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const USER: Address = Address::repeat_byte(0x11);
+    const MARKET: Address = Address::repeat_byte(0x22);
+
+    #[test]
+    fn test_queries_are_grouped_by_chain() {
+        let request = ProofDataRequest::builder()
+            .query(LINEA_CHAIN_ID, ProofDataQuery::new(USER, MARKET, 1))
+            .query(BASE_CHAIN_ID, ProofDataQuery::new(USER, MARKET, 1))
+            .query(LINEA_CHAIN_ID, ProofDataQuery::new(MARKET, USER, 8453))
+            .finality_policy(BASE_CHAIN_ID, FinalityPolicy::Finalized)
+            .rpc(RpcSelection::Fallback)
+            .build()
+            .unwrap();
+
+        assert_eq!(request.chain_ids(), [LINEA_CHAIN_ID, BASE_CHAIN_ID]);
+        assert_eq!(
+            request.chains()[0].columns(),
+            (vec![USER, MARKET], vec![MARKET, USER], vec![1, 8453])
+        );
+        assert_eq!(
+            request.chains()[0].finality_policy(),
+            FinalityPolicy::default_for_chain(LINEA_CHAIN_ID)
+        );
+        assert_eq!(
+            request.chains()[1].finality_policy(),
+            FinalityPolicy::Finalized
+        );
+        assert_eq!(request.inclusion(), InclusionMode::Sequencer);
+        assert!(request.rpc().is_fallback());
+        assert!(
+            request
+                .chains()
+                .iter()
+                .all(|c| c.historical_block().is_none())
+        );
+    }
+
+    #[test]
+    fn test_historical_block_is_set_per_chain() {
+        let request = ProofDataRequest::builder()
+            .query(ETHEREUM_CHAIN_ID, ProofDataQuery::new(USER, MARKET, 1))
+            .query(BASE_CHAIN_ID, ProofDataQuery::new(USER, MARKET, 1))
+            .historical_block(ETHEREUM_CHAIN_ID, 100)
+            .historical_block(ETHEREUM_CHAIN_ID, 20_000_000)
+            .build()
+            .unwrap();
+
+        assert_eq!(request.chains()[0].historical_block(), Some(20_000_000));
+        assert_eq!(request.chains()[1].historical_block(), None);
+    }
+
+    #[test]
+    fn test_events_are_grouped_by_chain() {
+        let event = EventQuery::new(B256::repeat_byte(0x33), MARKET);
+        let request = ProofDataRequest::builder()
+            .query(LINEA_CHAIN_ID, ProofDataQuery::new(USER, MARKET, 1))
+            .query(BASE_CHAIN_ID, ProofDataQuery::new(USER, MARKET, 1))
+            .event(BASE_CHAIN_ID, event)
+            .build()
+            .unwrap();
+
+        assert!(request.chains()[0].events().is_empty());
+        assert_eq!(request.chains()[1].events(), [event]);
+    }
+
+    #[test]
+    fn test_storage_proofs_are_set_per_chain() {
+        let request = ProofDataRequest::builder()
+            .query(LINEA_CHAIN_ID, ProofDataQuery::new(USER, MARKET, 1))
+            .query(BASE_CHAIN_ID, ProofDataQuery::new(USER, MARKET, 1))
+            .storage_proofs(LINEA_CHAIN_ID)
+            .storage_proofs(LINEA_CHAIN_ID)
+            .inclusion(InclusionMode::L1)
+            .build()
+            .unwrap();
+
+        assert!(request.chains()[0].storage_proofs());
+        assert!(!request.chains()[1].storage_proofs());
+    }
+
+    #[test]
+    fn test_invalid_requests_are_rejected_at_build_time() {
+        let query = ProofDataQuery::new(USER, MARKET, LINEA_CHAIN_ID);
+        let event = EventQuery::new(B256::repeat_byte(0x33), MARKET);
+        let cases = [
+            (ProofDataRequest::builder(), "no queries"),
+            (
+                ProofDataRequest::builder().query(1234, query),
+                "Invalid chain ID: 1234",
+            ),
+            (
+                ProofDataRequest::builder()
+                    .query(BASE_CHAIN_ID, query)
+                    .query(BASE_CHAIN_ID, query),
+                "duplicate query",
+            ),
+            (
+                ProofDataRequest::builder()
+                    .query(BASE_CHAIN_ID, query)
+                    .finality_policy(LINEA_CHAIN_ID, FinalityPolicy::Safe),
+                "without queries",
+            ),
+            (
+                ProofDataRequest::builder()
+                    .query(ETHEREUM_CHAIN_ID, query)
+                    .inclusion(InclusionMode::L1),
+                "L1 Inclusion only supported",
+            ),
+            (
+                ProofDataRequest::builder()
+                    .query(BASE_CHAIN_ID, query)
+                    .historical_block(ETHEREUM_CHAIN_ID, 100),
+                "historical block set for chain 1 without queries",
+            ),
+            (
+                ProofDataRequest::builder()
+                    .query(BASE_CHAIN_ID, query)
+                    .historical_block(BASE_CHAIN_ID, 100),
+                "only supported for Ethereum",
+            ),
+            (
+                ProofDataRequest::builder()
+                    .query(ETHEREUM_CHAIN_ID, query)
+                    .historical_block(ETHEREUM_CHAIN_ID, 0),
+                "genesis block",
+            ),
+            (
+                ProofDataRequest::builder()
+                    .query(BASE_CHAIN_ID, query)
+                    .event(LINEA_CHAIN_ID, event),
+                "event query set for chain 59144 without queries",
+            ),
+            (
+                ProofDataRequest::builder()
+                    .query(BASE_CHAIN_ID, query)
+                    .event(BASE_CHAIN_ID, event)
+                    .event(BASE_CHAIN_ID, event),
+                "duplicate event query",
+            ),
+            (
+                ProofDataRequest::builder()
+                    .query(BASE_CHAIN_ID, query)
+                    .event(BASE_CHAIN_ID, event)
+                    .inclusion(InclusionMode::L1),
+                "event proofs not supported",
+            ),
+            (
+                ProofDataRequest::builder()
+                    .query(BASE_CHAIN_ID, query)
+                    .event(
+                        BASE_CHAIN_ID,
+                        EventQuery::new(B256::repeat_byte(0x33), USER),
+                    ),
+                "is not a market queried on chain 8453",
+            ),
+            (
+                ProofDataRequest::builder()
+                    .query(BASE_CHAIN_ID, query)
+                    .storage_proofs(LINEA_CHAIN_ID),
+                "storage proofs set for chain 59144 without queries",
+            ),
+            (
+                ProofDataRequest::builder()
+                    .query(BASE_CHAIN_ID, query)
+                    .storage_proofs(BASE_CHAIN_ID)
+                    .inclusion(InclusionMode::L1),
+                "storage proofs not supported",
+            ),
+        ];
+
+        for (builder, expected) in cases {
+            let err = builder.build().unwrap_err();
+            assert!(matches!(err, HostError::InvalidRequest(_)));
+            assert!(err.to_string().contains(expected), "{err}");
+        }
+    }
+}
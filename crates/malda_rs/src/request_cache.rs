@@ -0,0 +1,462 @@
+// Copyright (c) 2025 Merge Layers Inc.
+//
+// This source code is licensed under the Business Source License 1.1
+// (the "License"); you may not use this file except in compliance with the
+// License. You may obtain a copy of the License at
+//
+//     https://github.com/malda-protocol/malda-zk-coprocessor/blob/main/LICENSE-BSL
+//
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+//
+//! Request-scoped cache of chain data shared by all input builders.
+//!
+//! A multi-chain request spawns one builder per chain, and each builder
+//! creates its own Steel environments. Builders that validate against the
+//! same L1 block would otherwise fetch the same headers and proofs once per
+//! chain. `RequestCache` wraps the request's data source and serves its RPC
+//! endpoints through a local proxy that caches every response for a fixed
+//! block, keyed by `(chain, block)`:
+//!
+//! - headers (`eth_getBlockByNumber` for a block number, `eth_getBlockByHash`)
+//! - account and storage proofs (`eth_getProof`)
+//! - storage, code, balance and nonce reads at a block
+//!
+//! Concurrent identical requests are sent upstream once. Requests for a tag
+//! such as `latest`, failed requests and sequencer commitments are never
+//! cached. The primary and fallback endpoints of a chain share one cache, as
+//! data at a fixed block is the same on both.
+//!
+//! The cache lives as long as the `RequestCache`, which the viewcalls entry
+//! points create per request and drop when the input is built.
+
+use crate::data_source::{ChainDataSource, endpoint_id};
+use crate::error::HostError;
+
+use anyhow::{Context, Result};
+use axum::{
+    Router,
+    body::Bytes,
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::post,
+};
+use serde_json::Value;
+use tokio::{net::TcpListener, sync::OnceCell, task::JoinHandle};
+
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{
+        Arc, Mutex, RwLock,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+    },
+};
+
+/// Methods served from the cache, with the index of their block parameter.
+const CACHED_METHODS: [(&str, usize); 7] = [
+    ("eth_getBlockByNumber", 0),
+    ("eth_getBlockByHash", 0),
+    ("eth_getProof", 2),
+    ("eth_getStorageAt", 2),
+    ("eth_getCode", 1),
+    ("eth_getBalance", 1),
+    ("eth_getTransactionCount", 1),
+];
+
+/// Data source that caches the responses of another data source for the
+/// duration of one request.
+pub struct RequestCache {
+    inner: Arc<dyn ChainDataSource>,
+    proxy: CacheProxy,
+}
+
+impl RequestCache {
+    /// Starts a caching proxy in front of `inner`.
+    ///
+    /// # Errors
+    /// Returns an error if the proxy cannot be started.
+    pub async fn start(inner: Arc<dyn ChainDataSource>) -> Result<Self> {
+        let proxy = CacheProxy::start().await?;
+        Ok(Self { inner, proxy })
+    }
+
+    /// Returns the number of requests served from the cache and the number
+    /// of requests sent upstream.
+    pub fn stats(&self) -> (usize, usize) {
+        (
+            self.proxy.state.hits.load(Ordering::Relaxed),
+            self.proxy.state.misses.load(Ordering::Relaxed),
+        )
+    }
+}
+
+impl ChainDataSource for RequestCache {
+    fn rpc_url(
+        &self,
+        chain_name: &str,
+        fallback: bool,
+        testnet: bool,
+    ) -> Result<String, HostError> {
+        let upstream = self.inner.rpc_url(chain_name, fallback, testnet)?;
+        Ok(self.proxy.register(
+            &endpoint_id("rpc", chain_name, fallback, testnet),
+            Upstream {
+                url: upstream,
+                chain: endpoint_id("rpc", chain_name, false, testnet),
+            },
+        ))
+    }
+
+    fn sequencer_request_url(
+        &self,
+        chain_name: &str,
+        fallback: bool,
+        testnet: bool,
+    ) -> Result<String, HostError> {
+        self.inner
+            .sequencer_request_url(chain_name, fallback, testnet)
+    }
+
+    fn beacon_api_url(&self, testnet: bool) -> Option<String> {
+        self.inner.beacon_api_url(testnet)
+    }
+}
+
+impl Drop for RequestCache {
+    fn drop(&mut self) {
+        let (hits, misses) = self.stats();
+        tracing::debug!("request cache: {hits} hits, {misses} misses");
+    }
+}
+
+/// Cache key: the chain, the block and the call at that block.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct CacheKey {
+    chain: String,
+    block: String,
+    call: String,
+}
+
+struct Upstream {
+    url: String,
+    /// Cache namespace of the chain, shared by its primary and fallback
+    /// endpoints.
+    chain: String,
+}
+
+/// Local HTTP proxy that caches block-pinned JSON-RPC responses.
+struct CacheProxy {
+    addr: SocketAddr,
+    state: Arc<ProxyState>,
+    server: JoinHandle<()>,
+}
+
+struct ProxyState {
+    upstreams: RwLock<HashMap<String, Arc<Upstream>>>,
+    entries: Mutex<HashMap<CacheKey, Arc<OnceCell<Value>>>>,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+    client: reqwest::Client,
+}
+
+impl CacheProxy {
+    async fn start() -> Result<Self> {
+        let state = Arc::new(ProxyState {
+            upstreams: RwLock::new(HashMap::new()),
+            entries: Mutex::new(HashMap::new()),
+            hits: AtomicUsize::new(0),
+            misses: AtomicUsize::new(0),
+            client: reqwest::Client::new(),
+        });
+        let app = Router::new()
+            .route("/{endpoint}", post(handle_request))
+            .with_state(state.clone());
+
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .context("failed to bind request cache proxy")?;
+        let addr = listener.local_addr()?;
+        let server = tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, app).await {
+                tracing::error!("request cache proxy stopped: {e}");
+            }
+        });
+
+        Ok(Self {
+            addr,
+            state,
+            server,
+        })
+    }
+
+    fn register(&self, endpoint: &str, upstream: Upstream) -> String {
+        self.state
+            .upstreams
+            .write()
+            .expect("request cache lock poisoned")
+            .insert(endpoint.to_string(), Arc::new(upstream));
+        format!("http://{}/{endpoint}", self.addr)
+    }
+}
+
+impl Drop for CacheProxy {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+async fn handle_request(
+    State(state): State<Arc<ProxyState>>,
+    Path(endpoint): Path<String>,
+    body: Bytes,
+) -> Response {
+    match state.serve_json_rpc(&endpoint, &body).await {
+        Ok(body) => {
+            ([("content-type", "application/json")], body).into_response()
+        }
+        Err(e) => {
+            tracing::error!("request cache error on {endpoint}: {e:#}");
+            (StatusCode::BAD_GATEWAY, format!("{e:#}")).into_response()
+        }
+    }
+}
+
+impl ProxyState {
+    fn upstream(&self, endpoint: &str) -> Result<Arc<Upstream>> {
+        self.upstreams
+            .read()
+            .expect("request cache lock poisoned")
+            .get(endpoint)
+            .cloned()
+            .with_context(|| format!("no upstream registered for {endpoint}"))
+    }
+
+    /// Serves a single or batched JSON-RPC request.
+    async fn serve_json_rpc(
+        &self,
+        endpoint: &str,
+        body: &[u8],
+    ) -> Result<Vec<u8>> {
+        let upstream = self.upstream(endpoint)?;
+        let request: Value = serde_json::from_slice(body)
+            .context("request is not valid JSON")?;
+        let response = match request {
+            Value::Array(calls) => {
+                let mut responses = Vec::with_capacity(calls.len());
+                for call in calls {
+                    responses.push(self.serve_call(&upstream, call).await?);
+                }
+                Value::Array(responses)
+            }
+            call => self.serve_call(&upstream, call).await?,
+        };
+        Ok(serde_json::to_vec(&response)?)
+    }
+
+    async fn serve_call(
+        &self,
+        upstream: &Upstream,
+        call: Value,
+    ) -> Result<Value> {
+        let Some(key) = cache_key(&upstream.chain, &call) else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+            return self.forward(&upstream.url, &call).await;
+        };
+
+        let entry = self
+            .entries
+            .lock()
+            .expect("request cache lock poisoned")
+            .entry(key)
+            .or_default()
+            .clone();
+        let fetched = AtomicBool::new(false);
+        let mut response = entry
+            .get_or_try_init(|| {
+                let (fetched, call) = (&fetched, &call);
+                async move {
+                    fetched.store(true, Ordering::Relaxed);
+                    self.misses.fetch_add(1, Ordering::Relaxed);
+                    let response = self.forward(&upstream.url, call).await?;
+                    // Only successful responses are cached, errors are
+                    // retried
+                    if response.get("result").is_none() {
+                        return Err(UncachedResponse(response).into());
+                    }
+                    Ok::<_, anyhow::Error>(response)
+                }
+            })
+            .await
+            .cloned()
+            .or_else(|e| match e.downcast::<UncachedResponse>() {
+                Ok(UncachedResponse(response)) => Ok(response),
+                Err(e) => Err(e),
+            })?;
+        if !fetched.load(Ordering::Relaxed) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+        }
+
+        // Cached responses carry the ID of the call that fetched them
+        if let (Some(response), Some(id)) =
+            (response.as_object_mut(), call.get("id"))
+        {
+            response.insert("id".to_string(), id.clone());
+        }
+        Ok(response)
+    }
+
+    async fn forward(&self, url: &str, call: &Value) -> Result<Value> {
+        Ok(self
+            .client
+            .post(url)
+            .json(call)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+}
+
+/// A response that is passed through without being cached.
+#[derive(Debug)]
+struct UncachedResponse(Value);
+
+impl std::fmt::Display for UncachedResponse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "uncached response: {}", self.0)
+    }
+}
+
+impl std::error::Error for UncachedResponse {}
+
+/// Returns the cache key of a call, or `None` if its response may change,
+/// e.g. because it refers to a block tag.
+fn cache_key(chain: &str, call: &Value) -> Option<CacheKey> {
+    let method = call.get("method")?.as_str()?;
+    let (_, block_index) = CACHED_METHODS
+        .iter()
+        .find(|(cached, _)| *cached == method)?;
+    let params = call.get("params")?;
+    // Block numbers and hashes are hex strings, tags are not
+    let block = params.get(block_index)?.as_str()?;
+    if !block.starts_with("0x") {
+        return None;
+    }
+    Some(CacheKey {
+        chain: chain.to_string(),
+        block: block.to_lowercase(),
+        call: format!("{method}{params}"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use axum::Json;
+    use serde_json::json;
+
+    /// Upstream that counts its requests and answers every call with a block.
+    async fn counting_upstream() -> (String, Arc<AtomicUsize>) {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let app = Router::new()
+            .route(
+                "/",
+                post(|State(requests): State<Arc<AtomicUsize>>| async move {
+                    requests.fetch_add(1, Ordering::Relaxed);
+                    Json(json!({
+                        "jsonrpc": "2.0",
+                        "id": 1,
+                        "result": { "number": "0x10", "hash": "0xaa" },
+                    }))
+                }),
+            )
+            .with_state(requests.clone());
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        (url, requests)
+    }
+
+    struct FixedDataSource(String);
+
+    impl ChainDataSource for FixedDataSource {
+        fn rpc_url(
+            &self,
+            _: &str,
+            _: bool,
+            _: bool,
+        ) -> Result<String, HostError> {
+            Ok(self.0.clone())
+        }
+
+        fn sequencer_request_url(
+            &self,
+            _: &str,
+            _: bool,
+            _: bool,
+        ) -> Result<String, HostError> {
+            Ok(self.0.clone())
+        }
+    }
+
+    #[test]
+    fn test_only_block_pinned_calls_are_cached() {
+        let cached = |method: &str, params: Value| {
+            let call = json!({
+                "id": 1,
+                "method": method,
+                "params": params,
+            });
+            cache_key("rpc_ethereum", &call).is_some()
+        };
+
+        assert!(cached("eth_getBlockByNumber", json!(["0x10", false])));
+        assert!(!cached("eth_getBlockByNumber", json!(["latest", false])));
+        assert!(cached("eth_getProof", json!(["0x01", [], "0x10"])));
+        assert!(!cached("eth_getProof", json!(["0x01", [], "finalized"])));
+        assert!(!cached("eth_chainId", json!([])));
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_builders_share_block_fetches() {
+        let (url, requests) = counting_upstream().await;
+        let cache = RequestCache::start(Arc::new(FixedDataSource(url)))
+            .await
+            .unwrap();
+        // Primary and fallback endpoints share the cache
+        let urls = [
+            cache.rpc_url("ETHEREUM", false, false).unwrap(),
+            cache.rpc_url("ETHEREUM", true, false).unwrap(),
+        ];
+
+        let client = reqwest::Client::new();
+        let fetches = (0..8).map(|i| {
+            client
+                .post(&urls[i % 2])
+                .json(&json!({
+                    "jsonrpc": "2.0",
+                    "id": i,
+                    "method": "eth_getBlockByNumber",
+                    "params": ["0x10", false],
+                }))
+                .send()
+        });
+        for (i, response) in futures::future::join_all(fetches)
+            .await
+            .into_iter()
+            .enumerate()
+        {
+            let response: Value = response.unwrap().json().await.unwrap();
+            assert_eq!(response["id"], i);
+            assert_eq!(response["result"]["hash"], "0xaa");
+        }
+
+        assert_eq!(requests.load(Ordering::Relaxed), 1);
+        assert_eq!(cache.stats(), (7, 1));
+    }
+}
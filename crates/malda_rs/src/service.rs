@@ -0,0 +1,1095 @@
+// Copyright (c) 2025 Merge Layers Inc.
+//
+// This source code is licensed under the Business Source License 1.1
+// (the "License"); you may not use this file except in compliance with the
+// License. You may obtain a copy of the License at
+//
+//     https://github.com/malda-protocol/malda-zk-coprocessor/blob/main/LICENSE-BSL
+//
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+//
+//! Long-running proof request service.
+//!
+//! `ProofService` accepts proof data requests over HTTP/JSON, persists them as
+//! jobs in an embedded SQLite database and proves them in the background:
+//!
+//! - `POST /jobs` takes a `JobSpec`, validates it and queues a job. It answers
+//!   `202 Accepted` with the job, or `400 Bad Request` if the spec is invalid.
+//! - `GET /jobs/{id}` returns the job's status and, once it succeeded, the
+//!   journal, seal and image ID of its proof.
+//!
+//! A worker takes the oldest queued job and adds every later queued job that
+//! is compatible with it, i.e. uses the same `InclusionMode`, `RpcSelection`
+//! and finality policy per chain, up to `service.max_batch_jobs` jobs. The
+//! queries of the batch are merged into a single `ProofDataRequest` and
+//! proven once by the `JobRunner`; every job of the batch receives the same
+//! result, whose journal holds the outputs of all queries of the batch.
+//!
+//! Jobs survive restarts: jobs that were running when the service stopped
+//! are queued again when the database is opened.
+
+use crate::commitments::CommitmentSource;
+use crate::config::{MaldaConfig, ProverKind, ServiceConfig};
+use crate::data_source::ChainDataSource;
+use crate::error::HostError;
+use crate::prover::{ProofResult, ProverBackend};
+use crate::request::{
+    EventQuery, InclusionMode, ProofDataQuery, ProofDataRequest, RpcSelection,
+};
+use crate::types::FinalityPolicy;
+use crate::verify::verify_receipt;
+use crate::viewcalls::get_proof_data_prove;
+
+use alloy::primitives::{B256, Bytes};
+use anyhow::{Context, Result, bail};
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+};
+use futures::future::BoxFuture;
+use rusqlite::{Connection, OptionalExtension, params};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio::{net::TcpListener, sync::Notify, task::JoinHandle};
+
+use std::{
+    net::SocketAddr,
+    path::Path as FsPath,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Runs the proof of a batch.
+pub trait JobRunner: Send + Sync {
+    /// Proves `request`.
+    ///
+    /// # Errors
+    /// Returns an error if the input cannot be built or the proof fails. The
+    /// error is stored on every job of the batch.
+    fn run(
+        &self,
+        request: ProofDataRequest,
+    ) -> BoxFuture<'_, Result<ProofResult>>;
+}
+
+/// Runs batches with `get_proof_data_prove` and verifies the receipts.
+///
+/// Results are checked against the image ID of the guest program the prover
+/// was configured with, not the one the result reports, and must carry a
+/// seal unless the prover is the `execute` or `dev` backend.
+pub struct ProvingRunner {
+    config: Arc<MaldaConfig>,
+    data_source: Arc<dyn ChainDataSource>,
+    commitments: Arc<dyn CommitmentSource>,
+    prover: Arc<dyn ProverBackend>,
+}
+
+impl ProvingRunner {
+    /// Creates a runner building inputs from `data_source` and
+    /// `commitments` and proving them with `prover`.
+    pub fn new(
+        config: Arc<MaldaConfig>,
+        data_source: Arc<dyn ChainDataSource>,
+        commitments: Arc<dyn CommitmentSource>,
+        prover: Arc<dyn ProverBackend>,
+    ) -> Self {
+        Self {
+            config,
+            data_source,
+            commitments,
+            prover,
+        }
+    }
+}
+
+impl JobRunner for ProvingRunner {
+    fn run(
+        &self,
+        request: ProofDataRequest,
+    ) -> BoxFuture<'_, Result<ProofResult>> {
+        Box::pin(async move {
+            let result = get_proof_data_prove(
+                &self.config,
+                self.data_source.clone(),
+                self.commitments.clone(),
+                &*self.prover,
+                &request,
+            )
+            .await?;
+            // Never hand out a proof the on-chain verifier would reject
+            let expected = self.prover.program().image_id();
+            if result.image_id != expected {
+                bail!(
+                    "proof has image ID {}, but the {} guest has {expected}",
+                    result.image_id,
+                    self.prover.program().name()
+                );
+            }
+            if let Some(receipt) = &result.receipt {
+                verify_receipt(receipt, expected)?;
+            }
+            if result.seal.is_empty()
+                && !matches!(
+                    self.config.prover,
+                    ProverKind::Execute | ProverKind::Dev
+                )
+            {
+                bail!(
+                    "the {} prover returned no seal to submit",
+                    self.prover.name()
+                );
+            }
+            Ok(result)
+        })
+    }
+}
+
+/// A proof data request as submitted to the service.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JobSpec {
+    pub chains: Vec<ChainSpec>,
+    #[serde(default)]
+    pub inclusion: InclusionMode,
+    #[serde(default)]
+    pub rpc: RpcSelection,
+}
+
+/// The queries of a single chain of a `JobSpec`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ChainSpec {
+    pub chain_id: u64,
+    /// Finality policy, `FinalityPolicy::default_for_chain` if not set.
+    #[serde(default)]
+    pub finality_policy: Option<FinalityPolicy>,
+    /// Historical block to query at, near the tip if not set.
+    #[serde(default)]
+    pub historical_block: Option<u64>,
+    pub queries: Vec<ProofDataQuery>,
+    /// Events to prove, see `receipts`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<EventQuery>,
+    /// Whether the amounts are read from storage proofs, see `storage`.
+    #[serde(default)]
+    pub storage_proofs: bool,
+}
+
+impl JobSpec {
+    /// Builds the validated request of the spec.
+    ///
+    /// # Errors
+    /// Returns `HostError::InvalidRequest` if the request is invalid, see
+    /// `ProofDataRequestBuilder::build`.
+    pub fn to_request(&self) -> Result<ProofDataRequest, HostError> {
+        let mut builder = ProofDataRequest::builder()
+            .inclusion(self.inclusion)
+            .rpc(self.rpc);
+        for chain in &self.chains {
+            builder = builder.queries(chain.chain_id, chain.queries.clone());
+            if let Some(finality_policy) = chain.finality_policy {
+                builder =
+                    builder.finality_policy(chain.chain_id, finality_policy);
+            }
+            if let Some(block) = chain.historical_block {
+                builder = builder.historical_block(chain.chain_id, block);
+            }
+            for event in &chain.events {
+                builder = builder.event(chain.chain_id, *event);
+            }
+            if chain.storage_proofs {
+                builder = builder.storage_proofs(chain.chain_id);
+            }
+        }
+        builder.build()
+    }
+
+    /// Merges the queries and events of `other` into `self`, skipping the
+    /// ones `self` already holds.
+    ///
+    /// Returns `false` and leaves `self` unchanged if the specs are not
+    /// compatible, i.e. differ in inclusion mode, RPC selection or the
+    /// finality policy, historical block or storage proof mode of a chain
+    /// they share.
+    fn merge(&mut self, other: &JobSpec) -> bool {
+        if self.inclusion != other.inclusion || self.rpc != other.rpc {
+            return false;
+        }
+        let conflicts = other.chains.iter().any(|chain| {
+            self.chains.iter().any(|own| {
+                own.chain_id == chain.chain_id
+                    && (own.finality_policy != chain.finality_policy
+                        || own.historical_block != chain.historical_block
+                        || own.storage_proofs != chain.storage_proofs)
+            })
+        });
+        if conflicts {
+            return false;
+        }
+
+        for chain in &other.chains {
+            match self
+                .chains
+                .iter_mut()
+                .find(|own| own.chain_id == chain.chain_id)
+            {
+                Some(own) => {
+                    for query in &chain.queries {
+                        if !own.queries.contains(query) {
+                            own.queries.push(*query);
+                        }
+                    }
+                    for event in &chain.events {
+                        if !own.events.contains(event) {
+                            own.events.push(*event);
+                        }
+                    }
+                }
+                None => self.chains.push(chain.clone()),
+            }
+        }
+        true
+    }
+}
+
+impl From<&ProofDataRequest> for JobSpec {
+    /// Converts a request back to its spec, with every chain's finality
+    /// policy set explicitly.
+    fn from(request: &ProofDataRequest) -> Self {
+        Self {
+            chains: request
+                .chains()
+                .iter()
+                .map(|chain| ChainSpec {
+                    chain_id: chain.chain_id(),
+                    finality_policy: Some(chain.finality_policy()),
+                    historical_block: chain.historical_block(),
+                    queries: chain.queries().to_vec(),
+                    events: chain.events().to_vec(),
+                    storage_proofs: chain.storage_proofs(),
+                })
+                .collect(),
+            inclusion: request.inclusion(),
+            rpc: request.rpc(),
+        }
+    }
+}
+
+/// Lifecycle of a job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+}
+
+impl JobStatus {
+    fn parse(status: &str) -> rusqlite::Result<Self> {
+        Ok(match status {
+            "queued" => JobStatus::Queued,
+            "running" => JobStatus::Running,
+            "succeeded" => JobStatus::Succeeded,
+            "failed" => JobStatus::Failed,
+            _ => {
+                return Err(rusqlite::Error::InvalidColumnType(
+                    0,
+                    format!("unknown job status {status}"),
+                    rusqlite::types::Type::Text,
+                ));
+            }
+        })
+    }
+}
+
+/// A job as returned by the status API.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JobView {
+    pub id: i64,
+    pub status: JobStatus,
+    /// ID of the batch the job was proven in, the ID of its oldest job.
+    pub batch_id: Option<i64>,
+    pub error: Option<String>,
+    pub result: Option<JobResult>,
+    /// Submission time, in seconds since the Unix epoch.
+    pub created_at: u64,
+    /// Time of the last status change, in seconds since the Unix epoch.
+    pub updated_at: u64,
+}
+
+/// Proof of a succeeded job.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JobResult {
+    pub journal: Bytes,
+    pub seal: Bytes,
+    pub image_id: B256,
+}
+
+/// SQLite-backed job queue.
+#[derive(Clone)]
+struct JobStore {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl JobStore {
+    /// Opens or creates the database and queues the jobs that were running
+    /// when the service stopped.
+    fn open(path: &FsPath) -> Result<Self> {
+        let conn = Connection::open(path).with_context(|| {
+            format!("failed to open job database {}", path.display())
+        })?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS jobs (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                spec TEXT NOT NULL,
+                status TEXT NOT NULL,
+                batch_id INTEGER,
+                error TEXT,
+                journal BLOB,
+                seal BLOB,
+                image_id BLOB,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS jobs_status ON jobs (status, id);",
+        )?;
+        let requeued = conn.execute(
+            "UPDATE jobs SET status = 'queued', batch_id = NULL
+             WHERE status = 'running'",
+            [],
+        )?;
+        if requeued > 0 {
+            tracing::info!("Requeued {requeued} interrupted jobs");
+        }
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    /// Runs `f` on the connection without blocking the async runtime.
+    async fn call<T: Send + 'static>(
+        &self,
+        f: impl FnOnce(&Connection) -> rusqlite::Result<T> + Send + 'static,
+    ) -> Result<T> {
+        let conn = self.conn.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            f(&conn.lock().expect("job database lock poisoned"))
+        })
+        .await?;
+        Ok(result?)
+    }
+
+    async fn insert(&self, spec: &JobSpec) -> Result<i64> {
+        let spec = serde_json::to_string(spec)?;
+        self.call(move |conn| {
+            let now = now();
+            conn.execute(
+                "INSERT INTO jobs (spec, status, created_at, updated_at)
+                 VALUES (?1, 'queued', ?2, ?2)",
+                params![spec, now],
+            )?;
+            Ok(conn.last_insert_rowid())
+        })
+        .await
+    }
+
+    async fn get(&self, id: i64) -> Result<Option<JobView>> {
+        self.call(move |conn| {
+            conn.query_row(
+                "SELECT id, status, batch_id, error, journal, seal, image_id,
+                        created_at, updated_at
+                 FROM jobs WHERE id = ?1",
+                [id],
+                |row| {
+                    let journal: Option<Vec<u8>> = row.get(4)?;
+                    let seal: Option<Vec<u8>> = row.get(5)?;
+                    let image_id: Option<Vec<u8>> = row.get(6)?;
+                    let result = match (journal, seal, image_id) {
+                        (Some(journal), Some(seal), Some(image_id))
+                            if image_id.len() == 32 =>
+                        {
+                            Some(JobResult {
+                                journal: journal.into(),
+                                seal: seal.into(),
+                                image_id: B256::from_slice(&image_id),
+                            })
+                        }
+                        _ => None,
+                    };
+                    Ok(JobView {
+                        id: row.get(0)?,
+                        status: JobStatus::parse(&row.get::<_, String>(1)?)?,
+                        batch_id: row.get(2)?,
+                        error: row.get(3)?,
+                        result,
+                        created_at: row.get(7)?,
+                        updated_at: row.get(8)?,
+                    })
+                },
+            )
+            .optional()
+        })
+        .await
+    }
+
+    /// Returns the queued jobs, oldest first.
+    async fn queued(&self) -> Result<Vec<(i64, String)>> {
+        self.call(|conn| {
+            let mut statement = conn.prepare(
+                "SELECT id, spec FROM jobs WHERE status = 'queued' ORDER BY id",
+            )?;
+            let jobs = statement
+                .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+                .collect();
+            jobs
+        })
+        .await
+    }
+
+    async fn start_batch(&self, ids: Vec<i64>) -> Result<()> {
+        self.call(move |conn| {
+            let batch_id = ids[0];
+            let now = now();
+            for id in ids {
+                conn.execute(
+                    "UPDATE jobs SET status = 'running', batch_id = ?2,
+                        updated_at = ?3
+                     WHERE id = ?1",
+                    params![id, batch_id, now],
+                )?;
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    async fn finish(
+        &self,
+        ids: Vec<i64>,
+        result: Result<ProofResult, String>,
+    ) -> Result<()> {
+        self.call(move |conn| {
+            let now = now();
+            for id in ids {
+                match &result {
+                    Ok(proof) => conn.execute(
+                        "UPDATE jobs SET status = 'succeeded', journal = ?2,
+                            seal = ?3, image_id = ?4, updated_at = ?5
+                         WHERE id = ?1",
+                        params![
+                            id,
+                            proof.journal.to_vec(),
+                            proof.seal.to_vec(),
+                            proof.image_id.as_bytes().to_vec(),
+                            now
+                        ],
+                    )?,
+                    Err(error) => conn.execute(
+                        "UPDATE jobs SET status = 'failed', error = ?2,
+                            updated_at = ?3
+                         WHERE id = ?1",
+                        params![id, error, now],
+                    )?,
+                };
+            }
+            Ok(())
+        })
+        .await
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+struct ServiceState {
+    store: JobStore,
+    notify: Notify,
+}
+
+/// Running proof request service.
+///
+/// The HTTP server and the worker run in background tasks that are stopped
+/// when the service is dropped.
+pub struct ProofService {
+    addr: SocketAddr,
+    server: JoinHandle<()>,
+    worker: JoinHandle<()>,
+}
+
+impl ProofService {
+    /// Opens the job database and starts the HTTP server and the worker.
+    ///
+    /// # Arguments
+    /// * `settings` - Listen address, database path and batching settings.
+    /// * `runner` - Runner proving the batches.
+    ///
+    /// # Errors
+    /// Returns an error if the database cannot be opened or the listen
+    /// address cannot be bound.
+    pub async fn start(
+        settings: &ServiceConfig,
+        runner: Arc<dyn JobRunner>,
+    ) -> Result<Self> {
+        let store = JobStore::open(&settings.database)?;
+        let state = Arc::new(ServiceState {
+            store,
+            notify: Notify::new(),
+        });
+
+        let app = Router::new()
+            .route("/jobs", post(submit_job))
+            .route("/jobs/{id}", get(get_job))
+            .with_state(state.clone());
+        let listener = TcpListener::bind(&settings.listen_addr)
+            .await
+            .with_context(|| {
+                format!("failed to bind {}", settings.listen_addr)
+            })?;
+        let addr = listener.local_addr()?;
+        let server = tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, app).await {
+                tracing::error!("proof service stopped: {e}");
+            }
+        });
+
+        let worker = tokio::spawn(run_worker(
+            state,
+            runner,
+            settings.max_batch_jobs,
+            Duration::from_millis(settings.poll_interval_ms),
+        ));
+
+        Ok(Self {
+            addr,
+            server,
+            worker,
+        })
+    }
+
+    /// Returns the address the HTTP API listens on.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Waits until the HTTP server stops.
+    ///
+    /// # Errors
+    /// Returns an error if the server task panicked.
+    pub async fn wait(&mut self) -> Result<()> {
+        (&mut self.server).await?;
+        Ok(())
+    }
+}
+
+impl Drop for ProofService {
+    fn drop(&mut self) {
+        self.server.abort();
+        self.worker.abort();
+    }
+}
+
+async fn submit_job(
+    State(state): State<Arc<ServiceState>>,
+    Json(spec): Json<JobSpec>,
+) -> Response {
+    if let Err(e) = spec.to_request() {
+        return error_response(StatusCode::BAD_REQUEST, e.to_string());
+    }
+    let job = match state.store.insert(&spec).await {
+        Ok(id) => state.store.get(id).await,
+        Err(e) => Err(e),
+    };
+    match job {
+        Ok(Some(job)) => {
+            state.notify.notify_one();
+            (StatusCode::ACCEPTED, Json(job)).into_response()
+        }
+        Ok(None) => error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "job vanished after insert".to_string(),
+        ),
+        Err(e) => {
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}"))
+        }
+    }
+}
+
+async fn get_job(
+    State(state): State<Arc<ServiceState>>,
+    Path(id): Path<i64>,
+) -> Response {
+    match state.store.get(id).await {
+        Ok(Some(job)) => Json(job).into_response(),
+        Ok(None) => {
+            error_response(StatusCode::NOT_FOUND, format!("job {id} not found"))
+        }
+        Err(e) => {
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}"))
+        }
+    }
+}
+
+fn error_response(status: StatusCode, error: String) -> Response {
+    (status, Json(json!({ "error": error }))).into_response()
+}
+
+async fn run_worker(
+    state: Arc<ServiceState>,
+    runner: Arc<dyn JobRunner>,
+    max_batch_jobs: usize,
+    poll_interval: Duration,
+) {
+    loop {
+        match run_next_batch(&state.store, &*runner, max_batch_jobs).await {
+            // Look for the next batch right away
+            Ok(true) => continue,
+            Ok(false) => {}
+            Err(e) => tracing::error!("proof service worker failed: {e:#}"),
+        }
+        tokio::select! {
+            _ = state.notify.notified() => {}
+            _ = tokio::time::sleep(poll_interval) => {}
+        }
+    }
+}
+
+/// Proves the next batch of queued jobs.
+///
+/// Returns `false` if no job is queued.
+async fn run_next_batch(
+    store: &JobStore,
+    runner: &dyn JobRunner,
+    max_batch_jobs: usize,
+) -> Result<bool> {
+    let queued = store.queued().await?;
+    if queued.is_empty() {
+        return Ok(false);
+    }
+
+    let mut batch: Option<JobSpec> = None;
+    let mut ids = Vec::new();
+    for (id, spec) in queued {
+        if ids.len() == max_batch_jobs {
+            break;
+        }
+        let spec: JobSpec = match serde_json::from_str(&spec) {
+            Ok(spec) => spec,
+            Err(e) => {
+                store
+                    .finish(vec![id], Err(format!("invalid stored job: {e}")))
+                    .await?;
+                continue;
+            }
+        };
+        if let Some(batch) = &mut batch {
+            if !batch.merge(&spec) {
+                continue;
+            }
+        } else {
+            batch = Some(spec);
+        }
+        ids.push(id);
+    }
+    let Some(batch) = batch else {
+        return Ok(true);
+    };
+
+    tracing::info!("Proving batch of jobs {ids:?}");
+    store.start_batch(ids.clone()).await?;
+    let result = match batch.to_request() {
+        Ok(request) => runner.run(request).await.map_err(|e| format!("{e:#}")),
+        Err(e) => Err(e.to_string()),
+    };
+    if let Err(e) = &result {
+        tracing::warn!("Batch of jobs {ids:?} failed: {e}");
+    }
+    store.finish(ids, result).await?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commitments::HttpCommitmentSource;
+    use crate::constants::*;
+    use crate::data_source::RpcDataSource;
+    use crate::elfs_ids::GET_PROOF_DATA_ID;
+    use crate::journal::decode_journal;
+    use crate::programs::GuestProgram;
+    use crate::prover::ExecuteOnly;
+    use crate::types::{SolidityDataType, TakeLastXBytes, abi};
+    use crate::verify::encode_seal;
+
+    use alloy::primitives::{Address, U256};
+    use alloy_sol_types::SolValue;
+    use risc0_zkvm::{
+        FakeReceipt, InnerReceipt, Receipt, ReceiptClaim, sha::Digest,
+    };
+    use serde_json::Value;
+
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    const USER: Address = Address::repeat_byte(0x11);
+    const MARKET: Address = Address::repeat_byte(0x22);
+
+    /// Runner that records the requests it is asked to prove.
+    #[derive(Default)]
+    struct MockRunner {
+        requests: Mutex<Vec<ProofDataRequest>>,
+    }
+
+    impl JobRunner for MockRunner {
+        fn run(
+            &self,
+            request: ProofDataRequest,
+        ) -> BoxFuture<'_, Result<ProofResult>> {
+            self.requests.lock().unwrap().push(request);
+            Box::pin(async {
+                Ok(ProofResult {
+                    journal: Bytes::from_static(b"journal"),
+                    seal: Bytes::from_static(b"seal"),
+                    image_id: Digest::from([7u32; 8]),
+                    stats: None,
+                    receipt: None,
+                })
+            })
+        }
+    }
+
+    /// Runner that proves every batch with a fake receipt of the embedded
+    /// guest, whose journal holds a zero amount entry per query.
+    struct FakeReceiptRunner;
+
+    impl FakeReceiptRunner {
+        fn receipt(request: &ProofDataRequest) -> Receipt {
+            let mut output = Vec::new();
+            let mut finality_policy_hashes = Vec::new();
+            for chain in request.chains() {
+                for query in chain.queries() {
+                    let (entry, _) = abi::encode_packed(&[
+                        SolidityDataType::Address(query.user),
+                        SolidityDataType::Address(query.market),
+                        SolidityDataType::Number(U256::ZERO),
+                        SolidityDataType::Number(U256::ZERO),
+                        SolidityDataType::NumberWithShift(
+                            U256::from(chain.chain_id()),
+                            TakeLastXBytes(32),
+                        ),
+                        SolidityDataType::NumberWithShift(
+                            U256::from(query.target_chain_id),
+                            TakeLastXBytes(32),
+                        ),
+                        SolidityDataType::Bool(request.inclusion().is_l1()),
+                    ]);
+                    output.push(Bytes::from(entry));
+                }
+                finality_policy_hashes
+                    .push(chain.finality_policy().hash(chain.chain_id()));
+            }
+            let journal = (output, finality_policy_hashes, Vec::<Bytes>::new())
+                .abi_encode_params();
+            let claim = ReceiptClaim::ok(GET_PROOF_DATA_ID, journal.clone());
+            Receipt::new(InnerReceipt::Fake(FakeReceipt::new(claim)), journal)
+        }
+    }
+
+    impl JobRunner for FakeReceiptRunner {
+        fn run(
+            &self,
+            request: ProofDataRequest,
+        ) -> BoxFuture<'_, Result<ProofResult>> {
+            Box::pin(async move {
+                Ok(ProofResult::from_receipt(
+                    Self::receipt(&request),
+                    Digest::from(GET_PROOF_DATA_ID),
+                    None,
+                )?)
+            })
+        }
+    }
+
+    /// Runner that never finishes, standing in for a service that stops
+    /// while proving.
+    struct PendingRunner(AtomicUsize);
+
+    impl JobRunner for PendingRunner {
+        fn run(
+            &self,
+            _: ProofDataRequest,
+        ) -> BoxFuture<'_, Result<ProofResult>> {
+            self.0.fetch_add(1, Ordering::Relaxed);
+            Box::pin(futures::future::pending())
+        }
+    }
+
+    fn settings(name: &str) -> ServiceConfig {
+        let database = std::env::temp_dir().join(format!(
+            "malda-service-{name}-{}-{}.sqlite",
+            std::process::id(),
+            now()
+        ));
+        let _ = std::fs::remove_file(&database);
+        ServiceConfig {
+            listen_addr: "127.0.0.1:0".to_string(),
+            database,
+            max_batch_jobs: 16,
+            poll_interval_ms: 50,
+        }
+    }
+
+    fn spec(chain_id: u64, user: Address, inclusion: InclusionMode) -> Value {
+        json!({
+            "chains": [{
+                "chain_id": chain_id,
+                "queries": [{
+                    "user": user,
+                    "market": MARKET,
+                    "target_chain_id": LINEA_CHAIN_ID,
+                }],
+            }],
+            "inclusion": inclusion,
+        })
+    }
+
+    async fn submit(service: &ProofService, spec: &Value) -> (u16, Value) {
+        let response = reqwest::Client::new()
+            .post(format!("http://{}/jobs", service.addr()))
+            .json(spec)
+            .send()
+            .await
+            .unwrap();
+        (response.status().as_u16(), response.json().await.unwrap())
+    }
+
+    async fn wait_for(service: &ProofService, id: i64, status: &str) -> Value {
+        for _ in 0..200 {
+            let job: Value =
+                reqwest::get(format!("http://{}/jobs/{id}", service.addr()))
+                    .await
+                    .unwrap()
+                    .json()
+                    .await
+                    .unwrap();
+            if job["status"] == status {
+                return job;
+            }
+            tokio::time::sleep(Duration::from_millis(25)).await;
+        }
+        panic!("job {id} did not reach status {status}");
+    }
+
+    #[test]
+    fn test_only_compatible_specs_are_merged() {
+        let parse = |value: Value| -> JobSpec {
+            serde_json::from_value(value).unwrap()
+        };
+        let mut batch = parse(spec(BASE_CHAIN_ID, USER, InclusionMode::L1));
+
+        // Same query on the same chain is deduplicated
+        assert!(batch.merge(&parse(spec(
+            BASE_CHAIN_ID,
+            USER,
+            InclusionMode::L1
+        ))));
+        assert!(batch.merge(&parse(spec(
+            LINEA_CHAIN_ID,
+            USER,
+            InclusionMode::L1
+        ))));
+        assert!(!batch.merge(&parse(spec(
+            OPTIMISM_CHAIN_ID,
+            USER,
+            InclusionMode::Sequencer
+        ))));
+
+        let mut finalized =
+            parse(spec(BASE_CHAIN_ID, MARKET, InclusionMode::L1));
+        finalized.chains[0].finality_policy = Some(FinalityPolicy::Finalized);
+        assert!(!batch.merge(&finalized));
+
+        let mut storage_proofs =
+            parse(spec(LINEA_CHAIN_ID, MARKET, InclusionMode::L1));
+        storage_proofs.chains[0].storage_proofs = true;
+        assert!(!batch.merge(&storage_proofs));
+
+        let request = batch.to_request().unwrap();
+        assert_eq!(request.chain_ids(), [BASE_CHAIN_ID, LINEA_CHAIN_ID]);
+        assert_eq!(request.chains()[0].queries().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_spec_is_rejected() {
+        let runner = Arc::new(MockRunner::default());
+        let service = ProofService::start(&settings("invalid"), runner.clone())
+            .await
+            .unwrap();
+
+        let (status, body) =
+            submit(&service, &spec(ETHEREUM_CHAIN_ID, USER, InclusionMode::L1))
+                .await;
+        assert_eq!(status, 400);
+        assert!(
+            body["error"]
+                .as_str()
+                .unwrap()
+                .contains("L1 Inclusion only supported")
+        );
+
+        let response =
+            reqwest::get(format!("http://{}/jobs/42", service.addr()))
+                .await
+                .unwrap();
+        assert_eq!(response.status().as_u16(), 404);
+    }
+
+    #[tokio::test]
+    async fn test_succeeded_job_returns_journal_and_seal() {
+        let service = ProofService::start(
+            &settings("succeeded"),
+            Arc::new(FakeReceiptRunner),
+        )
+        .await
+        .unwrap();
+
+        let (status, job) =
+            submit(&service, &spec(BASE_CHAIN_ID, USER, InclusionMode::L1))
+                .await;
+        assert_eq!(status, 202);
+        assert_eq!(job["status"], "queued");
+        let job =
+            wait_for(&service, job["id"].as_i64().unwrap(), "succeeded").await;
+        let job: JobView = serde_json::from_value(job).unwrap();
+        assert_eq!(job.batch_id, Some(job.id));
+        assert_eq!(job.error, None);
+        let result = job.result.expect("succeeded jobs have a result");
+
+        let journal = decode_journal(&result.journal).unwrap();
+        assert_eq!(journal.entries.len(), 1);
+        assert_eq!(journal.entries[0].user, USER);
+        assert_eq!(journal.entries[0].market, MARKET);
+        assert_eq!(journal.entries[0].chain_id, BASE_CHAIN_ID as u32);
+        assert!(journal.entries[0].l1_inclusion);
+        assert_eq!(
+            journal.finality_policy_hashes,
+            [FinalityPolicy::default_for_chain(BASE_CHAIN_ID)
+                .hash(BASE_CHAIN_ID)]
+        );
+
+        let receipt = Receipt::new(
+            InnerReceipt::Fake(FakeReceipt::new(ReceiptClaim::ok(
+                GET_PROOF_DATA_ID,
+                result.journal.to_vec(),
+            ))),
+            result.journal.to_vec(),
+        );
+        assert_eq!(Some(result.seal), encode_seal(&receipt).unwrap());
+        assert_eq!(
+            result.image_id.as_slice(),
+            Digest::from(GET_PROOF_DATA_ID).as_bytes()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_jobs_are_batched_after_restart() {
+        let settings = settings("restart");
+
+        // The first service stops while proving the first job
+        let pending = Arc::new(PendingRunner(AtomicUsize::new(0)));
+        let service = ProofService::start(&settings, pending.clone())
+            .await
+            .unwrap();
+        let (status, first) =
+            submit(&service, &spec(BASE_CHAIN_ID, USER, InclusionMode::L1))
+                .await;
+        assert_eq!(status, 202);
+        let first = first["id"].as_i64().unwrap();
+        wait_for(&service, first, "running").await;
+        let (_, second) =
+            submit(&service, &spec(LINEA_CHAIN_ID, USER, InclusionMode::L1))
+                .await;
+        let second = second["id"].as_i64().unwrap();
+        drop(service);
+        assert_eq!(pending.0.load(Ordering::Relaxed), 1);
+
+        // The restarted service proves both jobs in a single batch
+        let runner = Arc::new(MockRunner::default());
+        let service = ProofService::start(&settings, runner.clone())
+            .await
+            .unwrap();
+        let first = wait_for(&service, first, "succeeded").await;
+        let second = wait_for(&service, second, "succeeded").await;
+
+        assert_eq!(first["batch_id"], second["batch_id"]);
+        assert_eq!(first["result"], second["result"]);
+        assert_eq!(first["result"]["journal"], "0x6a6f75726e616c");
+        let requests = runner.requests.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].chain_ids(), [BASE_CHAIN_ID, LINEA_CHAIN_ID]);
+    }
+
+    #[tokio::test]
+    async fn test_rpc_failure_fails_the_job() {
+        // Mock RPC endpoint answering every request with an error
+        let app = Router::new().fallback(|Json(call): Json<Value>| async move {
+            Json(json!({
+                "jsonrpc": "2.0",
+                "id": call["id"],
+                "error": { "code": -32000, "message": "mock rpc unavailable" },
+            }))
+        });
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let rpc = tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        let mut config = MaldaConfig::default();
+        config
+            .apply_overrides(|name| {
+                (name.starts_with("RPC_URL_")
+                    || name.starts_with("SEQUENCER_REQUEST_"))
+                .then(|| url.clone())
+                .filter(|_| !name.ends_with("_EXTRA"))
+            })
+            .unwrap();
+        config.validate().unwrap();
+        let config = Arc::new(config);
+        let data_source = Arc::new(RpcDataSource::new(config.clone()));
+        let runner = Arc::new(ProvingRunner::new(
+            config,
+            data_source.clone(),
+            Arc::new(HttpCommitmentSource::new(data_source, None)),
+            Arc::new(ExecuteOnly::new(GuestProgram::get_proof_data().unwrap())),
+        ));
+        let service =
+            ProofService::start(&settings("rpc"), runner).await.unwrap();
+
+        let (_, job) = submit(
+            &service,
+            &spec(LINEA_CHAIN_ID, USER, InclusionMode::Sequencer),
+        )
+        .await;
+        let job =
+            wait_for(&service, job["id"].as_i64().unwrap(), "failed").await;
+        assert!(job["result"].is_null());
+        assert!(
+            job["error"]
+                .as_str()
+                .unwrap()
+                .contains("mock rpc unavailable"),
+            "unexpected error: {}",
+            job["error"]
+        );
+        rpc.abort();
+    }
+}
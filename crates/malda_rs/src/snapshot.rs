@@ -0,0 +1,284 @@
+// Copyright (c) 2025 Merge Layers Inc.
+//
+// This source code is licensed under the Business Source License 1.1
+// (the "License"); you may not use this file except in compliance with the
+// License. You may obtain a copy of the License at
+//
+//     https://github.com/malda-protocol/malda-zk-coprocessor/blob/main/LICENSE-BSL
+//
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+//
+//! Portable snapshots of guest inputs.
+//!
+//! The guest input is built from live RPC state, so a proof that fails on a
+//! remote prover cannot be rebuilt once the chains have moved on. An
+//! `InputSnapshot` saves the serialized input together with the request it
+//! was built for, the hash of the configuration settings that shaped it and
+//! the image ID of the guest it targets. It can be executed or proven again
+//! at any time, with any `ProverBackend`.
+//!
+//! `viewcalls::get_proof_data_input` writes a snapshot of every input it
+//! builds to `MaldaConfig::snapshot_dir`, if set. Snapshots are JSON files:
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "created_at": 1735689600,
+//!   "request": { "chains": [...], "inclusion": "l1", "rpc": "primary" },
+//!   "config_hash": "0x...",
+//!   "image_id": "0x...",
+//!   "input": "0x..."
+//! }
+//! ```
+
+use crate::config::MaldaConfig;
+use crate::error::HostError;
+use crate::programs::GuestProgram;
+use crate::prover::{ExecuteOnly, ProofResult, ProverBackend};
+use crate::request::ProofDataRequest;
+use crate::service::JobSpec;
+use crate::types::NetworkProfile;
+
+use alloy::primitives::{B256, Bytes, keccak256};
+use anyhow::{Context, Result, bail, ensure};
+use serde::{Deserialize, Serialize};
+
+use std::{
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Version of the snapshot format written by this crate.
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// A serialized guest input with the context it was built in.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct InputSnapshot {
+    /// Snapshot format version, `SNAPSHOT_VERSION`.
+    pub version: u32,
+    /// Creation time, in seconds since the Unix epoch.
+    pub created_at: u64,
+    /// The request the input was built for.
+    pub request: JobSpec,
+    /// `MaldaConfig::hash` of the configuration that built the input.
+    pub config_hash: B256,
+    /// Image ID of the guest the input was built for.
+    pub image_id: B256,
+    /// The serialized guest input.
+    pub input: Bytes,
+}
+
+impl InputSnapshot {
+    /// Creates a snapshot of `input`, built by `config` for `request`. The
+    /// image ID is the one of the guest `config` selects.
+    ///
+    /// # Errors
+    /// Returns an error if the guest of `config` cannot be loaded, see
+    /// `GuestProgram::for_config`.
+    pub fn new(
+        config: &MaldaConfig,
+        request: &ProofDataRequest,
+        input: Vec<u8>,
+    ) -> Result<Self> {
+        Ok(Self {
+            version: SNAPSHOT_VERSION,
+            created_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or_default(),
+            request: request.into(),
+            config_hash: config.hash(),
+            image_id: GuestProgram::for_config(config)?.image_id_b256(),
+            input: input.into(),
+        })
+    }
+
+    /// Returns the validated request of the snapshot.
+    ///
+    /// # Errors
+    /// Returns `HostError::InvalidRequest` if the saved request is invalid.
+    pub fn request(&self) -> Result<ProofDataRequest, HostError> {
+        self.request.to_request()
+    }
+
+    /// Returns a file name that is unique per creation time and input.
+    pub fn file_name(&self) -> String {
+        let chain_ids = self
+            .request
+            .chains
+            .iter()
+            .map(|chain| chain.chain_id.to_string())
+            .collect::<Vec<_>>()
+            .join("-");
+        let input_hash = keccak256(&self.input);
+        format!(
+            "{}-{chain_ids}-{}.json",
+            self.created_at,
+            alloy::hex::encode(&input_hash[..4])
+        )
+    }
+
+    /// Writes the snapshot to `path`.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be written.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        std::fs::write(path, serde_json::to_vec_pretty(self)?)
+            .with_context(|| format!("failed to write {}", path.display()))
+    }
+
+    /// Writes the snapshot to `dir` under `file_name`, creating `dir` if
+    /// needed.
+    ///
+    /// # Returns
+    /// * `Result<PathBuf>` - Path of the written file.
+    ///
+    /// # Errors
+    /// Returns an error if the directory or file cannot be written.
+    pub fn save_in(&self, dir: impl AsRef<Path>) -> Result<PathBuf> {
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create {}", dir.display()))?;
+        let path = dir.join(self.file_name());
+        self.save(&path)?;
+        Ok(path)
+    }
+
+    /// Reads a snapshot from `path`.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read or parsed, or was written
+    /// in another snapshot format version.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let snapshot: Self = serde_json::from_slice(&contents)
+            .with_context(|| format!("invalid snapshot {}", path.display()))?;
+        ensure!(
+            snapshot.version == SNAPSHOT_VERSION,
+            "snapshot {} has format version {}, expected {SNAPSHOT_VERSION}",
+            path.display(),
+            snapshot.version
+        );
+        Ok(snapshot)
+    }
+
+    /// Returns the get_proof_data guest the snapshot was built for, i.e. the
+    /// network profile whose ELF has the snapshot's image ID.
+    ///
+    /// # Arguments
+    /// * `elf_dir` - Directory of the non-mainnet ELFs, see
+    ///   `GuestProgram::for_profile`.
+    ///
+    /// # Errors
+    /// Returns an error if no profile's ELF has the snapshot's image ID.
+    pub fn program(&self, elf_dir: Option<&Path>) -> Result<GuestProgram> {
+        [
+            NetworkProfile::Mainnet,
+            NetworkProfile::Sepolia,
+            NetworkProfile::Devnet,
+        ]
+        .into_iter()
+        .filter_map(|profile| GuestProgram::for_profile(profile, elf_dir).ok())
+        .find(|program| program.image_id_b256() == self.image_id)
+        .with_context(|| format!("no guest image {} found", self.image_id))
+    }
+
+    /// Executes the saved input with the guest it was built for, without
+    /// proving.
+    ///
+    /// # Errors
+    /// Returns an error if the guest image is not found (see `program`) or
+    /// the execution fails.
+    pub async fn execute(&self, elf_dir: Option<&Path>) -> Result<ProofResult> {
+        self.prove(&ExecuteOnly::new(self.program(elf_dir)?)).await
+    }
+
+    /// Proves the saved input with `prover`.
+    ///
+    /// # Errors
+    /// Returns an error if the snapshot targets another guest image than the
+    /// program of `prover`, or the backend fails.
+    pub async fn prove(
+        &self,
+        prover: &dyn ProverBackend,
+    ) -> Result<ProofResult> {
+        let program = prover.program();
+        if self.image_id != program.image_id_b256() {
+            bail!(
+                "snapshot was built for guest image {}, the {} prover runs \
+                 {} ({})",
+                self.image_id,
+                prover.name(),
+                program.name(),
+                program.image_id_b256()
+            );
+        }
+        prover.prove(self.input.to_vec()).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::{BASE_CHAIN_ID, LINEA_CHAIN_ID};
+    use crate::request::{InclusionMode, ProofDataQuery};
+
+    use alloy::primitives::Address;
+
+    fn mainnet_config() -> MaldaConfig {
+        let mut config = MaldaConfig::default();
+        config.guest.profile = Some(NetworkProfile::Mainnet);
+        config
+    }
+
+    fn snapshot() -> InputSnapshot {
+        let request = ProofDataRequest::builder()
+            .query(
+                LINEA_CHAIN_ID,
+                ProofDataQuery::new(
+                    Address::repeat_byte(1),
+                    Address::repeat_byte(2),
+                    BASE_CHAIN_ID,
+                ),
+            )
+            .inclusion(InclusionMode::L1)
+            .build()
+            .unwrap();
+        InputSnapshot::new(&mainnet_config(), &request, vec![1, 2, 3, 4])
+            .unwrap()
+    }
+
+    #[test]
+    fn test_snapshot_round_trips_through_file() {
+        let dir = std::env::temp_dir()
+            .join(format!("malda-snapshot-{}", std::process::id()));
+        let snapshot = snapshot();
+
+        let path = snapshot.save_in(&dir).unwrap();
+        let loaded = InputSnapshot::load(&path).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(loaded, snapshot);
+        assert_eq!(
+            loaded.request().unwrap(),
+            snapshot.request.to_request().unwrap()
+        );
+        assert_eq!(loaded.config_hash, mainnet_config().hash());
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_for_other_image_is_rejected() {
+        let mut snapshot = snapshot();
+        snapshot.image_id = B256::ZERO;
+
+        let err = snapshot.execute(None).await.unwrap_err();
+
+        assert!(err.to_string().contains("guest image"), "{err}");
+    }
+}
@@ -0,0 +1,126 @@
+// Copyright (c) 2025 Merge Layers Inc.
+//
+// This source code is licensed under the Business Source License 1.1
+// (the "License"); you may not use this file except in compliance with the
+// License. You may obtain a copy of the License at
+//
+//     https://github.com/malda-protocol/malda-zk-coprocessor/blob/main/LICENSE-BSL
+//
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+//
+//! Host builders for storage proofs.
+//!
+//! Derives the amount slots of every query from the chain's
+//! `storage::MarketLayout` and fetches their `eth_getProof` account and
+//! storage proofs at the view call block, building the
+//! `storage::StorageProofInput` the guest verifies instead of executing the
+//! multicall.
+
+use crate::error::HostError;
+use crate::storage::{
+    AccountProof, MarketLayout, StorageProofInput, StorageSlotProof,
+};
+use crate::viewcalls::parse_rpc_url;
+
+use alloy::{
+    primitives::{Address, B256},
+    providers::{Provider, ProviderBuilder},
+};
+use futures::future::join_all;
+
+/// Builds the storage proofs of a chain's queries.
+///
+/// # Arguments
+/// * `rpc_url` - RPC URL of the chain.
+/// * `chain_id` - The chain the markets are deployed on.
+/// * `view_call_block` - Number of the view call block, whose state root the
+///   proofs are verified against.
+/// * `users` - Users to query.
+/// * `markets` - Markets to query.
+/// * `target_chain_ids` - Target chain IDs to query.
+///
+/// # Returns
+/// * `Result<StorageProofInput, HostError>` - One account proof per market,
+///   holding the proofs of all its queried slots.
+///
+/// # Errors
+/// Returns an error if:
+/// - A target chain ID exceeds `uint32`.
+/// - RPC calls fail.
+pub async fn get_storage_proof_input(
+    rpc_url: &str,
+    chain_id: u64,
+    view_call_block: u64,
+    users: &[Address],
+    markets: &[Address],
+    target_chain_ids: &[u64],
+) -> Result<StorageProofInput, HostError> {
+    let provider = ProviderBuilder::new().connect_http(parse_rpc_url(rpc_url)?);
+    let layout = MarketLayout::for_chain(chain_id);
+
+    // Slots by market, in the order of the markets' first query
+    let mut slots: Vec<(Address, Vec<B256>)> = Vec::new();
+    for ((user, market), target_chain_id) in
+        users.iter().zip(markets).zip(target_chain_ids)
+    {
+        if u32::try_from(*target_chain_id).is_err() {
+            return Err(HostError::InvalidRequest(format!(
+                "target chain ID {target_chain_id} exceeds uint32"
+            )));
+        }
+        let (amount_in, amount_out) =
+            layout.amount_slots(*user, *target_chain_id);
+        let index = match slots.iter().position(|(m, _)| m == market) {
+            Some(index) => index,
+            None => {
+                slots.push((*market, Vec::new()));
+                slots.len() - 1
+            }
+        };
+        let keys = &mut slots[index].1;
+        for key in [amount_in, amount_out] {
+            if !keys.contains(&key) {
+                keys.push(key);
+            }
+        }
+    }
+
+    let provider = &provider;
+    let futures: Vec<_> = slots
+        .into_iter()
+        .map(|(market, keys)| async move {
+            provider
+                .get_proof(market, keys)
+                .number(view_call_block)
+                .await
+                .map_err(|e| HostError::rpc("Failed to fetch storage proof", e))
+        })
+        .collect();
+    let accounts = join_all(futures)
+        .await
+        .into_iter()
+        .map(|proof| {
+            proof.map(|proof| AccountProof {
+                address: proof.address,
+                nonce: proof.nonce,
+                balance: proof.balance,
+                storage_root: proof.storage_hash,
+                code_hash: proof.code_hash,
+                proof: proof.account_proof,
+                storage: proof
+                    .storage_proof
+                    .into_iter()
+                    .map(|slot| StorageSlotProof {
+                        key: slot.key.as_b256(),
+                        value: slot.value,
+                        proof: slot.proof,
+                    })
+                    .collect(),
+            })
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok(StorageProofInput { accounts })
+}
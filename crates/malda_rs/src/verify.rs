@@ -0,0 +1,159 @@
+// Copyright (c) 2025 Merge Layers Inc.
+//
+// This source code is licensed under the Business Source License 1.1
+// (the "License"); you may not use this file except in compliance with the
+// License. You may obtain a copy of the License at
+//
+//     https://github.com/malda-protocol/malda-zk-coprocessor/blob/main/LICENSE-BSL
+//
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+//
+//! Verification of get_proof_data receipts before on-chain submission.
+//!
+//! `ZkVerifier.sol` forwards `(seal, imageId, sha256(journal))` to the RISC
+//! Zero verifier router, which dispatches on the first four bytes of the seal.
+//! `verify_receipt` runs the same checks off-chain, so a relayer only submits
+//! proofs the contract accepts:
+//!
+//! - the receipt verifies against the expected image ID and its journal,
+//! - the journal decodes as a get_proof_data journal,
+//! - the seal is encoded with the selector of the receipt's verifier.
+
+use crate::error::HostError;
+use crate::journal::{Journal, decode_journal};
+
+use alloy::primitives::Bytes;
+use risc0_zkvm::{InnerReceipt, Receipt, VerificationError, sha::Digest};
+use thiserror::Error;
+
+/// Error returned by `verify_receipt`.
+#[derive(Debug, Error)]
+pub enum VerifyError {
+    /// The receipt does not verify against the expected image ID, e.g.
+    /// because it was produced by another guest or its seal is invalid.
+    #[error("receipt verification failed: {0}")]
+    Receipt(#[from] VerificationError),
+
+    /// The journal is not a get_proof_data journal.
+    #[error("invalid journal: {0}")]
+    Journal(#[source] HostError),
+
+    /// The seal could not be encoded for the verifier router.
+    #[error("failed to encode seal: {0}")]
+    Seal(String),
+}
+
+/// A receipt that passed `verify_receipt`.
+///
+/// Fields:
+/// - `journal`: The decoded journal.
+/// - `journal_bytes`: The raw journal, passed to `ZkVerifier.verifyInput`.
+/// - `seal`: The selector-prefixed seal for the verifier router. `None` for
+///   succinct and composite receipts, which the router cannot verify; these
+///   must be compressed to Groth16 before they are submitted.
+#[derive(Debug, Clone)]
+pub struct VerifiedReceipt {
+    pub journal: Journal,
+    pub journal_bytes: Bytes,
+    pub seal: Option<Bytes>,
+}
+
+/// Verifies a get_proof_data receipt and encodes its seal for on-chain
+/// submission.
+///
+/// Groth16, succinct and composite receipts are verified with the default
+/// verifier parameters, the ones the deployed router supports. Fake receipts
+/// only verify in dev mode (`RISC0_DEV_MODE`).
+///
+/// # Arguments
+/// * `receipt` - The receipt returned by the prover.
+/// * `expected_image_id` - Image ID the on-chain verifier expects, usually
+///   `GET_PROOF_DATA_ID`.
+///
+/// # Returns
+/// * `Result<VerifiedReceipt, VerifyError>` - The decoded journal and the
+///   seal to submit.
+///
+/// # Errors
+/// Returns an error if the receipt does not verify against
+/// `expected_image_id`, the journal cannot be decoded, or the seal cannot be
+/// encoded.
+pub fn verify_receipt(
+    receipt: &Receipt,
+    expected_image_id: impl Into<Digest>,
+) -> Result<VerifiedReceipt, VerifyError> {
+    receipt.verify(expected_image_id)?;
+
+    let journal =
+        decode_journal(&receipt.journal.bytes).map_err(VerifyError::Journal)?;
+
+    Ok(VerifiedReceipt {
+        journal,
+        journal_bytes: receipt.journal.bytes.clone().into(),
+        seal: encode_seal(receipt)?,
+    })
+}
+
+/// Encodes the seal of `receipt` as expected by the verifier router.
+///
+/// The seal of a Groth16 receipt is prefixed with the selector of its
+/// verifier parameters, the seal of a fake receipt with the selector of the
+/// mock verifier. The receipt itself is not verified.
+///
+/// # Returns
+/// * `Result<Option<Bytes>, VerifyError>` - The encoded seal, or `None` for
+///   receipts the router cannot verify.
+///
+/// # Errors
+/// Returns an error if the seal cannot be encoded.
+pub fn encode_seal(receipt: &Receipt) -> Result<Option<Bytes>, VerifyError> {
+    match &receipt.inner {
+        InnerReceipt::Groth16(_) | InnerReceipt::Fake(_) => {
+            risc0_ethereum_contracts::encode_seal(receipt)
+                .map(|seal| Some(seal.into()))
+                .map_err(|e| VerifyError::Seal(e.to_string()))
+        }
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elfs_ids::GET_PROOF_DATA_ID;
+
+    use alloy::primitives::B256;
+    use alloy_sol_types::SolValue;
+    use risc0_zkvm::{FakeReceipt, ReceiptClaim};
+
+    fn fake_receipt(image_id: [u32; 8]) -> Receipt {
+        let journal =
+            (Vec::<Bytes>::new(), vec![B256::ZERO], Vec::<Bytes>::new())
+                .abi_encode_params();
+        let claim = ReceiptClaim::ok(image_id, journal.clone());
+        Receipt::new(InnerReceipt::Fake(FakeReceipt::new(claim)), journal)
+    }
+
+    #[test]
+    fn test_fake_receipt_seal_has_mock_selector() {
+        let seal = encode_seal(&fake_receipt(GET_PROOF_DATA_ID))
+            .unwrap()
+            .expect("fake receipts have an on-chain seal");
+
+        assert_eq!(&seal[..4], &[0xff; 4]);
+        assert_eq!(seal.len(), 4 + 32);
+    }
+
+    #[test]
+    fn test_verify_receipt_rejects_other_image_id() {
+        let mut other_image_id = GET_PROOF_DATA_ID;
+        other_image_id[0] ^= 1;
+
+        let result =
+            verify_receipt(&fake_receipt(other_image_id), GET_PROOF_DATA_ID);
+
+        assert!(matches!(result, Err(VerifyError::Receipt(_))));
+    }
+}
@@ -25,12 +25,13 @@
 //! - **Sequencer Commitment Handling**: Process sequencer commitments for L2
 //!   chains (Optimism, Base)
 //! - **L1 Block Verification**: Verify L1 block data for L2 chains with dispute
-//!   game validation
+//!   game validation, against one L1 anchor per Ethereum network shared by
+//!   all chains of a request
 //! - **Reorg Protection**: Manage linking blocks to protect against chain
 //!   reorganizations
-//! - **Bonsai Integration**: Generate ZK proofs using the Bonsai SDK
-//! - **Boundless Market Integration**: Submit proof requests to the Boundless
-//!   market for decentralized proving
+//! - **Pluggable Provers**: Prove locally, on Bonsai or on the Boundless
+//!   market, or only execute the guest, through a `ProverBackend` selected
+//!   by configuration
 //!
 //! ## Supported Networks
 //!
@@ -51,16 +52,26 @@
 //! - **Blacklist Verification**: Checks that dispute games are not blacklisted
 //! - **Game Type Validation**: Verifies dispute games use the correct game type
 
+use crate::commitments::{CommitmentSource, check_commitment};
+use crate::config::{MaldaConfig, RedactedUrl};
 use crate::constants::*;
-use crate::elfs_ids::*;
+use crate::data_source::ChainDataSource;
+use crate::error::HostError;
+use crate::events::get_event_input;
+use crate::programs::GuestProgram;
+use crate::prover::{ProofResult, ProverBackend};
+use crate::request::{
+    ChainRequest, InclusionMode, ProofDataRequest, RpcSelection,
+};
+use crate::request_cache::RequestCache;
+use crate::snapshot::InputSnapshot;
+use crate::storage_proofs::get_storage_proof_input;
 use crate::types::*;
 use crate::types::{
-    Call3, ExecutionPayload, IDisputeGame, IDisputeGameFactory, IL1Block,
-    IL1MessageService, IMulticall3, SequencerCommitment,
+    Call3, IDisputeGame, IDisputeGameFactory, IL1Block, IL1MessageService,
+    IMulticall3, SequencerCommitment,
 };
 
-use core::panic;
-
 use risc0_op_steel::{
     DisputeGameIndex,
     optimism::{OP_MAINNET_CHAIN_SPEC, OpEvmEnv, OpEvmInput},
@@ -71,799 +82,468 @@ use risc0_steel::{
     host::BlockNumberOrTag,
     serde::RlpHeader,
 };
-use risc0_zkvm::{
-    ExecutorEnv, ProveInfo, ProverOpts, SessionInfo, default_executor,
-    default_prover,
-};
 
-use alloy::primitives::{Address, Bytes, U64, U256};
+use alloy::primitives::{Address, U64, U256};
 use alloy_consensus::Header;
 
 use anyhow::{Error, Result};
-use bonsai_sdk;
 use futures::future::join_all;
 use tokio;
 use url::Url;
 
-use std::time::Duration;
-
-use bonsai_sdk::blocking::Client;
-use risc0_zkvm::Receipt;
-use tracing::info;
+use std::{collections::BTreeSet, sync::Arc};
 
-use dotenvy;
+use tracing::{debug, info, warn};
 
-use alloy::{signers::local::PrivateKeySigner, sol_types::SolValue};
-use anyhow::{Context, bail};
-use boundless_market::{
-    Client as BoundlessClient, storage::storage_provider_from_env,
-};
-use std::str::FromStr;
-
-/// Timeout duration for transaction confirmation.
+/// The L1 anchors of a request, with the numbers of the anchored blocks.
 ///
-/// The maximum time to wait for a transaction to be confirmed on the
-/// blockchain. This is used for operations that require transaction
-/// confirmation, such as submitting proof requests to the Boundless market.
-pub const TX_TIMEOUT: Duration = Duration::from_secs(30);
-
-/// Statistics for a Malda ZK proof session.
-///
-/// This struct contains detailed information about the computational resources
-/// used during proof generation. It is useful for auditing, performance
-/// analysis, and understanding the cost of ZK proof generation.
-///
-/// Fields:
-/// - `segments`: Number of proof segments generated by the ZKVM session.
-/// - `total_cycles`: Total computational cycles used (all segments).
-/// - `user_cycles`: User-specific computational cycles (cycles spent on user
-///   logic).
-/// - `paging_cycles`: Cycles used for memory paging operations (if any).
-/// - `reserved_cycles`: Reserved cycles for system operations (if any).
-#[derive(Debug, Clone)]
-pub struct MaldaSessionStats {
-    pub segments: usize,
-    pub total_cycles: u64,
-    pub user_cycles: u64,
-    pub paging_cycles: u64,
-    pub reserved_cycles: u64,
+/// Chains that check data against Ethereum (Ethereum itself, and L1
+/// inclusion) are built at the anchored block of their Ethereum network, so
+/// the guest validates the block once for all of them. See `L1Anchor`.
+#[derive(Debug, Clone, Default)]
+pub struct L1Anchors {
+    anchors: Vec<(L1Anchor, u64)>,
 }
 
-/// Information about a completed Malda ZK proof.
-///
-/// This struct contains the proof receipt, session statistics, and timing
-/// information for a ZK proof session. It is returned by proof generation
-/// functions and is used for both on-chain and off-chain verification.
-///
-/// Fields:
-/// - `receipt`: The ZK proof receipt containing the proof data (can be
-///   submitted on-chain).
-/// - `stats`: Statistics about the proof generation session (see
-///   `MaldaSessionStats`).
-/// - `uuid`: Unique identifier for the proof session (from Bonsai SDK).
-/// - `stark_time`: Time taken for STARK proof generation in seconds.
-/// - `snark_time`: Time taken for SNARK proof generation in seconds.
-#[derive(Debug)]
-pub struct MaldaProveInfo {
-    pub receipt: Receipt,
-    pub stats: MaldaSessionStats,
-    pub uuid: String,
-    pub stark_time: u64,
-    pub snark_time: u64,
-}
+impl L1Anchors {
+    /// Returns the number of the anchored block of `ethereum_chain_id`.
+    pub fn block(&self, ethereum_chain_id: u64) -> Option<u64> {
+        self.anchors
+            .iter()
+            .find(|(anchor, _)| anchor.chain_id == ethereum_chain_id)
+            .map(|(_, block)| *block)
+    }
 
-/// Runs a Bonsai ZK proof session with the provided input data.
-///
-/// This function handles the complete Bonsai SDK workflow:
-/// 1. Uploads input data to Bonsai
-/// 2. Creates a proof session
-/// 3. Polls for session completion (STARK phase)
-/// 4. Creates a SNARK session
-/// 5. Polls for SNARK completion
-/// 6. Downloads and deserializes the proof receipt
-///
-/// The function provides detailed timing information for both STARK and SNARK
-/// phases.
-///
-/// # Arguments
-/// * `input_data` - The serialized input data for the ZKVM session.
-///
-/// # Returns
-/// * `Result<MaldaProveInfo, anyhow::Error>` - Proof information and statistics
-///   if successful, or an error.
-///
-/// # Errors
-/// Returns an error if:
-/// - The Bonsai client fails to initialize.
-/// - The input upload, session creation, or polling fails.
-/// - The SNARK proof or receipt download fails.
-/// - The receipt cannot be deserialized.
-/// - Session status indicates failure.
-///
-/// # Panics
-/// Panics if the required environment variable `IMAGE_ID_BONSAI` is not set.
-///
-/// # Environment Variables
-/// Requires the following environment variable:
-/// - `IMAGE_ID_BONSAI`: Bonsai image ID for ZK proof generation
-fn run_bonsai(input_data: Vec<u8>) -> Result<MaldaProveInfo, anyhow::Error> {
-    // Initialize the Bonsai client from environment variables (uses RISC Zero
-    // version for compatibility)
-    let client = Client::from_env(risc0_zkvm::VERSION)?;
-
-    // Get the Bonsai image ID from the environment (required for proof session)
-    let image_id_hex: String = dotenvy::var("IMAGE_ID_BONSAI")
-        .expect("IMAGE_ID_BONSAI must be set in environment");
-
-    // Upload the input data to Bonsai and get an input ID
-    let input_id = client.upload_input(input_data)?;
-
-    let assumptions: Vec<String> = vec![];
-    let execute_only = false;
-
-    // Create a new proof session on Bonsai
-    let session = client.create_session(
-        image_id_hex,
-        input_id,
-        assumptions,
-        execute_only,
-    )?;
-
-    let polling_interval = Duration::from_millis(500);
-
-    // --- STARK phase: Wait for the session to complete and collect stats ---
-    let stark_time = std::time::Instant::now();
-    let succinct_stats = loop {
-        let res = session.status(&client)?;
-        if res.status == "RUNNING" {
-            // Session is still running, wait and poll again
-            std::thread::sleep(polling_interval);
-            continue;
-        }
-        if res.status == "SUCCEEDED" {
-            // Session succeeded, extract stats
-            let stats = res
-                .stats
-                .expect("Missing stats object on Bonsai status res");
-            tracing::debug!(
-                "Bonsai usage: cycles: {} total_cycles: {}",
-                stats.cycles,
-                stats.total_cycles
-            );
+    /// Returns the anchors, in the order the guest reads them.
+    pub fn anchors(&self) -> Vec<&L1Anchor> {
+        self.anchors.iter().map(|(anchor, _)| anchor).collect()
+    }
 
-            break MaldaSessionStats {
-                segments: stats.segments,
-                total_cycles: stats.total_cycles,
-                user_cycles: stats.cycles,
-                paging_cycles: 0, // Paging cycles not tracked in this context
-                reserved_cycles: 0, /* Reserved cycles not tracked in this
-                                   * context */
-            };
-        } else {
-            // Session failed or exited unexpectedly
-            return Err(anyhow::Error::msg(format!(
-                "Bonsai prover workflow [{}] exited: {} err: {}",
-                session.uuid,
-                res.status,
-                res.error_msg
-                    .unwrap_or("Bonsai workflow missing error_msg".into())
-            )));
-        }
-    };
-    let stark_time = stark_time.elapsed();
-
-    // --- SNARK phase: Create a SNARK session and wait for completion ---
-    let snark_session = client.create_snark(session.uuid.clone())?;
-
-    let start = std::time::Instant::now();
-    let snark_receipt_url = loop {
-        let res = snark_session.status(&client)?;
-        match res.status.as_str() {
-            "RUNNING" => {
-                // SNARK session is still running, wait and poll again
-                std::thread::sleep(polling_interval);
-                continue;
-            }
-            "SUCCEEDED" => {
-                // SNARK session succeeded, get the output URL
-                break res.output.ok_or_else(|| {
-                    anyhow::Error::msg(format!(
-                        "Bonsai prover workflow [{}] reported success, but provided no receipt",
-                        snark_session.uuid
-                    ))
-                })?;
-            }
-            _ => {
-                // SNARK session failed or exited unexpectedly
-                return Err(anyhow::Error::msg(format!(
-                    "Bonsai prover workflow [{}] exited: {} err: {}",
-                    snark_session.uuid,
-                    res.status,
-                    res.error_msg
-                        .unwrap_or("Bonsai workflow missing error_msg".into())
-                )));
-            }
+    /// Returns the anchors a guest proving only `chain_id` needs.
+    pub fn for_chain(
+        &self,
+        chain_id: u64,
+        inclusion: InclusionMode,
+    ) -> Vec<&L1Anchor> {
+        if !is_ethereum_chain(chain_id) && !inclusion.is_l1() {
+            return Vec::new();
         }
-    };
-
-    let snark_time = start.elapsed();
-
-    // Download the Groth16 receipt (proof) from Bonsai and deserialize it
-    let receipt_buf = client.download(&snark_receipt_url)?;
-    let groth16_receipt: Receipt = bincode::deserialize(&receipt_buf)?;
+        let ethereum_chain_id = get_ethereum_chain_id(chain_id);
+        self.anchors
+            .iter()
+            .map(|(anchor, _)| anchor)
+            .filter(|anchor| anchor.chain_id == ethereum_chain_id)
+            .collect()
+    }
+}
 
-    Ok(MaldaProveInfo {
-        receipt: groth16_receipt,
-        stats: succinct_stats,
-        uuid: session.uuid,
-        stark_time: stark_time.as_secs(),
-        snark_time: snark_time.as_secs(),
-    })
+/// The guest input of one chain of a request, proven on its own.
+///
+/// The input has the layout of a request holding only this chain, with the
+/// L1 anchor it needs, see `get_chain_proof_data_inputs`.
+#[derive(Debug, Clone)]
+pub struct ChainProofInput {
+    pub chain_id: u64,
+    pub input: Vec<u8>,
 }
 
-/// Submits a proof data request to the Boundless market for decentralized
-/// proving.
+/// Prepares input data for the ZKVM for multiple chains' proof data queries.
 ///
-/// This function creates a proof request and submits it to the Boundless
-/// market, which will handle the ZK proof generation in a decentralized manner.
-/// The function waits for the request to be fulfilled and returns the proof
-/// journal and seal.
+/// This function prepares the input data handed to a `ProverBackend` by
+/// collecting proof data inputs from multiple chains in parallel and
+/// serializing them into the format expected by the guest.
 ///
-/// The function supports both onchain and offchain submission modes:
-/// - **Offchain**: Uses `client.submit_offchain()` for gasless submission
-/// - **Onchain**: Uses `client.submit_onchain()` for on-chain submission with
-///   gas costs
+/// The function fetches the request's L1 anchors, processes each chain's
+/// proof data independently and then combines all inputs into a single
+/// serialized input: the anchors, the number of chains and the chain inputs.
+/// If `config.snapshot_dir` is set, the input is also saved there as an
+/// `InputSnapshot`.
 ///
 /// # Arguments
-/// * `users` - Vector of user address vectors, one per chain.
-/// * `markets` - Vector of market contract address vectors, one per chain.
-/// * `target_chain_id` - Vector of target chain IDs to query (vector of
-///   vectors).
-/// * `chain_ids` - Vector of chain IDs to query.
-/// * `l1_inclusion` - Whether to include L1 data in the proof.
-/// * `fallback` - Whether to use fallback RPC URLs (default: false).
-/// * `onchain` - Whether to submit onchain (true) or offchain (false).
+/// * `config` - Configuration the request's networks are checked against.
+/// * `data_source` - Source of the RPC and sequencer endpoints.
+/// * `commitments` - Source of the sequencer commitments.
+/// * `request` - The validated proof data request.
 ///
 /// # Returns
-/// * `Result<(Bytes, Bytes), Error>` - Tuple of (journal, seal) if successful,
-///   or an error.
+/// * `Result<Vec<u8>, HostError>` - Serialized input data for the ZKVM.
 ///
 /// # Errors
-/// Returns an error if:
-/// - Environment variables `RPC_URL` or `PRIVATE_KEY` are not set.
-/// - The Boundless client fails to initialize.
-/// - The request submission or fulfillment fails.
-/// - The input data preparation fails.
-///
-/// # Environment Variables
-/// Requires the following environment variables:
-/// - `RPC_URL`: Ethereum RPC endpoint for transactions
-/// - `PRIVATE_KEY`: Private key for signing transactions
-///
-/// Optional environment variables:
-/// - `PROGRAM_URL`: URL of a pre-uploaded program to avoid re-upload latency
-pub async fn get_proof_data_prove_boundless(
-    users: Vec<Vec<Address>>,
-    markets: Vec<Vec<Address>>,
-    target_chain_id: Vec<Vec<u64>>,
-    chain_ids: Vec<u64>,
-    l1_inclusion: bool,
-    fallback: bool,
-    onchain: bool,
-) -> Result<(Bytes, Bytes), Error> {
-    // Only initialize tracing if it hasn't been set up already
-    if tracing_subscriber::util::SubscriberInitExt::try_init(
-        tracing_subscriber::fmt()
-            .with_env_filter(tracing_subscriber::EnvFilter::from_default_env()),
-    )
-    .is_err()
-    {
-        // Tracing is already initialized, which is fine
-        tracing::debug!("Tracing subscriber already initialized");
-    }
+/// Returns an error if a chain's network is not configured, any chain's
+/// input fails to build (see `get_chain_inputs`) or serialization fails.
+pub async fn get_proof_data_input(
+    config: &MaldaConfig,
+    data_source: Arc<dyn ChainDataSource>,
+    commitments: Arc<dyn CommitmentSource>,
+    request: &ProofDataRequest,
+) -> Result<Vec<u8>, HostError> {
+    ensure_networks_configured(config, &request.chain_ids())?;
+
+    let chain_count = request.chains().len() as u64;
+    let (l1_anchors, all_inputs) =
+        get_chain_inputs(data_source, commitments, request).await?;
+
+    // Serialize the anchors and the number of chains ahead of the chain
+    // inputs (required by the ZKVM input format)
+    let input: Vec<u8> = bytemuck::pod_collect_to_vec(
+        &risc0_zkvm::serde::to_vec(&(l1_anchors.anchors(), chain_count))
+            .map_err(|e| {
+                HostError::encode("Failed to serialize L1 anchors", e)
+            })?,
+    );
 
-    // Load environment variables from .env if present
-    match dotenvy::dotenv() {
-        Ok(path) => {
-            tracing::debug!("Loaded environment variables from {:?}", path)
+    // Concatenate the header and all chain inputs into a single input vector
+    let input = [input, all_inputs.concat()].concat();
+
+    // Save the input so a failing proof can be reproduced later. A snapshot
+    // is a diagnostic, failing to write it does not fail the request.
+    if let Some(dir) = &config.snapshot_dir {
+        match InputSnapshot::new(config, request, input.clone())
+            .and_then(|snapshot| snapshot.save_in(dir))
+        {
+            Ok(path) => info!("Saved input snapshot to {}", path.display()),
+            Err(e) => warn!("Failed to save input snapshot: {e:#}"),
         }
-        Err(e) if e.not_found() => tracing::debug!("No .env file found"),
-        Err(e) => bail!("failed to load .env file: {}", e),
     }
 
-    // Get required environment variables for RPC and signing
-    let rpc_url = dotenvy::var("RPC_URL")
-        .context("RPC_URL environment variable not set")?;
-    let private_key = dotenvy::var("PRIVATE_KEY")
-        .context("PRIVATE_KEY environment variable not set")?;
-
-    let rpc_url = Url::parse(&rpc_url)?;
-    let private_key = PrivateKeySigner::from_str(&private_key)?;
-
-    // Create a Boundless client from the provided parameters.
-    let client = BoundlessClient::builder()
-        .with_storage_provider(Some(storage_provider_from_env()?))
-        .with_rpc_url(rpc_url)
-        .with_private_key(private_key)
-        .build()
-        .await
-        .context("failed to build boundless client")?;
-
-    // Prepare the input bytes for the proof request (serializes all chain data)
-    let input_bytes = get_proof_data_input(
-        users,
-        markets,
-        target_chain_id,
-        chain_ids,
-        l1_inclusion,
-        fallback,
-    )
-    .await;
-
-    // Build the request - use program URL if available to avoid re-upload
-    let request = if let Ok(program_url) = dotenvy::var("PROGRAM_URL") {
-        tracing::info!("Using pre-uploaded program from URL: {}", program_url);
-        let parsed_url =
-            Url::parse(&program_url).context("Failed to parse PROGRAM_URL")?;
-        client
-            .new_request()
-            .with_program_url(parsed_url)?
-            .with_stdin(input_bytes)
-            .with_groth16_proof()
-    } else {
-        tracing::info!("No PROGRAM_URL found, uploading program directly");
-        client
-            .new_request()
-            .with_program(GET_PROOF_DATA_ELF)
-            .with_stdin(input_bytes)
-            .with_groth16_proof()
-    };
-
-    // Submit the request to the Boundless market (onchain or offchain)
-    let (request_id, expires_at) = if onchain {
-        client.submit_onchain(request).await?
-    } else {
-        client.submit_offchain(request).await?
-    };
-
-    // Wait for the request to be fulfilled. The market will return the journal
-    // and seal.
-    tracing::info!("Waiting for request {:x} to be fulfilled", request_id);
-    let (journal, seal) = client
-        .wait_for_request_fulfillment(
-            request_id,
-            Duration::from_secs(5), // check every 5 seconds
-            expires_at,
-        )
-        .await?;
-    tracing::info!("Request {:x} fulfilled", request_id);
-
-    Ok((journal, seal))
+    Ok(input)
 }
 
-/// Executes proof data queries across multiple chains in parallel.
+/// Builds one guest input per chain of a request, for proving the chains
+/// separately and aggregating their receipts (see `composition`).
 ///
-/// This function executes ZKVM sessions for proof data queries without
-/// generating full proofs. It's useful for testing and validation purposes. The
-/// function processes each chain's proof data in parallel and then executes the
-/// ZKVM.
+/// The inputs are built like `get_proof_data_input` builds the input of the
+/// whole request, so all chains share the same L1 anchors, but each input
+/// holds only its chain and the anchor that chain needs. Snapshots are not
+/// saved.
 ///
 /// # Arguments
-/// * `users` - Vector of user address vectors, one per chain.
-/// * `markets` - Vector of market contract address vectors, one per chain.
-/// * `target_chain_id` - Vector of target chain IDs to query (vector of
-///   vectors).
-/// * `chain_ids` - Vector of chain IDs to query.
-/// * `l1_inclusion` - Whether to include L1 data in the proof.
-/// * `fallback` - Whether to use fallback RPC URLs (default: false).
+/// * `config` - Configuration the request's networks are checked against.
+/// * `data_source` - Source of the RPC and sequencer endpoints.
+/// * `commitments` - Source of the sequencer commitments.
+/// * `request` - The validated proof data request.
 ///
 /// # Returns
-/// * `Result<SessionInfo, Error>` - Session info from the ZKVM execution.
+/// * `Result<Vec<ChainProofInput>, HostError>` - One input per chain, in the
+///   order of `request.chains()`.
 ///
 /// # Errors
-/// Returns an error if:
-/// - Array lengths don't match.
-/// - RPC calls fail.
-/// - ZKVM execution fails.
-/// - Parallel task execution fails.
-pub async fn get_proof_data_exec(
-    users: Vec<Vec<Address>>,
-    markets: Vec<Vec<Address>>,
-    target_chain_id: Vec<Vec<u64>>,
-    chain_ids: Vec<u64>,
-    l1_inclusion: bool,
-    fallback: bool,
-) -> Result<SessionInfo, Error> {
-    // Ensure all input vectors are the same length for parallel processing
-    assert_eq!(
-        users.len(),
-        markets.len(),
-        "Users and markets array lengths must match"
-    );
-    assert_eq!(
-        users.len(),
-        chain_ids.len(),
-        "Users and chain_ids array lengths must match"
-    );
-
-    // Spawn a parallel async task for each chain's proof data input
-    let futures: Vec<_> = (0..chain_ids.len())
-        .map(|i| {
-            let users = users[i].clone();
-            let markets = markets[i].clone();
-            let target_chain_id = target_chain_id[i].clone();
-            let chain_id = chain_ids[i];
-            let fallback = fallback;
-            tokio::spawn(async move {
-                get_proof_data_zkvm_input(
-                    users,
-                    markets,
-                    target_chain_id,
-                    chain_id,
-                    l1_inclusion,
-                    fallback,
-                )
-                .await
+/// Returns an error if a chain's network is not configured, any chain's
+/// input fails to build (see `get_chain_inputs`) or serialization fails.
+pub async fn get_chain_proof_data_inputs(
+    config: &MaldaConfig,
+    data_source: Arc<dyn ChainDataSource>,
+    commitments: Arc<dyn CommitmentSource>,
+    request: &ProofDataRequest,
+) -> Result<Vec<ChainProofInput>, HostError> {
+    ensure_networks_configured(config, &request.chain_ids())?;
+
+    let (l1_anchors, chain_inputs) =
+        get_chain_inputs(data_source, commitments, request).await?;
+
+    request
+        .chains()
+        .iter()
+        .zip(chain_inputs)
+        .map(|(chain, chain_input)| {
+            let chain_id = chain.chain_id();
+            let anchors = l1_anchors.for_chain(chain_id, request.inclusion());
+            let header: Vec<u8> = bytemuck::pod_collect_to_vec(
+                &risc0_zkvm::serde::to_vec(&(anchors, 1u64)).map_err(|e| {
+                    HostError::encode("Failed to serialize L1 anchors", e)
+                })?,
+            );
+            Ok(ChainProofInput {
+                chain_id,
+                input: [header, chain_input].concat(),
             })
         })
-        .collect();
-
-    // Wait for all tasks to complete and collect their results
-    let results = join_all(futures).await;
-    let all_inputs = results
-        .into_iter()
-        .map(|r| r.expect("Failed to join parallel execution task"))
-        .flatten()
-        .collect::<Vec<u8>>();
-
-    // Build the ZKVM executor environment with all chain inputs
-    let env = ExecutorEnv::builder()
-        .write(&(chain_ids.len() as u64))
-        .expect("Failed to write chain count to executor environment")
-        .write_slice(&all_inputs)
-        .build()
-        .expect("Failed to build executor environment");
-
-    // Execute the ZKVM with the prepared environment and return the session
-    // info
-    Ok(default_executor()
-        .execute(env, GET_PROOF_DATA_ELF)
-        .expect("Failed to execute ZKVM"))
+        .collect()
 }
 
-/// Creates the executor environment with proof data from multiple chains.
+/// Builds the L1 anchors and the serialized inputs of several chains.
 ///
-/// This function prepares the ZKVM executor environment by collecting proof
-/// data inputs from multiple chains in parallel and combining them into a
-/// single environment.
-///
-/// The function processes each chain's proof data independently and then
-/// combines all inputs into a single executor environment for ZKVM execution.
+/// The anchors are fetched first (see `get_l1_anchors`), then each chain's
+/// input is built by `get_proof_data_zkvm_input` in its own task. All tasks
+/// share a `RequestCache`, so headers and proofs needed by several chains,
+/// e.g. the anchored L1 block they all validate against, are fetched once.
+/// The inputs are returned in the order of `request.chains()`, matching the
+/// order in which the guest reads them.
 ///
 /// # Arguments
-/// * `users` - Vector of user address vectors, one per chain.
-/// * `markets` - Vector of market contract address vectors, one per chain.
-/// * `target_chain_ids` - Vector of target chain IDs to query (vector of
-///   vectors).
-/// * `chain_ids` - Vector of chain IDs to query.
-/// * `l1_inclusion` - Whether to include L1 data in the proof.
-/// * `fallback` - Whether to use fallback RPC URLs.
+/// * `data_source` - Source of the RPC and sequencer endpoints.
+/// * `commitments` - Source of the sequencer commitments.
+/// * `request` - The validated proof data request.
 ///
 /// # Returns
-/// * `ExecutorEnv<'static>` - Environment configured with proof data inputs.
+/// * `Result<(L1Anchors, Vec<Vec<u8>>), HostError>` - The anchors and the
+///   serialized input of each chain.
 ///
-/// # Panics
-/// Panics if:
-/// - Array lengths don't match.
-/// - Parallel task execution fails.
-/// - Environment building fails.
-async fn get_proof_data_env(
-    users: Vec<Vec<Address>>,
-    markets: Vec<Vec<Address>>,
-    target_chain_ids: Vec<Vec<u64>>,
-    chain_ids: Vec<u64>,
-    l1_inclusion: bool,
-    fallback: bool,
-) -> ExecutorEnv<'static> {
-    // Ensure all input vectors are the same length for parallel processing
-    assert_eq!(users.len(), markets.len());
-    assert_eq!(users.len(), chain_ids.len());
+/// # Errors
+/// Returns an error if the request cache cannot be started, an anchor fails
+/// to build, or any chain's input fails to build or its task fails to join.
+/// A single failing chain fails the whole batch, so the result always holds
+/// one input per chain.
+async fn get_chain_inputs(
+    data_source: Arc<dyn ChainDataSource>,
+    commitments: Arc<dyn CommitmentSource>,
+    request: &ProofDataRequest,
+) -> Result<(L1Anchors, Vec<Vec<u8>>), HostError> {
+    // Share fetched chain data between the chains of this request
+    let data_source: Arc<dyn ChainDataSource> = Arc::new(
+        RequestCache::start(data_source)
+            .await
+            .map_err(|e| HostError::rpc("Failed to start request cache", e))?,
+    );
+    let l1_anchors =
+        Arc::new(get_l1_anchors(&*data_source, &*commitments, request).await?);
 
     // Spawn a parallel async task for each chain's proof data input
-    let futures: Vec<_> = (0..chain_ids.len())
-        .map(|i| {
-            let users = users[i].clone();
-            let markets = markets[i].clone();
-            let chain_id = chain_ids[i];
-            let target_chain_id = target_chain_ids[i].clone();
-            let fallback = fallback;
+    let futures: Vec<_> = request
+        .chains()
+        .iter()
+        .map(|chain| {
+            let chain = chain.clone();
+            let inclusion = request.inclusion();
+            let rpc = request.rpc();
+            let data_source = data_source.clone();
+            let commitments = commitments.clone();
+            let l1_anchors = l1_anchors.clone();
             tokio::spawn(async move {
                 get_proof_data_zkvm_input(
-                    users,
-                    markets,
-                    target_chain_id,
-                    chain_id,
-                    l1_inclusion,
-                    fallback,
+                    &*data_source,
+                    &*commitments,
+                    &l1_anchors,
+                    &chain,
+                    inclusion,
+                    rpc,
                 )
                 .await
             })
         })
         .collect();
 
-    // Wait for all tasks to complete and collect their results
-    let results = join_all(futures).await;
-    let all_inputs = results
-        .into_iter()
-        .filter_map(|r| r.ok())
-        .flat_map(|input| input)
-        .collect::<Vec<_>>();
-
-    // Build the ZKVM executor environment with all chain inputs
-    ExecutorEnv::builder()
-        .write(&(chain_ids.len() as u64))
-        .unwrap()
-        .write_slice(&all_inputs)
-        .build()
-        .unwrap()
+    // Wait for all tasks to complete and collect their results in order
+    let mut all_inputs = Vec::new();
+    for result in join_all(futures).await {
+        all_inputs.push(result??);
+    }
+
+    Ok((Arc::unwrap_or_clone(l1_anchors), all_inputs))
 }
 
-/// Prepares input data for the ZKVM for multiple chains' proof data queries.
+/// Fetches the L1 anchors a request needs.
 ///
-/// This function prepares the input data for Bonsai SDK proof generation by
-/// collecting proof data inputs from multiple chains in parallel and
-/// serializing them into the format expected by the Bonsai SDK.
-///
-/// The function processes each chain's proof data independently and then
-/// combines all inputs into a single serialized format for Bonsai processing.
+/// Ethereum chains and chains with L1 inclusion check data against their
+/// Ethereum network. For each such network, one sequencer commitment of the
+/// default OpStack chain is fetched, and the L1Block contract of the
+/// committed block is read to find the Ethereum block all these chains are
+/// built at.
 ///
 /// # Arguments
-/// * `users` - Vector of user address vectors, one per chain.
-/// * `markets` - Vector of market contract address vectors, one per chain.
-/// * `target_chain_ids` - Vector of target chain IDs to query (vector of
-///   vectors).
-/// * `chain_ids` - Vector of chain IDs to query.
-/// * `l1_inclusion` - Whether to include L1 data in the proof.
-/// * `fallback` - Whether to use fallback RPC URLs.
+/// * `data_source` - Source of the RPC and sequencer endpoints.
+/// * `commitments` - Source of the sequencer commitments.
+/// * `request` - The validated proof data request.
 ///
 /// # Returns
-/// * `Vec<u8>` - Serialized input data for the ZKVM.
+/// * `Result<L1Anchors, HostError>` - The anchors, empty if no chain needs
+///   one.
 ///
-/// # Panics
-/// Panics if:
-/// - Array lengths don't match.
-/// - Parallel task execution fails.
-/// - Serialization fails.
-async fn get_proof_data_input(
-    users: Vec<Vec<Address>>,
-    markets: Vec<Vec<Address>>,
-    target_chain_ids: Vec<Vec<u64>>,
-    chain_ids: Vec<u64>,
-    l1_inclusion: bool,
-    fallback: bool,
-) -> Vec<u8> {
-    // Ensure all input vectors are the same length for parallel processing
-    assert_eq!(users.len(), markets.len());
-    assert_eq!(users.len(), chain_ids.len());
-
-    // Spawn a parallel async task for each chain's proof data input
-    let futures: Vec<_> = (0..chain_ids.len())
-        .map(|i| {
-            let users = users[i].clone();
-            let markets = markets[i].clone();
-            let chain_id = chain_ids[i];
-            let target_chain_id = target_chain_ids[i].clone();
-            let fallback = fallback;
-            tokio::spawn(async move {
-                get_proof_data_zkvm_input(
-                    users,
-                    markets,
-                    target_chain_id,
-                    chain_id,
-                    l1_inclusion,
-                    fallback,
-                )
-                .await
-            })
-        })
-        .collect();
-
-    // Wait for all tasks to complete and collect their results
-    let results = join_all(futures).await;
-    let all_inputs = results
+/// # Errors
+/// Returns an error if a sequencer commitment is unavailable or the L1Block
+/// call input fails to build.
+pub async fn get_l1_anchors(
+    data_source: &dyn ChainDataSource,
+    commitments: &dyn CommitmentSource,
+    request: &ProofDataRequest,
+) -> Result<L1Anchors, HostError> {
+    let l1_inclusion = request.inclusion().is_l1();
+    let fallback = request.rpc().is_fallback();
+    let ethereum_chain_ids: BTreeSet<u64> = request
+        .chain_ids()
         .into_iter()
-        .filter_map(|r| r.ok())
-        .flat_map(|input| input)
-        .collect::<Vec<_>>();
+        .filter(|&chain_id| is_ethereum_chain(chain_id) || l1_inclusion)
+        .map(get_ethereum_chain_id)
+        .collect();
 
-    // Serialize the number of chains as the first field (required by the ZKVM
-    // input format)
-    let input: Vec<u8> = bytemuck::pod_collect_to_vec(
-        &risc0_zkvm::serde::to_vec(&(chain_ids.len() as u64)).unwrap(),
-    );
+    let mut anchors = Vec::new();
+    for ethereum_chain_id in ethereum_chain_ids {
+        let sequencer_chain = get_default_sequencer_chain(
+            ethereum_chain_id,
+            ethereum_chain_id == ETHEREUM_SEPOLIA_CHAIN_ID,
+        );
+        let (sequencer_commitment, block) = get_current_sequencer_commitment(
+            commitments,
+            sequencer_chain,
+            fallback,
+        )
+        .await?;
+        let (l1_block_call_input, ethereum_block) = get_l1block_call_input(
+            data_source,
+            BlockNumberOrTag::Number(block),
+            sequencer_chain,
+            fallback,
+        )
+        .await?;
+        info!(
+            "Anchored chain {ethereum_chain_id} at block {ethereum_block} \
+             through chain {sequencer_chain} block {block}"
+        );
+        anchors.push((
+            L1Anchor {
+                chain_id: ethereum_chain_id,
+                sequencer_commitment,
+                l1_block_call_input,
+            },
+            ethereum_block,
+        ));
+    }
 
-    // Concatenate the chain count and all chain inputs into a single input
-    // vector
-    [input, all_inputs].concat()
+    Ok(L1Anchors { anchors })
 }
 
-/// Generates ZK proofs for proof data queries across multiple chains.
+/// Runs the get_proof_data guest on proof data queries across multiple chains.
 ///
-/// This function generates ZK proofs using the local RISC Zero prover. It runs
-/// the proof generation in a blocking task to avoid blocking the async runtime.
-/// The function provides detailed timing information for environment creation
-/// and proof generation.
+/// The guest input is built once, in parallel over all chains, and handed to
+/// `prover`. Whether the guest is proven locally, on Bonsai or Boundless,
+/// only executed, or wrapped in a fake receipt depends on the backend, which
+/// is usually built from the configuration with `prover_from_config`.
 ///
 /// # Arguments
-/// * `users` - Vector of user address vectors, one per chain.
-/// * `markets` - Vector of market contract address vectors, one per chain.
-/// * `target_chain_ids` - Vector of target chain IDs to query (vector of
-///   vectors).
-/// * `chain_ids` - Vector of chain IDs to query.
-/// * `l1_inclusion` - Whether to include L1 data in the proof.
-/// * `fallback` - Whether to use fallback RPC URLs (default: false).
+/// * `config` - Host configuration.
+/// * `data_source` - Source of the RPC and sequencer endpoints.
+/// * `commitments` - Source of the sequencer commitments.
+/// * `prover` - Backend that runs the guest on the built input.
+/// * `request` - The validated proof data request.
 ///
 /// # Returns
-/// * `Result<ProveInfo, Error>` - Proof information from the ZKVM.
+/// * `Result<ProofResult, Error>` - Journal, seal, image ID and statistics
+///   reported by the backend.
 ///
 /// # Errors
 /// Returns an error if:
-/// - Array lengths don't match.
-/// - RPC calls fail.
-/// - Proof generation fails.
-/// - ZKVM execution fails.
+/// - A chain's network is not configured, or not supported by the network
+///   profile of the backend's guest.
+/// - Any chain's input fails to build (see `get_chain_inputs`).
+/// - The backend fails to execute or prove the guest.
 pub async fn get_proof_data_prove(
-    users: Vec<Vec<Address>>,
-    markets: Vec<Vec<Address>>,
-    target_chain_ids: Vec<Vec<u64>>,
-    chain_ids: Vec<u64>,
-    l1_inclusion: bool,
-    fallback: bool,
-) -> Result<ProveInfo, Error> {
-    // Run the proof generation in a blocking task to avoid blocking the async
-    // runtime
-    let prove_info = tokio::task::spawn_blocking(move || {
-        let rt = tokio::runtime::Runtime::new().unwrap();
-
-        // Time the environment creation step
-        let start_time = std::time::Instant::now();
-        let env = rt.block_on(get_proof_data_env(
-            users,
-            markets,
-            target_chain_ids,
-            chain_ids,
-            l1_inclusion,
-            fallback,
-        ));
-        let duration = start_time.elapsed();
-        info!("Env creation time: {:?}", duration);
-
-        // Time the proof generation step
-        let start_time = std::time::Instant::now();
-        let proof = default_prover().prove_with_opts(
-            env,
-            GET_PROOF_DATA_ELF,
-            &ProverOpts::groth16(),
-        );
-        let duration = start_time.elapsed();
-        info!("Bonsai proof time: {:?}", duration);
-        proof
-    })
-    .await?;
-
-    prove_info
+    config: &MaldaConfig,
+    data_source: Arc<dyn ChainDataSource>,
+    commitments: Arc<dyn CommitmentSource>,
+    prover: &dyn ProverBackend,
+    request: &ProofDataRequest,
+) -> Result<ProofResult, Error> {
+    // The guest panics on chains outside of its profile, fail before
+    // fetching anything
+    ensure_program_supports(prover.program(), request)?;
+
+    // Time the input creation step
+    let start_time = std::time::Instant::now();
+    let input =
+        get_proof_data_input(config, data_source, commitments, request).await?;
+    info!("Input creation time: {:?}", start_time.elapsed());
+
+    // Time the backend step
+    let start_time = std::time::Instant::now();
+    let result = prover.prove(input).await?;
+    info!("{} prover time: {:?}", prover.name(), start_time.elapsed());
+
+    Ok(result)
 }
 
-/// Generates ZK proofs for proof data queries across multiple chains using the
-/// Bonsai SDK.
-///
-/// This function uses the Bonsai SDK to generate ZK proofs for proof data
-/// queries. It runs the proof generation in a blocking task to avoid blocking
-/// the async runtime. The function provides detailed timing information for
-/// both STARK and SNARK phases.
-///
-/// # Arguments
-/// * `users` - Vector of user address vectors, one per chain.
-/// * `markets` - Vector of market contract address vectors, one per chain.
-/// * `target_chain_ids` - Vector of target chain IDs to query (vector of
-///   vectors).
-/// * `chain_ids` - Vector of chain IDs to query.
-/// * `l1_inclusion` - Whether to include L1 data in the proof.
-/// * `fallback` - Whether to use fallback RPC URLs (default: false).
-///
-/// # Returns
-/// * `Result<MaldaProveInfo, Error>` - Proof information from the Bonsai SDK.
+/// Checks that every chain of `request` is in the network profile `program`
+/// was built for, if known.
 ///
 /// # Errors
-/// Returns an error if:
-/// - Array lengths don't match.
-/// - RPC calls fail.
-/// - Proof generation fails.
-/// - Bonsai SDK operations fail.
-pub async fn get_proof_data_prove_sdk(
-    users: Vec<Vec<Address>>,
-    markets: Vec<Vec<Address>>,
-    target_chain_ids: Vec<Vec<u64>>,
-    chain_ids: Vec<u64>,
-    l1_inclusion: bool,
-    fallback: bool,
-) -> Result<MaldaProveInfo, Error> {
-    // Run the proof generation in a blocking task to avoid blocking the async
-    // runtime
-    let prove_info = tokio::task::spawn_blocking(move || {
-        let rt = tokio::runtime::Runtime::new().unwrap();
-
-        // Time the environment creation step
-        let start_time = std::time::Instant::now();
-        let input = rt.block_on(get_proof_data_input(
-            users,
-            markets,
-            target_chain_ids,
-            chain_ids,
-            l1_inclusion,
-            fallback,
-        ));
-        let duration = start_time.elapsed();
-        info!("Env creation time: {:?}", duration);
-
-        // Time the Bonsai proof generation step
-        let start_time = std::time::Instant::now();
-        let proof = run_bonsai(input);
-        let duration = start_time.elapsed();
-        info!("Bonsai proof time: {:?}", duration);
-        proof
-    })
-    .await?;
-
-    prove_info
+/// Returns an `InvalidRequest` error naming the first unsupported chain.
+pub(crate) fn ensure_program_supports(
+    program: &GuestProgram,
+    request: &ProofDataRequest,
+) -> Result<(), HostError> {
+    let Some(profile) = program.profile() else {
+        return Ok(());
+    };
+    for chain_id in request.chain_ids() {
+        if !profile.allows(chain_id) {
+            return Err(HostError::InvalidRequest(format!(
+                "chain {chain_id} is not supported by the {} guest {}",
+                profile.name(),
+                program.name()
+            )));
+        }
+    }
+    Ok(())
 }
 
 /// Prepares input data for the ZKVM for a single chain's proof data queries.
 ///
 /// This function orchestrates the entire proof data preparation process for a
 /// single chain:
-/// 1. Gets sequencer commitments and block numbers
-/// 2. Prepares L1 block call inputs if needed
-/// 3. Handles L1 inclusion environment setup
-/// 4. Fetches linking blocks for reorg protection
-/// 5. Prepares proof data call inputs
-/// 6. Serializes everything into ZKVM input format
+/// 1. Takes the Ethereum block from the L1 anchor, or gets the sequencer
+///    commitment and block number
+/// 2. Handles L1 inclusion environment setup
+/// 3. Fetches linking blocks for reorg protection
+/// 4. Prepares proof data call inputs, at the chain's historical block if it
+///    has one (see `get_historical_proof_data_call_input`)
+/// 5. Builds the receipt proofs of the chain's events, if any (see
+///    `events::get_event_input`)
+/// 6. Builds the storage proofs of the queried amounts instead of the
+///    multicall preflight, if requested (see
+///    `storage_proofs::get_storage_proof_input`)
+/// 7. Serializes everything into ZKVM input format
 ///
 /// The function handles different chain types (OpStack, Linea, Ethereum) with
 /// their specific requirements for L1 inclusion and block validation.
 ///
 /// # Arguments
-/// * `users` - Vector of user addresses to query.
-/// * `markets` - Vector of market contract addresses to query.
-/// * `target_chain_ids` - Vector of target chain IDs to query.
-/// * `chain_id` - Chain ID for the queries.
-/// * `l1_inclusion` - Whether to include L1 data in the proof.
-/// * `fallback` - Whether to use fallback RPC URLs.
+/// * `data_source` - Source of the RPC and sequencer endpoints.
+/// * `commitments` - Source of the sequencer commitments.
+/// * `l1_anchors` - The request's L1 anchors, see `get_l1_anchors`.
+/// * `chain` - The chain's queries and finality policy, taken from a
+///   validated `ProofDataRequest`.
+/// * `inclusion` - Whether to include L1 data in the proof.
+/// * `rpc` - Whether to use the primary or fallback RPC URLs.
 ///
 /// # Returns
-/// * `Vec<u8>` - Serialized input data for the ZKVM.
+/// * `Result<Vec<u8>, HostError>` - Serialized input data for the ZKVM.
 ///
-/// # Panics
-/// Panics if:
-/// - Invalid chain ID is provided.
-/// - RPC calls fail.
+/// # Errors
+/// Returns an error if:
+/// - Invalid chain ID is provided, or L1 inclusion is requested for a chain
+///   without L1 inclusion support.
+/// - The chain needs an L1 anchor that `l1_anchors` lacks.
+/// - A historical block is requested without a beacon API endpoint.
+/// - An event query selects no supported event, or its transaction is newer
+///   than the view call block.
+/// - A target chain ID exceeds `uint32` with storage proofs.
+/// - RPC calls or sequencer requests fail.
 /// - Required block numbers are not available.
 /// - Serialization fails.
 pub async fn get_proof_data_zkvm_input(
-    users: Vec<Address>,
-    markets: Vec<Address>,
-    target_chain_ids: Vec<u64>,
-    chain_id: u64,
-    l1_inclusion: bool,
-    fallback: bool,
-) -> Vec<u8> {
-    println!("=== get_proof_data_zkevm_input args");
-    println!("=== * users={:?}", users);
-    println!("=== * markets={:?}", markets);
-    println!("=== * target_chain_ids={:?}", target_chain_ids);
-    println!("=== * chain_id={}", chain_id);
-    println!("=== * l1_inclusion={}", l1_inclusion);
-    println!("=== * fallback={}", fallback);
+    data_source: &dyn ChainDataSource,
+    commitments: &dyn CommitmentSource,
+    l1_anchors: &L1Anchors,
+    chain: &ChainRequest,
+    inclusion: InclusionMode,
+    rpc: RpcSelection,
+) -> Result<Vec<u8>, HostError> {
+    let (users, markets, target_chain_ids) = chain.columns();
+    let chain_id = chain.chain_id();
+    let finality_policy = chain.finality_policy();
+    let historical_block = chain.historical_block();
+    let storage_proofs = chain.storage_proofs();
+    let l1_inclusion = inclusion.is_l1();
+    let fallback = rpc.is_fallback();
+    debug!("=== get_proof_data_zkevm_input args");
+    debug!("=== * users={:?}", users);
+    debug!("=== * markets={:?}", markets);
+    debug!("=== * target_chain_ids={:?}", target_chain_ids);
+    debug!("=== * chain_id={}", chain_id);
+    debug!("=== * finality_policy={:?}", finality_policy);
+    debug!("=== * historical_block={:?}", historical_block);
+    debug!("=== * storage_proofs={}", storage_proofs);
+    debug!("=== * l1_inclusion={}", l1_inclusion);
+    debug!("=== * fallback={}", fallback);
     // Determine if the chain is a Sepolia testnet variant
     let is_sepolia = matches!(
         chain_id,
@@ -874,106 +554,211 @@ pub async fn get_proof_data_zkvm_input(
     );
 
     // Get the chain name and testnet status for RPC URL selection
-    let (chain_name, is_testnet) = get_chain_params(chain_id);
-    let rpc_url = get_rpc_url(chain_name, fallback, is_testnet);
-    println!("=== * chain_name={}", chain_name);
-    println!("=== * rpc_url={}", rpc_url);
-
-    println!("get_sequencer_commitments_and_blocks START");
-    // Fetch sequencer commitments and block numbers for the chain
-    let (block, commitment, block_2, commitment_2) =
-        get_sequencer_commitments_and_blocks(
-            chain_id,
-            rpc_url,
-            is_sepolia,
-            l1_inclusion,
-            fallback,
-        )
-        .await;
-    println!("get_sequencer_commitments_and_blocks END");
-
-    println!("get_l1block_call_inputs_and_l1_block_numbers START");
-    // Prepare L1 block call inputs and block numbers if needed
-    let (
-        l1_block_call_input_1,
-        ethereum_block_1,
-        l1_block_call_input_2,
-        _ethereum_block_2,
-    ) = get_l1block_call_inputs_and_l1_block_numbers(
-        chain_id,
-        is_sepolia,
-        l1_inclusion,
-        block,
-        block_2,
-        fallback,
-    )
-    .await;
-    println!("get_l1block_call_inputs_and_l1_block_numbers END");
+    let (chain_name, is_testnet) = get_chain_params(chain_id)?;
+    let rpc_url = data_source.rpc_url(chain_name, fallback, is_testnet)?;
+    debug!("=== * chain_name={}", chain_name);
+    debug!("=== * rpc_url={:?}", RedactedUrl(&rpc_url));
+
+    // Chains checked against Ethereum are built at the anchored Ethereum
+    // block; the others need their own sequencer commitment
+    let (block, commitment, ethereum_block_1) =
+        if is_ethereum_chain(chain_id) || l1_inclusion {
+            let ethereum_chain_id = get_ethereum_chain_id(chain_id);
+            let ethereum_block =
+                l1_anchors.block(ethereum_chain_id).ok_or_else(|| {
+                    HostError::InvalidRequest(format!(
+                        "no L1 anchor for chain {ethereum_chain_id}"
+                    ))
+                })?;
+            (None, None, Some(ethereum_block))
+        } else {
+            debug!("get_sequencer_commitments_and_blocks START");
+            let (block, commitment, _block_2, _commitment_2) =
+                get_sequencer_commitments_and_blocks(
+                    data_source,
+                    commitments,
+                    chain_id,
+                    &rpc_url,
+                    is_sepolia,
+                    l1_inclusion,
+                    fallback,
+                )
+                .await?;
+            debug!("get_sequencer_commitments_and_blocks END");
+            (block, commitment, None)
+        };
 
-    println!("get_env_input_for_l1_inclusion_and_l2_block_number START");
+    // Determine which chain and RPC URL to use for reorg protection linking
+    // blocks
+    let (chaind_id_linking_blocks, rpc_url_linking_blocks) =
+        if is_opstack_chain(chain_id) && l1_inclusion {
+            let (ethereum_chain_id, is_ethereum_testnet) =
+                if matches!(chain_id, OPTIMISM_CHAIN_ID | BASE_CHAIN_ID) {
+                    (ETHEREUM_CHAIN_ID, false)
+                } else {
+                    (ETHEREUM_SEPOLIA_CHAIN_ID, true)
+                };
+            (
+                ethereum_chain_id,
+                data_source.rpc_url(
+                    "ETHEREUM",
+                    fallback,
+                    is_ethereum_testnet,
+                )?,
+            )
+        } else {
+            (chain_id, rpc_url.clone())
+        };
+
+    // For OpStack chains with L1 inclusion the Ethereum block is the tip of
+    // the linking blocks, so the policy can be resolved before the L1
+    // environment is built.
+    let l1_reorg_protection_depth = match ethereum_block_1 {
+        Some(ethereum_block) if is_opstack_chain(chain_id) && l1_inclusion => {
+            resolve_reorg_protection_depth(
+                chaind_id_linking_blocks,
+                &rpc_url_linking_blocks,
+                ethereum_block,
+                &finality_policy,
+            )
+            .await?
+        }
+        _ => finality_policy.min_depth(chaind_id_linking_blocks),
+    };
+
+    debug!("get_env_input_for_l1_inclusion_and_l2_block_number START");
     // Prepare environment input for L1 inclusion and L2 block number
     let (env_input_l1_inclusion, l2_block_number_on_l1) =
         get_env_input_for_l1_inclusion_and_l2_block_number(
+            data_source,
             chain_id,
             is_sepolia,
             l1_inclusion,
             ethereum_block_1,
+            l1_reorg_protection_depth,
             fallback,
         )
-        .await;
-    println!("get_env_input_for_l1_inclusion_and_l2_block_number END");
+        .await?;
+    debug!("get_env_input_for_l1_inclusion_and_l2_block_number END");
 
     // Determine the block number to use for linking blocks and proof data call
     // input
     let block = if l1_inclusion && is_linea_chain(chain_id) {
-        l2_block_number_on_l1.unwrap()
+        l2_block_number_on_l1
     } else if is_ethereum_chain(chain_id)
         || (is_opstack_chain(chain_id) && l1_inclusion)
     {
-        ethereum_block_1.unwrap()
+        ethereum_block_1
+    } else {
+        block
+    }
+    .ok_or_else(|| {
+        HostError::InvalidRequest(format!(
+            "no block to query for chain {chain_id} (l1_inclusion={l1_inclusion})"
+        ))
+    })?;
+
+    // Resolve how many linking blocks the finality policy requires
+    let reorg_protection_depth = if is_opstack_chain(chain_id) && l1_inclusion {
+        l1_reorg_protection_depth
+    } else {
+        resolve_reorg_protection_depth(
+            chaind_id_linking_blocks,
+            &rpc_url_linking_blocks,
+            block,
+            &finality_policy,
+        )
+        .await?
+    };
+    // A historical environment commits to the beacon root of the block
+    // before the first linking block, so it needs at least one
+    let reorg_protection_depth = if historical_block.is_some() {
+        reorg_protection_depth.max(1)
     } else {
-        block.unwrap()
+        reorg_protection_depth
     };
+    debug!("=== * reorg_protection_depth={}", reorg_protection_depth);
 
-    // Determine which chain and RPC URL to use for reorg protection linking
-    // blocks
-    let (chaind_id_linking_blocks, rpc_url_linking_blocks) =
-        if is_opstack_chain(chain_id) && l1_inclusion {
-            let (ethereum_chain_id, is_ethereum_testnet) =
-                if matches!(chain_id, OPTIMISM_CHAIN_ID | BASE_CHAIN_ID) {
-                    (ETHEREUM_CHAIN_ID, false)
-                } else {
-                    (ETHEREUM_SEPOLIA_CHAIN_ID, true)
-                };
-            (
-                ethereum_chain_id,
-                get_rpc_url("ETHEREUM", fallback, is_ethereum_testnet),
-            )
-        } else {
-            (chain_id, rpc_url)
-        };
+    // With storage proofs the environment only provides the verified header,
+    // so the multicall is not preflighted
+    let (call_users, call_markets, call_target_chain_ids) = if storage_proofs {
+        (Vec::new(), Vec::new(), Vec::new())
+    } else {
+        (users.clone(), markets.clone(), target_chain_ids.clone())
+    };
 
-    println!("fetch linking blocks START");
+    debug!("fetch linking blocks START");
     // Fetch linking blocks for reorg protection and prepare proof data call
     // input in parallel
-    let (linking_blocks, (proof_data_call_input, proof_data_call_input_op)) = tokio::join!(
+    let (linking_blocks, proof_data_call_input) = tokio::join!(
         get_linking_blocks(
-            chaind_id_linking_blocks,
-            rpc_url_linking_blocks,
-            block
-        ),
-        get_proof_data_call_input(
-            chain_id,
-            rpc_url,
+            &rpc_url_linking_blocks,
             block,
-            users.clone(),
-            markets.clone(),
-            target_chain_ids.clone(),
-            l1_inclusion,
-            fallback,
-        )
+            reorg_protection_depth
+        ),
+        async {
+            match historical_block {
+                Some(historical_block) => get_historical_proof_data_call_input(
+                    data_source,
+                    chain_id,
+                    &rpc_url,
+                    historical_block,
+                    block,
+                    reorg_protection_depth,
+                    call_users,
+                    call_markets,
+                    call_target_chain_ids,
+                )
+                .await
+                .map(|input| (Some(input), None)),
+                None => {
+                    get_proof_data_call_input(
+                        data_source,
+                        chain_id,
+                        &rpc_url,
+                        block,
+                        reorg_protection_depth,
+                        call_users,
+                        call_markets,
+                        call_target_chain_ids,
+                        l1_inclusion,
+                        fallback,
+                    )
+                    .await
+                }
+            }
+        }
     );
-    println!("fetch linking blocks END");
+    let linking_blocks = linking_blocks?;
+    let (proof_data_call_input, proof_data_call_input_op) =
+        proof_data_call_input?;
+    debug!("fetch linking blocks END");
+
+    // Events are proven in the view call block or its ancestors; the call
+    // input above already checked the depth against `block`
+    let view_call_block =
+        historical_block.unwrap_or(block - reorg_protection_depth);
+    let event_input =
+        get_event_input(&rpc_url, view_call_block, chain.events()).await?;
+    let storage_proof_input = if storage_proofs {
+        Some(
+            get_storage_proof_input(
+                &rpc_url,
+                chain_id,
+                view_call_block,
+                &users,
+                &markets,
+                &target_chain_ids,
+            )
+            .await?,
+        )
+    } else {
+        None
+    };
+
+    // The guest cannot prove block tags, it verifies and commits the depth
+    // they were resolved to
+    let finality_policy = finality_policy.resolve_tag(reorg_protection_depth);
 
     // Serialize all inputs into the format expected by the ZKVM guest
     let input: Vec<u8> = bytemuck::pod_collect_to_vec(
@@ -984,17 +769,17 @@ pub async fn get_proof_data_zkvm_input(
             &markets,
             &target_chain_ids,
             &commitment,
-            &l1_block_call_input_1,
             &linking_blocks,
             &env_input_l1_inclusion,
             &proof_data_call_input_op,
-            &commitment_2,
-            &l1_block_call_input_2,
+            &finality_policy,
+            &event_input,
+            &storage_proof_input,
         ))
-        .unwrap(),
+        .map_err(|e| HostError::encode("Failed to serialize guest input", e))?,
     );
 
-    input
+    Ok(input)
 }
 
 /// Returns the environment input for L1 inclusion and the L2 block number for a
@@ -1005,67 +790,83 @@ pub async fn get_proof_data_zkvm_input(
 /// `get_env_input_for_linea_l1_call` to get L2 block information.
 ///
 /// # Arguments
+/// * `data_source` - Source of the RPC and sequencer endpoints.
 /// * `chain_id` - The chain ID to query.
 /// * `is_sepolia` - Whether the chain is a Sepolia testnet variant.
 /// * `l1_inclusion` - Whether to include L1 data in the proof.
 /// * `ethereum_block` - The Ethereum block number (optional, required if
 ///   l1_inclusion is true).
+/// * `reorg_protection_depth` - Number of Ethereum blocks to step back from
+///   `ethereum_block` for OpStack chains.
 /// * `fallback` - Whether to use fallback RPC URLs.
 ///
 /// # Returns
-/// * `(Option<EvmInput<EthEvmFactory>>, Option<u64>)` - The environment input
-///   and L2 block number, if available.
+/// * `Result<(Option<EvmInput<EthEvmFactory>>, Option<u64>), HostError>` - The
+///   environment input and L2 block number, if available.
 ///
-/// # Panics
-/// Panics if:
+/// # Errors
+/// Returns an error if:
 /// - L1 inclusion is requested for an unsupported chain.
-/// - OpStack chain ID is provided (use get_env_input_for_opstack_l1_inclusion
-///   instead).
 /// - Ethereum block number is not provided when l1_inclusion is true.
+/// - Building the L1 environment fails.
 pub async fn get_env_input_for_l1_inclusion_and_l2_block_number(
+    data_source: &dyn ChainDataSource,
     chain_id: u64,
     is_sepolia: bool,
     l1_inclusion: bool,
     ethereum_block: Option<u64>,
+    reorg_protection_depth: u64,
     fallback: bool,
-) -> (Option<EvmInput<EthEvmFactory>>, Option<u64>) {
-    println!("=== get_env_input_for_l1_inclusion_and_l2_block_number args");
-    println!("=== * chain_id={}", chain_id);
-    println!("=== * is_sepolia={:?}", is_sepolia);
-    println!("=== * l1_inclusion={}", l1_inclusion);
-    println!("=== * ethereum_block={:?}", ethereum_block);
-    println!("=== * fallback={}", fallback);
+) -> Result<(Option<EvmInput<EthEvmFactory>>, Option<u64>), HostError> {
+    debug!("=== get_env_input_for_l1_inclusion_and_l2_block_number args");
+    debug!("=== * chain_id={}", chain_id);
+    debug!("=== * is_sepolia={:?}", is_sepolia);
+    debug!("=== * l1_inclusion={}", l1_inclusion);
+    debug!("=== * ethereum_block={:?}", ethereum_block);
+    debug!("=== * fallback={}", fallback);
     if !l1_inclusion {
         // If L1 inclusion is not required, return None for both values
-        (None, None)
+        Ok((None, None))
     } else {
         // Prepare the L1 RPC URL
-        let l1_rpc_url = get_rpc_url("ETHEREUM", fallback, is_sepolia);
+        let l1_rpc_url =
+            data_source.rpc_url("ETHEREUM", fallback, is_sepolia)?;
         // Determine the L1 block to use for inclusion
+        let ethereum_block = ethereum_block.ok_or_else(|| {
+            HostError::InvalidRequest(
+                "L1 inclusion requires an Ethereum block".to_string(),
+            )
+        })?;
         let l1_block = if is_linea_chain(chain_id) {
-            ethereum_block.unwrap()
+            ethereum_block
         } else {
-            if is_sepolia {
-                ethereum_block.unwrap()
-                    - REORG_PROTECTION_DEPTH_ETHEREUM_SEPOLIA
-            } else if !is_sepolia {
-                ethereum_block.unwrap() - REORG_PROTECTION_DEPTH_ETHEREUM
-            } else {
-                panic!("Invalid chain ID");
-            }
+            ethereum_block
+                .checked_sub(reorg_protection_depth)
+                .ok_or_else(|| {
+                    HostError::InvalidRequest(format!(
+                        "reorg protection depth {reorg_protection_depth} \
+                         exceeds Ethereum block {ethereum_block}"
+                    ))
+                })?
         };
 
         // Delegate to the appropriate helper based on chain type
         if is_opstack_chain(chain_id) {
-            get_env_input_for_opstack_dispute_game(chain_id, l1_block, fallback)
-                .await
+            get_env_input_for_opstack_dispute_game(
+                data_source,
+                chain_id,
+                l1_block,
+                fallback,
+            )
+            .await
         } else if is_linea_chain(chain_id) {
-            get_env_input_for_linea_l1_call(chain_id, l1_rpc_url, l1_block)
+            get_env_input_for_linea_l1_call(chain_id, &l1_rpc_url, l1_block)
                 .await
         } else {
-            panic!(
+            Err(HostError::InvalidRequest(
                 "L1 Inclusion only supported for Optimism, Base, Linea and their Sepolia variants"
-            );
+                    .to_string(),
+            ))
         }
     }
 }
@@ -1078,27 +879,37 @@ pub async fn get_env_input_for_l1_inclusion_and_l2_block_number(
 /// consistent interface for L1 inclusion handling.
 ///
 /// # Arguments
+/// * `data_source` - Source of the RPC and sequencer endpoints.
 /// * `chain_id` - The chain ID to query (must be an OpStack chain).
 /// * `l1_block` - The L1 block number.
 /// * `fallback` - Whether to use fallback RPC URLs.
 ///
 /// # Returns
-/// * `(Option<EvmInput<EthEvmFactory>>, Option<u64>)` - The environment input
-///   and L2 block number, if available.
+/// * `Result<(Option<EvmInput<EthEvmFactory>>, Option<u64>), HostError>` - The
+///   environment input and L2 block number, if available.
 ///
-/// # Panics
-/// Panics if:
+/// # Errors
+/// Returns an error if:
 /// - Non-OpStack chain ID is provided.
 /// - Dispute game validation fails.
 pub async fn get_env_input_for_opstack_l1_inclusion(
+    data_source: &dyn ChainDataSource,
     chain_id: u64,
     l1_block: u64,
     fallback: bool,
-) -> (Option<EvmInput<EthEvmFactory>>, Option<u64>) {
+) -> Result<(Option<EvmInput<EthEvmFactory>>, Option<u64>), HostError> {
     if !is_opstack_chain(chain_id) {
-        panic!("This function only supports OpStack chains");
+        return Err(HostError::InvalidRequest(
+            "This function only supports OpStack chains".to_string(),
+        ));
     }
-    get_env_input_for_opstack_dispute_game(chain_id, l1_block, fallback).await
+    get_env_input_for_opstack_dispute_game(
+        data_source,
+        chain_id,
+        l1_block,
+        fallback,
+    )
+    .await
 }
 
 /// Returns the environment input for OpStack dispute game and a dummy L2 block
@@ -1114,51 +925,51 @@ pub async fn get_env_input_for_opstack_l1_inclusion(
 /// 7. Confirms root claim matches the commitment
 ///
 /// # Arguments
+/// * `data_source` - Source of the RPC and sequencer endpoints.
 /// * `chain_id` - The chain ID to query (must be an OpStack chain).
 /// * `l1_block` - The L1 block number for the dispute game.
 /// * `fallback` - Whether to use fallback RPC URLs.
 ///
 /// # Returns
-/// * `(Option<EvmInput<EthEvmFactory>>, Option<u64>)` - The environment input
-///   and a dummy L2 block number.
+/// * `Result<(Option<EvmInput<EthEvmFactory>>, Option<u64>), HostError>` - The
+///   environment input and a dummy L2 block number.
 ///
-/// # Panics
-/// Panics if:
+/// # Errors
+/// Returns an error if:
 /// - Invalid chain ID is provided.
+/// - RPC calls fail.
 /// - Dispute game validation fails (wrong game type, status, blacklisted,
 ///   etc.).
 /// - Insufficient time has passed since game resolution.
 /// - Root claim does not match the commitment.
 pub async fn get_env_input_for_opstack_dispute_game(
+    data_source: &dyn ChainDataSource,
     chain_id: u64,
     l1_block: u64,
     fallback: bool,
-) -> (Option<EvmInput<EthEvmFactory>>, Option<u64>) {
+) -> Result<(Option<EvmInput<EthEvmFactory>>, Option<u64>), HostError> {
     // Get OpStack configuration (RPC URLs, portal address, etc.)
     let (l1_rpc_url, optimism_portal, l2_rpc_url, _chain_name) =
-        get_opstack_config(chain_id, fallback);
+        get_opstack_config(data_source, chain_id, fallback)?;
 
     // Build the Ethereum environment for the L1 block
     let mut env = EthEvmEnv::builder()
-        .rpc(Url::parse(l1_rpc_url).expect("Failed to parse RPC URL"))
+        .rpc(parse_rpc_url(&l1_rpc_url)?)
         .block_number_or_tag(BlockNumberOrTag::Number(l1_block))
         .chain_spec(&ETH_MAINNET_CHAIN_SPEC)
         .build()
         .await
-        .expect("Failed to build EVM environment");
+        .map_err(|e| HostError::rpc("Failed to build EVM environment", e))?;
     // Build the OpStack environment with the dispute game
     let builder = OpEvmEnv::builder()
-        .dispute_game_from_rpc(
-            optimism_portal,
-            Url::parse(l1_rpc_url).expect("Failed to parse RPC URL"),
-        )
+        .dispute_game_from_rpc(optimism_portal, parse_rpc_url(&l1_rpc_url)?)
         .game_index(DisputeGameIndex::Finalized);
     let mut op_env = builder
-        .rpc(Url::parse(l2_rpc_url).expect("Failed to parse RPC URL"))
+        .rpc(parse_rpc_url(&l2_rpc_url)?)
         .chain_spec(&OP_MAINNET_CHAIN_SPEC)
         .build()
         .await
-        .expect("Failed to build OP-EVM environment");
+        .map_err(|e| HostError::rpc("Failed to build OP-EVM environment", e))?;
 
     // This is just an arbitrary simple call needed in order to do into_env to
     // get the game_index
@@ -1169,12 +980,11 @@ pub async fn get_env_input_for_opstack_dispute_game(
         .call_builder(&block_hash_call)
         .call()
         .await
-        .expect("Failed to execute factory call");
+        .map_err(|e| HostError::rpc("Failed to execute factory call", e))?;
 
-    let input = op_env
-        .into_input()
-        .await
-        .expect("Failed to convert environment to input");
+    let input = op_env.into_input().await.map_err(|e| {
+        HostError::rpc("Failed to convert environment to input", e)
+    })?;
     let op_env_commitment = input
         .clone()
         .into_env(&OP_MAINNET_CHAIN_SPEC)
@@ -1184,7 +994,7 @@ pub async fn get_env_input_for_opstack_dispute_game(
 
     let root_claim = op_env_commitment.digest;
 
-    let portal_adress = get_portal_address(chain_id);
+    let portal_adress = get_portal_address(chain_id)?;
 
     // Get the portal contract for additional checks
     let mut contract = Contract::preflight(portal_adress, &mut env);
@@ -1195,19 +1005,26 @@ pub async fn get_env_input_for_opstack_dispute_game(
         .call_builder(&factory_call)
         .call()
         .await
-        .expect("Failed to execute factory call");
+        .map_err(|e| HostError::rpc("Failed to execute factory call", e))?;
 
     let game_call = IDisputeGameFactory::gameAtIndexCall { index: game_index };
 
     let mut contract = Contract::preflight(factory_address, &mut env);
-    let returns = contract
-        .call_builder(&game_call)
-        .call()
-        .await
-        .expect("Failed to execute game at index call");
+    let returns =
+        contract
+            .call_builder(&game_call)
+            .call()
+            .await
+            .map_err(|e| {
+                HostError::rpc("Failed to execute game at index call", e)
+            })?;
 
     let game_type = returns._0;
-    assert_eq!(game_type, U256::from(0), "game type not respected game");
+    if game_type != U256::from(0) {
+        return Err(HostError::Validation(
+            "game type not respected game".to_string(),
+        ));
+    }
 
     let created_at = returns._1;
     let game_address = returns._2;
@@ -1220,11 +1037,17 @@ pub async fn get_env_input_for_opstack_dispute_game(
         .call_builder(&respected_game_type_updated_at_call)
         .call()
         .await
-        .expect("Failed to execute respected game type updated at call");
-    assert!(
-        created_at >= updated_at,
-        "game created before respected game type update"
-    );
+        .map_err(|e| {
+            HostError::rpc(
+                "Failed to execute respected game type updated at call",
+                e,
+            )
+        })?;
+    if created_at < updated_at {
+        return Err(HostError::Validation(
+            "game created before respected game type update".to_string(),
+        ));
+    }
 
     // Get game contract for status checks
     let mut contract = Contract::preflight(game_address, &mut env);
@@ -1235,12 +1058,12 @@ pub async fn get_env_input_for_opstack_dispute_game(
         .call_builder(&status_call)
         .call()
         .await
-        .expect("Failed to execute status call");
-    assert_eq!(
-        status,
-        GameStatus::DEFENDER_WINS,
-        "game status not DEFENDER_WINS"
-    );
+        .map_err(|e| HostError::rpc("Failed to execute status call", e))?;
+    if status != GameStatus::DEFENDER_WINS {
+        return Err(HostError::Validation(
+            "game status not DEFENDER_WINS".to_string(),
+        ));
+    }
 
     // Check if game is blacklisted
     let mut contract = Contract::preflight(portal_adress, &mut env);
@@ -1250,8 +1073,10 @@ pub async fn get_env_input_for_opstack_dispute_game(
         .call_builder(&blacklist_call)
         .call()
         .await
-        .expect("Failed to execute blacklist call");
-    assert!(!is_blacklisted, "game is blacklisted");
+        .map_err(|e| HostError::rpc("Failed to execute blacklist call", e))?;
+    if is_blacklisted {
+        return Err(HostError::Validation("game is blacklisted".to_string()));
+    }
 
     // Check game resolution time
     let mut contract = Contract::preflight(game_address, &mut env);
@@ -1260,7 +1085,7 @@ pub async fn get_env_input_for_opstack_dispute_game(
         .call_builder(&resolved_at_call)
         .call()
         .await
-        .expect("Failed to execute resolved at call");
+        .map_err(|e| HostError::rpc("Failed to execute resolved at call", e))?;
 
     let mut contract = Contract::preflight(portal_adress, &mut env);
     let proof_maturity_delay_call =
@@ -1269,14 +1094,18 @@ pub async fn get_env_input_for_opstack_dispute_game(
         .call_builder(&proof_maturity_delay_call)
         .call()
         .await
-        .expect("Failed to execute proof maturity delay call");
+        .map_err(|e| {
+            HostError::rpc("Failed to execute proof maturity delay call", e)
+        })?;
 
     let current_timestamp = env.header().inner().inner().timestamp;
-    assert!(
-        U256::from(current_timestamp) - U256::from(resolved_at)
-            > proof_maturity_delay - U256::from(300),
-        "insufficient time passed since game resolution"
-    );
+    if U256::from(current_timestamp) - U256::from(resolved_at)
+        <= proof_maturity_delay - U256::from(300)
+    {
+        return Err(HostError::Validation(
+            "insufficient time passed since game resolution".to_string(),
+        ));
+    }
 
     // Finally verify root claim matches
     let mut contract = Contract::preflight(game_address, &mut env);
@@ -1285,164 +1114,278 @@ pub async fn get_env_input_for_opstack_dispute_game(
         .call_builder(&root_claim_call)
         .call()
         .await
-        .expect("Failed to execute root claim call");
+        .map_err(|e| HostError::rpc("Failed to execute root claim call", e))?;
 
-    assert_eq!(root, root_claim, "root claim not respected");
+    if root != root_claim {
+        return Err(HostError::Validation(
+            "root claim not respected".to_string(),
+        ));
+    }
 
-    (
-        Some(
-            env.into_input()
-                .await
-                .expect("Failed to convert environment to input"),
-        ),
+    Ok((
+        Some(env.into_input().await.map_err(|e| {
+            HostError::rpc("Failed to convert environment to input", e)
+        })?),
         // irrelevant for l1 inclusion on opstack
         Some(1),
-    )
+    ))
 }
 
-/// Returns L1 block call inputs and L1 block numbers for a given chain.
+/// Prepares multicall input for batch proof data checking.
+///
+/// This function creates a multicall to batch multiple
+/// `getProofData(address,uint32)` calls for efficient proof data retrieval. It
+/// handles both standard EVM chains and OpStack chains with L1 inclusion
+/// validation.
 ///
-/// This function prepares L1 block call inputs for chains that require L1
-/// inclusion or are Ethereum chains themselves. It currently only processes one
-/// block (block_2 is unused but kept for interface consistency).
+/// The function applies reorg protection by querying blocks that are
+/// sufficiently confirmed based on the chain's protection depth.
 ///
-/// This function is designed to support confirming the L1 block via both OP and
-/// Base sequencer commitments for additional security. However, the second path
-/// (Base) is currently disabled for latency reasons. Only the OP path is
-/// active.
+/// Without queries no multicall is executed and the environment only carries
+/// the header, as used with storage proofs (see `storage_proofs`).
 ///
 /// # Arguments
-/// * `chain_id` - The chain ID to query.
-/// * `is_sepolia` - Whether the chain is a Sepolia testnet variant.
-/// * `l1_inclusion` - Whether to include L1 data in the proof.
-/// * `block` - The block number (optional, required if l1_inclusion is true or
-///   chain is Ethereum).
-/// * `_block_2` - The second block number (optional, unused, kept for interface
-///   consistency).
+/// * `data_source` - Source of the RPC and sequencer endpoints.
+/// * `chain_id` - Chain ID for the queries.
+/// * `chain_url` - RPC URL for the chain.
+/// * `block` - Block number to query at (will be adjusted for reorg
+///   protection).
+/// * `reorg_protection_depth` - Number of blocks to step back from `block`.
+/// * `users` - Vector of user addresses to query proof data for.
+/// * `markets` - Vector of market contract addresses to query.
+/// * `target_chain_ids` - Vector of target chain IDs to query proof data for.
+/// * `validate_l1_inclusion` - Whether to validate L1 inclusion for OpStack
+///   chains.
 /// * `fallback` - Whether to use fallback RPC URLs.
 ///
 /// # Returns
-/// * `(Option<EvmInput<EthEvmFactory>>, Option<u64>,
-///   Option<EvmInput<EthEvmFactory>>, Option<u64>)` - Tuple of optional L1
-///   block call inputs and block numbers.
-///
-/// # Panics
-/// Panics if:
-/// - Block number is not provided when required.
-/// - L1 block call input generation fails.
-pub async fn get_l1block_call_inputs_and_l1_block_numbers(
+/// * `Result<(Option<EvmInput<EthEvmFactory>>, Option<OpEvmInput>), HostError>`
+///   - Formatted EVM input for the multicall and optional OpEvmInput.
+///
+/// # Errors
+/// Returns an error if:
+/// - Invalid chain ID is provided.
+/// - `reorg_protection_depth` exceeds `block`.
+/// - RPC connection fails.
+/// - Environment building fails.
+pub async fn get_proof_data_call_input(
+    data_source: &dyn ChainDataSource,
     chain_id: u64,
-    is_sepolia: bool,
-    l1_inclusion: bool,
-    block: Option<u64>,
-    _block_2: Option<u64>,
+    chain_url: &str,
+    block: u64,
+    reorg_protection_depth: u64,
+    users: Vec<Address>,
+    markets: Vec<Address>,
+    target_chain_ids: Vec<u64>,
+    validate_l1_inclusion: bool,
     fallback: bool,
-) -> (
-    Option<EvmInput<EthEvmFactory>>,
-    Option<u64>,
-    Option<EvmInput<EthEvmFactory>>,
-    Option<u64>,
-) {
-    println!("=== get_l1block_call_inputs_and_l1_block_numbers args");
-    println!("=== * chain_id={}", chain_id);
-    println!("=== * is_sepolia={:?}", is_sepolia);
-    println!("=== * l1_inclusion={}", l1_inclusion);
-    println!("=== * block={:?}", block);
-    println!("=== * fallback={}", fallback);
-    println!("===");
-    if is_ethereum_chain(chain_id) || l1_inclusion {
-        // For Ethereum or L1 inclusion, prepare the L1 block call input for the
-        // appropriate chain
-        let (chain_id_1, _chain_id_2) = match is_sepolia {
-            true => (OPTIMISM_SEPOLIA_CHAIN_ID, BASE_SEPOLIA_CHAIN_ID),
-            false => (OPTIMISM_CHAIN_ID, BASE_CHAIN_ID),
-        };
-        println!("get_l1block_call_input START");
-        let (l1_block_call_input_1, ethereum_block_1) = get_l1block_call_input(
-            BlockNumberOrTag::Number(block.unwrap()),
-            chain_id_1,
-            fallback,
-        )
-        .await;
-        println!("get_l1block_call_input END");
-        // NOTE: The following code is intended to enable L1 block confirmation
-        // via both OP and Base for extra security, but is currently disabled
-        // for latency reasons. Only the OP path is active.
-        // let (l1_block_call_input_2, ethereum_block_2) =
-        //     get_l1block_call_input(BlockNumberOrTag::Number(block_2.
-        // unwrap()), chain_id_2, fallback).await;
-
-        (
-            Some(l1_block_call_input_1),
-            Some(ethereum_block_1),
-            None::<EvmInput<EthEvmFactory>>,
-            None::<u64>,
-        )
-        // (Some(l1_block_call_input_1), Some(ethereum_block_1),
-        // Some(l1_block_call_input_2), Some(ethereum_block_2))
+) -> Result<(Option<EvmInput<EthEvmFactory>>, Option<OpEvmInput>), HostError> {
+    debug!("=== get_proof_data_call_input args");
+    debug!("=== * chain_id={:?}", chain_id);
+    debug!("=== * chain_url={:?}", RedactedUrl(&chain_url));
+    debug!("=== * block={:?}", block);
+    debug!("=== * users={:?}", users);
+    debug!("=== * markets={:?}", markets);
+    debug!("=== * target_chain_ids={:?}", target_chain_ids);
+    debug!("=== * validate_l1_inclusion={:?}", validate_l1_inclusion);
+    debug!("=== * fallback={:?}", fallback);
+    debug!("===");
+    // Calculate the block number to use for reorg protection
+    let block_reorg_protected =
+        block.checked_sub(reorg_protection_depth).ok_or_else(|| {
+            HostError::InvalidRequest(format!(
+                "reorg protection depth {reorg_protection_depth} exceeds block \
+                 {block}"
+            ))
+        })?;
+    debug!("=== * reorg_protection_depth={:?}", reorg_protection_depth);
+    debug!("=== * block_reorg_protected={:?}", block_reorg_protected);
+
+    let multicall = proof_data_multicall(&users, &markets, &target_chain_ids);
+
+    // Use separate code paths for each environment type
+    if is_opstack_chain(chain_id) && validate_l1_inclusion {
+        // Build an environment based on the state of the latest finalized fault
+        // dispute game
+        let (l1_rpc_url, optimism_portal, chain_url_final, _chain_name) =
+            get_opstack_config(data_source, chain_id, !fallback)?;
+        let mut env = OpEvmEnv::builder()
+            .dispute_game_from_rpc(optimism_portal, parse_rpc_url(&l1_rpc_url)?)
+            .game_index(DisputeGameIndex::Finalized)
+            .rpc(parse_rpc_url(&chain_url_final)?)
+            .chain_spec(&OP_MAINNET_CHAIN_SPEC)
+            .build()
+            .await
+            .map_err(|e| {
+                HostError::rpc("Failed to build OP-EVM environment", e)
+            })?;
+
+        if !users.is_empty() {
+            let mut contract = Contract::preflight(MULTICALL, &mut env);
+            let _returns = contract
+                .call_builder(&multicall)
+                // .gas_price(U256::from(gas_price))
+                // .from(Address::ZERO)
+                .call()
+                .await
+                .map_err(|e| {
+                    HostError::rpc("Failed to execute multicall", e)
+                })?;
+        }
+
+        Ok((
+            None,
+            Some(env.into_input().await.map_err(|e| {
+                HostError::rpc("Failed to convert environment to input", e)
+            })?),
+        ))
     } else {
-        // For other chains, no L1 block call input is needed
-        (None, None, None, None)
+        let chain_url_final = if fallback {
+            let (chain_name, is_testnet) = get_chain_params(chain_id)?;
+            data_source.rpc_url(chain_name, true, is_testnet)?
+        } else {
+            chain_url.to_string()
+        };
+        debug!("=== * chain_url_final={:?}", RedactedUrl(&chain_url_final));
+        debug!("=== * block_reorg_protected={:?}", block_reorg_protected);
+        let mut env = EthEvmEnv::builder()
+            .rpc(parse_rpc_url(&chain_url_final)?)
+            .block_number_or_tag(BlockNumberOrTag::Number(
+                block_reorg_protected,
+            ))
+            .chain_spec(&LINEA_MAINNET_CHAIN_SPEC)
+            .build()
+            .await
+            .map_err(|e| {
+                HostError::rpc("Failed to build EVM environment", e)
+            })?;
+
+        if !users.is_empty() {
+            let mut contract = Contract::preflight(MULTICALL, &mut env);
+            let _returns = contract
+                .call_builder(&multicall)
+                // .gas_price(U256::from(gas_price))
+                // .from(Address::ZERO)
+                .call()
+                .await
+                .map_err(|e| {
+                    HostError::rpc("Failed to execute multicall", e)
+                })?;
+        }
+        // NOTE: multicall is not used here??
+
+        Ok((
+            Some(env.into_input().await.map_err(|e| {
+                HostError::rpc("Failed to convert environment to input", e)
+            })?),
+            None,
+        ))
     }
 }
 
-/// Prepares multicall input for batch proof data checking.
-///
-/// This function creates a multicall to batch multiple
-/// `getProofData(address,uint32)` calls for efficient proof data retrieval. It
-/// handles both standard EVM chains and OpStack chains with L1 inclusion
-/// validation.
+/// Prepares multicall input for proof data queries at a historical Ethereum
+/// block.
 ///
-/// The function applies reorg protection by querying blocks that are
-/// sufficiently confirmed based on the chain's protection depth.
+/// The environment is executed at `historical_block` but commits to the
+/// beacon block root of `block - reorg_protection_depth`, the block the
+/// linking blocks start from. Steel proves the historical state against that
+/// root through the beacon state history, and the guest links the root to
+/// the first linking block through EIP-4788.
 ///
 /// # Arguments
-/// * `chain_id` - Chain ID for the queries.
+/// * `data_source` - Source of the RPC and beacon API endpoints.
+/// * `chain_id` - Chain ID for the queries, Ethereum or Ethereum Sepolia.
 /// * `chain_url` - RPC URL for the chain.
-/// * `block` - Block number to query at (will be adjusted for reorg
-///   protection).
+/// * `historical_block` - Block number to query at.
+/// * `block` - Tip of the linking blocks.
+/// * `reorg_protection_depth` - Number of blocks to step back from `block`
+///   for the commitment block.
 /// * `users` - Vector of user addresses to query proof data for.
 /// * `markets` - Vector of market contract addresses to query.
 /// * `target_chain_ids` - Vector of target chain IDs to query proof data for.
-/// * `validate_l1_inclusion` - Whether to validate L1 inclusion for OpStack
-///   chains.
-/// * `fallback` - Whether to use fallback RPC URLs.
 ///
 /// # Returns
-/// * `(Option<EvmInput<EthEvmFactory>>, Option<OpEvmInput>)` - Formatted EVM
-///   input for the multicall and optional OpEvmInput.
+/// * `Result<EvmInput<EthEvmFactory>, HostError>` - History EVM input for the
+///   multicall.
 ///
-/// # Panics
-/// Panics if:
-/// - Invalid chain ID is provided.
-/// - RPC connection fails.
-/// - Environment building fails.
-pub async fn get_proof_data_call_input(
+/// # Errors
+/// Returns an error if:
+/// - The data source has no beacon API endpoint.
+/// - `historical_block` is not older than the commitment block.
+/// - RPC or beacon API requests fail.
+pub async fn get_historical_proof_data_call_input(
+    data_source: &dyn ChainDataSource,
     chain_id: u64,
     chain_url: &str,
+    historical_block: u64,
     block: u64,
+    reorg_protection_depth: u64,
     users: Vec<Address>,
     markets: Vec<Address>,
     target_chain_ids: Vec<u64>,
-    validate_l1_inclusion: bool,
-    fallback: bool,
-) -> (Option<EvmInput<EthEvmFactory>>, Option<OpEvmInput>) {
-    println!("=== get_proof_data_call_input args");
-    println!("=== * chain_id={:?}", chain_id);
-    println!("=== * chain_url={:?}", chain_url);
-    println!("=== * block={:?}", block);
-    println!("=== * users={:?}", users);
-    println!("=== * markets={:?}", markets);
-    println!("=== * target_chain_ids={:?}", target_chain_ids);
-    println!("=== * validate_l1_inclusion={:?}", validate_l1_inclusion);
-    println!("=== * fallback={:?}", fallback);
-    println!("===");
-    // Calculate the block number to use for reorg protection
-    let reorg_protection_depth = get_reorg_protection_depth(chain_id);
-    let block_reorg_protected = block - reorg_protection_depth;
-    println!("=== * reorg_protection_depth={:?}", reorg_protection_depth);
-    println!("=== * block_reorg_protected={:?}", block_reorg_protected);
+) -> Result<EvmInput<EthEvmFactory>, HostError> {
+    let (_chain_name, is_testnet) = get_chain_params(chain_id)?;
+    let beacon_api_url =
+        data_source.beacon_api_url(is_testnet).ok_or_else(|| {
+            HostError::InvalidRequest(format!(
+                "no beacon API configured for historical blocks on chain \
+                 {chain_id}"
+            ))
+        })?;
+    let commitment_block =
+        block.checked_sub(reorg_protection_depth).ok_or_else(|| {
+            HostError::InvalidRequest(format!(
+                "reorg protection depth {reorg_protection_depth} exceeds block \
+                 {block}"
+            ))
+        })?;
+    if historical_block >= commitment_block {
+        return Err(HostError::InvalidRequest(format!(
+            "historical block {historical_block} is not older than the \
+             commitment block {commitment_block}"
+        )));
+    }
+    debug!("=== * historical_block={:?}", historical_block);
+    debug!("=== * commitment_block={:?}", commitment_block);
+
+    let multicall = proof_data_multicall(&users, &markets, &target_chain_ids);
+    let mut env = EthEvmEnv::builder()
+        .rpc(parse_rpc_url(chain_url)?)
+        .beacon_api(parse_rpc_url(&beacon_api_url)?)
+        .block_number_or_tag(BlockNumberOrTag::Number(historical_block))
+        .commitment_block_number_or_tag(BlockNumberOrTag::Number(
+            commitment_block,
+        ))
+        .chain_spec(&ETH_MAINNET_CHAIN_SPEC)
+        .build()
+        .await
+        .map_err(|e| {
+            HostError::rpc("Failed to build historical EVM environment", e)
+        })?;
 
+    // Without queries the environment only carries the header, e.g. for
+    // storage proofs
+    if !users.is_empty() {
+        let mut contract = Contract::preflight(MULTICALL, &mut env);
+        let _returns = contract
+            .call_builder(&multicall)
+            .call()
+            .await
+            .map_err(|e| HostError::rpc("Failed to execute multicall", e))?;
+    }
+
+    env.into_input().await.map_err(|e| {
+        HostError::rpc("Failed to convert environment to input", e)
+    })
+}
+
+/// Builds the multicall batching one `getProofData(address,uint32)` call per
+/// query.
+fn proof_data_multicall(
+    users: &[Address],
+    markets: &[Address],
+    target_chain_ids: &[u64],
+) -> IMulticall3::aggregate3Call {
     // Create array of Call3 structs for each proof data check
     let mut calls = Vec::with_capacity(users.len());
 
@@ -1473,85 +1416,7 @@ pub async fn get_proof_data_call_input(
     }
 
     // Make single multicall
-    let multicall = IMulticall3::aggregate3Call { calls };
-
-    // Use separate code paths for each environment type
-    if is_opstack_chain(chain_id) && validate_l1_inclusion {
-        // Build an environment based on the state of the latest finalized fault
-        // dispute game
-        let (l1_rpc_url, optimism_portal, chain_url_final, _chain_name) =
-            get_opstack_config(chain_id, !fallback);
-        let mut env = OpEvmEnv::builder()
-            .dispute_game_from_rpc(
-                optimism_portal,
-                Url::parse(l1_rpc_url).expect("Failed to parse RPC URL"),
-            )
-            .game_index(DisputeGameIndex::Finalized)
-            .rpc(Url::parse(chain_url_final).map_err(|e| {
-                eprintln!("ERROR parsing RPC URL in get_proof_data_call_input (op_env): {:?} - URL: {}", e, chain_url_final);
-                e
-            }).expect("Failed to parse RPC URL"))
-            .chain_spec(&OP_MAINNET_CHAIN_SPEC)
-            .build()
-            .await
-            .expect("Failed to build OP-EVM environment");
-
-        let mut contract = Contract::preflight(MULTICALL, &mut env);
-        let _returns = contract
-            .call_builder(&multicall)
-            // .gas_price(U256::from(gas_price))
-            // .from(Address::ZERO)
-            .call()
-            .await
-            .expect("Failed to execute multicall");
-
-        (
-            None,
-            Some(
-                env.into_input()
-                    .await
-                    .expect("Failed to convert environment to input"),
-            ),
-        )
-    } else {
-        let chain_url_final = if fallback {
-            let (chain_name, is_testnet) = get_chain_params(chain_id);
-            get_rpc_url(chain_name, true, is_testnet)
-        } else {
-            chain_url
-        };
-        println!("=== * chain_url_final={:?}", chain_url_final);
-        println!("=== * block_reorg_protected={:?}", block_reorg_protected);
-        let mut env = EthEvmEnv::builder()
-        .rpc(Url::parse(chain_url_final).map_err(|e| {
-            eprintln!("ERROR parsing RPC URL in get_proof_data_call_input (op_env): {:?} - URL: {}", e, chain_url_final);
-            e
-        }).expect("Failed to parse RPC URL"))
-            .block_number_or_tag(BlockNumberOrTag::Number(block_reorg_protected))
-            .chain_spec(&LINEA_MAINNET_CHAIN_SPEC)
-            .build()
-            .await
-            .expect("Failed to build EVM environment");
-
-        let mut contract = Contract::preflight(MULTICALL, &mut env);
-        let _returns = contract
-            .call_builder(&multicall)
-            // .gas_price(U256::from(gas_price))
-            // .from(Address::ZERO)
-            .call()
-            .await
-            .expect("Failed to execute multicall");
-        // NOTE: multicall is not used here??
-
-        (
-            Some(
-                env.into_input()
-                    .await
-                    .expect("Failed to convert environment to input"),
-            ),
-            None,
-        )
-    }
+    IMulticall3::aggregate3Call { calls }
 }
 
 /// Fetches sequencer commitments and block numbers for a given chain, handling
@@ -1566,6 +1431,8 @@ pub async fn get_proof_data_call_input(
 /// to get commitments.
 ///
 /// # Arguments
+/// * `data_source` - Source of the RPC endpoints.
+/// * `commitments` - Source of the sequencer commitments.
 /// * `chain_id` - The chain ID to query.
 /// * `rpc_url` - The RPC URL for the chain.
 /// * `is_sepolia` - Whether the chain is a Sepolia testnet variant.
@@ -1573,35 +1440,40 @@ pub async fn get_proof_data_call_input(
 /// * `fallback` - Whether to use fallback RPC URLs.
 ///
 /// # Returns
-/// * `(Option<u64>, Option<SequencerCommitment>, Option<u64>,
-///   Option<SequencerCommitment>)` - Tuple of (block, commitment, block_2,
-///   commitment_2), where the second pair is only relevant for some
+/// * `Result<(Option<u64>, Option<SequencerCommitment>, Option<u64>,
+///   Option<SequencerCommitment>), HostError>` - Tuple of (block, commitment,
+///   block_2, commitment_2), where the second pair is only relevant for some
 ///   Sepolia/mainnet cases.
 ///
-/// # Panics
-/// Panics if:
+/// # Errors
+/// Returns an error if:
 /// - An invalid chain ID is provided.
 /// - RPC calls fail.
-/// - Sequencer API requests fail.
+/// - No valid sequencer commitment is available.
 pub async fn get_sequencer_commitments_and_blocks(
+    data_source: &dyn ChainDataSource,
+    commitments: &dyn CommitmentSource,
     chain_id: u64,
     rpc_url: &str,
     is_sepolia: bool,
     l1_inclusion: bool,
     fallback: bool,
-) -> (
-    Option<u64>,
-    Option<SequencerCommitment>,
-    Option<u64>,
-    Option<SequencerCommitment>,
-) {
-    println!("=== get_sequencer_commitments_and_blocks args");
-    println!("=== * chain_id={}", chain_id);
-    println!("=== * rpc_url={:?}", rpc_url);
-    println!("=== * is_sepolia={:?}", is_sepolia);
-    println!("=== * l1_inclusion={}", l1_inclusion);
-    println!("=== * fallback={}", fallback);
-    println!("===");
+) -> Result<
+    (
+        Option<u64>,
+        Option<SequencerCommitment>,
+        Option<u64>,
+        Option<SequencerCommitment>,
+    ),
+    HostError,
+> {
+    debug!("=== get_sequencer_commitments_and_blocks args");
+    debug!("=== * chain_id={}", chain_id);
+    debug!("=== * rpc_url={:?}", RedactedUrl(&rpc_url));
+    debug!("=== * is_sepolia={:?}", is_sepolia);
+    debug!("=== * l1_inclusion={}", l1_inclusion);
+    debug!("=== * fallback={}", fallback);
+    debug!("===");
 
     if is_opstack_chain(chain_id)
         || is_ethereum_chain(chain_id)
@@ -1610,164 +1482,166 @@ pub async fn get_sequencer_commitments_and_blocks(
         if !l1_inclusion && is_opstack_chain(chain_id) {
             // For OpStack chains without L1 inclusion, get the current
             // sequencer commitment
-            let (commitment, block) =
-                get_current_sequencer_commitment(chain_id, fallback).await;
-            println!("=== * block={:?}", block);
-            (
+            let (commitment, block) = get_current_sequencer_commitment(
+                commitments,
+                chain_id,
+                fallback,
+            )
+            .await?;
+            debug!("=== * block={:?}", block);
+            Ok((
                 Some(block),
                 Some(commitment),
                 None::<u64>,
                 None::<SequencerCommitment>,
-            )
+            ))
         } else {
             // For L1 inclusion or Ethereum chains, use the default sequencer
             // chain
             let default_chain =
                 get_default_sequencer_chain(chain_id, is_sepolia);
-            let (commitment, block) =
-                get_current_sequencer_commitment(default_chain, fallback).await;
-            println!("=== * default_chain={:?}", default_chain);
-            println!("=== * block={:?}", block);
-            (Some(block), Some(commitment), None, None)
+            let (commitment, block) = get_current_sequencer_commitment(
+                commitments,
+                default_chain,
+                fallback,
+            )
+            .await?;
+            debug!("=== * default_chain={:?}", default_chain);
+            debug!("=== * block={:?}", block);
+            Ok((Some(block), Some(commitment), None, None))
         }
     } else if is_linea_chain(chain_id) {
         // For Linea chains, get the current block number directly from RPC
         let block = EthEvmEnv::builder()
-            .rpc(Url::parse(rpc_url).unwrap())
+            .rpc(parse_rpc_url(rpc_url)?)
             .block_number_or_tag(BlockNumberOrTag::Latest)
             .chain_spec(&ETH_MAINNET_CHAIN_SPEC)
             .build()
             .await
-            .unwrap()
+            .map_err(|e| HostError::rpc("Failed to build EVM environment", e))?
             .header()
             .inner()
             .inner()
             .number;
-        println!("=== * block={:?}", block);
-        (Some(block), None, None, None)
+        debug!("=== * block={:?}", block);
+        Ok((Some(block), None, None, None))
     } else {
-        panic!("Invalid chain ID");
+        Err(HostError::InvalidRequest(format!(
+            "Invalid chain ID: {chain_id}"
+        )))
     }
 }
 /// Fetches the current sequencer commitment for L2 chains.
 ///
-/// This function queries the sequencer API to get the latest sequencer
-/// commitment for OpStack chains (Optimism, Base). The commitment contains
-/// execution payload data that can be used to verify L2 state.
+/// The latest commitment of an OpStack chain (Optimism, Base) is taken from
+/// `commitments` and checked with `check_commitment` before it is used: it
+/// must be signed by the chain's sequencer, hold an execution payload and be
+/// recent enough for the source. The commitment contains execution payload
+/// data that can be used to verify L2 state.
 ///
 /// # Arguments
+/// * `commitments` - Source of the sequencer commitments.
 /// * `chain_id` - Chain ID (Optimism, Base, or their Sepolia variants).
-/// * `fallback` - Whether to use fallback RPC URLs.
+/// * `fallback` - Whether to use the fallback sequencer endpoint.
 ///
 /// # Returns
-/// * `(SequencerCommitment, u64)` - Tuple of sequencer commitment and block
-///   number.
+/// * `Result<(SequencerCommitment, u64), HostError>` - Tuple of sequencer
+///   commitment and block number.
 ///
-/// # Panics
-/// Panics if:
-/// - Invalid chain ID is provided.
-/// - Sequencer API request fails.
-/// - JSON parsing fails.
-/// - Execution payload conversion fails.
+/// # Errors
+/// Returns a `Sequencer` error naming the source if no commitment is
+/// available or it fails a check, see `commitments::CommitmentError`.
 pub async fn get_current_sequencer_commitment(
+    commitments: &dyn CommitmentSource,
     chain_id: u64,
     fallback: bool,
-) -> (SequencerCommitment, u64) {
-    let (chain_name, is_testnet) = get_chain_params(chain_id);
-    let req = get_sequencer_request_url(chain_name, fallback, is_testnet);
-
-    let commitment = reqwest::get(req)
+) -> Result<(SequencerCommitment, u64), HostError> {
+    let invalid =
+        |e| HostError::sequencer("Failed to get sequencer commitment", e);
+    let commitment = commitments
+        .latest(chain_id, fallback)
         .await
-        .expect("Failed to fetch sequencer commitment")
-        .json::<SequencerCommitment>()
-        .await
-        .expect("Failed to parse sequencer commitment JSON");
-
-    let block = ExecutionPayload::try_from(&commitment)
-        .expect("Failed to convert commitment to execution payload")
-        .block_number;
+        .map_err(invalid)?;
+    let block = check_commitment(
+        &commitments.origin(chain_id, fallback),
+        chain_id,
+        &commitment,
+        commitments.max_age(),
+    )
+    .map_err(invalid)?;
 
-    (commitment, block)
+    Ok((commitment, block))
 }
 
 /// Retrieves L1 block information for L2 chains.
 ///
 /// This function queries the L1Block contract on L2 chains to get L1 block
-/// information. It makes two calls: one to get the L1 block hash and another to
-/// get the L1 block number. This information is used for L1 inclusion proofs.
+/// information. It makes two calls on the same environment: one to get the L1
+/// block hash and another to get the L1 block number, so both are read from
+/// the same L2 block even if `block` is a tag. This information is used for L1
+/// inclusion proofs.
 ///
 /// # Arguments
+/// * `data_source` - Source of the RPC and sequencer endpoints.
 /// * `block` - Block number or tag to query.
 /// * `chain_id` - Chain ID (Optimism, Base, or their Sepolia variants).
 /// * `fallback` - Whether to use fallback RPC URLs.
 ///
 /// # Returns
-/// * `(EvmInput<EthEvmFactory>, u64)` - Tuple of L1 block input and block
-///   number.
+/// * `Result<(EvmInput<EthEvmFactory>, u64), HostError>` - Tuple of L1 block
+///   input and block number.
 ///
-/// # Panics
-/// Panics if:
+/// # Errors
+/// Returns an error if:
 /// - Invalid chain ID is provided.
 /// - RPC calls fail.
 /// - Environment building fails.
 /// - Contract calls fail.
 pub async fn get_l1block_call_input(
+    data_source: &dyn ChainDataSource,
     block: BlockNumberOrTag,
     chain_id: u64,
     fallback: bool,
-) -> (EvmInput<EthEvmFactory>, u64) {
-    println!("=== get_l1block_call_input args");
-    println!("=== * chain_id={}", chain_id);
-    println!("=== * block={:?}", block);
-    println!("=== * fallback={}", fallback);
+) -> Result<(EvmInput<EthEvmFactory>, u64), HostError> {
+    debug!("=== get_l1block_call_input args");
+    debug!("=== * chain_id={}", chain_id);
+    debug!("=== * block={:?}", block);
+    debug!("=== * fallback={}", fallback);
     // Get the chain name and testnet status for the RPC URL
-    let (chain_name, is_testnet) = get_chain_params(chain_id);
-    println!("=== * chain_name={:?}", chain_name);
-    println!("=== * is_testnet={:?}", is_testnet);
-    let rpc_url = get_rpc_url(chain_name, fallback, is_testnet);
-    println!("=== * rpc_url={:?}", rpc_url);
+    let (chain_name, is_testnet) = get_chain_params(chain_id)?;
+    debug!("=== * chain_name={:?}", chain_name);
+    debug!("=== * is_testnet={:?}", is_testnet);
+    let rpc_url = data_source.rpc_url(chain_name, fallback, is_testnet)?;
+    debug!("=== * rpc_url={:?}", RedactedUrl(&rpc_url));
     let mut env = EthEvmEnv::builder()
-        .rpc(Url::parse(rpc_url).expect("Failed to parse RPC URL"))
+        .rpc(parse_rpc_url(&rpc_url)?)
         .block_number_or_tag(block)
         .chain_spec(&ETH_MAINNET_CHAIN_SPEC)
         .build()
         .await
-        .expect("Failed to build EVM environment");
+        .map_err(|e| HostError::rpc("Failed to build EVM environment", e))?;
 
-    println!("===");
-    // Call the L1Block contract to get the L1 block hash
-    let call = IL1Block::hashCall {};
+    debug!("===");
     let mut contract = Contract::preflight(L1_BLOCK_ADDRESS_OPSTACK, &mut env);
+    // Call the L1Block contract to get the L1 block hash
     contract
-        .call_builder(&call)
+        .call_builder(&IL1Block::hashCall {})
         .call()
         .await
-        .expect("Failed to call L1Block hash");
-
-    // Convert the environment to input for the ZKVM
-    let view_call_input_l1_block = env
-        .into_input()
-        .await
-        .expect("Failed to convert environment to input");
-
+        .map_err(|e| HostError::rpc("Failed to call L1Block hash", e))?;
     // Call the L1Block contract to get the L1 block number
-    let mut env = EthEvmEnv::builder()
-        .rpc(Url::parse(rpc_url).expect("Failed to parse RPC URL"))
-        .block_number_or_tag(block)
-        .chain_spec(&ETH_MAINNET_CHAIN_SPEC)
-        .build()
-        .await
-        .expect("Failed to build EVM environment");
-
-    let call = IL1Block::numberCall {};
-    let mut contract = Contract::preflight(L1_BLOCK_ADDRESS_OPSTACK, &mut env);
     let l1_block = contract
-        .call_builder(&call)
+        .call_builder(&IL1Block::numberCall {})
         .call()
         .await
-        .expect("Failed to call L1Block number");
+        .map_err(|e| HostError::rpc("Failed to call L1Block number", e))?;
+
+    // Convert the environment to input for the ZKVM
+    let view_call_input_l1_block = env.into_input().await.map_err(|e| {
+        HostError::rpc("Failed to convert environment to input", e)
+    })?;
 
-    (view_call_input_l1_block, l1_block)
+    Ok((view_call_input_l1_block, l1_block))
 }
 
 /// Fetches a sequence of blocks for reorg protection.
@@ -1780,33 +1654,38 @@ pub async fn get_l1block_call_input(
 /// `current_block - reorg_protection_depth + 1` up to `current_block`.
 ///
 /// # Arguments
-/// * `chain_id` - Chain ID to query.
 /// * `rpc_url` - RPC URL for the chain.
 /// * `current_block` - Latest block number to start from.
+/// * `reorg_protection_depth` - Number of blocks to fetch.
 ///
 /// # Returns
-/// * `Vec<RlpHeader<Header>>` - Vector of block headers within the reorg
-///   protection window.
+/// * `Result<Vec<RlpHeader<Header>>, HostError>` - Vector of block headers
+///   within the reorg protection window.
 ///
-/// # Panics
-/// Panics if:
-/// - Invalid chain ID is provided.
+/// # Errors
+/// Returns an error if:
+/// - `reorg_protection_depth` exceeds `current_block + 1`.
 /// - RPC calls fail.
 /// - Block fetching tasks fail to join.
 pub async fn get_linking_blocks(
-    chain_id: u64,
     rpc_url: &str,
     current_block: u64,
-) -> Vec<RlpHeader<Header>> {
-    println!("=== get_linking_blocks args");
-    println!("=== * chain_id={:?}", chain_id);
-    println!("=== * rpc_url={:?}", rpc_url);
-    println!("=== * current_block={:?}", current_block);
-    // Determine the reorg protection depth for the chain
-    let reorg_protection_depth = get_reorg_protection_depth(chain_id);
+    reorg_protection_depth: u64,
+) -> Result<Vec<RlpHeader<Header>>, HostError> {
+    debug!("=== get_linking_blocks args");
+    debug!("=== * rpc_url={:?}", RedactedUrl(&rpc_url));
+    debug!("=== * current_block={:?}", current_block);
+    debug!("=== * reorg_protection_depth={:?}", reorg_protection_depth);
 
     // Calculate the starting block for the reorg protection window
-    let start_block = current_block - reorg_protection_depth + 1;
+    let start_block = (current_block + 1)
+        .checked_sub(reorg_protection_depth)
+        .ok_or_else(|| {
+        HostError::InvalidRequest(format!(
+            "reorg protection depth {reorg_protection_depth} exceeds \
+                 block {current_block}"
+        ))
+    })?;
 
     // Create futures for parallel block fetching
     let futures: Vec<_> = (start_block..=current_block)
@@ -1814,23 +1693,22 @@ pub async fn get_linking_blocks(
             let rpc_url = rpc_url.to_string();
             tokio::spawn(async move {
                 let env = EthEvmEnv::builder()
-                    .rpc(Url::parse(&rpc_url).expect("Failed to parse RPC URL"))
+                    .rpc(parse_rpc_url(&rpc_url)?)
                     .block_number_or_tag(BlockNumberOrTag::Number(block_nr))
                     .chain_spec(&ETH_MAINNET_CHAIN_SPEC)
                     .build()
                     .await
-                    .expect("Failed to build EVM environment");
-                env.header().inner().clone()
+                    .map_err(|e| {
+                        HostError::rpc("Failed to build EVM environment", e)
+                    })?;
+                Ok::<_, HostError>(env.header().inner().clone())
             })
         })
         .collect();
 
-    // Execute all futures in parallel and collect results
-    join_all(futures)
-        .await
-        .into_iter()
-        .map(|r| r.expect("Failed to join block fetch task"))
-        .collect()
+    // Execute all futures in parallel and collect results, failing on the
+    // first missing block
+    join_all(futures).await.into_iter().map(|r| r?).collect()
 }
 
 /// Returns the environment input and L2 block number for Linea L1 call.
@@ -1845,11 +1723,11 @@ pub async fn get_linking_blocks(
 /// * `l1_block` - The L1 block number to query at.
 ///
 /// # Returns
-/// * `(Option<EvmInput<EthEvmFactory>>, Option<u64>)` - The environment input
-///   and L2 block number, if available.
+/// * `Result<(Option<EvmInput<EthEvmFactory>>, Option<u64>), HostError>` - The
+///   environment input and L2 block number, if available.
 ///
-/// # Panics
-/// Panics if:
+/// # Errors
+/// Returns an error if:
 /// - Invalid chain ID is provided (not a Linea chain).
 /// - RPC calls fail.
 /// - Environment building fails.
@@ -1857,22 +1735,26 @@ pub async fn get_env_input_for_linea_l1_call(
     chain_id: u64,
     l1_rpc_url: &str,
     l1_block: u64,
-) -> (Option<EvmInput<EthEvmFactory>>, Option<u64>) {
+) -> Result<(Option<EvmInput<EthEvmFactory>>, Option<u64>), HostError> {
     // Select the correct message service address for the chain
     let message_service_address = match chain_id {
         LINEA_CHAIN_ID => L1_MESSAGE_SERVICE_LINEA,
         LINEA_SEPOLIA_CHAIN_ID => L1_MESSAGE_SERVICE_LINEA_SEPOLIA,
-        _ => panic!("Invalid chain ID"),
+        _ => {
+            return Err(HostError::InvalidRequest(format!(
+                "Invalid Linea chain ID: {chain_id}"
+            )));
+        }
     };
 
     // Build the Ethereum environment for the L1 block
     let mut env = EthEvmEnv::builder()
-        .rpc(Url::parse(l1_rpc_url).expect("Failed to parse RPC URL"))
+        .rpc(parse_rpc_url(l1_rpc_url)?)
         .block_number_or_tag(BlockNumberOrTag::Number(l1_block))
         .chain_spec(&ETH_MAINNET_CHAIN_SPEC)
         .build()
         .await
-        .expect("Failed to build EVM environment");
+        .map_err(|e| HostError::rpc("Failed to build EVM environment", e))?;
 
     // Make single multicall to get the current L2 block number
     let current_l2_block_number_call =
@@ -1883,18 +1765,46 @@ pub async fn get_env_input_for_linea_l1_call(
         .call_builder(&current_l2_block_number_call)
         .call()
         .await
-        .expect("Failed to execute current l2 block number call");
-
-    let l2_block_number: u64 = U64::from(returns).try_into().unwrap();
-
-    (
-        Some(
-            env.into_input()
-                .await
-                .expect("Failed to convert environment to input"),
-        ),
+        .map_err(|e| {
+            HostError::rpc("Failed to execute current l2 block number call", e)
+        })?;
+
+    let l2_block_number: u64 = U64::from(returns).try_into().map_err(|e| {
+        HostError::decode("Failed to convert L2 block number", e)
+    })?;
+
+    Ok((
+        Some(env.into_input().await.map_err(|e| {
+            HostError::rpc("Failed to convert environment to input", e)
+        })?),
         Some(l2_block_number),
-    )
+    ))
+}
+
+/// Checks that `config` holds the endpoints of every chain in `chain_ids`.
+///
+/// A validated config always holds every endpoint of the networks it
+/// configures, so it is enough to check the network of each chain. This turns
+/// a missing endpoint into an error before any task is spawned.
+///
+/// # Errors
+/// Returns an error naming the first chain that is not supported or whose
+/// network is not configured.
+fn ensure_networks_configured(
+    config: &MaldaConfig,
+    chain_ids: &[u64],
+) -> Result<(), HostError> {
+    for &chain_id in chain_ids {
+        let (_chain_name, is_testnet) = get_chain_params(chain_id)?;
+        if !config.network(is_testnet).is_configured() {
+            return Err(HostError::Config(format!(
+                "chain {chain_id} requires the {} network, which is not \
+                 configured",
+                if is_testnet { "sepolia" } else { "mainnet" }
+            )));
+        }
+    }
+    Ok(())
 }
 
 /// Helper function to get chain parameters from chain ID.
@@ -1905,18 +1815,21 @@ pub async fn get_env_input_for_linea_l1_call(
 /// * `chain_id` - The chain ID to look up.
 ///
 /// # Returns
-/// * `(&'static str, bool)` - Tuple of (chain_name, is_testnet).
+/// * `Result<(&'static str, bool), HostError>` - Tuple of (chain_name,
+///   is_testnet).
 ///
-/// # Panics
-/// Panics if an invalid chain ID is provided.
+/// # Errors
+/// Returns an error if an invalid chain ID is provided.
 ///
 /// # Supported Chains
 /// - Ethereum mainnet and Sepolia
 /// - Optimism mainnet and Sepolia
 /// - Base mainnet and Sepolia
 /// - Linea mainnet and Sepolia
-fn get_chain_params(chain_id: u64) -> (&'static str, bool) {
-    match chain_id {
+pub(crate) fn get_chain_params(
+    chain_id: u64,
+) -> Result<(&'static str, bool), HostError> {
+    Ok(match chain_id {
         BASE_CHAIN_ID => ("BASE", false),
         OPTIMISM_CHAIN_ID => ("OPTIMISM", false),
         LINEA_CHAIN_ID => ("LINEA", false),
@@ -1925,8 +1838,12 @@ fn get_chain_params(chain_id: u64) -> (&'static str, bool) {
         BASE_SEPOLIA_CHAIN_ID => ("BASE", true),
         LINEA_SEPOLIA_CHAIN_ID => ("LINEA", true),
         ETHEREUM_SEPOLIA_CHAIN_ID => ("ETHEREUM", true),
-        _ => panic!("Invalid chain ID: {}", chain_id),
-    }
+        _ => {
+            return Err(HostError::InvalidRequest(format!(
+                "Invalid chain ID: {chain_id}"
+            )));
+        }
+    })
 }
 
 /// Helper function to get OpStack chain configuration.
@@ -1934,36 +1851,42 @@ fn get_chain_params(chain_id: u64) -> (&'static str, bool) {
 /// Returns the RPC URLs, portal address, and chain name for OpStack chains.
 ///
 /// # Arguments
+/// * `data_source` - Source of the RPC endpoints.
 /// * `chain_id` - The OpStack chain ID.
 /// * `fallback` - Whether to use fallback RPC URLs.
 ///
 /// # Returns
-/// * `(&'static str, Address, &'static str, &'static str)` - Tuple of
+/// * `Result<(String, Address, String, &'static str), HostError>` - Tuple of
 ///   (l1_rpc_url, portal_address, l2_rpc_url, chain_name).
 ///
-/// # Panics
-/// Panics if an invalid OpStack chain ID is provided.
+/// # Errors
+/// Returns an error if an invalid OpStack chain ID is provided.
 ///
 /// # Supported OpStack Chains
 /// - Optimism mainnet and Sepolia
 /// - Base mainnet and Sepolia
 fn get_opstack_config(
+    data_source: &dyn ChainDataSource,
     chain_id: u64,
     fallback: bool,
-) -> (&'static str, Address, &'static str, &'static str) {
-    let (chain_name, is_testnet) = get_chain_params(chain_id);
-    let l1_rpc_url = get_rpc_url("ETHEREUM", fallback, is_testnet);
-    let l2_rpc_url = get_rpc_url(chain_name, fallback, is_testnet);
-
+) -> Result<(String, Address, String, &'static str), HostError> {
+    let (chain_name, is_testnet) = get_chain_params(chain_id)?;
     let portal = match chain_id {
         OPTIMISM_CHAIN_ID => OPTIMISM_PORTAL,
         OPTIMISM_SEPOLIA_CHAIN_ID => OPTIMISM_SEPOLIA_PORTAL,
         BASE_CHAIN_ID => BASE_PORTAL,
         BASE_SEPOLIA_CHAIN_ID => BASE_SEPOLIA_PORTAL,
-        _ => panic!("Invalid OpStack chain ID: {}", chain_id),
+        _ => {
+            return Err(HostError::InvalidRequest(format!(
+                "Invalid OpStack chain ID: {chain_id}"
+            )));
+        }
     };
 
-    (l1_rpc_url, portal, l2_rpc_url, chain_name)
+    let l1_rpc_url = data_source.rpc_url("ETHEREUM", fallback, is_testnet)?;
+    let l2_rpc_url = data_source.rpc_url(chain_name, fallback, is_testnet)?;
+
+    Ok((l1_rpc_url, portal, l2_rpc_url, chain_name))
 }
 
 /// Helper function to get portal address for a chain.
@@ -1974,24 +1897,42 @@ fn get_opstack_config(
 /// * `chain_id` - The chain ID to look up.
 ///
 /// # Returns
-/// * `Address` - The portal contract address.
+/// * `Result<Address, HostError>` - The portal contract address.
 ///
-/// # Panics
-/// Panics if an invalid chain ID is provided.
+/// # Errors
+/// Returns an error if an invalid chain ID is provided.
 ///
 /// # Supported Chains
 /// - Optimism mainnet and Sepolia
 /// - Base mainnet and Sepolia
-fn get_portal_address(chain_id: u64) -> Address {
+fn get_portal_address(chain_id: u64) -> Result<Address, HostError> {
     match chain_id {
-        OPTIMISM_SEPOLIA_CHAIN_ID => OPTIMISM_SEPOLIA_PORTAL,
-        BASE_SEPOLIA_CHAIN_ID => BASE_SEPOLIA_PORTAL,
-        OPTIMISM_CHAIN_ID => OPTIMISM_PORTAL,
-        BASE_CHAIN_ID => BASE_PORTAL,
-        _ => panic!("Invalid chain ID for portal: {}", chain_id),
+        OPTIMISM_SEPOLIA_CHAIN_ID => Ok(OPTIMISM_SEPOLIA_PORTAL),
+        BASE_SEPOLIA_CHAIN_ID => Ok(BASE_SEPOLIA_PORTAL),
+        OPTIMISM_CHAIN_ID => Ok(OPTIMISM_PORTAL),
+        BASE_CHAIN_ID => Ok(BASE_PORTAL),
+        _ => Err(HostError::InvalidRequest(format!(
+            "Invalid chain ID for portal: {chain_id}"
+        ))),
     }
 }
 
+/// Helper function to parse an RPC URL.
+///
+/// # Arguments
+/// * `rpc_url` - The URL to parse.
+///
+/// # Returns
+/// * `Result<Url, HostError>` - The parsed URL.
+///
+/// # Errors
+/// Returns an error if the URL is malformed. The URL itself is not included,
+/// as RPC URLs commonly embed API keys.
+pub(crate) fn parse_rpc_url(rpc_url: &str) -> Result<Url, HostError> {
+    Url::parse(rpc_url)
+        .map_err(|e| HostError::Config(format!("invalid RPC URL: {e}")))
+}
+
 /// Helper function to check if a chain is an OpStack chain.
 ///
 /// Determines whether a given chain ID corresponds to an OpStack L2 chain.
@@ -2047,6 +1988,27 @@ fn is_ethereum_chain(chain_id: u64) -> bool {
     matches!(chain_id, ETHEREUM_CHAIN_ID | ETHEREUM_SEPOLIA_CHAIN_ID)
 }
 
+/// Helper function to get the Ethereum network a chain settles on.
+///
+/// # Arguments
+/// * `chain_id` - The chain ID to check.
+///
+/// # Returns
+/// * `u64` - Ethereum Sepolia for Sepolia chains, Ethereum mainnet otherwise.
+fn get_ethereum_chain_id(chain_id: u64) -> u64 {
+    if matches!(
+        chain_id,
+        OPTIMISM_SEPOLIA_CHAIN_ID
+            | BASE_SEPOLIA_CHAIN_ID
+            | ETHEREUM_SEPOLIA_CHAIN_ID
+            | LINEA_SEPOLIA_CHAIN_ID
+    ) {
+        ETHEREUM_SEPOLIA_CHAIN_ID
+    } else {
+        ETHEREUM_CHAIN_ID
+    }
+}
+
 /// Helper function to get the default sequencer commitment chain for a given
 /// chain.
 ///
@@ -2071,39 +2033,105 @@ fn get_default_sequencer_chain(_chain_id: u64, is_sepolia: bool) -> u64 {
     }
 }
 
-/// Helper function to get reorg protection depth for a chain.
+/// Resolves how many linking blocks are needed to satisfy a finality policy.
 ///
-/// Returns the number of blocks to look back for reorg protection based on the
-/// chain type. This ensures that blocks used in proofs are sufficiently
-/// confirmed to avoid chain reorganizations.
+/// Depth policies are used as-is. Minimum-age policies walk back from
+/// `current_block`, extrapolating from the observed block time, until the
+/// window spans the required number of seconds. Tag policies measure the
+/// distance from `current_block` to the block the RPC reports as `safe` or
+/// `finalized`. Every result is at least the policy's minimum depth.
 ///
 /// # Arguments
-/// * `chain_id` - The chain ID to get the protection depth for.
+/// * `chain_id` - Chain ID the linking blocks are fetched from.
+/// * `rpc_url` - RPC URL for the chain.
+/// * `current_block` - Newest block of the linking window.
+/// * `finality_policy` - The policy to resolve.
 ///
 /// # Returns
-/// * `u64` - The reorg protection depth in blocks.
-///
-/// # Panics
-/// Panics if an invalid chain ID is provided.
-///
-/// # Protection Depths
-/// Different chains have different protection depths based on their finality
-/// characteristics:
-/// - Ethereum: Higher depth due to longer finality
-/// - L2 chains: Lower depth due to faster finality
-/// - Testnets: Lower depth for faster testing
-fn get_reorg_protection_depth(chain_id: u64) -> u64 {
-    match chain_id {
-        OPTIMISM_CHAIN_ID => REORG_PROTECTION_DEPTH_OPTIMISM,
-        BASE_CHAIN_ID => REORG_PROTECTION_DEPTH_BASE,
-        LINEA_CHAIN_ID => REORG_PROTECTION_DEPTH_LINEA,
-        ETHEREUM_CHAIN_ID => REORG_PROTECTION_DEPTH_ETHEREUM,
-        OPTIMISM_SEPOLIA_CHAIN_ID => REORG_PROTECTION_DEPTH_OPTIMISM_SEPOLIA,
-        BASE_SEPOLIA_CHAIN_ID => REORG_PROTECTION_DEPTH_BASE_SEPOLIA,
-        LINEA_SEPOLIA_CHAIN_ID => REORG_PROTECTION_DEPTH_LINEA_SEPOLIA,
-        ETHEREUM_SEPOLIA_CHAIN_ID => REORG_PROTECTION_DEPTH_ETHEREUM_SEPOLIA,
-        _ => panic!("invalid chain id"),
-    }
+/// * `Result<u64, HostError>` - The number of linking blocks to fetch.
+///
+/// # Errors
+/// Returns an error if:
+/// - RPC calls fail.
+/// - A minimum-age policy reaches past the genesis block.
+async fn resolve_reorg_protection_depth(
+    chain_id: u64,
+    rpc_url: &str,
+    current_block: u64,
+    finality_policy: &FinalityPolicy,
+) -> Result<u64, HostError> {
+    let min_depth = finality_policy.min_depth(chain_id);
+    Ok(match finality_policy {
+        FinalityPolicy::Depth(_) => min_depth,
+        FinalityPolicy::MinAge(min_age) => {
+            let current_timestamp = get_block_header(
+                rpc_url,
+                BlockNumberOrTag::Number(current_block),
+            )
+            .await?
+            .timestamp;
+            let mut depth = min_depth.max(1);
+            loop {
+                let block = current_block.checked_sub(depth).ok_or_else(|| {
+                    HostError::InvalidRequest(format!(
+                        "minimum age of {min_age}s reaches past genesis from \
+                         block {current_block}"
+                    ))
+                })?;
+                let timestamp =
+                    get_block_header(rpc_url, BlockNumberOrTag::Number(block))
+                        .await?
+                        .timestamp;
+                let age = current_timestamp.saturating_sub(timestamp);
+                if age >= *min_age {
+                    break depth;
+                }
+                // Extrapolate from the observed block time, always making
+                // progress
+                depth = if age == 0 {
+                    depth * 2
+                } else {
+                    (depth * min_age).div_ceil(age).max(depth + 1)
+                };
+            }
+        }
+        FinalityPolicy::Safe | FinalityPolicy::Finalized => {
+            let tag = match finality_policy {
+                FinalityPolicy::Safe => BlockNumberOrTag::Safe,
+                _ => BlockNumberOrTag::Finalized,
+            };
+            let tagged_block = get_block_header(rpc_url, tag).await?.number;
+            min_depth.max(current_block.saturating_sub(tagged_block))
+        }
+    })
+}
+
+/// Helper function to fetch a single block header.
+///
+/// # Arguments
+/// * `rpc_url` - RPC URL for the chain.
+/// * `block` - Block number or tag to fetch.
+///
+/// # Returns
+/// * `Result<Header, HostError>` - The block header.
+///
+/// # Errors
+/// Returns an error if the RPC call fails.
+async fn get_block_header(
+    rpc_url: &str,
+    block: BlockNumberOrTag,
+) -> Result<Header, HostError> {
+    Ok(EthEvmEnv::builder()
+        .rpc(parse_rpc_url(rpc_url)?)
+        .block_number_or_tag(block)
+        .chain_spec(&ETH_MAINNET_CHAIN_SPEC)
+        .build()
+        .await
+        .map_err(|e| HostError::rpc("Failed to build EVM environment", e))?
+        .header()
+        .inner()
+        .inner()
+        .clone())
 }
 
 #[cfg(test)]
@@ -2111,12 +2139,32 @@ mod tests {
     use std::ops::Deref;
 
     use super::*;
+    use crate::commitments::HttpCommitmentSource;
+    use crate::data_source::RpcDataSource;
+    use crate::receipts::EventInput;
+    use crate::request::ProofDataQuery;
+    use crate::storage::StorageProofInput;
+
+    fn data_source() -> RpcDataSource {
+        RpcDataSource::new(Arc::new(
+            MaldaConfig::from_env().expect("Failed to load config"),
+        ))
+    }
+
+    fn commitments() -> HttpCommitmentSource {
+        HttpCommitmentSource::new(Arc::new(data_source()), None)
+    }
 
     /// @custom:property ZK07
     #[tokio::test]
     async fn test_sequencer_commitment_verification() {
-        let (commitment, block_number) =
-            get_current_sequencer_commitment(OPTIMISM_CHAIN_ID, false).await;
+        let (commitment, block_number) = get_current_sequencer_commitment(
+            &commitments(),
+            OPTIMISM_CHAIN_ID,
+            false,
+        )
+        .await
+        .expect("Failed to fetch sequencer commitment");
 
         let res = commitment.verify(Address::random(), OPTIMISM_CHAIN_ID);
         assert!(res.is_err(), "random signer should not marked as valid");
@@ -2192,9 +2240,6 @@ mod tests {
 
     /// @custom:property ZK09
     #[tokio::test]
-    #[should_panic(
-        expected = "L1 Inclusion only supported for Optimism, Base, Linea and their Sepolia variants"
-    )]
     async fn test_zk09_ethereum_l1_inclusion() {
         let users = Vec::from([Address::random()]);
         let markets = Vec::from([Address::random()]);
@@ -2205,15 +2250,29 @@ mod tests {
 
         // TODO(pyk): what happen when l1_inclusion=false?
 
-        get_proof_data_zkvm_input(
-            users,
-            markets,
-            target_chain_ids,
-            chain_id,
-            l1_inclusion,
-            fallback,
-        )
-        .await;
+        // The request is rejected before any RPC request is made
+        let err = ProofDataRequest::builder()
+            .query(
+                chain_id,
+                ProofDataQuery::new(users[0], markets[0], target_chain_ids[0]),
+            )
+            .inclusion(if l1_inclusion {
+                InclusionMode::L1
+            } else {
+                InclusionMode::Sequencer
+            })
+            .rpc(if fallback {
+                RpcSelection::Fallback
+            } else {
+                RpcSelection::Primary
+            })
+            .build()
+            .unwrap_err();
+        assert!(matches!(err, HostError::InvalidRequest(_)));
+        assert!(err.to_string().contains(
+            "L1 Inclusion only supported for Optimism, Base, Linea and their \
+             Sepolia variants"
+        ));
     }
 
     use risc0_steel::ethereum::EthEvmInput;
@@ -2229,17 +2288,44 @@ mod tests {
         let fallback = false;
 
         let (_commitment, expected_block_number) =
-            get_current_sequencer_commitment(OPTIMISM_CHAIN_ID, false).await;
+            get_current_sequencer_commitment(
+                &commitments(),
+                OPTIMISM_CHAIN_ID,
+                false,
+            )
+            .await
+            .expect("Failed to fetch sequencer commitment");
 
+        let request = ProofDataRequest::builder()
+            .query(
+                chain_id,
+                ProofDataQuery::new(users[0], markets[0], target_chain_ids[0]),
+            )
+            .inclusion(if l1_inclusion {
+                InclusionMode::L1
+            } else {
+                InclusionMode::Sequencer
+            })
+            .rpc(if fallback {
+                RpcSelection::Fallback
+            } else {
+                RpcSelection::Primary
+            })
+            .build()
+            .expect("Failed to build request");
+        let anchors = get_l1_anchors(&data_source(), &commitments(), &request)
+            .await
+            .expect("Failed to build L1 anchors");
         let input = get_proof_data_zkvm_input(
-            users,
-            markets,
-            target_chain_ids,
-            chain_id,
-            l1_inclusion,
-            fallback,
+            &data_source(),
+            &commitments(),
+            &anchors,
+            &request.chains()[0],
+            request.inclusion(),
+            request.rpc(),
         )
-        .await;
+        .await
+        .expect("Failed to build guest input");
 
         type Input = (
             Option<EthEvmInput>,         // 0
@@ -2248,15 +2334,15 @@ mod tests {
             Vec<Address>,                // 3: markets
             Vec<u64>,                    // 4: target_chain_ids
             Option<SequencerCommitment>, // 5
-            Option<EthEvmInput>,         // 6
-            Vec<RlpHeader<Header>>,      // 7
-            Option<EthEvmInput>,         // 8
-            Option<OpEvmInput>,          // 9: The vulnerable L2 state proof!
-            Option<SequencerCommitment>, // 10
-            Option<EthEvmInput>,         // 11
+            Vec<RlpHeader<Header>>,      // 6
+            Option<EthEvmInput>,         // 7
+            Option<OpEvmInput>,          // 8: The vulnerable L2 state proof!
+            FinalityPolicy,              // 9
+            Option<EventInput>,          // 10
+            Option<StorageProofInput>,   // 11
         );
         let des: Input = risc0_zkvm::serde::from_slice(&input).expect("X");
-        let evm_input = des.9.expect("should not be none");
+        let evm_input = des.8.expect("should not be none");
         let block_number: u64 =
             evm_input.into_env(&OP_MAINNET_CHAIN_SPEC).header().0.number;
 
@@ -2280,8 +2366,10 @@ mod tests {
             LINEA_CHAIN_ID,
         ]);
         let valid_target_chain_ids = valid_chain_ids.clone();
-        let valid_l1_inclusion = Vec::from([true, false]);
-        let valid_fallback = Vec::from([true, false]);
+        let valid_l1_inclusion =
+            Vec::from([InclusionMode::L1, InclusionMode::Sequencer]);
+        let valid_fallback =
+            Vec::from([RpcSelection::Fallback, RpcSelection::Primary]);
 
         for &chain_id in &valid_chain_ids {
             for &l1_inclusion in &valid_l1_inclusion {
@@ -2294,21 +2382,46 @@ mod tests {
                         valid_target_chain_ids
                     );
                     println!("=== * chain_id={}", chain_id);
-                    println!("=== * l1_inclusion={}", l1_inclusion);
-                    println!("=== * fallback={}", fallback);
-                    if chain_id == ETHEREUM_CHAIN_ID && l1_inclusion == true {
+                    println!("=== * l1_inclusion={:?}", l1_inclusion);
+                    println!("=== * fallback={:?}", fallback);
+                    if chain_id == ETHEREUM_CHAIN_ID && l1_inclusion.is_l1() {
                         println!("Skipped due to known bug");
                         continue;
                     }
+                    let request = ProofDataRequest::builder()
+                        .queries(
+                            chain_id,
+                            valid_target_chain_ids.iter().map(
+                                |&target_chain_id| {
+                                    ProofDataQuery::new(
+                                        users[0],
+                                        markets[0],
+                                        target_chain_id,
+                                    )
+                                },
+                            ),
+                        )
+                        .inclusion(l1_inclusion)
+                        .rpc(fallback)
+                        .build()
+                        .expect("Failed to build request");
+                    let anchors = get_l1_anchors(
+                        &data_source(),
+                        &commitments(),
+                        &request,
+                    )
+                    .await
+                    .expect("Failed to build L1 anchors");
                     get_proof_data_zkvm_input(
-                        users.clone(),
-                        markets.clone(),
-                        valid_target_chain_ids.clone(),
-                        chain_id,
-                        l1_inclusion,
-                        fallback,
+                        &data_source(),
+                        &commitments(),
+                        &anchors,
+                        &request.chains()[0],
+                        request.inclusion(),
+                        request.rpc(),
                     )
-                    .await;
+                    .await
+                    .expect("Failed to build guest input");
                     println!("===== SUCCESS =====");
                 }
             }
@@ -5,6 +5,10 @@
 //! - OpStack (Optimism/Base) environment validation
 //! - Chain length validation for reorg protection
 //! - Cross-chain balance query inputs
+//! - Storage proof amounts against the multicall
+//! - Storage proof inputs replayed from committed fixtures
+//! - Proof data inputs replayed from recorded RPC and beacon API fixtures
+//! - Guest cycle counts against the benchmark baseline
 
 #[cfg(test)]
 mod tests {
@@ -12,21 +16,96 @@ mod tests {
     use alloy::{
         eips::BlockNumberOrTag,
         providers::{Provider, ProviderBuilder},
+        rpc::types::Filter,
         transports::http::reqwest::Url,
     };
-    use alloy_primitives::{Address, address};
-    use malda_rs::{constants::*, types::*, validators::*, viewcalls::*};
+    use alloy_primitives::{Address, B256, U256, address, b256};
+    use malda_rs::{
+        commitments::HttpCommitmentSource,
+        config::MaldaConfig,
+        constants::*,
+        data_source::{ChainDataSource, ReplayDataSource, RpcDataSource},
+        error::HostError,
+        profile::{Baseline, profile_input},
+        programs::GuestProgram,
+        request::*,
+        snapshot::InputSnapshot,
+        storage::prove_amounts,
+        storage_proofs::get_storage_proof_input,
+        types::*,
+        validators::*,
+        viewcalls::*,
+    };
     use risc0_steel::{
-        ethereum::EthEvmEnv, host::BlockNumberOrTag as BlockRisc0,
+        ethereum::{ETH_MAINNET_CHAIN_SPEC, EthEvmEnv},
+        host::BlockNumberOrTag as BlockRisc0,
         serde::RlpHeader,
     };
 
+    use std::{collections::BTreeMap, path::Path, sync::Arc};
+
     // Arbitrary values for testing
     const USER: Address = address!("Ad7f33984bed10518012013D4aB0458D37FEE6F3");
 
     pub const WETH_MARKET_SEPOLIA: Address =
         address!("B84644c24B4D0823A0770ED698f7C20B88Bcf824");
 
+    const MUSDC: Address = address!("269C36A173D881720544Fb303E681370158FF1FD");
+    const MWETH: Address = address!("C7Bc6bD45Eb84D594f51cED3c5497E6812C7732f");
+
+    fn config() -> MaldaConfig {
+        MaldaConfig::from_env().expect("Failed to load config")
+    }
+
+    fn rpc_url(chain_name: &str, fallback: bool, testnet: bool) -> String {
+        config()
+            .rpc_url(chain_name, fallback, testnet)
+            .expect("Failed to get RPC URL")
+            .to_string()
+    }
+
+    fn data_source() -> RpcDataSource {
+        RpcDataSource::new(std::sync::Arc::new(config()))
+    }
+
+    fn commitments() -> HttpCommitmentSource {
+        HttpCommitmentSource::new(std::sync::Arc::new(data_source()), None)
+    }
+
+    fn proof_data_request(
+        chain_id: u64,
+        users: &[Address],
+        markets: &[Address],
+        target_chain_ids: &[u64],
+        l1_inclusion: bool,
+        fallback: bool,
+    ) -> Result<ProofDataRequest, HostError> {
+        assert_eq!(users.len(), markets.len());
+        assert_eq!(users.len(), target_chain_ids.len());
+        ProofDataRequest::builder()
+            .queries(
+                chain_id,
+                (0..users.len()).map(|i| {
+                    ProofDataQuery::new(
+                        users[i],
+                        markets[i],
+                        target_chain_ids[i],
+                    )
+                }),
+            )
+            .inclusion(if l1_inclusion {
+                InclusionMode::L1
+            } else {
+                InclusionMode::Sequencer
+            })
+            .rpc(if fallback {
+                RpcSelection::Fallback
+            } else {
+                RpcSelection::Primary
+            })
+            .build()
+    }
+
     /// Tests Linea environment validation with correct input parameters
     ///
     /// # Test Steps
@@ -39,7 +118,7 @@ mod tests {
     #[tokio::test]
     async fn test_validate_linea_env_correct_input() {
         let latest_block = EthEvmEnv::builder()
-            .rpc(Url::parse(get_rpc_url("LINEA", false, false)).unwrap())
+            .rpc(Url::parse(&rpc_url("LINEA", false, false)).unwrap())
             .block_number_or_tag(BlockRisc0::Latest)
             .chain_spec(&LINEA_MAINNET_CHAIN_SPEC)
             .build()
@@ -51,16 +130,19 @@ mod tests {
             .number;
 
         let proof_data_call_input = get_proof_data_call_input(
+            &data_source(),
             LINEA_CHAIN_ID,
-            get_rpc_url("LINEA", false, false),
+            &rpc_url("LINEA", false, false),
             latest_block,
+            FinalityPolicy::default_depth(LINEA_CHAIN_ID),
             vec![USER],
             vec![WETH_MARKET_SEPOLIA],
             vec![OPTIMISM_CHAIN_ID],
             false,
             false,
         )
-        .await;
+        .await
+        .expect("Failed to build proof data call input");
 
         let env = proof_data_call_input
             .0
@@ -83,7 +165,7 @@ mod tests {
     #[tokio::test]
     async fn test_validate_linea_env_input_of_wrong_chain_panics() {
         let latest_block = EthEvmEnv::builder()
-            .rpc(Url::parse(get_rpc_url("OPTIMISM", false, false)).unwrap())
+            .rpc(Url::parse(&rpc_url("OPTIMISM", false, false)).unwrap())
             .block_number_or_tag(BlockRisc0::Latest)
             .chain_spec(&LINEA_MAINNET_CHAIN_SPEC)
             .build()
@@ -95,16 +177,19 @@ mod tests {
             .number;
 
         let proof_data_call_input = get_proof_data_call_input(
+            &data_source(),
             OPTIMISM_CHAIN_ID,
-            get_rpc_url("OPTIMISM", false, false),
+            &rpc_url("OPTIMISM", false, false),
             latest_block,
+            FinalityPolicy::default_depth(OPTIMISM_CHAIN_ID),
             vec![USER],
             vec![WETH_MARKET_SEPOLIA],
             vec![LINEA_CHAIN_ID],
             false,
             false,
         )
-        .await;
+        .await
+        .expect("Failed to build proof data call input");
 
         let env = proof_data_call_input
             .0
@@ -135,7 +220,7 @@ mod tests {
     #[tokio::test]
     async fn test_validate_linea_env_input_manipulated_panics() {
         let latest_block = EthEvmEnv::builder()
-            .rpc(Url::parse(get_rpc_url("LINEA", false, false)).unwrap())
+            .rpc(Url::parse(&rpc_url("LINEA", false, false)).unwrap())
             .block_number_or_tag(BlockRisc0::Latest)
             .chain_spec(&LINEA_MAINNET_CHAIN_SPEC)
             .build()
@@ -147,16 +232,19 @@ mod tests {
             .number;
 
         let proof_data_call_input = get_proof_data_call_input(
+            &data_source(),
             LINEA_CHAIN_ID,
-            get_rpc_url("LINEA", false, false),
+            &rpc_url("LINEA", false, false),
             latest_block,
+            FinalityPolicy::default_depth(LINEA_CHAIN_ID),
             vec![USER],
             vec![WETH_MARKET_SEPOLIA],
             vec![OPTIMISM_CHAIN_ID],
             false,
             false,
         )
-        .await;
+        .await
+        .expect("Failed to build proof data call input");
 
         let env = proof_data_call_input
             .0
@@ -185,11 +273,15 @@ mod tests {
     /// - No panic occurs with valid input
     #[tokio::test]
     async fn test_validate_optimism_env_correct_input() {
-        let (sequencer_commitment, block) =
-            get_current_sequencer_commitment(OPTIMISM_CHAIN_ID, false).await;
+        let (sequencer_commitment, block) = get_current_sequencer_commitment(
+            &commitments(),
+            OPTIMISM_CHAIN_ID,
+            false,
+        )
+        .await
+        .expect("Failed to fetch sequencer commitment");
 
-        let http_url: Url =
-            get_rpc_url("OPTIMISM", false, false).parse().unwrap();
+        let http_url: Url = rpc_url("OPTIMISM", false, false).parse().unwrap();
 
         let provider = ProviderBuilder::new().connect_http(http_url);
         let correct_hash = provider
@@ -218,11 +310,15 @@ mod tests {
     /// - Panics due to hash mismatch
     #[tokio::test]
     async fn test_validate_optimism_env_wrong_hash_panics() {
-        let (sequencer_commitment, block) =
-            get_current_sequencer_commitment(OPTIMISM_CHAIN_ID, false).await;
+        let (sequencer_commitment, block) = get_current_sequencer_commitment(
+            &commitments(),
+            OPTIMISM_CHAIN_ID,
+            false,
+        )
+        .await
+        .expect("Failed to fetch sequencer commitment");
 
-        let http_url: Url =
-            get_rpc_url("OPTIMISM", false, false).parse().unwrap();
+        let http_url: Url = rpc_url("OPTIMISM", false, false).parse().unwrap();
 
         let provider = ProviderBuilder::new().connect_http(http_url);
 
@@ -258,11 +354,15 @@ mod tests {
     /// - Panics due to chain ID mismatch
     #[tokio::test]
     async fn test_validate_optimism_env_wrong_chain_id_panics() {
-        let (sequencer_commitment, block) =
-            get_current_sequencer_commitment(OPTIMISM_CHAIN_ID, false).await;
+        let (sequencer_commitment, block) = get_current_sequencer_commitment(
+            &commitments(),
+            OPTIMISM_CHAIN_ID,
+            false,
+        )
+        .await
+        .expect("Failed to fetch sequencer commitment");
 
-        let http_url: Url =
-            get_rpc_url("OPTIMISM", false, false).parse().unwrap();
+        let http_url: Url = rpc_url("OPTIMISM", false, false).parse().unwrap();
 
         let provider = ProviderBuilder::new().connect_http(http_url);
 
@@ -299,11 +399,15 @@ mod tests {
     #[tokio::test]
     async fn test_validate_optimism_env_wrong_commitment_panics() {
         // get commitment from base chain here
-        let (sequencer_commitment, block) =
-            get_current_sequencer_commitment(BASE_CHAIN_ID, false).await;
+        let (sequencer_commitment, block) = get_current_sequencer_commitment(
+            &commitments(),
+            BASE_CHAIN_ID,
+            false,
+        )
+        .await
+        .expect("Failed to fetch sequencer commitment");
 
-        let http_url: Url =
-            get_rpc_url("OPTIMISM", false, false).parse().unwrap();
+        let http_url: Url = rpc_url("OPTIMISM", false, false).parse().unwrap();
 
         let provider = ProviderBuilder::new().connect_http(http_url);
 
@@ -339,11 +443,22 @@ mod tests {
     /// - Panics for both signature and data manipulation
     #[tokio::test]
     async fn test_validate_optimism_env_manipulated_commitment_panics() {
-        let (sequencer_commitment, _block) =
-            get_current_sequencer_commitment(OPTIMISM_CHAIN_ID, false).await;
+        let (sequencer_commitment, _block) = get_current_sequencer_commitment(
+            &commitments(),
+            OPTIMISM_CHAIN_ID,
+            false,
+        )
+        .await
+        .expect("Failed to fetch sequencer commitment");
 
         let (wrong_sequencer_commitment, block) =
-            get_current_sequencer_commitment(BASE_CHAIN_ID, false).await;
+            get_current_sequencer_commitment(
+                &commitments(),
+                BASE_CHAIN_ID,
+                false,
+            )
+            .await
+            .expect("Failed to fetch sequencer commitment");
 
         let mut manipulated_commitment_signature = sequencer_commitment.clone();
         manipulated_commitment_signature.signature =
@@ -352,8 +467,7 @@ mod tests {
         let mut manipulated_commitment_data = sequencer_commitment.clone();
         manipulated_commitment_data.data = wrong_sequencer_commitment.data;
 
-        let http_url: Url =
-            get_rpc_url("OPTIMISM", false, false).parse().unwrap();
+        let http_url: Url = rpc_url("OPTIMISM", false, false).parse().unwrap();
 
         let provider = ProviderBuilder::new().connect_http(http_url);
 
@@ -402,11 +516,12 @@ mod tests {
     async fn test_validate_chain_length_input_correct() {
         let block_number = 21193475;
         let linking_blocks = get_linking_blocks(
-            ETHEREUM_CHAIN_ID,
-            get_rpc_url("ETHEREUM", false, false),
+            &rpc_url("ETHEREUM", false, false),
             block_number,
+            FinalityPolicy::default_depth(ETHEREUM_CHAIN_ID),
         )
-        .await;
+        .await
+        .expect("Failed to fetch linking blocks");
         if linking_blocks.is_empty() {
             // No linking blocks needed when reorg protection is zero
             return;
@@ -434,11 +549,12 @@ mod tests {
     async fn test_validate_chain_length_panics_if_chain_too_short() {
         let block_number = 21193475;
         let linking_blocks = get_linking_blocks(
-            ETHEREUM_CHAIN_ID,
-            get_rpc_url("ETHEREUM", false, false),
+            &rpc_url("ETHEREUM", false, false),
             block_number,
+            FinalityPolicy::default_depth(ETHEREUM_CHAIN_ID),
         )
-        .await;
+        .await
+        .expect("Failed to fetch linking blocks");
         if linking_blocks.is_empty() {
             // No linking blocks needed when reorg protection is zero
             return;
@@ -472,11 +588,12 @@ mod tests {
     async fn test_validate_chain_length_panics_if_hash_doesnt_match() {
         let block_number = 21193475;
         let linking_blocks = get_linking_blocks(
-            ETHEREUM_CHAIN_ID,
-            get_rpc_url("ETHEREUM", false, false),
+            &rpc_url("ETHEREUM", false, false),
             block_number,
+            FinalityPolicy::default_depth(ETHEREUM_CHAIN_ID),
         )
-        .await;
+        .await
+        .expect("Failed to fetch linking blocks");
         if linking_blocks.is_empty() {
             // No linking blocks needed when reorg protection is zero
             return;
@@ -513,15 +630,11 @@ mod tests {
     }
 
     /// @custom:property ZK09 (PoC)
-    /// @dev Verifies that the top-level input preparation function,
-    ///      `get_proof_data_zkvm_input`, panics when attempting to perform
-    ///      L1 inclusion for a transaction originating from Ethereum mainnet.
-    ///      This confirms the logic bug makes the self-sequencing feature
-    ///      unusable for Ethereum.
+    /// @dev Verifies that a proof data request asking for L1 inclusion for a
+    ///      transaction originating from Ethereum mainnet is rejected when it
+    ///      is built, before any input is prepared. Self-sequencing is not
+    ///      supported for Ethereum.
     #[tokio::test]
-    #[should_panic(
-        expected = "L1 Inclusion only supported for Optimism, Base, Linea and their Sepolia variants"
-    )]
     async fn test_ethereum_l1_inclusion() {
         let chain_id = ETHEREUM_CHAIN_ID;
         let l1_inclusion = true;
@@ -530,26 +643,27 @@ mod tests {
         let target_chain_ids = vec![LINEA_CHAIN_ID];
         let fallback = false;
 
-        get_proof_data_zkvm_input(
-            users,
-            markets,
-            target_chain_ids,
+        let err = proof_data_request(
             chain_id,
+            &users,
+            &markets,
+            &target_chain_ids,
             l1_inclusion,
             fallback,
         )
-        .await;
+        .unwrap_err();
+        assert!(err.to_string().contains(
+            "L1 Inclusion only supported for Optimism, Base, Linea and their \
+             Sepolia variants"
+        ));
     }
 
     /// @custom:property ZK10 (PoC for Incorrect ChainSpec Bug)
-    /// @dev Verifies that the ZK Coprocessor panics due to an incorrect
+    /// @dev Verifies that the ZK Coprocessor fails due to an incorrect
     ///      chain specification when preparing L1 inclusion proofs. This test
     ///      triggers the code path where an Optimism block is validated
     ///      against the Ethereum mainnet chain spec, which is incorrect.
     #[tokio::test]
-    #[should_panic(
-        expected = "Failed to build EVM environment: computed block hash does not match the hash returned by the API"
-    )]
     async fn test_incorrect_chain_spec() {
         let chain_id = OPTIMISM_CHAIN_ID;
         let l1_inclusion = true;
@@ -559,14 +673,373 @@ mod tests {
         let target_chain_ids = vec![LINEA_CHAIN_ID];
         let fallback = false;
 
-        get_proof_data_zkvm_input(
-            users,
-            markets,
-            target_chain_ids,
+        let request = proof_data_request(
             chain_id,
+            &users,
+            &markets,
+            &target_chain_ids,
             l1_inclusion,
             fallback,
         )
+        .expect("Failed to build request");
+        let err = async {
+            let anchors =
+                get_l1_anchors(&data_source(), &commitments(), &request)
+                    .await?;
+            get_proof_data_zkvm_input(
+                &data_source(),
+                &commitments(),
+                &anchors,
+                &request.chains()[0],
+                request.inclusion(),
+                request.rpc(),
+            )
+            .await
+        }
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains(
+            "Failed to build EVM environment: computed block hash does not \
+             match the hash returned by the API"
+        ));
+    }
+
+    /// Collects up to `limit` accounts from the address topics of the
+    /// markets' recent logs, so that not every compared amount is zero.
+    async fn recent_accounts(
+        url: &str,
+        markets: &[Address],
+        block: u64,
+        limit: usize,
+    ) -> Vec<Address> {
+        let provider =
+            ProviderBuilder::new().connect_http(url.parse().unwrap());
+        let filter = Filter::new()
+            .address(markets.to_vec())
+            .from_block(block.saturating_sub(5_000))
+            .to_block(block);
+        let logs = provider
+            .get_logs(&filter)
+            .await
+            .expect("Failed to fetch logs");
+
+        let mut accounts = vec![USER];
+        for topic in logs.iter().flat_map(|log| log.topics().iter().skip(1)) {
+            // Address topics are left-padded with zeros
+            if topic[..12] != [0u8; 12] || topic.is_zero() {
+                continue;
+            }
+            let account = Address::from_word(*topic);
+            if accounts.len() < limit && !accounts.contains(&account) {
+                accounts.push(account);
+            }
+        }
+        accounts
+    }
+
+    /// Proves the same queries at the same block with the multicall and with
+    /// storage proofs and checks that the outputs are identical.
+    async fn assert_storage_proofs_match_multicall(
+        chain_id: u64,
+        chain_name: &str,
+        target_chain_ids: &[u64],
+    ) {
+        let url = rpc_url(chain_name, false, false);
+        let latest_block = ProviderBuilder::new()
+            .connect_http(url.parse().unwrap())
+            .get_block_number()
+            .await
+            .expect("Failed to fetch block number");
+        let depth = FinalityPolicy::default_depth(chain_id);
+        let view_call_block = latest_block - depth;
+
+        let markets = [MUSDC, MWETH];
+        let accounts =
+            recent_accounts(&url, &markets, view_call_block, 8).await;
+        let mut users = Vec::new();
+        let mut query_markets = Vec::new();
+        let mut query_target_chain_ids = Vec::new();
+        for account in &accounts {
+            for market in markets {
+                for target_chain_id in target_chain_ids {
+                    users.push(*account);
+                    query_markets.push(market);
+                    query_target_chain_ids.push(*target_chain_id);
+                }
+            }
+        }
+
+        let chain_spec: &EthChainSpec = if chain_id == LINEA_CHAIN_ID {
+            &LINEA_MAINNET_CHAIN_SPEC
+        } else {
+            &ETH_MAINNET_CHAIN_SPEC
+        };
+        let env = get_proof_data_call_input(
+            &data_source(),
+            chain_id,
+            &url,
+            latest_block,
+            depth,
+            users.clone(),
+            query_markets.clone(),
+            query_target_chain_ids.clone(),
+            false,
+            false,
+        )
+        .await
+        .expect("Failed to build proof data call input")
+        .0
+        .expect("env input is None")
+        .into_env(chain_spec);
+        assert_eq!(env.header().inner().inner().number, view_call_block);
+        let mut multicall_output = Vec::new();
+        batch_call_get_proof_data(
+            chain_id,
+            users.clone(),
+            query_markets.clone(),
+            query_target_chain_ids.clone(),
+            &env,
+            false,
+            &mut multicall_output,
+        );
+
+        let storage_proof_input = get_storage_proof_input(
+            &url,
+            chain_id,
+            view_call_block,
+            &users,
+            &query_markets,
+            &query_target_chain_ids,
+        )
+        .await
+        .expect("Failed to build storage proof input");
+        let amounts = prove_amounts(
+            chain_id,
+            env.header().inner().inner().state_root,
+            &users,
+            &query_markets,
+            &query_target_chain_ids,
+            &storage_proof_input,
+        );
+        let mut storage_output = Vec::new();
+        for (i, amounts) in amounts.into_iter().enumerate() {
+            push_proof_data(
+                chain_id,
+                users[i],
+                query_markets[i],
+                query_target_chain_ids[i],
+                amounts,
+                false,
+                &mut storage_output,
+            );
+        }
+
+        assert_eq!(storage_output, multicall_output);
+    }
+
+    /// Differential test of the storage proof fast path on the host chain,
+    /// whose amounts are kept per target chain.
+    #[tokio::test]
+    async fn test_storage_proofs_match_multicall_on_host() {
+        assert_storage_proofs_match_multicall(
+            LINEA_CHAIN_ID,
+            "LINEA",
+            &[ETHEREUM_CHAIN_ID, BASE_CHAIN_ID],
+        )
         .await;
     }
+
+    /// Differential test of the storage proof fast path on a gateway chain.
+    #[tokio::test]
+    async fn test_storage_proofs_match_multicall_on_gateway() {
+        assert_storage_proofs_match_multicall(
+            BASE_CHAIN_ID,
+            "BASE",
+            &[LINEA_CHAIN_ID],
+        )
+        .await;
+    }
+
+    /// Builds a storage proof input from the fixtures in
+    /// `tests/fixtures/storage_proofs`, served by `ReplayDataSource` without
+    /// network access, and proves the amounts from it.
+    ///
+    /// The fixture is an `eth_getProof` response in the format written by
+    /// `RecordingDataSource`, for a synthetic Base state: market `0x22..22`
+    /// holds an amount in of 111 and an amount out of 222 for user
+    /// `0x11..11` at block 30000000, under state root `STATE_ROOT`.
+    #[tokio::test]
+    async fn test_storage_proof_input_replays_fixtures() {
+        const USER: Address = Address::repeat_byte(0x11);
+        const MARKET: Address = Address::repeat_byte(0x22);
+        const STATE_ROOT: B256 = b256!(
+            "54659c58568504eb486850b347999350a213fc341d97d180d5987c716459671c"
+        );
+
+        let fixture_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/storage_proofs");
+        let source = ReplayDataSource::start(fixture_dir)
+            .await
+            .expect("Failed to start replay data source");
+        let url = source
+            .rpc_url("BASE", false, false)
+            .expect("Failed to get replay URL");
+
+        let input = get_storage_proof_input(
+            &url,
+            BASE_CHAIN_ID,
+            30_000_000,
+            &[USER],
+            &[MARKET],
+            &[LINEA_CHAIN_ID],
+        )
+        .await
+        .expect("Failed to replay storage proof input");
+        assert_eq!(input.accounts.len(), 1);
+        assert_eq!(input.accounts[0].address, MARKET);
+
+        let amounts = prove_amounts(
+            BASE_CHAIN_ID,
+            STATE_ROOT,
+            &[USER],
+            &[MARKET],
+            &[LINEA_CHAIN_ID],
+            &input,
+        );
+        assert_eq!(amounts, vec![(U256::from(111), U256::from(222))]);
+
+        // Requests without a fixture fail instead of reaching the network
+        assert!(
+            get_storage_proof_input(
+                &url,
+                BASE_CHAIN_ID,
+                30_000_001,
+                &[USER],
+                &[MARKET],
+                &[LINEA_CHAIN_ID],
+            )
+            .await
+            .is_err()
+        );
+    }
+
+    /// Rebuilds the inputs in `tests/fixtures/inputs` from their recorded
+    /// RPC, sequencer and beacon API responses, served by `ReplayDataSource`
+    /// without network access, and checks that they match the recorded
+    /// inputs byte for byte.
+    ///
+    /// Each directory holds the responses of one request of
+    /// `tests/fixtures/requests` and the `input.json` snapshot built from
+    /// them. The replay configuration only marks mainnet as configured, the
+    /// URLs are never contacted.
+    ///
+    /// ```bash
+    /// just record-fixtures
+    /// cargo test -p malda_rs --test tests -- --ignored replays_recorded
+    /// ```
+    #[tokio::test]
+    #[ignore = "needs `just record-fixtures`"]
+    async fn test_proof_data_input_replays_recorded_fixtures() {
+        let fixtures =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/inputs");
+        let config = MaldaConfig::from_toml_str(
+            "[mainnet.ethereum]\nrpc_url = \"http://replay.invalid\"\n",
+        )
+        .expect("Failed to parse replay config");
+
+        let mut replayed = 0;
+        for entry in std::fs::read_dir(&fixtures)
+            .expect("Failed to read the recorded fixtures")
+        {
+            let dir = entry.expect("Failed to read fixture dir").path();
+            let snapshot = InputSnapshot::load(dir.join("input.json"))
+                .expect("Failed to load recorded input");
+            let request = snapshot
+                .request()
+                .expect("Failed to parse recorded request");
+
+            let data_source: Arc<dyn ChainDataSource> = Arc::new(
+                ReplayDataSource::start(&dir)
+                    .await
+                    .expect("Failed to start replay data source"),
+            );
+            let commitments =
+                Arc::new(HttpCommitmentSource::new(data_source.clone(), None));
+            let input = get_proof_data_input(
+                &config,
+                data_source,
+                commitments,
+                &request,
+            )
+            .await
+            .unwrap_or_else(|e| {
+                panic!("Failed to replay {}: {e}", dir.display())
+            });
+            assert_eq!(
+                input,
+                snapshot.input.to_vec(),
+                "Replayed input of {} differs from the recording",
+                dir.display()
+            );
+            replayed += 1;
+        }
+        assert!(
+            replayed > 0,
+            "No recorded fixtures in {}",
+            fixtures.display()
+        );
+    }
+
+    /// Executes the recorded inputs in `benches/inputs` with the profiling
+    /// guest and checks their cycles per chain and stage against
+    /// `benches/baseline.json`, so that a change that decodes an environment
+    /// or hashes a header more than once fails.
+    ///
+    /// Needs the profiling ELF and the recorded inputs and baseline:
+    ///
+    /// ```bash
+    /// just build-guest-profile
+    /// just bench-record
+    /// cargo test -p malda_rs --test tests -- --ignored guest_cycles
+    /// ```
+    #[test]
+    #[ignore = "needs `just build-guest-profile` and `just bench-record`"]
+    fn test_guest_cycles_within_baseline() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("../..");
+        let program = GuestProgram::load(
+            "get-proof-data-profile",
+            root.join("crates/malda_rs/bin/get-proof-data-mainnet-profile.bin"),
+        )
+        .expect("Failed to load the profiling guest");
+        let baseline = Baseline::load(root.join("benches/baseline.json"))
+            .expect("Failed to load the baseline");
+
+        let mut profiles = BTreeMap::new();
+        for entry in std::fs::read_dir(root.join("benches/inputs"))
+            .expect("Failed to read the benchmark inputs")
+        {
+            let path = entry.expect("Failed to read input entry").path();
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            let snapshot =
+                InputSnapshot::load(&path).expect("Failed to load input");
+            let profile = profile_input(&program, &snapshot.input)
+                .expect("Failed to profile input");
+            let name = path.file_stem().unwrap().to_string_lossy();
+            profiles.insert(name.into_owned(), profile);
+        }
+        assert!(!profiles.is_empty(), "no benchmark inputs recorded");
+
+        let regressions = baseline.regressions(&profiles, 2.0);
+        assert!(
+            regressions.is_empty(),
+            "cycle regressions:\n{}",
+            regressions
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join("\n")
+        );
+    }
 }
@@ -19,10 +19,27 @@
 
 use alloy_primitives::{Address, B256, U256, address};
 
-pub const MULTICALL: Address = address!("cA11bde05977b3631167028862bE2a173976CA11");
+pub const MULTICALL: Address =
+    address!("cA11bde05977b3631167028862bE2a173976CA11");
 /// Selector for getProofData(address,uint32)
 pub const SELECTOR_MALDA_GET_PROOF_DATA: [u8; 4] = [0x07, 0xd9, 0x23, 0xe9];
 
+// Storage slots read by `getProofData`, see `storage`. They follow
+// `forge inspect <contract> storageLayout` of the deployed implementations
+// and are checked against the compiled contracts by
+// `contracts/test/crosschain/StorageLayoutTest.t.sol`.
+/// Slot of `mTokenGateway.accAmountIn`, `mapping(address => uint256)`.
+///
+/// `OwnableUpgradeable` keeps its state in ERC-7201 namespaced storage, so
+/// the gateway's own variables start at slot 0.
+pub const GATEWAY_ACC_AMOUNT_IN_SLOT: U256 = U256::from_limbs([5, 0, 0, 0]);
+/// Slot of `mTokenGateway.accAmountOut`, `mapping(address => uint256)`.
+pub const GATEWAY_ACC_AMOUNT_OUT_SLOT: U256 = U256::from_limbs([6, 0, 0, 0]);
+/// Slot of `mErc20Host.acc`, `mapping(uint32 => Accumulated)`, where
+/// `Accumulated` holds the `inPerChain` and `outPerChain` mappings in its
+/// first and second slot.
+pub const HOST_ACC_SLOT: U256 = U256::from_limbs([22, 0, 0, 0]);
+
 /// Chain ID for the Ethereum Mainnet network.
 pub const ETHEREUM_CHAIN_ID: u64 = 1;
 /// Chain ID for the Optimism network.
@@ -42,23 +59,29 @@ pub const LINEA_SEPOLIA_CHAIN_ID: u64 = 59141;
 pub const BASE_SEPOLIA_CHAIN_ID: u64 = 84532;
 
 /// The address of the Optimism sequencer contract.
-pub const OPTIMISM_SEQUENCER: Address = address!("AAAA45d9549EDA09E70937013520214382Ffc4A2");
+pub const OPTIMISM_SEQUENCER: Address =
+    address!("AAAA45d9549EDA09E70937013520214382Ffc4A2");
 /// The address of the Base sequencer contract.
-pub const BASE_SEQUENCER: Address = address!("Af6E19BE0F9cE7f8afd49a1824851023A8249e8a");
+pub const BASE_SEQUENCER: Address =
+    address!("Af6E19BE0F9cE7f8afd49a1824851023A8249e8a");
 /// The address of the Linea sequencer contract.
-pub const LINEA_SEQUENCER: Address = address!("8f81e2e3f8b46467523463835f965ffe476e1c9e");
+pub const LINEA_SEQUENCER: Address =
+    address!("8f81e2e3f8b46467523463835f965ffe476e1c9e");
 
 /// The address of the Optimism sequencer contract on the sepolia network.
 pub const OPTIMISM_SEPOLIA_SEQUENCER: Address =
     address!("57CACBB0d30b01eb2462e5dC940c161aff3230D3");
 /// The address of the Base sequencer contract on the sepolia network.
-pub const BASE_SEPOLIA_SEQUENCER: Address = address!("b830b99c95Ea32300039624Cb567d324D4b1D83C");
+pub const BASE_SEPOLIA_SEQUENCER: Address =
+    address!("b830b99c95Ea32300039624Cb567d324D4b1D83C");
 /// The address of the Linea sequencer contract on the sepolia network.
-pub const LINEA_SEPOLIA_SEQUENCER: Address = address!("a27342f1b74c0cfb2cda74bac1628d0c1a9752f2");
+pub const LINEA_SEPOLIA_SEQUENCER: Address =
+    address!("a27342f1b74c0cfb2cda74bac1628d0c1a9752f2");
 
 /// The address of the L1Block contract on Optimism.
 /// This contract provides L1 block information to L2.
-pub const L1_BLOCK_ADDRESS_OPSTACK: Address = address!("4200000000000000000000000000000000000015");
+pub const L1_BLOCK_ADDRESS_OPSTACK: Address =
+    address!("4200000000000000000000000000000000000015");
 /// The address of the MessagePasser contract on Optimism.
 pub const MESSAGE_PASSER_ADDRESS_OPSTACK: Address =
     address!("4200000000000000000000000000000000000016");
@@ -69,11 +92,13 @@ pub const DISPUTE_GAME_FACTORY_OPTIMISM: Address =
     address!("e5965Ab5962eDc7477C8520243A95517CD252fA9");
 pub const DISPUTE_GAME_FACTORY_OPTIMISM_SEPOLIA: Address =
     address!("05F9613aDB30026FFd634f38e5C4dFd30a197Fa1");
-pub const DISPUTE_GAME_FACTORY_BASE: Address = address!("43edB88C4B80fDD2AdFF2412A7BebF9dF42cB40e");
+pub const DISPUTE_GAME_FACTORY_BASE: Address =
+    address!("43edB88C4B80fDD2AdFF2412A7BebF9dF42cB40e");
 pub const DISPUTE_GAME_FACTORY_BASE_SEPOLIA: Address =
     address!("d6E6dBf4F7EA0ac412fD8b65ED297e64BB7a06E1");
 
-pub const L1_MESSAGE_SERVICE_LINEA: Address = address!("d19d4B5d358258f05D7B411E21A1460D11B0876F");
+pub const L1_MESSAGE_SERVICE_LINEA: Address =
+    address!("d19d4B5d358258f05D7B411E21A1460D11B0876F");
 pub const L1_MESSAGE_SERVICE_LINEA_SEPOLIA: Address =
     address!("B218f8A4Bc926cF1cA7b3423c154a0D627Bdb7E5");
 
@@ -82,8 +107,9 @@ pub const L1_MESSAGE_SERVICE_LINEA_SEPOLIA: Address =
 /// This value is used in signature normalization to ensure s values are in the lower half
 /// of the curve order, which is required by some networks (like Ethereum) as a transaction validity rule.
 pub const SECP256K1N_HALF: U256 = U256::from_be_bytes([
-    0x7F, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
-    0x5D, 0x57, 0x6E, 0x73, 0x57, 0xA4, 0x50, 0x1D, 0xDF, 0xE9, 0x2F, 0x46, 0x68, 0x1B, 0x20, 0xA0,
+    0x7F, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+    0xFF, 0xFF, 0xFF, 0xFF, 0x5D, 0x57, 0x6E, 0x73, 0x57, 0xA4, 0x50, 0x1D,
+    0xDF, 0xE9, 0x2F, 0x46, 0x68, 0x1B, 0x20, 0xA0,
 ]);
 
 /// The number of blocks to wait before considering a chain reorganization unlikely.
@@ -99,7 +125,31 @@ pub const REORG_PROTECTION_DEPTH_BASE_SEPOLIA: u64 = 2;
 pub const REORG_PROTECTION_DEPTH_LINEA_SEPOLIA: u64 = 2;
 pub const REORG_PROTECTION_DEPTH_ETHEREUM_SEPOLIA: u64 = 2;
 
-pub const OPTIMISM_PORTAL: Address = address!("bEb5Fc579115071764c7423A4f12eDde41f106Ed");
-pub const OPTIMISM_SEPOLIA_PORTAL: Address = address!("16Fc5058F25648194471939df75CF27A2fdC48BC");
-pub const BASE_PORTAL: Address = address!("49048044D57e1C92A77f79988d21Fa8fAF74E97e");
-pub const BASE_SEPOLIA_PORTAL: Address = address!("49f53e41452C74589E85cA1677426Ba426459e85");
+/// Image ID of the mainnet get_proof_data guest.
+///
+/// The aggregate_proof_data guest built for a profile only accepts receipts
+/// of that profile's get_proof_data image, and the host checks the mainnet ID
+/// against the embedded ELF. Set the ID of a profile to the one `just
+/// build-guest <profile>` reports whenever the get_proof_data guest changes;
+/// this changes the aggregator's image ID as well.
+pub const GET_PROOF_DATA_ID_MAINNET: [u32; 8] = [
+    3475856131, 3764316085, 934553983, 3605193442, 3903902696, 1790775684,
+    1850505827, 419740983,
+];
+/// Image ID of the Sepolia get_proof_data guest.
+///
+/// Not recorded yet. No image has an all-zero ID, so the Sepolia aggregator
+/// rejects every receipt until it is set.
+pub const GET_PROOF_DATA_ID_SEPOLIA: [u32; 8] = [0; 8];
+/// Image ID of the devnet get_proof_data guest, see
+/// `GET_PROOF_DATA_ID_SEPOLIA`.
+pub const GET_PROOF_DATA_ID_DEVNET: [u32; 8] = [0; 8];
+
+pub const OPTIMISM_PORTAL: Address =
+    address!("bEb5Fc579115071764c7423A4f12eDde41f106Ed");
+pub const OPTIMISM_SEPOLIA_PORTAL: Address =
+    address!("16Fc5058F25648194471939df75CF27A2fdC48BC");
+pub const BASE_PORTAL: Address =
+    address!("49048044D57e1C92A77f79988d21Fa8fAF74E97e");
+pub const BASE_SEPOLIA_PORTAL: Address =
+    address!("49f53e41452C74589E85cA1677426Ba426459e85");
@@ -28,5 +28,11 @@ pub mod types;
 /// Validation and verification utilities
 pub mod validators;
 
+/// Receipt and event log proofs
+pub mod receipts;
+
+/// Storage proofs of proof data amounts
+pub mod storage;
+
 /// Cryptographic operations and primitives
 pub mod cryptography;
@@ -0,0 +1,559 @@
+// Copyright (c) 2025 Merge Layers Inc.
+//
+// This source code is licensed under the Business Source License 1.1
+// (the "License"); you may not use this file except in compliance with the
+// License. You may obtain a copy of the License at
+//
+//     https://github.com/malda-protocol/malda-zk-coprocessor/blob/main/LICENSE-BSL
+//
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+//
+//! Receipt and event log proofs.
+//!
+//! View calls can only prove state. This module proves that a transaction
+//! emitted an `ImTokenGateway` or `ImErc20Host` event instead: a
+//! `ReceiptProof` is a Merkle-Patricia proof of an EIP-2718 encoded receipt
+//! in the receipts trie of a block, keyed by the RLP-encoded transaction
+//! index.
+//!
+//! The blocks holding proven receipts must be verified by the chain's usual
+//! validation. `validate_event_input` accepts the block of the view call
+//! environment and its ancestors, which the `EventInput` links to it by
+//! parent hash, and commits one `ProvenEvent` per selected log. Anyone can
+//! deploy a contract emitting these events, so only logs emitted by a market
+//! queried on the chain are accepted.
+//!
+//! `build_receipt_proofs` builds the proofs on the host from all receipts of
+//! a block, e.g. as returned by `debug_getRawReceipts`.
+
+use alloy_consensus::Header;
+use alloy_primitives::{Address, B256, Bloom, Bytes, Log};
+use alloy_rlp::Decodable;
+use alloy_sol_types::{SolEventInterface, SolValue, sol};
+use alloy_trie::{
+    HashBuilder, Nibbles,
+    proof::{ProofRetainer, verify_proof},
+};
+use risc0_steel::serde::RlpHeader;
+use serde::{Deserialize, Serialize};
+
+sol! {
+    /// Events of `mTokenGateway` that can be proven.
+    interface ImTokenGateway {
+        event mTokenGateway_Supplied(
+            address indexed from,
+            address indexed receiver,
+            uint256 accAmountIn,
+            uint256 accAmountOut,
+            uint256 amount,
+            uint32 srcChainId,
+            uint32 dstChainId,
+            bytes4 lineaMethodSelector
+        );
+        event mTokenGateway_Extracted(
+            address indexed msgSender,
+            address indexed srcSender,
+            address indexed receiver,
+            uint256 accAmountIn,
+            uint256 accAmountOut,
+            uint256 amount,
+            uint32 srcChainId,
+            uint32 dstChainId
+        );
+        event mTokenGateway_Skipped(
+            address indexed msgSender,
+            address indexed srcSender,
+            address indexed receiver,
+            uint256 accAmountIn,
+            uint256 accAmountOut,
+            uint256 amount,
+            uint32 srcChainId,
+            uint32 dstChainId
+        );
+    }
+
+    /// Events of `mErc20Host` that can be proven.
+    interface ImErc20Host {
+        event mErc20Host_LiquidateExternal(
+            address indexed msgSender,
+            address indexed srcSender,
+            address userToLiquidate,
+            address receiver,
+            address indexed collateral,
+            uint32 srcChainId,
+            uint256 amount
+        );
+        event mErc20Host_MintExternal(
+            address indexed msgSender,
+            address indexed srcSender,
+            address indexed receiver,
+            uint32 chainId,
+            uint256 amount
+        );
+        event mErc20Host_BorrowExternal(
+            address indexed msgSender,
+            address indexed srcSender,
+            uint32 indexed chainId,
+            uint256 amount
+        );
+        event mErc20Host_RepayExternal(
+            address indexed msgSender,
+            address indexed srcSender,
+            address indexed position,
+            uint32 chainId,
+            uint256 amount
+        );
+        event mErc20Host_WithdrawExternal(
+            address indexed msgSender,
+            address indexed srcSender,
+            uint32 indexed chainId,
+            uint256 amount
+        );
+        event mErc20Host_BorrowOnExtensionChain(
+            address indexed sender,
+            uint32 dstChainId,
+            uint256 amount
+        );
+        event mErc20Host_WithdrawOnExtensionChain(
+            address indexed sender,
+            uint32 dstChainId,
+            uint256 amount
+        );
+    }
+
+    /// A log proven to be emitted in a verified block by a queried market,
+    /// committed to the journal ABI-encoded.
+    #[derive(Debug, PartialEq, Eq)]
+    struct ProvenEvent {
+        uint32 chainId;
+        uint64 blockNumber;
+        bytes32 blockHash;
+        uint32 txIndex;
+        uint32 logIndex;
+        address emitter;
+        bytes32[] topics;
+        bytes data;
+    }
+}
+
+/// Inclusion proof of one receipt in the receipts trie of a block.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReceiptProof {
+    /// Index of the transaction in the block.
+    pub tx_index: u64,
+    /// The EIP-2718 encoded receipt.
+    pub receipt: Bytes,
+    /// Trie nodes from the root to the receipt's leaf.
+    pub proof: Vec<Bytes>,
+    /// Indices of the proven logs within the receipt, ascending.
+    pub log_indices: Vec<u64>,
+}
+
+/// Receipt proofs of one block.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockReceiptProofs {
+    pub block_number: u64,
+    /// Proofs ordered by ascending transaction index.
+    pub receipts: Vec<ReceiptProof>,
+}
+
+/// Event proofs of one chain.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventInput {
+    /// Headers from the oldest block holding a proven receipt up to the
+    /// parent of the view call block, oldest first. Empty if every receipt
+    /// is in the view call block.
+    pub ancestors: Vec<RlpHeader<Header>>,
+    /// Proofs ordered by ascending block number.
+    pub blocks: Vec<BlockReceiptProofs>,
+}
+
+/// Returns whether `log` decodes as one of the events that can be proven.
+pub fn is_supported_event(log: &Log) -> bool {
+    let (topics, data) = (log.topics(), &log.data.data);
+    ImTokenGateway::ImTokenGatewayEvents::decode_raw_log(topics, data).is_ok()
+        || ImErc20Host::ImErc20HostEvents::decode_raw_log(topics, data).is_ok()
+}
+
+/// Decodes the logs of an EIP-2718 encoded receipt.
+///
+/// Fields after the logs, e.g. the deposit nonce of OpStack deposit
+/// receipts, are ignored.
+///
+/// # Errors
+/// Returns an error if the receipt is not RLP-encoded as
+/// `[status, cumulative_gas_used, logs_bloom, logs, ...]`.
+pub fn decode_receipt_logs(receipt: &[u8]) -> alloy_rlp::Result<Vec<Log>> {
+    // Typed receipts are prefixed with their type, legacy receipts start
+    // with the RLP list header.
+    let mut buf = match receipt.first() {
+        Some(&ty) if ty < 0x7f => &receipt[1..],
+        _ => receipt,
+    };
+    let header = alloy_rlp::Header::decode(&mut buf)?;
+    if !header.list {
+        return Err(alloy_rlp::Error::UnexpectedString);
+    }
+    let mut fields = buf
+        .get(..header.payload_length)
+        .ok_or(alloy_rlp::Error::InputTooShort)?;
+    // The status is a state root before Byzantium
+    let _status = Bytes::decode(&mut fields)?;
+    let _cumulative_gas_used = u64::decode(&mut fields)?;
+    let _logs_bloom = Bloom::decode(&mut fields)?;
+    Vec::<Log>::decode(&mut fields)
+}
+
+/// Verifies a receipt proof against a receipts root and returns the logs of
+/// the receipt.
+///
+/// # Panics
+/// Panics if the proof does not prove the receipt at `tx_index` or the
+/// receipt cannot be decoded.
+pub fn verify_receipt_proof(
+    receipts_root: B256,
+    proof: &ReceiptProof,
+) -> Vec<Log> {
+    verify_proof(
+        receipts_root,
+        receipt_key(proof.tx_index),
+        Some(proof.receipt.to_vec()),
+        &proof.proof,
+    )
+    .expect("invalid receipt proof");
+    decode_receipt_logs(&proof.receipt).expect("invalid receipt encoding")
+}
+
+/// Builds the receipts root of a block and the proofs of some of its
+/// receipts.
+///
+/// # Arguments
+/// * `receipts` - All EIP-2718 encoded receipts of the block, in transaction
+///   order.
+/// * `tx_indices` - Indices of the receipts to prove.
+///
+/// # Returns
+/// The receipts root, and the proof of each receipt in `tx_indices`, in the
+/// same order.
+///
+/// # Panics
+/// Panics if an index is out of range.
+pub fn build_receipt_proofs(
+    receipts: &[Bytes],
+    tx_indices: &[u64],
+) -> (B256, Vec<Vec<Bytes>>) {
+    let targets: Vec<Nibbles> =
+        tx_indices.iter().map(|index| receipt_key(*index)).collect();
+    for index in tx_indices {
+        assert!(
+            (*index as usize) < receipts.len(),
+            "receipt index {index} out of range"
+        );
+    }
+
+    // Leaves must be added in key order, which is not the index order as
+    // the key of index 0 is 0x80.
+    let mut leaves: Vec<(Nibbles, &Bytes)> = receipts
+        .iter()
+        .enumerate()
+        .map(|(index, receipt)| (receipt_key(index as u64), receipt))
+        .collect();
+    leaves.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    let mut builder = HashBuilder::default()
+        .with_proof_retainer(ProofRetainer::new(targets.clone()));
+    for (key, receipt) in leaves {
+        builder.add_leaf(key, receipt);
+    }
+    let root = builder.root();
+    let nodes = builder.take_proof_nodes();
+    let proofs = targets
+        .iter()
+        .map(|target| {
+            nodes
+                .matching_nodes_sorted(target)
+                .into_iter()
+                .map(|(_, node)| node)
+                .collect()
+        })
+        .collect();
+    (root, proofs)
+}
+
+/// Validates the event proofs of a chain and commits the selected logs.
+///
+/// # Arguments
+/// * `chain_id` - The chain the events were emitted on.
+/// * `header` - The verified header of the view call environment.
+/// * `header_hash` - The hash of `header`.
+/// * `input` - The ancestors and receipt proofs.
+/// * `markets` - The markets queried on the chain, the only accepted
+///   emitters.
+/// * `events` - Output vector for the ABI-encoded `ProvenEvent`s.
+///
+/// # Panics
+/// Panics if:
+/// * The ancestors are not hash-linked to `header`.
+/// * A proof refers to a block that is neither `header` nor an ancestor.
+/// * Blocks, receipts or logs are not strictly ascending, so no log can be
+///   committed twice.
+/// * A receipt proof is invalid or a log index is out of range.
+/// * A selected log is not a supported event or was not emitted by one of
+///   `markets`.
+pub fn validate_event_input(
+    chain_id: u64,
+    header: &Header,
+    header_hash: B256,
+    input: &EventInput,
+    markets: &[Address],
+    events: &mut Vec<Bytes>,
+) {
+    // Verify the ancestors from the newest one backwards.
+    let mut verified = vec![(header.number, header_hash, header.receipts_root)];
+    let mut parent_hash = header.parent_hash;
+    let mut parent_number = header.number;
+    for ancestor in input.ancestors.iter().rev() {
+        let hash = ancestor.hash_slow();
+        assert_eq!(hash, parent_hash, "ancestors not hashlinked");
+        parent_number = parent_number.checked_sub(1).expect("invalid ancestor");
+        assert_eq!(ancestor.number, parent_number, "invalid ancestor number");
+        verified.push((ancestor.number, hash, ancestor.receipts_root));
+        parent_hash = ancestor.parent_hash;
+    }
+
+    let mut previous_block = None;
+    for block in &input.blocks {
+        assert!(
+            previous_block < Some(block.block_number),
+            "receipt proof blocks not ascending"
+        );
+        previous_block = Some(block.block_number);
+        let (block_number, block_hash, receipts_root) = *verified
+            .iter()
+            .find(|(number, _, _)| *number == block.block_number)
+            .expect("receipt proof for unverified block");
+
+        let mut previous_tx = None;
+        for receipt in &block.receipts {
+            assert!(
+                previous_tx < Some(receipt.tx_index),
+                "receipt proofs not ascending"
+            );
+            previous_tx = Some(receipt.tx_index);
+            let logs = verify_receipt_proof(receipts_root, receipt);
+
+            assert!(!receipt.log_indices.is_empty(), "no logs selected");
+            let mut previous_log = None;
+            for &log_index in &receipt.log_indices {
+                assert!(
+                    previous_log < Some(log_index),
+                    "log indices not ascending"
+                );
+                previous_log = Some(log_index);
+                let log = logs
+                    .get(log_index as usize)
+                    .expect("log index out of range");
+                assert!(is_supported_event(log), "unsupported event");
+                assert!(
+                    markets.contains(&log.address),
+                    "event not emitted by a queried market"
+                );
+
+                let event = ProvenEvent {
+                    chainId: chain_id as u32,
+                    blockNumber: block_number,
+                    blockHash: block_hash,
+                    txIndex: receipt.tx_index as u32,
+                    logIndex: log_index as u32,
+                    emitter: log.address,
+                    topics: log.topics().to_vec(),
+                    data: log.data.data.clone(),
+                };
+                events.push(event.abi_encode().into());
+            }
+        }
+    }
+}
+
+/// Returns the receipts trie key of a transaction index.
+fn receipt_key(tx_index: u64) -> Nibbles {
+    Nibbles::unpack(alloy_rlp::encode(tx_index))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::{LogData, U256};
+    use alloy_rlp::Encodable;
+    use alloy_sol_types::SolEvent;
+
+    const MARKET: Address = Address::repeat_byte(0x22);
+
+    fn supplied_log(amount: u64) -> Log {
+        let event = ImTokenGateway::mTokenGateway_Supplied {
+            from: Address::repeat_byte(0x11),
+            receiver: Address::repeat_byte(0x11),
+            accAmountIn: U256::from(amount),
+            accAmountOut: U256::ZERO,
+            amount: U256::from(amount),
+            srcChainId: 8453,
+            dstChainId: 59144,
+            lineaMethodSelector: Default::default(),
+        };
+        Log {
+            address: MARKET,
+            data: event.encode_log_data(),
+        }
+    }
+
+    /// Encodes a typed (EIP-1559) receipt with the given logs.
+    fn encode_receipt(logs: &[Log]) -> Bytes {
+        let mut payload = Vec::new();
+        true.encode(&mut payload);
+        21_000u64.encode(&mut payload);
+        Bloom::default().encode(&mut payload);
+        logs.to_vec().encode(&mut payload);
+        let mut receipt = vec![0x02];
+        alloy_rlp::Header {
+            list: true,
+            payload_length: payload.len(),
+        }
+        .encode(&mut receipt);
+        receipt.extend(payload);
+        receipt.into()
+    }
+
+    fn block_with_receipts() -> (Header, Vec<Bytes>) {
+        let other = Log {
+            address: MARKET,
+            data: LogData::new_unchecked(vec![B256::ZERO], Bytes::new()),
+        };
+        // More than 0x80 receipts, so keys of both RLP forms are in the trie
+        let receipts: Vec<Bytes> = (0..130)
+            .map(|i| encode_receipt(&[other.clone(), supplied_log(i)]))
+            .collect();
+        let (receipts_root, _) = build_receipt_proofs(&receipts, &[]);
+        let header = Header {
+            number: 100,
+            receipts_root,
+            ..Default::default()
+        };
+        (header, receipts)
+    }
+
+    fn event_input(receipts: &[Bytes], tx_indices: &[u64]) -> EventInput {
+        let (_, proofs) = build_receipt_proofs(receipts, tx_indices);
+        EventInput {
+            ancestors: Vec::new(),
+            blocks: vec![BlockReceiptProofs {
+                block_number: 100,
+                receipts: tx_indices
+                    .iter()
+                    .zip(proofs)
+                    .map(|(&tx_index, proof)| ReceiptProof {
+                        tx_index,
+                        receipt: receipts[tx_index as usize].clone(),
+                        proof,
+                        log_indices: vec![1],
+                    })
+                    .collect(),
+            }],
+        }
+    }
+
+    #[test]
+    fn test_receipt_proofs_roundtrip() {
+        let (header, receipts) = block_with_receipts();
+        let input = event_input(&receipts, &[0, 5, 127, 129]);
+        let mut events = Vec::new();
+
+        validate_event_input(
+            59144,
+            &header,
+            header.hash_slow(),
+            &input,
+            &[MARKET],
+            &mut events,
+        );
+
+        assert_eq!(events.len(), 4);
+        let event = ProvenEvent::abi_decode(&events[2]).unwrap();
+        assert_eq!(event.blockNumber, 100);
+        assert_eq!(event.txIndex, 127);
+        assert_eq!(event.logIndex, 1);
+        assert_eq!(event.emitter, MARKET);
+        assert_eq!(
+            event.topics[0],
+            ImTokenGateway::mTokenGateway_Supplied::SIGNATURE_HASH
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid receipt proof")]
+    fn test_receipt_proof_rejects_other_receipt() {
+        let (header, receipts) = block_with_receipts();
+        let mut input = event_input(&receipts, &[5]);
+        input.blocks[0].receipts[0].receipt = receipts[6].clone();
+
+        validate_event_input(
+            59144,
+            &header,
+            header.hash_slow(),
+            &input,
+            &[MARKET],
+            &mut Vec::new(),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "unsupported event")]
+    fn test_unsupported_event_is_rejected() {
+        let (header, receipts) = block_with_receipts();
+        let mut input = event_input(&receipts, &[5]);
+        input.blocks[0].receipts[0].log_indices = vec![0];
+
+        validate_event_input(
+            59144,
+            &header,
+            header.hash_slow(),
+            &input,
+            &[MARKET],
+            &mut Vec::new(),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "event not emitted by a queried market")]
+    fn test_event_of_foreign_emitter_is_rejected() {
+        let (header, receipts) = block_with_receipts();
+        let input = event_input(&receipts, &[5]);
+
+        validate_event_input(
+            59144,
+            &header,
+            header.hash_slow(),
+            &input,
+            &[Address::repeat_byte(0x33)],
+            &mut Vec::new(),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "receipt proof for unverified block")]
+    fn test_receipt_proof_requires_verified_block() {
+        let (header, receipts) = block_with_receipts();
+        let mut input = event_input(&receipts, &[5]);
+        input.blocks[0].block_number = 99;
+
+        validate_event_input(
+            59144,
+            &header,
+            header.hash_slow(),
+            &input,
+            &[MARKET],
+            &mut Vec::new(),
+        );
+    }
+}
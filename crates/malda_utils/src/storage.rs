@@ -0,0 +1,436 @@
+// Copyright (c) 2025 Merge Layers Inc.
+//
+// This source code is licensed under the Business Source License 1.1
+// (the "License"); you may not use this file except in compliance with the
+// License. You may obtain a copy of the License at
+//
+//     https://github.com/malda-protocol/malda-zk-coprocessor/blob/main/LICENSE-BSL
+//
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+//
+//! Storage proofs of `getProofData` amounts.
+//!
+//! `getProofData(user, dstChainId)` only returns two mapping entries of the
+//! market, so instead of executing a multicall in revm the guest can verify
+//! `eth_getProof` account and storage proofs of those entries against the
+//! state root of the view call block.
+//!
+//! The slots are derived from the `MarketLayout` of the chain, never from
+//! the input, so a prover cannot substitute another slot: gateways store
+//! `accAmountIn[user]` and `accAmountOut[user]`, the host stores
+//! `acc[dstChainId].inPerChain[user]` and `acc[dstChainId].outPerChain[user]`.
+//! A wrong slot would be absent from the trie and prove a zero amount, so the
+//! slot constants are pinned to the contracts' storage layout.
+
+use crate::constants::*;
+
+use alloy_primitives::{Address, B256, Bytes, U256, keccak256};
+use alloy_trie::{KECCAK_EMPTY, Nibbles, TrieAccount, proof::verify_proof};
+use serde::{Deserialize, Serialize};
+
+/// Storage layout of the markets of a chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarketLayout {
+    /// `mTokenGateway`, deployed on the extension chains.
+    Gateway,
+    /// `mErc20Host`, deployed on the host chain.
+    Host,
+}
+
+impl MarketLayout {
+    /// Returns the layout of the markets deployed on `chain_id`.
+    pub fn for_chain(chain_id: u64) -> Self {
+        match chain_id {
+            LINEA_CHAIN_ID | LINEA_SEPOLIA_CHAIN_ID => MarketLayout::Host,
+            _ => MarketLayout::Gateway,
+        }
+    }
+
+    /// Returns the storage slots of the `(amountIn, amountOut)` pair
+    /// `getProofData(user, target_chain_id)` returns.
+    ///
+    /// # Panics
+    /// Panics if `target_chain_id` does not fit the `uint32` parameter, as
+    /// the call would revert.
+    pub fn amount_slots(
+        self,
+        user: Address,
+        target_chain_id: u64,
+    ) -> (B256, B256) {
+        let target_chain_id = u32::try_from(target_chain_id)
+            .expect("target chain id exceeds uint32");
+        let user = user.into_word();
+        match self {
+            MarketLayout::Gateway => (
+                mapping_slot(user, GATEWAY_ACC_AMOUNT_IN_SLOT),
+                mapping_slot(user, GATEWAY_ACC_AMOUNT_OUT_SLOT),
+            ),
+            MarketLayout::Host => {
+                let target_chain_id = B256::from(U256::from(target_chain_id));
+                // `Accumulated` struct of the target chain
+                let accumulated = U256::from_be_bytes(
+                    mapping_slot(target_chain_id, HOST_ACC_SLOT).0,
+                );
+                (
+                    mapping_slot(user, accumulated),
+                    mapping_slot(user, accumulated + U256::from(1)),
+                )
+            }
+        }
+    }
+}
+
+/// Proof of one storage slot, as returned by `eth_getProof`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StorageSlotProof {
+    pub key: B256,
+    pub value: U256,
+    /// Trie nodes from the storage root to the slot's leaf, or to the node
+    /// proving its absence if `value` is zero.
+    pub proof: Vec<Bytes>,
+}
+
+/// Proof of one account and some of its storage, as returned by
+/// `eth_getProof`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountProof {
+    pub address: Address,
+    pub nonce: u64,
+    pub balance: U256,
+    pub storage_root: B256,
+    pub code_hash: B256,
+    /// Trie nodes from the state root to the account's leaf.
+    pub proof: Vec<Bytes>,
+    pub storage: Vec<StorageSlotProof>,
+}
+
+/// Storage proofs of the markets of one chain.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StorageProofInput {
+    pub accounts: Vec<AccountProof>,
+}
+
+/// Verifies an account proof and its storage proofs.
+///
+/// # Arguments
+/// * `state_root` - The verified state root.
+/// * `account` - The account and storage proofs.
+///
+/// # Panics
+/// Panics if the account or a storage slot is not proven.
+pub fn verify_account_proof(state_root: B256, account: &AccountProof) {
+    let trie_account = TrieAccount {
+        nonce: account.nonce,
+        balance: account.balance,
+        storage_root: account.storage_root,
+        code_hash: account.code_hash,
+    };
+    verify_proof(
+        state_root,
+        Nibbles::unpack(keccak256(account.address)),
+        Some(alloy_rlp::encode(trie_account)),
+        &account.proof,
+    )
+    .expect("invalid account proof");
+
+    for slot in &account.storage {
+        // Zero slots are absent from the trie
+        let value =
+            (!slot.value.is_zero()).then(|| alloy_rlp::encode(slot.value));
+        verify_proof(
+            account.storage_root,
+            Nibbles::unpack(keccak256(slot.key)),
+            value,
+            &slot.proof,
+        )
+        .expect("invalid storage proof");
+    }
+}
+
+/// Proves the amounts `getProofData` returns for each query.
+///
+/// # Arguments
+/// * `chain_id` - The chain the markets are deployed on, selecting the
+///   `MarketLayout`.
+/// * `state_root` - The verified state root of the view call block.
+/// * `account` - Users to query.
+/// * `asset` - Markets to query.
+/// * `target_chain_ids` - Target chain IDs to query.
+/// * `input` - The account and storage proofs of the markets.
+///
+/// # Returns
+/// The `(amountIn, amountOut)` pair of each query, in query order.
+///
+/// # Panics
+/// Panics if:
+/// * A proof is invalid.
+/// * A market is not a contract, or a queried slot is not proven.
+/// * A target chain ID exceeds `uint32`.
+pub fn prove_amounts(
+    chain_id: u64,
+    state_root: B256,
+    account: &[Address],
+    asset: &[Address],
+    target_chain_ids: &[u64],
+    input: &StorageProofInput,
+) -> Vec<(U256, U256)> {
+    for proof in &input.accounts {
+        verify_account_proof(state_root, proof);
+    }
+
+    let layout = MarketLayout::for_chain(chain_id);
+    let slot_value = |market: &AccountProof, key: B256| {
+        market
+            .storage
+            .iter()
+            .find(|slot| slot.key == key)
+            .map(|slot| slot.value)
+            .expect("missing storage proof")
+    };
+    account
+        .iter()
+        .zip(asset)
+        .zip(target_chain_ids)
+        .map(|((user, market), target_chain_id)| {
+            let market = input
+                .accounts
+                .iter()
+                .find(|proof| proof.address == *market)
+                .expect("missing account proof");
+            // A call to an account without code would not return the pair
+            assert_ne!(market.code_hash, KECCAK_EMPTY, "market has no code");
+            let (amount_in, amount_out) =
+                layout.amount_slots(*user, *target_chain_id);
+            (
+                slot_value(market, amount_in),
+                slot_value(market, amount_out),
+            )
+        })
+        .collect()
+}
+
+/// Returns the slot of `key` in a mapping stored at `slot`.
+fn mapping_slot(key: B256, slot: impl Into<B256>) -> B256 {
+    let mut preimage = [0u8; 64];
+    preimage[..32].copy_from_slice(key.as_slice());
+    preimage[32..].copy_from_slice(slot.into().as_slice());
+    keccak256(preimage)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::b256;
+    use alloy_trie::{HashBuilder, proof::ProofRetainer};
+
+    const MARKET: Address = Address::repeat_byte(0x22);
+    const USER: Address = Address::repeat_byte(0x11);
+
+    /// Builds a trie of `leaves`, keyed by hashed keys, and the proofs of
+    /// `targets`.
+    fn build_trie(
+        leaves: &[(B256, Vec<u8>)],
+        targets: &[B256],
+    ) -> (B256, Vec<Vec<Bytes>>) {
+        let targets: Vec<Nibbles> =
+            targets.iter().map(|key| Nibbles::unpack(key)).collect();
+        let mut leaves: Vec<(Nibbles, &Vec<u8>)> = leaves
+            .iter()
+            .map(|(key, value)| (Nibbles::unpack(key), value))
+            .collect();
+        leaves.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        let mut builder = HashBuilder::default()
+            .with_proof_retainer(ProofRetainer::new(targets.clone()));
+        for (key, value) in leaves {
+            builder.add_leaf(key, value);
+        }
+        let root = builder.root();
+        let nodes = builder.take_proof_nodes();
+        let proofs = targets
+            .iter()
+            .map(|target| {
+                nodes
+                    .matching_nodes_sorted(target)
+                    .into_iter()
+                    .map(|(_, node)| node)
+                    .collect()
+            })
+            .collect();
+        (root, proofs)
+    }
+
+    /// Builds a state holding `MARKET` on `chain_id`, with `amount_in` and
+    /// a zero amount out for `USER` towards Linea.
+    fn state(chain_id: u64, amount_in: u64) -> (B256, StorageProofInput) {
+        let (slot_in, slot_out) =
+            MarketLayout::for_chain(chain_id).amount_slots(USER, 59144);
+        let storage = [
+            (keccak256(slot_in), alloy_rlp::encode(U256::from(amount_in))),
+            (keccak256([1u8; 32]), alloy_rlp::encode(U256::from(1))),
+            (keccak256([2u8; 32]), alloy_rlp::encode(U256::from(2))),
+        ];
+        let (storage_root, storage_proofs) =
+            build_trie(&storage, &[keccak256(slot_in), keccak256(slot_out)]);
+
+        let market = TrieAccount {
+            nonce: 1,
+            balance: U256::ZERO,
+            storage_root,
+            code_hash: B256::repeat_byte(0xcc),
+        };
+        let other = TrieAccount {
+            nonce: 7,
+            ..Default::default()
+        };
+        let accounts = [
+            (keccak256(MARKET), alloy_rlp::encode(market)),
+            (
+                keccak256(Address::repeat_byte(0x33)),
+                alloy_rlp::encode(other),
+            ),
+        ];
+        let (state_root, account_proofs) =
+            build_trie(&accounts, &[keccak256(MARKET)]);
+
+        let input = StorageProofInput {
+            accounts: vec![AccountProof {
+                address: MARKET,
+                nonce: market.nonce,
+                balance: market.balance,
+                storage_root,
+                code_hash: market.code_hash,
+                proof: account_proofs[0].clone(),
+                storage: vec![
+                    StorageSlotProof {
+                        key: slot_in,
+                        value: U256::from(amount_in),
+                        proof: storage_proofs[0].clone(),
+                    },
+                    StorageSlotProof {
+                        key: slot_out,
+                        value: U256::ZERO,
+                        proof: storage_proofs[1].clone(),
+                    },
+                ],
+            }],
+        };
+        (state_root, input)
+    }
+
+    #[test]
+    fn test_prove_amounts() {
+        for chain_id in [BASE_CHAIN_ID, LINEA_CHAIN_ID] {
+            let (state_root, input) = state(chain_id, 100);
+
+            let amounts = prove_amounts(
+                chain_id,
+                state_root,
+                &[USER, USER],
+                &[MARKET, MARKET],
+                &[59144, 59144],
+                &input,
+            );
+
+            assert_eq!(amounts, vec![(U256::from(100), U256::ZERO); 2]);
+        }
+    }
+
+    #[test]
+    fn test_amount_slots_match_solidity_layout() {
+        // keccak256(abi.encode(USER, 5)) and keccak256(abi.encode(USER, 6))
+        assert_eq!(
+            MarketLayout::Gateway.amount_slots(USER, BASE_CHAIN_ID),
+            (
+                b256!(
+                    "e211e23e74ee2556989cb624831cd15e4324ec0f5e5d9a1c3ec21f309f497c8c"
+                ),
+                b256!(
+                    "70f16d33119391b8b067ddbccc95d800dffc462fb735b11fb31d20dbd5ec1617"
+                ),
+            )
+        );
+        // With s = keccak256(abi.encode(uint32(8453), 22)), the slot of
+        // acc[8453]: keccak256(abi.encode(USER, s)) and
+        // keccak256(abi.encode(USER, s + 1))
+        assert_eq!(
+            MarketLayout::Host.amount_slots(USER, BASE_CHAIN_ID),
+            (
+                b256!(
+                    "3073b4343a7b281a56c29f5aa0eb0e049a991e6ded877b821ebbcb5e37ffc1ab"
+                ),
+                b256!(
+                    "abf7ddeccbe0247701e47ac776f6b57cab0858086edf7c9e30dccd2381aa5a2f"
+                ),
+            )
+        );
+    }
+
+    #[test]
+    fn test_host_slots_depend_on_target_chain() {
+        let host = MarketLayout::for_chain(LINEA_CHAIN_ID);
+        let gateway = MarketLayout::for_chain(BASE_CHAIN_ID);
+
+        assert_ne!(
+            host.amount_slots(USER, BASE_CHAIN_ID),
+            host.amount_slots(USER, OPTIMISM_CHAIN_ID)
+        );
+        assert_eq!(
+            gateway.amount_slots(USER, BASE_CHAIN_ID),
+            gateway.amount_slots(USER, OPTIMISM_CHAIN_ID)
+        );
+        assert_ne!(
+            host.amount_slots(USER, BASE_CHAIN_ID),
+            gateway.amount_slots(USER, BASE_CHAIN_ID)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid storage proof")]
+    fn test_wrong_amount_panics() {
+        let (state_root, mut input) = state(BASE_CHAIN_ID, 100);
+        input.accounts[0].storage[0].value = U256::from(1_000_000);
+
+        prove_amounts(
+            BASE_CHAIN_ID,
+            state_root,
+            &[USER],
+            &[MARKET],
+            &[59144],
+            &input,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "missing storage proof")]
+    fn test_layout_of_other_chain_panics() {
+        // Proofs of the gateway slots do not prove the host amounts
+        let (state_root, input) = state(BASE_CHAIN_ID, 100);
+
+        prove_amounts(
+            LINEA_CHAIN_ID,
+            state_root,
+            &[USER],
+            &[MARKET],
+            &[59144],
+            &input,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid account proof")]
+    fn test_wrong_state_root_panics() {
+        let (_, input) = state(BASE_CHAIN_ID, 100);
+
+        prove_amounts(
+            BASE_CHAIN_ID,
+            B256::repeat_byte(0x44),
+            &[USER],
+            &[MARKET],
+            &[59144],
+            &input,
+        );
+    }
+}
@@ -29,10 +29,12 @@ use alloy_rlp::RlpEncodable;
 use ssz_derive::{Decode, Encode};
 use ssz_types::{FixedVector, VariableList, typenum};
 
+use crate::constants::*;
 use crate::cryptography::signature_msg;
-use alloy_primitives::{Address, B256, Bytes, Signature, U256};
+use alloy_primitives::{Address, B256, Bytes, Signature, U256, keccak256};
 
 use risc0_steel::config::{ChainSpec, ForkCondition};
+use risc0_steel::ethereum::EthEvmInput;
 
 use revm::primitives::hardfork::SpecId;
 use std::{collections::BTreeMap, sync::LazyLock};
@@ -48,14 +50,27 @@ pub static LINEA_MAINNET_CHAIN_SPEC: LazyLock<EthChainSpec> =
         ]),
     });
 
+/// Width in bits of a packed integer, e.g. `TakeLastXBytes(32)` for
+/// `uint32`.
 pub struct TakeLastXBytes(pub usize);
 
+/// Value packed by `abi::encode_packed` like Solidity's `abi.encodePacked`.
 pub enum SolidityDataType<'a> {
+    /// `string`, packed as its UTF-8 bytes.
     String(&'a str),
+    /// `address`, packed as 20 bytes.
     Address(Address),
+    /// `bytes`, packed as is.
     Bytes(&'a [u8]),
+    /// `bool`, packed as one byte.
     Bool(bool),
+    /// `uint256`, packed as 32 bytes.
     Number(U256),
+    /// `uintN`, packed as its last `N / 8` bytes.
+    ///
+    /// Higher bits are truncated, as by the Solidity cast `uintN(value)`.
+    /// Widths that are not a multiple of 8 are rounded down to whole bytes
+    /// and have no Solidity equivalent; widths above 256 panic.
     NumberWithShift(U256, TakeLastXBytes),
 }
 
@@ -217,6 +232,292 @@ sol! {
     }
 }
 
+/// Confirmation requirement a chain entry must satisfy before its state is
+/// accepted by the guest.
+///
+/// The policy is supplied per chain as guest input and its hash is committed
+/// to the journal, so on-chain verifiers can reject proofs built with a weaker
+/// policy than they require.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FinalityPolicy {
+    /// Require at least this many linking blocks on top of the queried block.
+    ///
+    /// Depths below the chain's default reorg protection depth are raised to
+    /// it, so a policy can only strengthen the protection.
+    Depth(u64),
+    /// Require the newest linking block to be at least this many seconds
+    /// younger than the queried block.
+    MinAge(u64),
+    /// Query at the block the RPC reports as `safe`.
+    ///
+    /// The tag cannot be proven in the guest, so the host resolves it to the
+    /// `Depth` of the tagged block, see `resolve_tag`, and the guest rejects
+    /// unresolved tags.
+    Safe,
+    /// Query at the block the RPC reports as `finalized`.
+    ///
+    /// Resolved by the host like `Safe`.
+    Finalized,
+}
+
+impl FinalityPolicy {
+    /// Returns the default reorg protection depth for a chain.
+    ///
+    /// # Panics
+    /// Panics if the chain ID is not supported.
+    pub fn default_depth(chain_id: u64) -> u64 {
+        match chain_id {
+            OPTIMISM_CHAIN_ID => REORG_PROTECTION_DEPTH_OPTIMISM,
+            BASE_CHAIN_ID => REORG_PROTECTION_DEPTH_BASE,
+            LINEA_CHAIN_ID => REORG_PROTECTION_DEPTH_LINEA,
+            ETHEREUM_CHAIN_ID => REORG_PROTECTION_DEPTH_ETHEREUM,
+            OPTIMISM_SEPOLIA_CHAIN_ID => {
+                REORG_PROTECTION_DEPTH_OPTIMISM_SEPOLIA
+            }
+            BASE_SEPOLIA_CHAIN_ID => REORG_PROTECTION_DEPTH_BASE_SEPOLIA,
+            LINEA_SEPOLIA_CHAIN_ID => REORG_PROTECTION_DEPTH_LINEA_SEPOLIA,
+            ETHEREUM_SEPOLIA_CHAIN_ID => {
+                REORG_PROTECTION_DEPTH_ETHEREUM_SEPOLIA
+            }
+            _ => panic!("invalid chain id"),
+        }
+    }
+
+    /// Returns the policy used when none is configured for a chain.
+    pub fn default_for_chain(chain_id: u64) -> Self {
+        FinalityPolicy::Depth(Self::default_depth(chain_id))
+    }
+
+    /// Returns the minimum number of linking blocks the guest requires for
+    /// this policy on the given chain, never less than the chain's default
+    /// reorg protection depth.
+    pub fn min_depth(&self, chain_id: u64) -> u64 {
+        let default_depth = Self::default_depth(chain_id);
+        match self {
+            FinalityPolicy::Depth(depth) => (*depth).max(default_depth),
+            FinalityPolicy::MinAge(_)
+            | FinalityPolicy::Safe
+            | FinalityPolicy::Finalized => default_depth,
+        }
+    }
+
+    /// Returns whether the policy is a block tag, which the guest cannot
+    /// verify.
+    pub fn is_tag(&self) -> bool {
+        matches!(self, FinalityPolicy::Safe | FinalityPolicy::Finalized)
+    }
+
+    /// Returns the policy the guest verifies once the host resolved a tag to
+    /// `depth` linking blocks. Other policies are returned unchanged.
+    pub fn resolve_tag(self, depth: u64) -> Self {
+        if self.is_tag() {
+            FinalityPolicy::Depth(depth)
+        } else {
+            self
+        }
+    }
+
+    /// Returns the policy the guest enforces on `chain_id`: a `Depth` raised
+    /// to `min_depth`. Other policies are returned unchanged.
+    pub fn enforced(self, chain_id: u64) -> Self {
+        match self {
+            FinalityPolicy::Depth(_) => {
+                FinalityPolicy::Depth(self.min_depth(chain_id))
+            }
+            _ => self,
+        }
+    }
+
+    /// Returns the hash of the policy of `chain_id` as committed to the
+    /// journal.
+    ///
+    /// The hash is
+    /// `keccak256(abi.encodePacked(uint32 chainId, uint8 kind, uint64 value))`
+    /// of the `enforced` policy, so `Depth(0)` and the chain's default depth
+    /// hash alike and a verifier only has to know the depths that are
+    /// actually checked. `kind` is 0 for depth, 1 for minimum age, 2 for
+    /// `safe` and 3 for `finalized`. `value` is zero for the tag variants,
+    /// giving each tag one fixed hash per chain; the guest never commits it,
+    /// as tags are resolved to a depth before reaching it. Binding the chain
+    /// ID lets verifiers match the policy to the journal entries of the
+    /// chain, whose `chainId` field is also a `uint32`.
+    pub fn hash(&self, chain_id: u64) -> B256 {
+        let (kind, value) = match self.enforced(chain_id) {
+            FinalityPolicy::Depth(depth) => (0u8, depth),
+            FinalityPolicy::MinAge(seconds) => (1u8, seconds),
+            FinalityPolicy::Safe => (2u8, 0),
+            FinalityPolicy::Finalized => (3u8, 0),
+        };
+        let (bytes, _hex) = abi::encode_packed(&[
+            SolidityDataType::NumberWithShift(
+                U256::from(chain_id),
+                TakeLastXBytes(32),
+            ),
+            SolidityDataType::NumberWithShift(
+                U256::from(kind),
+                TakeLastXBytes(8),
+            ),
+            SolidityDataType::NumberWithShift(
+                U256::from(value),
+                TakeLastXBytes(64),
+            ),
+        ]);
+        keccak256(bytes)
+    }
+}
+
+/// Set of chains a guest build accepts.
+///
+/// The guest is built for exactly one profile, selected by its `mainnet`
+/// (default), `sepolia` or `devnet` feature, and panics on chains outside of
+/// it. Since the allow-list is compiled in, every profile has its own image
+/// ID, and a proof can never mix mainnet and testnet state unless it was
+/// built for `devnet`.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum NetworkProfile {
+    /// Ethereum, Optimism, Base and Linea mainnet.
+    #[default]
+    Mainnet,
+    /// The Sepolia variants of the mainnet chains.
+    Sepolia,
+    /// Both, for local devnets forking either network. Never deployed.
+    Devnet,
+}
+
+impl NetworkProfile {
+    /// Returns the profile name, as used for guest features and ELF file
+    /// names.
+    pub const fn name(self) -> &'static str {
+        match self {
+            NetworkProfile::Mainnet => "mainnet",
+            NetworkProfile::Sepolia => "sepolia",
+            NetworkProfile::Devnet => "devnet",
+        }
+    }
+
+    /// Returns the chain IDs the profile accepts.
+    pub const fn chain_ids(self) -> &'static [u64] {
+        const MAINNET: &[u64] = &[
+            ETHEREUM_CHAIN_ID,
+            OPTIMISM_CHAIN_ID,
+            BASE_CHAIN_ID,
+            LINEA_CHAIN_ID,
+        ];
+        const SEPOLIA: &[u64] = &[
+            ETHEREUM_SEPOLIA_CHAIN_ID,
+            OPTIMISM_SEPOLIA_CHAIN_ID,
+            BASE_SEPOLIA_CHAIN_ID,
+            LINEA_SEPOLIA_CHAIN_ID,
+        ];
+        const DEVNET: &[u64] = &[
+            ETHEREUM_CHAIN_ID,
+            OPTIMISM_CHAIN_ID,
+            BASE_CHAIN_ID,
+            LINEA_CHAIN_ID,
+            ETHEREUM_SEPOLIA_CHAIN_ID,
+            OPTIMISM_SEPOLIA_CHAIN_ID,
+            BASE_SEPOLIA_CHAIN_ID,
+            LINEA_SEPOLIA_CHAIN_ID,
+        ];
+        match self {
+            NetworkProfile::Mainnet => MAINNET,
+            NetworkProfile::Sepolia => SEPOLIA,
+            NetworkProfile::Devnet => DEVNET,
+        }
+    }
+
+    /// Returns whether the profile accepts `chain_id`.
+    pub fn allows(self, chain_id: u64) -> bool {
+        self.chain_ids().contains(&chain_id)
+    }
+}
+
+/// Prefix of the profiling markers the guest writes to stderr when built with
+/// its `profile` feature.
+///
+/// Every marker is one line, `malda-profile <event> <cycle count>`, where the
+/// event is `chain` before a chain's input is read, `stage <name>` at the end
+/// of a `ProfileStage` and `end <chain_id>` when the chain is done. The
+/// validation of an `L1Anchor` is marked the same way, from `anchor` to
+/// `end anchor <chain_id>`.
+pub const PROFILE_MARKER: &str = "malda-profile";
+
+/// Stage of `validators::validate_get_proof_data_call`, reported through
+/// `validators::set_stage_hook`.
+///
+/// A stage ends at its marker and covers every cycle since the previous one,
+/// so stages that run several times for a chain, e.g. the decoding of the L1
+/// and L2 environments, add up.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum ProfileStage {
+    /// Reading the input and turning EVM inputs into environments, i.e.
+    /// header hashing and state proof verification.
+    EnvDecode,
+    /// Sequencer commitment and Linea block signature checks, including the
+    /// L1 block hash read from a checked OpStack block.
+    Signatures,
+    /// OpStack dispute game validation for L1 inclusion.
+    DisputeGame,
+    /// Linea message service check for L1 inclusion.
+    LineaMessageService,
+    /// Linking block and finality policy checks.
+    ChainLength,
+    /// The proof data multicall.
+    Multicall,
+    /// Storage proof checks replacing the multicall, see `storage`.
+    StorageProofs,
+    /// Receipt proof checks of the chain's events.
+    Events,
+}
+
+impl ProfileStage {
+    /// All stages, in the order they run.
+    pub const ALL: [ProfileStage; 8] = [
+        ProfileStage::EnvDecode,
+        ProfileStage::Signatures,
+        ProfileStage::DisputeGame,
+        ProfileStage::LineaMessageService,
+        ProfileStage::ChainLength,
+        ProfileStage::Multicall,
+        ProfileStage::StorageProofs,
+        ProfileStage::Events,
+    ];
+
+    /// Returns the stage name used in profiling markers.
+    pub const fn name(self) -> &'static str {
+        match self {
+            ProfileStage::EnvDecode => "env_decode",
+            ProfileStage::Signatures => "signatures",
+            ProfileStage::DisputeGame => "dispute_game",
+            ProfileStage::LineaMessageService => "linea_message_service",
+            ProfileStage::ChainLength => "chain_length",
+            ProfileStage::Multicall => "multicall",
+            ProfileStage::StorageProofs => "storage_proofs",
+            ProfileStage::Events => "events",
+        }
+    }
+
+    /// Returns the stage with the given marker name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|stage| stage.name() == name)
+    }
+}
+
 /// Represents a commitment made by a sequencer, containing signed payload data.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SequencerCommitment {
@@ -237,6 +538,9 @@ impl SequencerCommitment {
     pub fn new(data: &[u8]) -> Result<Self> {
         let mut decoder = snap::raw::Decoder::new();
         let decompressed = decoder.decompress_vec(&data)?;
+        if decompressed.len() < 65 {
+            eyre::bail!("commitment shorter than its signature");
+        }
 
         let signature = Signature::try_from(&decompressed[..65])?;
         let data = Bytes::from(decompressed[65..].to_vec());
@@ -265,6 +569,24 @@ impl SequencerCommitment {
     }
 }
 
+/// Ethereum block shared by all chains of a batch that check data against
+/// Ethereum.
+///
+/// The guest input starts with at most one anchor per Ethereum network. The
+/// guest validates each anchor once, by checking the OpStack sequencer
+/// commitment and reading the L1 block hash from the L1Block contract of the
+/// committed OpStack block, and every per-chain L1 check refers to the
+/// validated hash.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct L1Anchor {
+    /// Ethereum chain ID of the anchored block (mainnet or Sepolia).
+    pub chain_id: u64,
+    /// Commitment of the Optimism sequencer to the OpStack block.
+    pub sequencer_commitment: SequencerCommitment,
+    /// Input of the L1Block contract call on the OpStack block.
+    pub l1_block_call_input: EthEvmInput,
+}
+
 /// Conversion implementation from SequencerCommitment to ExecutionPayload.
 impl TryFrom<&SequencerCommitment> for ExecutionPayload {
     type Error = eyre::Report;
@@ -277,14 +599,17 @@ impl TryFrom<&SequencerCommitment> for ExecutionPayload {
     /// # Returns
     /// * `Result<Self>` - The converted payload or an error
     fn try_from(value: &SequencerCommitment) -> Result<Self> {
-        let payload_bytes = &value.data[32..];
+        // The payload is preceded by the 32 byte parent beacon block root
+        let Some(payload_bytes) = value.data.get(32..) else {
+            eyre::bail!("commitment shorter than its beacon block root");
+        };
         ssz::Decode::from_ssz_bytes(payload_bytes)
             .map_err(|_| eyre::eyre!("decode failed"))
     }
 }
 
 /// Represents a complete blockchain execution payload.
-#[derive(Debug, Clone, Encode, Decode)]
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
 pub struct ExecutionPayload {
     /// Hash of the parent block
     pub parent_hash: B256,
@@ -338,7 +663,7 @@ pub type ExtraData = VariableList<u8, typenum::U32>;
 /// Copied from https://docs.rs/alloy/latest/alloy/eips/eip4895/struct.Withdrawal.html
 /// which doesn't work as direct input due to mismatch between crate versions
 /// between alloy and ssz
-#[derive(Clone, Debug, Encode, Decode, RlpEncodable)]
+#[derive(Clone, Debug, PartialEq, Encode, Decode, RlpEncodable)]
 pub struct Withdrawal {
     /// Sequential index of the withdrawal
     index: u64,
@@ -350,10 +675,77 @@ pub struct Withdrawal {
     amount: u64,
 }
 
+/// Builds an SSZ list from arbitrary elements, truncated to the list's
+/// maximum length. The length is drawn as for a byte list, so nested lists
+/// are bounded by the remaining input.
+#[cfg(feature = "arbitrary")]
+fn arbitrary_list<'a, T, N>(
+    u: &mut arbitrary::Unstructured<'a>,
+    element: impl Fn(&mut arbitrary::Unstructured<'a>) -> arbitrary::Result<T>,
+) -> arbitrary::Result<VariableList<T, N>>
+where
+    N: typenum::Unsigned,
+{
+    let len = u.arbitrary_len::<u8>()?.min(N::to_usize());
+    let elements = (0..len)
+        .map(|_| element(u))
+        .collect::<arbitrary::Result<Vec<_>>>()?;
+    VariableList::new(elements).map_err(|_| arbitrary::Error::IncorrectFormat)
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for ExecutionPayload {
+    fn arbitrary(
+        u: &mut arbitrary::Unstructured<'a>,
+    ) -> arbitrary::Result<Self> {
+        let b256 = |u: &mut arbitrary::Unstructured<'a>| {
+            u.arbitrary::<[u8; 32]>().map(B256::from)
+        };
+        Ok(ExecutionPayload {
+            parent_hash: b256(u)?,
+            fee_recipient: Address::from(u.arbitrary::<[u8; 20]>()?),
+            state_root: b256(u)?,
+            receipts_root: b256(u)?,
+            logs_bloom: FixedVector::from(u.arbitrary::<[u8; 256]>()?.to_vec()),
+            prev_randao: b256(u)?,
+            block_number: u.arbitrary()?,
+            gas_limit: u.arbitrary()?,
+            gas_used: u.arbitrary()?,
+            timestamp: u.arbitrary()?,
+            extra_data: arbitrary_list(u, |u| u.arbitrary())?,
+            base_fee_per_gas: U256::from_be_bytes(u.arbitrary::<[u8; 32]>()?),
+            block_hash: b256(u)?,
+            transactions: arbitrary_list(u, |u| {
+                arbitrary_list(u, |u| u.arbitrary())
+            })?,
+            withdrawals: arbitrary_list(u, |u| u.arbitrary())?,
+            blob_gas_used: u.arbitrary()?,
+            excess_blob_gas: u.arbitrary()?,
+            withdrawals_root: b256(u)?,
+        })
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for Withdrawal {
+    fn arbitrary(
+        u: &mut arbitrary::Unstructured<'a>,
+    ) -> arbitrary::Result<Self> {
+        Ok(Withdrawal {
+            index: u.arbitrary()?,
+            validator_index: u.arbitrary()?,
+            address: Address::from(u.arbitrary::<[u8; 20]>()?),
+            amount: u.arbitrary()?,
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use alloy_primitives::b256;
+
     #[test]
     fn test_number_with_shift_truncation() {
         // Simulate a chain ID that is larger than a u32 can hold.
@@ -393,4 +785,164 @@ mod tests {
         let (encoded_max_bytes, _) = abi::encode_packed(&input_max);
         assert_eq!(encoded_max_bytes, (u32::MAX).to_be_bytes().to_vec());
     }
+
+    #[test]
+    fn test_encode_packed_matches_sol_types() {
+        use alloy_sol_types::{SolType, SolValue, sol_data};
+
+        let address = Address::repeat_byte(0xab);
+        let values = [
+            U256::ZERO,
+            U256::from(u32::MAX),
+            U256::from(1) << 32,
+            U256::from_be_bytes([0x5a; 32]),
+            U256::MAX,
+        ];
+        for value in values {
+            let input = [
+                SolidityDataType::String("malda"),
+                SolidityDataType::Address(address),
+                SolidityDataType::Bytes(&[1, 2, 3]),
+                SolidityDataType::Bool(true),
+                SolidityDataType::Number(value),
+                SolidityDataType::NumberWithShift(value, TakeLastXBytes(8)),
+                SolidityDataType::NumberWithShift(value, TakeLastXBytes(32)),
+                SolidityDataType::NumberWithShift(value, TakeLastXBytes(64)),
+                SolidityDataType::NumberWithShift(value, TakeLastXBytes(128)),
+                SolidityDataType::NumberWithShift(value, TakeLastXBytes(256)),
+            ];
+            let expected = [
+                "malda".to_string().abi_encode_packed(),
+                address.abi_encode_packed(),
+                Bytes::from(vec![1, 2, 3]).abi_encode_packed(),
+                true.abi_encode_packed(),
+                value.abi_encode_packed(),
+                sol_data::Uint::<8>::abi_encode_packed(&value.wrapping_to()),
+                sol_data::Uint::<32>::abi_encode_packed(&value.wrapping_to()),
+                sol_data::Uint::<64>::abi_encode_packed(&value.wrapping_to()),
+                sol_data::Uint::<128>::abi_encode_packed(&value.wrapping_to()),
+                sol_data::Uint::<256>::abi_encode_packed(&value),
+            ]
+            .concat();
+
+            let (encoded, hexed) = abi::encode_packed(&input);
+            assert_eq!(encoded, expected, "packing of {value} differs");
+            assert_eq!(hexed, hex::encode(&expected));
+        }
+    }
+
+    #[test]
+    fn test_finality_policy_hash_is_unique_per_policy() {
+        let policies = [
+            FinalityPolicy::Depth(2),
+            FinalityPolicy::Depth(3),
+            FinalityPolicy::MinAge(2),
+            FinalityPolicy::Safe,
+            FinalityPolicy::Finalized,
+        ];
+
+        for (i, a) in policies.iter().enumerate() {
+            for b in policies.iter().skip(i + 1) {
+                assert_ne!(
+                    a.hash(BASE_CHAIN_ID),
+                    b.hash(BASE_CHAIN_ID),
+                    "{a:?} and {b:?} collide"
+                );
+            }
+            assert_ne!(
+                a.hash(BASE_CHAIN_ID),
+                a.hash(OPTIMISM_CHAIN_ID),
+                "{a:?} does not depend on the chain"
+            );
+        }
+
+        assert_eq!(
+            FinalityPolicy::default_for_chain(BASE_CHAIN_ID),
+            FinalityPolicy::Depth(REORG_PROTECTION_DEPTH_BASE)
+        );
+    }
+
+    #[test]
+    fn test_finality_policy_hash_covers_enforced_policy() {
+        let default = FinalityPolicy::default_for_chain(BASE_CHAIN_ID);
+        for depth in [0, 1] {
+            assert_eq!(
+                FinalityPolicy::Depth(depth).hash(BASE_CHAIN_ID),
+                default.hash(BASE_CHAIN_ID)
+            );
+        }
+        assert_eq!(
+            default.hash(BASE_CHAIN_ID),
+            b256!(
+                "dc2da97478e99d16f7bbd7cc127bf96b7fbaef1c16f0bc2694a5a2df04952ca0"
+            )
+        );
+        assert_eq!(
+            FinalityPolicy::Safe.hash(BASE_CHAIN_ID),
+            b256!(
+                "595d768f2e70d89652d758be6d181bc7341d1e0d9eb7ad2d1c235f8541912a6c"
+            )
+        );
+        assert_eq!(
+            FinalityPolicy::Finalized.hash(BASE_CHAIN_ID),
+            b256!(
+                "897fd696ac7319197bc394d2c1b1d06df5aa38cc3d7c47d35bba5b50fe8c40a2"
+            )
+        );
+    }
+
+    #[test]
+    fn test_resolve_tag() {
+        assert_eq!(
+            FinalityPolicy::Finalized.resolve_tag(64),
+            FinalityPolicy::Depth(64)
+        );
+        assert_eq!(
+            FinalityPolicy::Safe.resolve_tag(12),
+            FinalityPolicy::Depth(12)
+        );
+        assert_eq!(
+            FinalityPolicy::MinAge(60).resolve_tag(12),
+            FinalityPolicy::MinAge(60)
+        );
+    }
+
+    #[test]
+    fn test_depth_policy_cannot_weaken_default_depth() {
+        for depth in [0, 1, REORG_PROTECTION_DEPTH_BASE] {
+            assert_eq!(
+                FinalityPolicy::Depth(depth).min_depth(BASE_CHAIN_ID),
+                REORG_PROTECTION_DEPTH_BASE
+            );
+        }
+        assert_eq!(
+            FinalityPolicy::Depth(REORG_PROTECTION_DEPTH_BASE + 5)
+                .min_depth(BASE_CHAIN_ID),
+            REORG_PROTECTION_DEPTH_BASE + 5
+        );
+    }
+
+    #[test]
+    fn test_short_commitment_data_is_rejected() {
+        let signature = Signature::new(U256::from(1), U256::from(1), false);
+        for len in [0, 1, 31] {
+            let commitment = SequencerCommitment {
+                data: Bytes::from(vec![0u8; len]),
+                signature,
+            };
+            assert!(ExecutionPayload::try_from(&commitment).is_err());
+        }
+    }
+
+    #[test]
+    fn test_network_profiles_do_not_mix_networks() {
+        for &chain_id in NetworkProfile::Mainnet.chain_ids() {
+            assert!(!NetworkProfile::Sepolia.allows(chain_id));
+            assert!(NetworkProfile::Devnet.allows(chain_id));
+        }
+        for &chain_id in NetworkProfile::Sepolia.chain_ids() {
+            assert!(!NetworkProfile::Mainnet.allows(chain_id));
+            assert!(NetworkProfile::Devnet.allows(chain_id));
+        }
+    }
 }
@@ -18,8 +18,12 @@
 //! - Proof data queries across multiple EVM chains
 //! - Linea block validation through sequencer signatures
 //! - OpStack (Optimism/Base) validation through sequencer commitments
-//! - Ethereum L1 block validation through OpStack L2s
+//! - Ethereum L1 block validation through OpStack L2s, once per batch (see
+//!   `L1Anchor`)
 //! - Chain length validation for reorg protection
+//! - Historical Ethereum blocks through EIP-4788 beacon roots
+//! - Proof data amounts through storage proofs instead of a multicall, see
+//!   `storage`
 //!
 //! Supported networks include:
 //! - Ethereum (L1) - Mainnet and Sepolia
@@ -29,6 +33,8 @@
 
 use crate::constants::*;
 use crate::cryptography::{recover_signer, signature_from_bytes};
+use crate::receipts::{EventInput, validate_event_input};
+use crate::storage::{StorageProofInput, prove_amounts};
 use crate::types::*;
 use alloy_consensus::Header;
 use alloy_primitives::{Address, B256, Bytes, U256};
@@ -38,18 +44,107 @@ use risc0_op_steel::optimism::{
 };
 use risc0_steel::EvmFactory;
 use risc0_steel::{
-    Commitment, Contract, EvmEnv, StateDb,
+    Commitment, CommitmentVersion, Contract, EvmEnv, StateDb,
     ethereum::{ETH_MAINNET_CHAIN_SPEC, EthEvmFactory, EthEvmInput},
     serde::RlpHeader,
 };
 
+use std::sync::OnceLock;
+
+/// Function called at the end of every `ProfileStage`, see `set_stage_hook`.
+static STAGE_HOOK: OnceLock<fn(ProfileStage)> = OnceLock::new();
+
+/// Sets the function called at the end of every stage of
+/// `validate_get_proof_data_call`.
+///
+/// The profiling build of the guest uses it to report cycle counts. The hook
+/// can only be set once; later calls are ignored.
+pub fn set_stage_hook(hook: fn(ProfileStage)) {
+    let _ = STAGE_HOOK.set(hook);
+}
+
+/// Reports the end of `stage` to the stage hook, if set.
+fn end_stage(stage: ProfileStage) {
+    if let Some(hook) = STAGE_HOOK.get() {
+        hook(stage);
+    }
+}
+
+/// EVM environment of an Ethereum or Linea block.
+pub type EthEnv = EvmEnv<StateDb, EthEvmFactory, Commitment>;
+
+/// EVM environment of an OpStack block.
+pub type OpEnv = EvmEnv<StateDb, OpEvmFactory, Commitment>;
+
+/// Ethereum block hash validated from an `L1Anchor`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ValidatedL1Anchor {
+    /// Ethereum chain ID of the anchored block.
+    pub chain_id: u64,
+    /// Hash of the anchored block.
+    pub block_hash: B256,
+}
+
+/// Validates an L1 anchor through its OpStack sequencer commitment.
+///
+/// See `get_validated_ethereum_block_hash_via_opstack`.
+///
+/// # Arguments
+/// * `anchor` - The anchor to validate.
+///
+/// # Returns
+/// * `ValidatedL1Anchor` - The Ethereum chain ID and validated block hash.
+///
+/// # Panics
+/// Panics if:
+/// * The anchor's chain is not an Ethereum chain.
+/// * The sequencer commitment or the L1Block call fails validation.
+pub fn validate_l1_anchor(anchor: L1Anchor) -> ValidatedL1Anchor {
+    assert!(
+        anchor.chain_id == ETHEREUM_CHAIN_ID
+            || anchor.chain_id == ETHEREUM_SEPOLIA_CHAIN_ID,
+        "invalid L1 anchor chain id"
+    );
+    let block_hash = get_validated_ethereum_block_hash_via_opstack(
+        Some(&anchor.sequencer_commitment),
+        Some(anchor.l1_block_call_input),
+        anchor.chain_id,
+        None,
+        None,
+    );
+    ValidatedL1Anchor {
+        chain_id: anchor.chain_id,
+        block_hash,
+    }
+}
+
+/// Returns the validated hash of the anchor for `ethereum_chain_id`.
+///
+/// # Panics
+/// Panics if the batch has no anchor for `ethereum_chain_id`.
+fn l1_anchor_hash(
+    l1_anchors: &[ValidatedL1Anchor],
+    ethereum_chain_id: u64,
+) -> B256 {
+    l1_anchors
+        .iter()
+        .find(|anchor| anchor.chain_id == ethereum_chain_id)
+        .expect("no L1 anchor for the chain")
+        .block_hash
+}
+
 /// Validates and executes proof data queries across multiple accounts and
 /// tokens using multicall.
 ///
 /// This function orchestrates the validation of proof data queries for multiple
 /// accounts and assets across different EVM chains. It sorts and verifies the
 /// relevant parameters, validates block hashes and chain length for reorg
-/// protection, and executes a batch multicall to retrieve proof data.
+/// protection, and executes a batch multicall to retrieve proof data, or
+/// verifies storage proofs of the amounts if `storage_proof_input` is given.
+///
+/// Every EVM input is turned into an environment once and every header is
+/// hashed once; the environments and hashes are then shared by reference
+/// between the validation steps.
 ///
 /// # Arguments
 /// * `chain_id` - The chain ID to validate against.
@@ -57,26 +152,32 @@ use risc0_steel::{
 /// * `asset` - Vector of token contract addresses to query.
 /// * `target_chain_ids` - Vector of target chain IDs for each account.
 /// * `env_input_for_viewcall` - Optional EVM environment input for the chain.
-/// * `sequencer_commitment_opstack` - Optional sequencer commitment for L2
-///   chains.
-/// * `env_input_opstack_for_l1_block_call` - Optional Optimism environment
-///   input for L1 validation.
+/// * `sequencer_commitment_opstack` - Optional sequencer commitment for
+///   OpStack chains without L1 inclusion.
 /// * `linking_blocks` - Vector of blocks for reorg protection.
 /// * `output` - Output vector for proof data results.
 /// * `env_input_eth_for_l1_inclusion` - Optional Ethereum environment input for
 ///   L1 inclusion.
 /// * `env_input_opstack_for_viewcall_with_l1_inclusion` - Optional OpStack
 ///   environment input for L1 inclusion.
-/// * `sequencer_commitment_opstack_2` - Optional second sequencer commitment
-///   for L2 chains.
-/// * `env_input_opstack_for_l1_block_call_2` - Optional second Optimism
-///   environment input for L1 validation.
+/// * `l1_anchors` - Validated L1 anchors of the batch, used by Ethereum
+///   chains and L1 inclusion.
+/// * `finality_policy` - Confirmation requirement for the queried block.
+/// * `event_input` - Optional receipt proofs of events emitted by the
+///   queried markets in the view call block or its ancestors, see
+///   `receipts::validate_event_input`.
+/// * `events` - Output vector for the proven events.
+/// * `storage_proof_input` - Optional storage proofs of the queried amounts,
+///   verified against the view call block instead of executing the
+///   multicall, see `storage::prove_amounts`.
 ///
 /// # Panics
 /// Panics if:
 /// * Chain ID is invalid
 /// * Environment validation fails
-/// * Chain length is insufficient
+/// * Event or storage proofs are given for an OpStack chain with L1
+///   inclusion, or are invalid
+/// * Chain length is insufficient for the finality policy
 /// * Block hashes don't match
 /// * Multicall execution fails
 /// * Return data decoding fails
@@ -87,13 +188,15 @@ pub fn validate_get_proof_data_call(
     target_chain_ids: Vec<u64>,
     env_input_for_viewcall: Option<EthEvmInput>,
     sequencer_commitment_opstack: Option<SequencerCommitment>,
-    env_input_opstack_for_l1_block_call: Option<EthEvmInput>,
     linking_blocks: &Vec<RlpHeader<Header>>,
     output: &mut Vec<Bytes>,
-    env_input_eth_for_l1_inclusion: &Option<EthEvmInput>,
+    env_input_eth_for_l1_inclusion: Option<EthEvmInput>,
     env_input_opstack_for_viewcall_with_l1_inclusion: Option<OpEvmInput>,
-    sequencer_commitment_opstack_2: Option<SequencerCommitment>,
-    env_input_opstack_for_l1_block_call_2: Option<EthEvmInput>,
+    l1_anchors: &[ValidatedL1Anchor],
+    finality_policy: &FinalityPolicy,
+    event_input: Option<EventInput>,
+    events: &mut Vec<Bytes>,
+    storage_proof_input: Option<StorageProofInput>,
 ) {
     println!("=== validate_get_proof_data_call args");
     println!("=== * chain_id={:?}", chain_id);
@@ -101,61 +204,139 @@ pub fn validate_get_proof_data_call(
     println!("=== * asset={:?}", asset);
     println!("=== * target_chain_ids={:?}", target_chain_ids);
     println!("=== * linking_blocks={:?}", linking_blocks);
-    // Sort and verify all relevant parameters for the proof data call,
-    // including environment and block headers.
+    // The L1 environment is used for the view call header of OpStack chains,
+    // the dispute game and the Linea message service, so it is built once.
+    let eth_env_for_l1_inclusion = env_input_eth_for_l1_inclusion
+        .map(|input| input.into_env(&ETH_MAINNET_CHAIN_SPEC));
+
+    // Sort and verify all relevant parameters for the proof data call.
     println!("=== sort_and_verify_relevant_params START");
     let (
         env_for_viewcall,
-        block_header_to_validate,
-        env_header_hash_to_validate,
-        env_header_to_validate,
         op_env_for_viewcall_with_l1_inclusion,
-        op_env_commitment,
         chain_id_for_length_validation,
-        validate_l1_inclusion,
     ) = sort_and_verify_relevant_params(
         chain_id,
         env_input_for_viewcall,
-        linking_blocks,
-        env_input_eth_for_l1_inclusion,
+        eth_env_for_l1_inclusion.as_ref(),
         env_input_opstack_for_viewcall_with_l1_inclusion,
     );
     println!("=== sort_and_verify_relevant_params END");
 
+    // The header the linking blocks start from is the one of the view call
+    // environment, or of the L1 environment for OpStack L1 inclusion.
+    let env_header = env_for_viewcall
+        .as_ref()
+        .or(eth_env_for_l1_inclusion.as_ref())
+        .expect("env_input is None")
+        .header();
+    let env_header_hash_to_validate = env_header.seal();
+
+    // Hash each linking block once. The block to validate is the last
+    // linking block if present, otherwise the environment's header, whose
+    // hash the environment already holds.
+    let linking_hashes: Vec<B256> = linking_blocks
+        .iter()
+        .map(|header| header.hash_slow())
+        .collect();
+    let (block_header_to_validate, block_hash_to_validate) =
+        match (linking_blocks.last(), linking_hashes.last()) {
+            (Some(header), Some(hash)) => (header, *hash),
+            _ => (env_header.inner(), env_header_hash_to_validate),
+        };
+    end_stage(ProfileStage::EnvDecode);
+
     // Validate the block hash for the given chain and environment.
     let validated_block_hash = get_validated_block_hash(
         chain_id,
-        env_header_to_validate,
+        env_header.inner().inner(),
         sequencer_commitment_opstack,
-        env_input_opstack_for_l1_block_call,
-        env_input_eth_for_l1_inclusion,
+        eth_env_for_l1_inclusion.as_ref(),
         block_header_to_validate,
-        validate_l1_inclusion,
-        op_env_commitment.as_ref(),
-        sequencer_commitment_opstack_2,
-        env_input_opstack_for_l1_block_call_2,
+        block_hash_to_validate,
+        op_env_for_viewcall_with_l1_inclusion
+            .as_ref()
+            .map(|op_env| op_env.commitment()),
+        l1_anchors,
     );
 
-    // Ensure the chain length and hash linking are valid for reorg protection.
-    validate_chain_length(
+    // Historical environments commit to the beacon root of a recent block
+    // instead of their own header, so the linking blocks start from there.
+    let (historical_hash, historical_timestamp) = match env_for_viewcall
+        .as_ref()
+        .map(|env| env.commitment())
+        .filter(|commitment| is_beacon_commitment(commitment))
+    {
+        Some(commitment) => {
+            validate_beacon_commitment(chain_id, commitment, linking_blocks)
+        }
+        None => (
+            env_header_hash_to_validate,
+            env_header.inner().inner().timestamp,
+        ),
+    };
+
+    // Ensure the linking blocks satisfy the finality policy and are hash
+    // linked for reorg protection.
+    validate_chain_finality(
         chain_id_for_length_validation,
-        env_header_hash_to_validate,
+        finality_policy,
+        historical_hash,
+        historical_timestamp,
         linking_blocks,
+        &linking_hashes,
         validated_block_hash,
     );
+    end_stage(ProfileStage::ChainLength);
 
     // Execute the batch multicall to retrieve proof data, using the appropriate
-    // environment.
-    if op_env_for_viewcall_with_l1_inclusion.is_some() {
+    // environment, or read the amounts from storage proofs of the view call
+    // block's state.
+    let validate_l1_inclusion = eth_env_for_l1_inclusion.is_some();
+    // Events must be emitted by the queried markets, which the multicall
+    // consumes.
+    let event_markets = event_input.as_ref().map(|_| asset.clone());
+    let stage = if let Some(storage_proof_input) = storage_proof_input {
+        let env = env_for_viewcall
+            .as_ref()
+            .expect("storage proofs not supported with OpStack L1 inclusion");
+        let amounts = prove_amounts(
+            chain_id,
+            env.header().inner().inner().state_root,
+            &account,
+            &asset,
+            &target_chain_ids,
+            &storage_proof_input,
+        );
+        let batch_params = account
+            .iter()
+            .zip(asset.iter())
+            .zip(target_chain_ids.iter());
+        for (((user, market), target_chain_id), amounts) in
+            batch_params.zip(amounts)
+        {
+            push_proof_data(
+                chain_id,
+                *user,
+                *market,
+                *target_chain_id,
+                amounts,
+                validate_l1_inclusion,
+                output,
+            );
+        }
+        ProfileStage::StorageProofs
+    } else if let Some(op_env) = &op_env_for_viewcall_with_l1_inclusion {
         batch_call_get_proof_data(
             chain_id,
             account,
             asset,
             target_chain_ids,
-            op_env_for_viewcall_with_l1_inclusion.unwrap(),
+            op_env,
             validate_l1_inclusion,
             output,
-        )
+        );
+        ProfileStage::Multicall
     } else {
         println!("=== validate_get_proof_data_call without inclusion");
         println!("=== batch_call_get_proof_data START");
@@ -164,42 +345,56 @@ pub fn validate_get_proof_data_call(
             account,
             asset,
             target_chain_ids,
-            env_for_viewcall,
+            env_for_viewcall.as_ref().expect("env_input is None"),
             validate_l1_inclusion,
             output,
         );
         println!("=== batch_call_get_proof_data END");
+        ProfileStage::Multicall
+    };
+    end_stage(stage);
+
+    // Events are proven against the view call block, which the checks above
+    // verified, and its ancestors.
+    if let Some((event_input, markets)) = event_input.zip(event_markets) {
+        let env = env_for_viewcall
+            .as_ref()
+            .expect("event proofs not supported with OpStack L1 inclusion");
+        validate_event_input(
+            chain_id,
+            env.header().inner().inner(),
+            env.header().seal(),
+            &event_input,
+            &markets,
+            events,
+        );
     }
+    end_stage(ProfileStage::Events);
 }
 
 /// Sorts and verifies relevant parameters for proof data validation.
 ///
 /// This function processes and validates input parameters for different chain
 /// types, handling both L1 and L2 validation scenarios. It determines which
-/// environment and block header should be used for subsequent validation, based
-/// on the chain type and inclusion requirements.
+/// environment the view call runs on, based on the chain type and inclusion
+/// requirements.
 ///
 /// # Arguments
 /// * `chain_id` - The chain ID to determine validation strategy.
 /// * `env_input_for_viewcall` - Optional EVM input for view calls (used for L1
 ///   or Linea chains).
-/// * `linking_blocks` - Vector of blocks for reorg protection.
-/// * `env_input_eth_for_l1_inclusion` - Optional Ethereum input for L1
+/// * `eth_env_for_l1_inclusion` - Optional Ethereum environment for L1
 ///   inclusion (used for OpStack/Linea L2s).
 /// * `env_input_opstack_for_viewcall_with_l1_inclusion` - Optional OpStack
 ///   input for L1 inclusion (used for OpStack L2s).
 ///
 /// # Returns
 /// Returns a tuple containing:
-/// * `EvmEnv` - The validated EVM environment for the view call.
-/// * `RlpHeader<Header>` - The block header to validate (from the environment
-///   or linking blocks).
-/// * `B256` - The header hash to validate.
-/// * `Header` - The inner header to validate.
-/// * `Option<EvmEnv>` - Optional OpStack EVM environment (for L1 inclusion).
-/// * `Option<Commitment>` - Optional commitment (for OpStack L1 inclusion).
+/// * `Option<EthEnv>` - The environment for the view call, unless it runs on
+///   the OpStack environment.
+/// * `Option<OpEnv>` - The OpStack environment for the view call with L1
+///   inclusion, whose header is validated through `eth_env_for_l1_inclusion`.
 /// * `u64` - Chain ID for length validation.
-/// * `bool` - Whether to validate L1 inclusion.
 ///
 /// # Panics
 /// Panics if:
@@ -209,47 +404,22 @@ pub fn validate_get_proof_data_call(
 pub fn sort_and_verify_relevant_params(
     chain_id: u64,
     env_input_for_viewcall: Option<EthEvmInput>,
-    linking_blocks: &Vec<RlpHeader<Header>>,
-    env_input_eth_for_l1_inclusion: &Option<EthEvmInput>,
+    eth_env_for_l1_inclusion: Option<&EthEnv>,
     env_input_opstack_for_viewcall_with_l1_inclusion: Option<OpEvmInput>,
-) -> (
-    EvmEnv<StateDb, EthEvmFactory, Commitment>,
-    RlpHeader<Header>,
-    B256,
-    Header,
-    Option<EvmEnv<StateDb, OpEvmFactory, Commitment>>,
-    Option<Commitment>,
-    u64,
-    bool,
-) {
-    let validate_l1_inclusion = env_input_eth_for_l1_inclusion.is_some();
-
-    // Determine which environment and parameters to use based on chain type and
-    // inclusion requirements.
-    let (
-        env_for_viewcall,
-        op_env_for_viewcall_with_l1_inclusion,
-        op_env_commitment,
-        chain_id_for_length_validation,
-    ) = if (chain_id == OPTIMISM_CHAIN_ID
+) -> (Option<EthEnv>, Option<OpEnv>, u64) {
+    if (chain_id == OPTIMISM_CHAIN_ID
         || chain_id == BASE_CHAIN_ID
         || chain_id == OPTIMISM_SEPOLIA_CHAIN_ID
         || chain_id == BASE_SEPOLIA_CHAIN_ID)
-        && validate_l1_inclusion
+        && eth_env_for_l1_inclusion.is_some()
     {
-        // For OpStack L2s with L1 inclusion, use the L1 environment and OpStack
-        // environment for inclusion.
-        let env_for_viewcall = env_input_eth_for_l1_inclusion
-            .as_ref()
-            .expect("env_eth_input is None")
-            .clone()
-            .into_env(&ETH_MAINNET_CHAIN_SPEC);
+        // For OpStack L2s with L1 inclusion, the view call runs on the
+        // OpStack environment and the linking blocks start from the L1
+        // environment.
         let op_env_for_viewcall_with_l1_inclusion =
             env_input_opstack_for_viewcall_with_l1_inclusion
                 .expect("op_evm_input is None")
                 .into_env(&OP_MAINNET_CHAIN_SPEC);
-        let op_env_commitment =
-            op_env_for_viewcall_with_l1_inclusion.commitment().clone();
         let chain_id_for_length_validation = match chain_id {
             OPTIMISM_CHAIN_ID | BASE_CHAIN_ID => ETHEREUM_CHAIN_ID,
             OPTIMISM_SEPOLIA_CHAIN_ID | BASE_SEPOLIA_CHAIN_ID => {
@@ -258,9 +428,8 @@ pub fn sort_and_verify_relevant_params(
             _ => panic!("invalid chain id"),
         };
         (
-            env_for_viewcall,
+            None,
             Some(op_env_for_viewcall_with_l1_inclusion),
-            Some(op_env_commitment),
             chain_id_for_length_validation,
         )
     } else {
@@ -272,37 +441,15 @@ pub fn sort_and_verify_relevant_params(
         };
 
         (
-            env_input_for_viewcall
-                .expect("env_input is None")
-                .into_env(&chain_spec),
-            None,
+            Some(
+                env_input_for_viewcall
+                    .expect("env_input is None")
+                    .into_env(&chain_spec),
+            ),
             None,
             chain_id,
         )
-    };
-
-    // Select the block header to validate: use the last linking block if
-    // present, otherwise use the environment's header.
-    let block_header_to_validate = if linking_blocks.is_empty() {
-        env_for_viewcall.header().inner().clone()
-    } else {
-        linking_blocks[linking_blocks.len() - 1].clone()
-    };
-
-    let env_header_hash_to_validate = env_for_viewcall.header().seal();
-    let env_header_to_validate =
-        env_for_viewcall.header().inner().inner().clone();
-
-    (
-        env_for_viewcall,
-        block_header_to_validate,
-        env_header_hash_to_validate,
-        env_header_to_validate,
-        op_env_for_viewcall_with_l1_inclusion,
-        op_env_commitment,
-        chain_id_for_length_validation,
-        validate_l1_inclusion,
-    )
+    }
 }
 
 /// Validates an OpStack dispute game commitment.
@@ -328,7 +475,7 @@ pub fn sort_and_verify_relevant_params(
 /// * Root claim doesn't match.
 pub fn validate_opstack_dispute_game_commitment(
     chain_id: u64,
-    eth_env: EvmEnv<StateDb, EthEvmFactory, Commitment>,
+    eth_env: &EthEnv,
     op_env_commitment: &Commitment,
 ) {
     // Decode the game index and root claim from the commitment.
@@ -345,7 +492,7 @@ pub fn validate_opstack_dispute_game_commitment(
     };
 
     // Get the portal contract for additional checks.
-    let portal_contract = Contract::new(portal_adress, &eth_env);
+    let portal_contract = Contract::new(portal_adress, eth_env);
 
     // Get factory address from portal.
     let factory_call = IOptimismPortal::disputeGameFactoryCall {};
@@ -354,7 +501,7 @@ pub fn validate_opstack_dispute_game_commitment(
 
     // Query the dispute game at the given index.
     let game_call = IDisputeGameFactory::gameAtIndexCall { index: game_index };
-    let contract = Contract::new(factory_address, &eth_env);
+    let contract = Contract::new(factory_address, eth_env);
     let returns = contract.call_builder(&game_call).call();
 
     let game_type = returns._0;
@@ -376,7 +523,7 @@ pub fn validate_opstack_dispute_game_commitment(
     );
 
     // Get game contract for status checks.
-    let game_contract = Contract::new(game_address, &eth_env);
+    let game_contract = Contract::new(game_address, eth_env);
 
     // Check game status.
     let status_call = IDisputeGame::statusCall {};
@@ -424,20 +571,15 @@ pub fn validate_opstack_dispute_game_commitment(
 ///
 /// # Arguments
 /// * `chain_id` - The chain ID to determine validation strategy.
-/// * `env_header_to_validate` - The block header to validate.
-/// * `sequencer_commitment_opstack` - Optional sequencer commitment for L2
-///   chains.
-/// * `env_input_opstack_for_l1_block_call` - Optional Optimism environment
-///   input for L1 validation.
-/// * `env_input_eth_for_l1_inclusion` - Optional Ethereum environment input for
-///   L1 inclusion validation.
+/// * `env_header_to_validate` - The header of the view call environment.
+/// * `sequencer_commitment_opstack` - Optional sequencer commitment for
+///   OpStack chains without L1 inclusion.
+/// * `eth_env_for_l1_inclusion` - Optional Ethereum environment for L1
+///   inclusion validation. L1 inclusion is validated if it is set.
 /// * `block_header_to_validate` - Last block in the chain for hash validation.
-/// * `validate_l1_inclusion` - Whether to validate L1 inclusion.
+/// * `block_hash_to_validate` - Hash of `block_header_to_validate`.
 /// * `op_env_commitment` - Optional storage hash for L1 inclusion validation.
-/// * `sequencer_commitment_opstack_2` - Optional second sequencer commitment
-///   for L2 chains.
-/// * `env_input_opstack_for_l1_block_call_2` - Optional second Optimism
-///   environment input for L1 validation.
+/// * `l1_anchors` - Validated L1 anchors of the batch.
 ///
 /// # Returns
 /// * `B256` - The validated block hash.
@@ -446,30 +588,26 @@ pub fn validate_opstack_dispute_game_commitment(
 /// Panics if:
 /// * Chain ID is invalid or unsupported.
 /// * Validation fails for the specific chain type.
+/// * An L1 anchor is needed but missing.
 pub fn get_validated_block_hash(
     chain_id: u64,
-    env_header_to_validate: Header,
+    env_header_to_validate: &Header,
     sequencer_commitment_opstack: Option<SequencerCommitment>,
-    env_input_opstack_for_l1_block_call: Option<EthEvmInput>,
-    env_input_eth_for_l1_inclusion: &Option<EthEvmInput>,
-    block_header_to_validate: RlpHeader<Header>,
-    validate_l1_inclusion: bool,
+    eth_env_for_l1_inclusion: Option<&EthEnv>,
+    block_header_to_validate: &RlpHeader<Header>,
+    block_hash_to_validate: B256,
     op_env_commitment: Option<&Commitment>,
-    sequencer_commitment_opstack_2: Option<SequencerCommitment>,
-    env_input_opstack_for_l1_block_call_2: Option<EthEvmInput>,
+    l1_anchors: &[ValidatedL1Anchor],
 ) -> B256 {
     // Dispatch to the correct validation logic based on chain type.
     if chain_id == LINEA_CHAIN_ID || chain_id == LINEA_SEPOLIA_CHAIN_ID {
         get_validated_block_hash_linea(
             chain_id,
             env_header_to_validate,
-            sequencer_commitment_opstack,
-            env_input_opstack_for_l1_block_call,
-            env_input_eth_for_l1_inclusion,
+            eth_env_for_l1_inclusion,
             block_header_to_validate,
-            validate_l1_inclusion,
-            sequencer_commitment_opstack_2,
-            env_input_opstack_for_l1_block_call_2,
+            block_hash_to_validate,
+            l1_anchors,
         )
     } else if chain_id == OPTIMISM_CHAIN_ID
         || chain_id == BASE_CHAIN_ID
@@ -479,24 +617,16 @@ pub fn get_validated_block_hash(
         get_validated_block_hash_opstack(
             chain_id,
             sequencer_commitment_opstack,
-            env_input_opstack_for_l1_block_call,
-            env_input_eth_for_l1_inclusion,
-            block_header_to_validate,
-            validate_l1_inclusion,
+            eth_env_for_l1_inclusion,
+            block_hash_to_validate,
             op_env_commitment,
-            sequencer_commitment_opstack_2,
-            env_input_opstack_for_l1_block_call_2,
+            l1_anchors,
         )
     } else if chain_id == ETHEREUM_CHAIN_ID
         || chain_id == ETHEREUM_SEPOLIA_CHAIN_ID
     {
-        get_validated_ethereum_block_hash_via_opstack(
-            sequencer_commitment_opstack.as_ref(),
-            env_input_opstack_for_l1_block_call,
-            chain_id,
-            sequencer_commitment_opstack_2.as_ref(),
-            env_input_opstack_for_l1_block_call_2,
-        )
+        // The anchor is the Ethereum block the linking blocks end at.
+        l1_anchor_hash(l1_anchors, chain_id)
     } else {
         panic!("invalid chain id");
     }
@@ -509,16 +639,13 @@ pub fn get_validated_block_hash(
 ///
 /// # Arguments
 /// * `chain_id` - The OpStack chain ID (Optimism/Base).
-/// * `sequencer_commitment` - Optional sequencer commitment.
-/// * `env_input_opstack_for_l1_block_call` - Optional Optimism environment
-///   input.
-/// * `env_input_eth_for_l1_inclusion` - Optional Ethereum environment input.
-/// * `block_header_to_validate` - Last block for hash validation.
-/// * `validate_l1_inclusion` - Whether to validate L1 inclusion.
+/// * `sequencer_commitment` - Optional sequencer commitment, required
+///   without L1 inclusion.
+/// * `eth_env_for_l1_inclusion` - Optional Ethereum environment. L1
+///   inclusion is validated if it is set.
+/// * `block_hash_to_validate` - Hash of the last block.
 /// * `op_env_commitment` - Optional storage hash for L1 validation.
-/// * `sequencer_commitment_opstack_2` - Optional second sequencer commitment.
-/// * `env_input_opstack_for_l1_block_call_2` - Optional second Optimism
-///   environment input.
+/// * `l1_anchors` - Validated L1 anchors of the batch.
 ///
 /// # Returns
 /// * `B256` - The validated block hash.
@@ -530,17 +657,13 @@ pub fn get_validated_block_hash(
 pub fn get_validated_block_hash_opstack(
     chain_id: u64,
     sequencer_commitment: Option<SequencerCommitment>,
-    env_input_opstack_for_l1_block_call: Option<EthEvmInput>,
-    env_input_eth_for_l1_inclusion: &Option<EthEvmInput>,
-    block_header_to_validate: RlpHeader<Header>,
-    validate_l1_inclusion: bool,
+    eth_env_for_l1_inclusion: Option<&EthEnv>,
+    block_hash_to_validate: B256,
     op_env_commitment: Option<&Commitment>,
-    sequencer_commitment_opstack_2: Option<SequencerCommitment>,
-    env_input_opstack_for_l1_block_call_2: Option<EthEvmInput>,
+    l1_anchors: &[ValidatedL1Anchor],
 ) -> B256 {
-    // Compute the hash of the block header to validate.
-    let validated_hash = block_header_to_validate.hash_slow();
-    if validate_l1_inclusion {
+    let validated_hash = block_hash_to_validate;
+    if let Some(eth_env) = eth_env_for_l1_inclusion {
         // For L1 inclusion, determine the correct Ethereum chain ID.
         let ethereum_chain_id = match chain_id {
             OPTIMISM_CHAIN_ID | BASE_CHAIN_ID => ETHEREUM_CHAIN_ID,
@@ -550,27 +673,16 @@ pub fn get_validated_block_hash_opstack(
             _ => panic!("invalid chain id"),
         };
 
-        // Validate the Ethereum block hash via OpStack.
-        let ethereum_hash = get_validated_ethereum_block_hash_via_opstack(
-            sequencer_commitment.as_ref(),
-            env_input_opstack_for_l1_block_call,
-            ethereum_chain_id,
-            sequencer_commitment_opstack_2.as_ref(),
-            env_input_opstack_for_l1_block_call_2,
-        );
-
-        // Ensure the hashes match.
+        // The linking blocks must end at the anchored Ethereum block.
+        let ethereum_hash = l1_anchor_hash(l1_anchors, ethereum_chain_id);
         assert_eq!(ethereum_hash, validated_hash, "hash mismatch  opstack");
         // Validate the OpStack dispute game commitment.
         validate_opstack_dispute_game_commitment(
             chain_id,
-            env_input_eth_for_l1_inclusion
-                .as_ref()
-                .unwrap()
-                .clone()
-                .into_env(&ETH_MAINNET_CHAIN_SPEC),
+            eth_env,
             op_env_commitment.unwrap(),
-        )
+        );
+        end_stage(ProfileStage::DisputeGame);
     } else {
         // For non-L1 inclusion, validate the OpStack environment directly.
         validate_opstack_env(
@@ -578,6 +690,7 @@ pub fn get_validated_block_hash_opstack(
             &sequencer_commitment.unwrap(),
             validated_hash,
         );
+        end_stage(ProfileStage::Signatures);
     }
     validated_hash
 }
@@ -589,16 +702,12 @@ pub fn get_validated_block_hash_opstack(
 ///
 /// # Arguments
 /// * `chain_id` - The Linea chain ID.
-/// * `env_header_to_validate` - The block header to validate.
-/// * `sequencer_commitment_opstack` - Optional sequencer commitment.
-/// * `env_input_opstack_for_l1_block_call` - Optional Optimism environment
-///   input.
-/// * `env_input_eth_for_l1_inclusion` - Optional Ethereum environment input.
+/// * `env_header_to_validate` - The header of the view call environment.
+/// * `eth_env_for_l1_inclusion` - Optional Ethereum environment. L1
+///   inclusion is validated if it is set.
 /// * `block_header_to_validate` - Last block for hash validation.
-/// * `validate_l1_inclusion` - Whether to validate L1 inclusion.
-/// * `sequencer_commitment_opstack_2` - Optional second sequencer commitment.
-/// * `env_input_opstack_for_l1_block_call_2` - Optional second Optimism
-///   environment input.
+/// * `block_hash_to_validate` - Hash of `block_header_to_validate`.
+/// * `l1_anchors` - Validated L1 anchors of the batch.
 ///
 /// # Returns
 /// * `B256` - The validated block hash.
@@ -609,42 +718,33 @@ pub fn get_validated_block_hash_opstack(
 /// * L1 inclusion validation fails when requested.
 pub fn get_validated_block_hash_linea(
     chain_id: u64,
-    env_header_to_validate: Header,
-    sequencer_commitment_opstack: Option<SequencerCommitment>,
-    env_input_opstack_for_l1_block_call: Option<EthEvmInput>,
-    env_input_eth_for_l1_inclusion: &Option<EthEvmInput>,
-    block_header_to_validate: RlpHeader<Header>,
-    validate_l1_inclusion: bool,
-    sequencer_commitment_opstack_2: Option<SequencerCommitment>,
-    env_input_opstack_for_l1_block_call_2: Option<EthEvmInput>,
+    env_header_to_validate: &Header,
+    eth_env_for_l1_inclusion: Option<&EthEnv>,
+    block_header_to_validate: &RlpHeader<Header>,
+    block_hash_to_validate: B256,
+    l1_anchors: &[ValidatedL1Anchor],
 ) -> B256 {
-    if validate_l1_inclusion {
+    if let Some(eth_env) = eth_env_for_l1_inclusion {
         // For L1 inclusion, determine the correct Ethereum chain ID.
         let ethereum_chain_id = match chain_id {
             LINEA_CHAIN_ID => ETHEREUM_CHAIN_ID,
             LINEA_SEPOLIA_CHAIN_ID => ETHEREUM_SEPOLIA_CHAIN_ID,
             _ => panic!("invalid chain id"),
         };
-        // Validate the Ethereum block hash via OpStack.
-        let ethereum_hash = get_validated_ethereum_block_hash_via_opstack(
-            sequencer_commitment_opstack.as_ref(),
-            env_input_opstack_for_l1_block_call,
-            ethereum_chain_id,
-            sequencer_commitment_opstack_2.as_ref(),
-            env_input_opstack_for_l1_block_call_2,
-        );
         // Validate the Linea environment with L1 inclusion (block number only,
-        // not hash).
+        // not hash) against the anchored Ethereum block.
         validate_linea_env_with_l1_inclusion(
             chain_id,
             env_header_to_validate.number,
-            env_input_eth_for_l1_inclusion.as_ref().unwrap(),
-            ethereum_hash,
+            eth_env,
+            l1_anchor_hash(l1_anchors, ethereum_chain_id),
         );
+        end_stage(ProfileStage::LineaMessageService);
     }
     // Always validate the Linea environment (signature check).
-    validate_linea_env(chain_id, &block_header_to_validate);
-    block_header_to_validate.hash_slow()
+    validate_linea_env(chain_id, block_header_to_validate);
+    end_stage(ProfileStage::Signatures);
+    block_hash_to_validate
 }
 
 /// Executes batch multicall for proof data queries.
@@ -673,7 +773,7 @@ pub fn batch_call_get_proof_data<H>(
     account: Vec<Address>,
     asset: Vec<Address>,
     target_chain_ids: Vec<u64>,
-    env: EvmEnv<StateDb, H, Commitment>,
+    env: &EvmEnv<StateDb, H, Commitment>,
     validate_l1_inclusion: bool,
     output: &mut Vec<Bytes>,
 ) where
@@ -717,7 +817,7 @@ pub fn batch_call_get_proof_data<H>(
     }
     println!("=== * multicall preparation END");
 
-    let multicall_contract = Contract::new(MULTICALL, &env);
+    let multicall_contract = Contract::new(MULTICALL, env);
 
     // Make single multicall.
     let multicall = IMulticall3::aggregate3Call { calls };
@@ -743,28 +843,61 @@ pub fn batch_call_get_proof_data<H>(
             println!("=== * amounts={:?}", amounts);
             println!("=== * validate_l1_inclusion={:?}", validate_l1_inclusion);
 
-            let input = vec![
-                SolidityDataType::Address(*user),
-                SolidityDataType::Address(*market),
-                SolidityDataType::Number(amounts.0), // amountIn
-                SolidityDataType::Number(amounts.1), // amountOut
-                SolidityDataType::NumberWithShift(
-                    U256::from(chain_id),
-                    TakeLastXBytes(32),
-                ),
-                SolidityDataType::NumberWithShift(
-                    U256::from(*target_chain_id),
-                    TakeLastXBytes(32),
-                ),
-                SolidityDataType::Bool(validate_l1_inclusion),
-            ];
-
-            let (bytes, _hash) = abi::encode_packed(&input);
-            output.push(bytes.into());
+            push_proof_data(
+                chain_id,
+                *user,
+                *market,
+                *target_chain_id,
+                amounts,
+                validate_l1_inclusion,
+                output,
+            );
         },
     );
 }
 
+/// Encodes the proof data of one query and pushes it to `output`.
+///
+/// The encoding is the same whether the amounts come from the multicall or
+/// from storage proofs.
+///
+/// # Arguments
+/// * `chain_id` - The chain the query was executed on.
+/// * `user` - The queried account.
+/// * `market` - The queried market.
+/// * `target_chain_id` - The queried target chain ID.
+/// * `amounts` - The `(amountIn, amountOut)` pair `getProofData` returned.
+/// * `validate_l1_inclusion` - Whether L1 inclusion is being validated.
+/// * `output` - Output vector for proof data results.
+pub fn push_proof_data(
+    chain_id: u64,
+    user: Address,
+    market: Address,
+    target_chain_id: u64,
+    amounts: (U256, U256),
+    validate_l1_inclusion: bool,
+    output: &mut Vec<Bytes>,
+) {
+    let input = vec![
+        SolidityDataType::Address(user),
+        SolidityDataType::Address(market),
+        SolidityDataType::Number(amounts.0), // amountIn
+        SolidityDataType::Number(amounts.1), // amountOut
+        SolidityDataType::NumberWithShift(
+            U256::from(chain_id),
+            TakeLastXBytes(32),
+        ),
+        SolidityDataType::NumberWithShift(
+            U256::from(target_chain_id),
+            TakeLastXBytes(32),
+        ),
+        SolidityDataType::Bool(validate_l1_inclusion),
+    ];
+
+    let (bytes, _hash) = abi::encode_packed(&input);
+    output.push(bytes.into());
+}
+
 /// Validates Linea environment with L1 inclusion verification.
 ///
 /// This function verifies that a Linea block is properly included in the L1
@@ -775,7 +908,7 @@ pub fn batch_call_get_proof_data<H>(
 /// # Arguments
 /// * `chain_id` - The Linea chain ID.
 /// * `env_block_number` - The block number to validate.
-/// * `env_eth_input` - The Ethereum EVM input for L1 validation.
+/// * `env_eth` - The Ethereum EVM environment for L1 validation.
 /// * `ethereum_hash` - The Ethereum block hash to validate against.
 ///
 /// # Panics
@@ -786,7 +919,7 @@ pub fn batch_call_get_proof_data<H>(
 pub fn validate_linea_env_with_l1_inclusion(
     chain_id: u64,
     env_block_number: u64,
-    env_eth_input: &EthEvmInput,
+    env_eth: &EthEnv,
     ethereum_hash: B256,
 ) {
     // Select the correct message service address for the given chain.
@@ -796,8 +929,6 @@ pub fn validate_linea_env_with_l1_inclusion(
         _ => panic!("invalid chain id"),
     };
 
-    let env_eth = env_eth_input.clone().into_env(&ETH_MAINNET_CHAIN_SPEC);
-
     let eth_hash = env_eth.header().seal();
 
     // Ensure the Ethereum hash matches.
@@ -806,7 +937,7 @@ pub fn validate_linea_env_with_l1_inclusion(
     let current_l2_block_number_call =
         IL1MessageService::currentL2BlockNumberCall {};
 
-    let contract = Contract::new(msg_service_address, &env_eth);
+    let contract = Contract::new(msg_service_address, env_eth);
     let returns = contract.call_builder(&current_l2_block_number_call).call();
 
     let l2_block_number = returns;
@@ -961,6 +1092,7 @@ pub fn get_validated_ethereum_block_hash_via_opstack(
     let env_op = env_input_opstack_for_l1_block_call_1
         .expect("env_input_opstack_for_l1_block_call_1 is None")
         .into_env(&ETH_MAINNET_CHAIN_SPEC);
+    end_stage(ProfileStage::EnvDecode);
 
     // Determine which OpStack chain to use for validation.
     let (verify_via_chain_1, _verify_via_chain_2) =
@@ -981,6 +1113,7 @@ pub fn get_validated_ethereum_block_hash_via_opstack(
     let l1_block = Contract::new(L1_BLOCK_ADDRESS_OPSTACK, &env_op);
     let call = IL1Block::hashCall {};
     let l1_hash_1 = l1_block.call_builder(&call).call();
+    end_stage(ProfileStage::Signatures);
 
     // (Optional) Could validate via a second chain, but currently omitted.
     // let env_op_2 =
@@ -999,9 +1132,8 @@ pub fn get_validated_ethereum_block_hash_via_opstack(
 /// Validates block chain length and hash linking for reorg protection.
 ///
 /// Ensures sufficient block confirmations and proper hash linking between
-/// blocks to prevent reorganization attacks. Checks that the chain is long
-/// enough, that each block is hash-linked to its parent, and that the final
-/// hash matches the expected current hash.
+/// blocks to prevent reorganization attacks, using the chain's default
+/// finality policy. See `validate_chain_finality` for details.
 ///
 /// # Arguments
 /// * `chain_id` - The chain ID to determine reorg protection depth.
@@ -1021,18 +1153,125 @@ pub fn validate_chain_length(
     linking_blocks: &Vec<RlpHeader<Header>>,
     current_hash: B256,
 ) {
-    // Determine the required reorg protection depth for the given chain.
-    let reorg_protection_depth = match chain_id {
-        OPTIMISM_CHAIN_ID => REORG_PROTECTION_DEPTH_OPTIMISM,
-        BASE_CHAIN_ID => REORG_PROTECTION_DEPTH_BASE,
-        LINEA_CHAIN_ID => REORG_PROTECTION_DEPTH_LINEA,
-        ETHEREUM_CHAIN_ID => REORG_PROTECTION_DEPTH_ETHEREUM,
-        OPTIMISM_SEPOLIA_CHAIN_ID => REORG_PROTECTION_DEPTH_OPTIMISM_SEPOLIA,
-        BASE_SEPOLIA_CHAIN_ID => REORG_PROTECTION_DEPTH_BASE_SEPOLIA,
-        LINEA_SEPOLIA_CHAIN_ID => REORG_PROTECTION_DEPTH_LINEA_SEPOLIA,
-        ETHEREUM_SEPOLIA_CHAIN_ID => REORG_PROTECTION_DEPTH_ETHEREUM_SEPOLIA,
-        _ => panic!("invalid chain id"),
-    };
+    let linking_hashes: Vec<B256> = linking_blocks
+        .iter()
+        .map(|header| header.hash_slow())
+        .collect();
+    validate_chain_finality(
+        chain_id,
+        &FinalityPolicy::default_for_chain(chain_id),
+        historical_hash,
+        0,
+        linking_blocks,
+        &linking_hashes,
+        current_hash,
+    );
+}
+
+/// Returns whether `commitment` commits to a beacon block root.
+fn is_beacon_commitment(commitment: &Commitment) -> bool {
+    let (_id, version) = commitment.decode_id();
+    version == CommitmentVersion::Beacon as u16
+}
+
+/// Validates the beacon commitment of a historical Ethereum environment
+/// against the first linking block.
+///
+/// A historical environment is executed at an old block but commits to the
+/// beacon block root of a recent block, which Steel links to the old block
+/// through the beacon state history. The first linking block must be the
+/// child of that recent block: it carries the commitment's timestamp and
+/// holds the committed root as its EIP-4788 `parent_beacon_block_root`.
+///
+/// # Arguments
+/// * `chain_id` - The chain ID of the environment.
+/// * `commitment` - The beacon commitment of the environment.
+/// * `linking_blocks` - Vector of blocks linking the committed block to the
+///   validated block.
+///
+/// # Returns
+/// The hash and timestamp the linking blocks start from, i.e. the parent hash
+/// and the timestamp of the first linking block. The timestamp is at least
+/// the one of the committed block, so age checks stay conservative.
+///
+/// # Panics
+/// Panics if:
+/// * The chain is not Ethereum or Ethereum Sepolia.
+/// * There are no linking blocks.
+/// * The first linking block does not match the commitment.
+pub fn validate_beacon_commitment(
+    chain_id: u64,
+    commitment: &Commitment,
+    linking_blocks: &[RlpHeader<Header>],
+) -> (B256, u64) {
+    assert!(
+        chain_id == ETHEREUM_CHAIN_ID || chain_id == ETHEREUM_SEPOLIA_CHAIN_ID,
+        "beacon commitments only supported for Ethereum"
+    );
+    let first = linking_blocks
+        .first()
+        .expect("beacon commitment without linking blocks");
+    let (timestamp, _version) = commitment.decode_id();
+    assert_eq!(
+        U256::from(first.timestamp),
+        timestamp,
+        "beacon commitment timestamp mismatch"
+    );
+    assert_eq!(
+        first.parent_beacon_block_root,
+        Some(commitment.digest),
+        "beacon commitment root mismatch"
+    );
+    (first.parent_hash, first.timestamp)
+}
+
+/// Validates that linking blocks satisfy a finality policy.
+///
+/// Checks that the chain is at least as long as the policy's minimum depth,
+/// that each block is hash-linked to its parent, and that the final hash
+/// matches the expected current hash. For `FinalityPolicy::MinAge`, also
+/// checks that the newest linking block is at least the required number of
+/// seconds younger than the historical block.
+///
+/// # Arguments
+/// * `chain_id` - The chain ID to determine the default protection depth.
+/// * `finality_policy` - The confirmation requirement to enforce.
+/// * `historical_hash` - The hash of the historical block.
+/// * `historical_timestamp` - The timestamp of the historical block.
+/// * `linking_blocks` - Vector of blocks linking historical to current.
+/// * `linking_hashes` - Hashes of `linking_blocks`, computed once by the
+///   caller.
+/// * `current_hash` - The expected current block hash.
+///
+/// # Panics
+/// Panics if:
+/// * The policy is a block tag not resolved to a depth by the host.
+/// * Chain length is less than the policy's minimum depth.
+/// * Linking blocks do not cover the policy's minimum age.
+/// * Blocks are not properly hash-linked.
+/// * Final hash doesn't match current hash.
+/// * Chain ID is invalid or unsupported.
+pub fn validate_chain_finality(
+    chain_id: u64,
+    finality_policy: &FinalityPolicy,
+    historical_hash: B256,
+    historical_timestamp: u64,
+    linking_blocks: &Vec<RlpHeader<Header>>,
+    linking_hashes: &[B256],
+    current_hash: B256,
+) {
+    assert_eq!(
+        linking_blocks.len(),
+        linking_hashes.len(),
+        "linking block hashes missing"
+    );
+    // A tag cannot be proven, the committed policy must be what was checked
+    assert!(
+        !finality_policy.is_tag(),
+        "finality tag not resolved to a depth"
+    );
+    // Determine the required reorg protection depth for the given policy.
+    let reorg_protection_depth = finality_policy.min_depth(chain_id);
     let chain_length = linking_blocks.len() as u64;
     // Ensure the chain is long enough for reorg protection.
     assert!(
@@ -1041,16 +1280,27 @@ pub fn validate_chain_length(
     );
     let mut previous_hash = historical_hash;
     // Check that each block is hash-linked to its parent.
-    for header in linking_blocks.iter() {
+    for (header, hash) in linking_blocks.iter().zip(linking_hashes) {
         let parent_hash = header.parent_hash;
         assert_eq!(parent_hash, previous_hash, "blocks not hashlinked");
-        previous_hash = header.hash_slow();
+        previous_hash = *hash;
     }
     // Ensure the final hash matches the expected current hash.
     assert_eq!(
         previous_hash, current_hash,
         "last hash doesnt correspond to verified hash"
     );
+    // Ensure enough time has passed on top of the historical block.
+    if let FinalityPolicy::MinAge(min_age) = finality_policy {
+        let newest_timestamp = linking_blocks
+            .last()
+            .map(|header| header.timestamp)
+            .unwrap_or(historical_timestamp);
+        assert!(
+            newest_timestamp.saturating_sub(historical_timestamp) >= *min_age,
+            "linking blocks do not cover minimum age"
+        );
+    }
 }
 
 #[cfg(test)]
@@ -1244,6 +1494,90 @@ mod tests {
         panic!("Chain shorter than reorg depth was validated!");
     }
 
+    #[test]
+    #[should_panic(expected = "linking blocks do not cover minimum age")]
+    fn test_validate_chain_finality_rejects_chain_younger_than_min_age() {
+        let chain_id = BASE_CHAIN_ID;
+        let historical_timestamp = 1_000;
+
+        let historical_hash = B256::random();
+        let mut parent_hash = historical_hash;
+
+        let mut young_chain = Vec::new();
+        for i in 1..=REORG_PROTECTION_DEPTH_BASE {
+            let mut header = create_mock_header(parent_hash, i).inner().clone();
+            header.timestamp = historical_timestamp + 2 * i;
+            let block = RlpHeader::new(header);
+            parent_hash = block.hash_slow();
+            young_chain.push(block);
+        }
+        let current_hash = parent_hash;
+        let linking_hashes: Vec<B256> = young_chain
+            .iter()
+            .map(|header| header.hash_slow())
+            .collect();
+
+        validate_chain_finality(
+            chain_id,
+            &FinalityPolicy::MinAge(60),
+            historical_hash,
+            historical_timestamp,
+            &young_chain,
+            &linking_hashes,
+            current_hash,
+        );
+
+        panic!("Chain younger than the minimum age was validated!");
+    }
+
+    #[test]
+    #[should_panic(expected = "finality tag not resolved to a depth")]
+    fn test_validate_chain_finality_rejects_unresolved_tag() {
+        let historical_hash = B256::random();
+        let mut parent_hash = historical_hash;
+        let mut chain = Vec::new();
+        for i in 1..=REORG_PROTECTION_DEPTH_BASE {
+            let block = create_mock_header(parent_hash, i);
+            parent_hash = block.hash_slow();
+            chain.push(block);
+        }
+        let linking_hashes: Vec<B256> =
+            chain.iter().map(|header| header.hash_slow()).collect();
+
+        validate_chain_finality(
+            BASE_CHAIN_ID,
+            &FinalityPolicy::Finalized,
+            historical_hash,
+            0,
+            &chain,
+            &linking_hashes,
+            parent_hash,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "chain length is less than reorg protection")]
+    fn test_validate_chain_finality_rejects_depth_below_default() {
+        let chain_id = BASE_CHAIN_ID;
+
+        let historical_hash = B256::random();
+        let block = create_mock_header(historical_hash, 1);
+        let current_hash = block.hash_slow();
+
+        // A single linking block satisfies Depth(0), but not the default
+        validate_chain_finality(
+            chain_id,
+            &FinalityPolicy::Depth(0),
+            historical_hash,
+            0,
+            &vec![block],
+            &[current_hash],
+            current_hash,
+        );
+
+        panic!("Depth below the default protection was validated!");
+    }
+
     use risc0_steel::{Account, ethereum::EthEvmEnv};
     use url::Url;
 
@@ -1288,38 +1622,83 @@ mod tests {
             .unwrap();
 
         let linea_input = linea_env.into_input().await.unwrap();
-        let eth_input = eth_env.into_input().await.unwrap();
-
-        let linking_blocks = Vec::<RlpHeader<Header>>::new();
+        let eth_env_for_l1_inclusion = eth_env
+            .into_input()
+            .await
+            .unwrap()
+            .into_env(&ETH_MAINNET_CHAIN_SPEC);
 
         let (
             returned_env,
-            _block_header_to_validate,
-            _env_header_hash_to_validate,
-            _env_header_to_validate,
             _op_env_for_viewcall,
-            _op_env_commitment,
             _returned_chain_id_for_validation,
-            returned_l1_inclusion_flag,
         ) = sort_and_verify_relevant_params(
             LINEA_CHAIN_ID,
             Some(linea_input),
-            &linking_blocks,
-            &Some(eth_input),
+            Some(&eth_env_for_l1_inclusion),
             None,
         );
 
-        assert!(
-            returned_l1_inclusion_flag,
-            "l1_inclusion should be true, but was set to false"
-        );
         assert_eq!(
-            returned_env.header().seal(),
+            returned_env
+                .expect("no view call environment returned")
+                .header()
+                .seal(),
             expected_header_hash,
             "The returned `env_for_viewcall` should have been the L1 Ethereum environment, but it was not."
         );
     }
 
+    fn create_beacon_child(parent_hash: B256, root: B256) -> RlpHeader<Header> {
+        RlpHeader::new(Header {
+            timestamp: 1_700_000_012,
+            parent_beacon_block_root: Some(root),
+            ..create_mock_header(parent_hash, 2).inner().clone()
+        })
+    }
+
+    #[test]
+    fn test_beacon_commitment_links_from_first_linking_block() {
+        let parent_hash = B256::random();
+        let root = B256::random();
+        let commitment = Commitment::new(
+            CommitmentVersion::Beacon as u16,
+            1_700_000_012,
+            root,
+            B256::ZERO,
+        );
+        let linking_blocks = vec![create_beacon_child(parent_hash, root)];
+
+        assert!(is_beacon_commitment(&commitment));
+        assert_eq!(
+            validate_beacon_commitment(
+                ETHEREUM_CHAIN_ID,
+                &commitment,
+                &linking_blocks
+            ),
+            (parent_hash, 1_700_000_012)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "beacon commitment root mismatch")]
+    fn test_beacon_commitment_rejects_other_root() {
+        let commitment = Commitment::new(
+            CommitmentVersion::Beacon as u16,
+            1_700_000_012,
+            B256::random(),
+            B256::ZERO,
+        );
+        let linking_blocks =
+            vec![create_beacon_child(B256::random(), B256::random())];
+
+        validate_beacon_commitment(
+            ETHEREUM_CHAIN_ID,
+            &commitment,
+            &linking_blocks,
+        );
+    }
+
     async fn get_latest_linea_header() -> RlpHeader<Header> {
         let rpc_url = "https://rpc.linea.build";
         EthEvmEnv::builder()
@@ -4,21 +4,41 @@ fn main() {}
 mod tests {
     use alloy_consensus::Header;
     use alloy_primitives::{Address, Bytes, address};
-    use malda_rs::viewcalls::get_proof_data_zkvm_input;
+    use malda_rs::{
+        commitments::commitment_source_from_config,
+        config::MaldaConfig,
+        data_source::data_source_from_config,
+        error::HostError,
+        request::{
+            InclusionMode, ProofDataQuery, ProofDataRequest, RpcSelection,
+        },
+        viewcalls::{get_l1_anchors, get_proof_data_zkvm_input},
+    };
     use malda_utils::constants::{
         BASE_CHAIN_ID, ETHEREUM_CHAIN_ID, LINEA_CHAIN_ID,
     };
     use malda_utils::{
-        types::{LINEA_MAINNET_CHAIN_SPEC, SequencerCommitment},
-        validators::validate_get_proof_data_call,
+        receipts::EventInput,
+        storage::StorageProofInput,
+        types::{
+            FinalityPolicy, LINEA_MAINNET_CHAIN_SPEC, SequencerCommitment,
+        },
+        validators::{validate_get_proof_data_call, validate_l1_anchor},
     };
     use risc0_op_steel::optimism::OpEvmInput;
     use risc0_steel::{ethereum::EthEvmInput, serde::RlpHeader};
     use risc0_zkvm;
+    use std::sync::Arc;
     use tokio;
 
     const MUSDC: Address = address!("269C36A173D881720544Fb303E681370158FF1FD");
     const MWETH: Address = address!("C7Bc6bD45Eb84D594f51cED3c5497E6812C7732f");
+    // Fixed users keep the RPC requests identical across runs, so the tests
+    // can be replayed from fixtures (MALDA_DATA_SOURCE=replay).
+    const USER_1: Address =
+        address!("1111111111111111111111111111111111111111");
+    const USER_2: Address =
+        address!("2222222222222222222222222222222222222222");
 
     type DecodedInput = (
         Option<EthEvmInput>,         // 0: env_input
@@ -27,14 +47,48 @@ mod tests {
         Vec<Address>,                // 3: asset
         Vec<u64>,                    // 4: target_chain_ids
         Option<SequencerCommitment>, // 5: sequencer_commitment
-        Option<EthEvmInput>,         // 6: env_op_input
-        Vec<RlpHeader<Header>>,      // 7: linking_blocks
-        Option<EthEvmInput>,         // 8: env_eth_input
-        Option<OpEvmInput>,          // 9: op_evm_input
-        Option<SequencerCommitment>, // 10: sequencer_commitment_opstack_2
-        Option<EthEvmInput>,         // 11: env_op_input_2
+        Vec<RlpHeader<Header>>,      // 6: linking_blocks
+        Option<EthEvmInput>,         // 7: env_eth_input
+        Option<OpEvmInput>,          // 8: op_evm_input
+        FinalityPolicy,              // 9: finality_policy
+        Option<EventInput>,          // 10: event_input
+        Option<StorageProofInput>,   // 11: storage_proof_input
     );
 
+    fn proof_data_request(
+        chain_id: u64,
+        users: &[Address],
+        markets: &[Address],
+        target_chain_ids: &[u64],
+        l1_inclusion: bool,
+        fallback: bool,
+    ) -> Result<ProofDataRequest, HostError> {
+        assert_eq!(users.len(), markets.len());
+        assert_eq!(users.len(), target_chain_ids.len());
+        ProofDataRequest::builder()
+            .queries(
+                chain_id,
+                (0..users.len()).map(|i| {
+                    ProofDataQuery::new(
+                        users[i],
+                        markets[i],
+                        target_chain_ids[i],
+                    )
+                }),
+            )
+            .inclusion(if l1_inclusion {
+                InclusionMode::L1
+            } else {
+                InclusionMode::Sequencer
+            })
+            .rpc(if fallback {
+                RpcSelection::Fallback
+            } else {
+                RpcSelection::Primary
+            })
+            .build()
+    }
+
     fn decode_input(input: Vec<u8>) -> DecodedInput {
         let des: DecodedInput =
             risc0_zkvm::serde::from_slice(&input).expect("X");
@@ -61,17 +115,13 @@ mod tests {
         let target_chain_ids = decoded_input.4;
         println!("=== * target_chain_ids={:?}", target_chain_ids);
         let sequencer_commitment = decoded_input.5;
-        // when chain_id=linea and l1_inclusion=false, env_op_input is unused in
-        // the Guest program
-        // let env_op_input = decoded_input.6;
-
-        let linking_blocks = decoded_input.7;
+        let linking_blocks = decoded_input.6;
         println!("=== * linking_blocks={:?}", linking_blocks);
     }
 
     #[tokio::test]
     async fn test_linea_e2e_non_l1_inclusion() {
-        let users = Vec::from([Address::random(), Address::random()]);
+        let users = Vec::from([USER_1, USER_2]);
         let markets = Vec::from([MUSDC, MWETH]);
         let target_chain_ids = Vec::from([ETHEREUM_CHAIN_ID, LINEA_CHAIN_ID]);
         let chain_id = LINEA_CHAIN_ID;
@@ -86,15 +136,37 @@ mod tests {
         println!("=== * l1_inclusion={}", l1_inclusion);
         println!("=== * fallback={}", fallback);
         println!("=== get_proof_data_zkvm_input START");
-        let input = get_proof_data_zkvm_input(
-            users,
-            markets,
-            target_chain_ids,
+        let config =
+            Arc::new(MaldaConfig::from_env().expect("Failed to load config"));
+        let data_source = data_source_from_config(config.clone())
+            .await
+            .expect("Failed to create data source");
+        let commitments =
+            commitment_source_from_config(&config, data_source.clone())
+                .await
+                .expect("Failed to create commitment source");
+        let request = proof_data_request(
             chain_id,
+            &users,
+            &markets,
+            &target_chain_ids,
             l1_inclusion,
             fallback,
         )
-        .await;
+        .expect("Failed to build request");
+        let anchors = get_l1_anchors(&*data_source, &*commitments, &request)
+            .await
+            .expect("Failed to build L1 anchors");
+        let input = get_proof_data_zkvm_input(
+            &*data_source,
+            &*commitments,
+            &anchors,
+            &request.chains()[0],
+            request.inclusion(),
+            request.rpc(),
+        )
+        .await
+        .expect("Failed to build guest input");
         println!("=== get_proof_data_zkvm_input END");
         println!("=== decode_input START");
         let decoded_input = decode_input(input);
@@ -106,13 +178,19 @@ mod tests {
         let target_chain_ids = decoded_input.4;
         let env_input = decoded_input.0;
         let sequencer_commitment = decoded_input.5;
-        let env_op_input = decoded_input.6;
-        let linking_blocks = decoded_input.7;
+        let linking_blocks = decoded_input.6;
         let mut output: Vec<Bytes> = Vec::new();
-        let env_eth_input = decoded_input.8;
-        let op_evm_input = decoded_input.9;
-        let sequencer_commitment_opstack_2 = decoded_input.10;
-        let env_op_input_2 = decoded_input.11;
+        let env_eth_input = decoded_input.7;
+        let op_evm_input = decoded_input.8;
+        let finality_policy = decoded_input.9;
+        let event_input = decoded_input.10;
+        let storage_proof_input = decoded_input.11;
+        let l1_anchors = anchors
+            .anchors()
+            .into_iter()
+            .cloned()
+            .map(validate_l1_anchor)
+            .collect::<Vec<_>>();
         println!("=== validate_get_proof_data_call START");
         validate_get_proof_data_call(
             chain_id,
@@ -121,13 +199,15 @@ mod tests {
             target_chain_ids,
             env_input,
             sequencer_commitment,
-            env_op_input,
             &linking_blocks,
             &mut output,
-            &env_eth_input,
+            env_eth_input,
             op_evm_input,
-            sequencer_commitment_opstack_2,
-            env_op_input_2,
+            &l1_anchors,
+            &finality_policy,
+            event_input,
+            &mut Vec::new(),
+            storage_proof_input,
         );
         println!("=== validate_get_proof_data_call END");
 
@@ -136,7 +216,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_linea_e2e_l1_inclusion() {
-        let users = Vec::from([Address::random(), Address::random()]);
+        let users = Vec::from([USER_1, USER_2]);
         let markets = Vec::from([MUSDC, MWETH]);
         let target_chain_ids =
             Vec::from([ETHEREUM_CHAIN_ID, ETHEREUM_CHAIN_ID]);
@@ -152,15 +232,37 @@ mod tests {
         println!("=== * l1_inclusion={}", l1_inclusion);
         println!("=== * fallback={}", fallback);
         println!("=== get_proof_data_zkvm_input START");
-        let input = get_proof_data_zkvm_input(
-            users,
-            markets,
-            target_chain_ids,
+        let config =
+            Arc::new(MaldaConfig::from_env().expect("Failed to load config"));
+        let data_source = data_source_from_config(config.clone())
+            .await
+            .expect("Failed to create data source");
+        let commitments =
+            commitment_source_from_config(&config, data_source.clone())
+                .await
+                .expect("Failed to create commitment source");
+        let request = proof_data_request(
             chain_id,
+            &users,
+            &markets,
+            &target_chain_ids,
             l1_inclusion,
             fallback,
         )
-        .await;
+        .expect("Failed to build request");
+        let anchors = get_l1_anchors(&*data_source, &*commitments, &request)
+            .await
+            .expect("Failed to build L1 anchors");
+        let input = get_proof_data_zkvm_input(
+            &*data_source,
+            &*commitments,
+            &anchors,
+            &request.chains()[0],
+            request.inclusion(),
+            request.rpc(),
+        )
+        .await
+        .expect("Failed to build guest input");
         println!("=== get_proof_data_zkvm_input END");
         println!("=== decode_input START");
         let decoded_input = decode_input(input);
@@ -172,13 +274,19 @@ mod tests {
         let target_chain_ids = decoded_input.4;
         let env_input = decoded_input.0;
         let sequencer_commitment = decoded_input.5;
-        let env_op_input = decoded_input.6;
-        let linking_blocks = decoded_input.7;
+        let linking_blocks = decoded_input.6;
         let mut output: Vec<Bytes> = Vec::new();
-        let env_eth_input = decoded_input.8;
-        let op_evm_input = decoded_input.9;
-        let sequencer_commitment_opstack_2 = decoded_input.10;
-        let env_op_input_2 = decoded_input.11;
+        let env_eth_input = decoded_input.7;
+        let op_evm_input = decoded_input.8;
+        let finality_policy = decoded_input.9;
+        let event_input = decoded_input.10;
+        let storage_proof_input = decoded_input.11;
+        let l1_anchors = anchors
+            .anchors()
+            .into_iter()
+            .cloned()
+            .map(validate_l1_anchor)
+            .collect::<Vec<_>>();
         println!("=== validate_get_proof_data_call START");
         validate_get_proof_data_call(
             chain_id,
@@ -187,13 +295,15 @@ mod tests {
             target_chain_ids,
             env_input,
             sequencer_commitment,
-            env_op_input,
             &linking_blocks,
             &mut output,
-            &env_eth_input,
+            env_eth_input,
             op_evm_input,
-            sequencer_commitment_opstack_2,
-            env_op_input_2,
+            &l1_anchors,
+            &finality_policy,
+            event_input,
+            &mut Vec::new(),
+            storage_proof_input,
         );
         println!("=== validate_get_proof_data_call END");
 
@@ -202,7 +312,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_ethereum_e2e_non_l1_inclusion() {
-        let users = Vec::from([Address::random(), Address::random()]);
+        let users = Vec::from([USER_1, USER_2]);
         let markets = Vec::from([MUSDC, MWETH]);
         let target_chain_ids = Vec::from([LINEA_CHAIN_ID, LINEA_CHAIN_ID]);
         let chain_id = ETHEREUM_CHAIN_ID;
@@ -217,15 +327,37 @@ mod tests {
         println!("=== * l1_inclusion={}", l1_inclusion);
         println!("=== * fallback={}", fallback);
         println!("=== get_proof_data_zkvm_input START");
-        let input = get_proof_data_zkvm_input(
-            users,
-            markets,
-            target_chain_ids,
+        let config =
+            Arc::new(MaldaConfig::from_env().expect("Failed to load config"));
+        let data_source = data_source_from_config(config.clone())
+            .await
+            .expect("Failed to create data source");
+        let commitments =
+            commitment_source_from_config(&config, data_source.clone())
+                .await
+                .expect("Failed to create commitment source");
+        let request = proof_data_request(
             chain_id,
+            &users,
+            &markets,
+            &target_chain_ids,
             l1_inclusion,
             fallback,
         )
-        .await;
+        .expect("Failed to build request");
+        let anchors = get_l1_anchors(&*data_source, &*commitments, &request)
+            .await
+            .expect("Failed to build L1 anchors");
+        let input = get_proof_data_zkvm_input(
+            &*data_source,
+            &*commitments,
+            &anchors,
+            &request.chains()[0],
+            request.inclusion(),
+            request.rpc(),
+        )
+        .await
+        .expect("Failed to build guest input");
         println!("=== get_proof_data_zkvm_input END");
         println!("=== decode_input START");
         let decoded_input = decode_input(input);
@@ -237,13 +369,19 @@ mod tests {
         let target_chain_ids = decoded_input.4;
         let env_input = decoded_input.0;
         let sequencer_commitment = decoded_input.5;
-        let env_op_input = decoded_input.6;
-        let linking_blocks = decoded_input.7;
+        let linking_blocks = decoded_input.6;
         let mut output: Vec<Bytes> = Vec::new();
-        let env_eth_input = decoded_input.8;
-        let op_evm_input = decoded_input.9;
-        let sequencer_commitment_opstack_2 = decoded_input.10;
-        let env_op_input_2 = decoded_input.11;
+        let env_eth_input = decoded_input.7;
+        let op_evm_input = decoded_input.8;
+        let finality_policy = decoded_input.9;
+        let event_input = decoded_input.10;
+        let storage_proof_input = decoded_input.11;
+        let l1_anchors = anchors
+            .anchors()
+            .into_iter()
+            .cloned()
+            .map(validate_l1_anchor)
+            .collect::<Vec<_>>();
         println!("=== validate_get_proof_data_call START");
         validate_get_proof_data_call(
             chain_id,
@@ -252,13 +390,15 @@ mod tests {
             target_chain_ids,
             env_input,
             sequencer_commitment,
-            env_op_input,
             &linking_blocks,
             &mut output,
-            &env_eth_input,
+            env_eth_input,
             op_evm_input,
-            sequencer_commitment_opstack_2,
-            env_op_input_2,
+            &l1_anchors,
+            &finality_policy,
+            event_input,
+            &mut Vec::new(),
+            storage_proof_input,
         );
         println!("=== validate_get_proof_data_call END");
 
@@ -267,7 +407,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_base_e2e_non_l1_inclusion() {
-        let users = Vec::from([Address::random(), Address::random()]);
+        let users = Vec::from([USER_1, USER_2]);
         let markets = Vec::from([MUSDC, MWETH]);
         let target_chain_ids = Vec::from([LINEA_CHAIN_ID, LINEA_CHAIN_ID]);
         let chain_id = BASE_CHAIN_ID;
@@ -282,15 +422,37 @@ mod tests {
         println!("=== * l1_inclusion={}", l1_inclusion);
         println!("=== * fallback={}", fallback);
         println!("=== get_proof_data_zkvm_input START");
-        let input = get_proof_data_zkvm_input(
-            users,
-            markets,
-            target_chain_ids,
+        let config =
+            Arc::new(MaldaConfig::from_env().expect("Failed to load config"));
+        let data_source = data_source_from_config(config.clone())
+            .await
+            .expect("Failed to create data source");
+        let commitments =
+            commitment_source_from_config(&config, data_source.clone())
+                .await
+                .expect("Failed to create commitment source");
+        let request = proof_data_request(
             chain_id,
+            &users,
+            &markets,
+            &target_chain_ids,
             l1_inclusion,
             fallback,
         )
-        .await;
+        .expect("Failed to build request");
+        let anchors = get_l1_anchors(&*data_source, &*commitments, &request)
+            .await
+            .expect("Failed to build L1 anchors");
+        let input = get_proof_data_zkvm_input(
+            &*data_source,
+            &*commitments,
+            &anchors,
+            &request.chains()[0],
+            request.inclusion(),
+            request.rpc(),
+        )
+        .await
+        .expect("Failed to build guest input");
         println!("=== get_proof_data_zkvm_input END");
         println!("=== decode_input START");
         let decoded_input = decode_input(input);
@@ -302,13 +464,19 @@ mod tests {
         let target_chain_ids = decoded_input.4;
         let env_input = decoded_input.0;
         let sequencer_commitment = decoded_input.5;
-        let env_op_input = decoded_input.6;
-        let linking_blocks = decoded_input.7;
+        let linking_blocks = decoded_input.6;
         let mut output: Vec<Bytes> = Vec::new();
-        let env_eth_input = decoded_input.8;
-        let op_evm_input = decoded_input.9;
-        let sequencer_commitment_opstack_2 = decoded_input.10;
-        let env_op_input_2 = decoded_input.11;
+        let env_eth_input = decoded_input.7;
+        let op_evm_input = decoded_input.8;
+        let finality_policy = decoded_input.9;
+        let event_input = decoded_input.10;
+        let storage_proof_input = decoded_input.11;
+        let l1_anchors = anchors
+            .anchors()
+            .into_iter()
+            .cloned()
+            .map(validate_l1_anchor)
+            .collect::<Vec<_>>();
         println!("=== validate_get_proof_data_call START");
         validate_get_proof_data_call(
             chain_id,
@@ -317,13 +485,15 @@ mod tests {
             target_chain_ids,
             env_input,
             sequencer_commitment,
-            env_op_input,
             &linking_blocks,
             &mut output,
-            &env_eth_input,
+            env_eth_input,
             op_evm_input,
-            sequencer_commitment_opstack_2,
-            env_op_input_2,
+            &l1_anchors,
+            &finality_policy,
+            event_input,
+            &mut Vec::new(),
+            storage_proof_input,
         );
         println!("=== validate_get_proof_data_call END");
 
@@ -332,7 +502,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_base_e2e_l1_inclusion() {
-        let users = Vec::from([Address::random(), Address::random()]);
+        let users = Vec::from([USER_1, USER_2]);
         let markets = Vec::from([MUSDC, MWETH]);
         let target_chain_ids = Vec::from([LINEA_CHAIN_ID, LINEA_CHAIN_ID]);
         let chain_id = BASE_CHAIN_ID;
@@ -347,15 +517,37 @@ mod tests {
         println!("=== * l1_inclusion={}", l1_inclusion);
         println!("=== * fallback={}", fallback);
         println!("=== get_proof_data_zkvm_input START");
-        let input = get_proof_data_zkvm_input(
-            users,
-            markets,
-            target_chain_ids,
+        let config =
+            Arc::new(MaldaConfig::from_env().expect("Failed to load config"));
+        let data_source = data_source_from_config(config.clone())
+            .await
+            .expect("Failed to create data source");
+        let commitments =
+            commitment_source_from_config(&config, data_source.clone())
+                .await
+                .expect("Failed to create commitment source");
+        let request = proof_data_request(
             chain_id,
+            &users,
+            &markets,
+            &target_chain_ids,
             l1_inclusion,
             fallback,
         )
-        .await;
+        .expect("Failed to build request");
+        let anchors = get_l1_anchors(&*data_source, &*commitments, &request)
+            .await
+            .expect("Failed to build L1 anchors");
+        let input = get_proof_data_zkvm_input(
+            &*data_source,
+            &*commitments,
+            &anchors,
+            &request.chains()[0],
+            request.inclusion(),
+            request.rpc(),
+        )
+        .await
+        .expect("Failed to build guest input");
         println!("=== get_proof_data_zkvm_input END");
         println!("=== decode_input START");
         let decoded_input = decode_input(input);
@@ -367,13 +559,19 @@ mod tests {
         let target_chain_ids = decoded_input.4;
         let env_input = decoded_input.0;
         let sequencer_commitment = decoded_input.5;
-        let env_op_input = decoded_input.6;
-        let linking_blocks = decoded_input.7;
+        let linking_blocks = decoded_input.6;
         let mut output: Vec<Bytes> = Vec::new();
-        let env_eth_input = decoded_input.8;
-        let op_evm_input = decoded_input.9;
-        let sequencer_commitment_opstack_2 = decoded_input.10;
-        let env_op_input_2 = decoded_input.11;
+        let env_eth_input = decoded_input.7;
+        let op_evm_input = decoded_input.8;
+        let finality_policy = decoded_input.9;
+        let event_input = decoded_input.10;
+        let storage_proof_input = decoded_input.11;
+        let l1_anchors = anchors
+            .anchors()
+            .into_iter()
+            .cloned()
+            .map(validate_l1_anchor)
+            .collect::<Vec<_>>();
         println!("=== validate_get_proof_data_call START");
         validate_get_proof_data_call(
             chain_id,
@@ -382,13 +580,15 @@ mod tests {
             target_chain_ids,
             env_input,
             sequencer_commitment,
-            env_op_input,
             &linking_blocks,
             &mut output,
-            &env_eth_input,
+            env_eth_input,
             op_evm_input,
-            sequencer_commitment_opstack_2,
-            env_op_input_2,
+            &l1_anchors,
+            &finality_policy,
+            event_input,
+            &mut Vec::new(),
+            storage_proof_input,
         );
         println!("=== validate_get_proof_data_call END");
 